@@ -1,107 +1,317 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use raylib::*;
+use std::{
+	fs::File,
+	io::{BufReader, BufWriter, Write},
+	time::{SystemTime, UNIX_EPOCH},
+};
 
-enum GameScreen {
-    Logo,
-    Title,
-    GamePlay,
-    Ending,
+use raylib::{
+	audio::AudioDevice,
+	backend::{self, Backend, HeadlessBackend, WindowBackend},
+	input_map::{Binding, InputMap},
+	mixer::{AudioMixer, MixBus, Playlist},
+	screen::{Screen, ScreenStack, Transition, TransitionEffect},
+	*,
+};
+
+const SCREEN_WIDTH: i32 = 800;
+const SCREEN_HEIGHT: i32 = 450;
+
+const SETTINGS_PATH: &str = "settings.cfg";
+const TITLE_MUSIC: &str = "resources/title.ogg";
+const GAMEPLAY_MUSIC: [&str; 3] = ["resources/gameplay_1.ogg", "resources/gameplay_2.ogg", "resources/gameplay_3.ogg"];
+// seconds the music crossfades over on a screen change
+const MUSIC_FADE: f32 = 1.0;
+
+// what the screens share
+struct Game<'a> {
+	input: InputMap,
+	// None without an audio device, the game then plays silent
+	mixer: Option<AudioMixer<'a>>,
+}
+
+impl Game<'_> {
+	fn play_music(&mut self, path: &str) {
+		if let Some(mixer) = &mut self.mixer {
+			if let Err(err) = mixer.play_music_file(path, MUSIC_FADE) {
+				eprintln!("music {path}: {err}");
+			}
+		}
+	}
+
+	fn play_playlist(&mut self, playlist: Playlist) {
+		if let Some(mixer) = &mut self.mixer {
+			if let Err(err) = mixer.play_playlist(playlist, MUSIC_FADE) {
+				eprintln!("playlist: {err}");
+			}
+		}
+	}
+}
+
+fn input_map() -> InputMap {
+	let mut input = InputMap::new();
+	input
+		.bind("confirm", Binding::Key(KeyboardKey_KEY_ENTER))
+		.bind("confirm", Binding::Gesture(Gesture_GESTURE_TAP))
+		.bind("confirm", Binding::GamepadButton(GamepadButton_GAMEPAD_BUTTON_RIGHT_FACE_DOWN))
+		.bind("pause", Binding::Key(KeyboardKey_KEY_P))
+		.bind("pause", Binding::GamepadButton(GamepadButton_GAMEPAD_BUTTON_MIDDLE_RIGHT))
+		.bind("volume_down", Binding::Key(KeyboardKey_KEY_MINUS))
+		.bind("volume_up", Binding::Key(KeyboardKey_KEY_EQUAL));
+	input
+}
+
+struct Logo {
+	frames_counter: i32,
+}
+
+impl<'a> Screen<Game<'a>> for Logo {
+	fn update(&mut self, _game: &mut Game<'a>, _delta_time: f32) -> Transition<Game<'a>> {
+		self.frames_counter += 1;
+
+		// ~2 seconds @60 fps
+		if self.frames_counter > 120 {
+			Transition::Replace(Box::new(Title))
+		} else {
+			Transition::None
+		}
+	}
+
+	fn draw(&self, backend: &mut dyn Backend, _game: &Game<'a>) {
+		backend.draw_text("LOGO SCREEN", 20, 20, 40, LIGHTGRAY);
+		backend.draw_text("WAIT for 2 SECONDS", 290, 220, 10, GRAY);
+	}
+}
+
+struct Title;
+
+impl<'a> Screen<Game<'a>> for Title {
+	fn enter(&mut self, game: &mut Game<'a>) {
+		game.play_music(TITLE_MUSIC);
+	}
+
+	fn update(&mut self, game: &mut Game<'a>, _delta_time: f32) -> Transition<Game<'a>> {
+		if game.input.pressed("confirm") {
+			Transition::Replace(Box::new(GamePlay))
+		} else {
+			Transition::None
+		}
+	}
+
+	fn draw(&self, backend: &mut dyn Backend, game: &Game<'a>) {
+		backend.draw_rectangle(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT, GREEN);
+		backend.draw_text("TITLE SCREEN", 20, 20, 40, DARKGREEN);
+		backend.draw_text("PRESS ENTER or TAP to JUMP to GAMEPLAY SCREEN", 120, 220, 20, DARKGREEN);
+		if let Some(mixer) = &game.mixer {
+			let volume = format!("VOLUME {:.0}% (- / +)", mixer.volume(MixBus::Master) * 100.0);
+			backend.draw_text(&volume, 120, 250, 20, DARKGREEN);
+		}
+	}
+}
+
+struct GamePlay;
+
+impl<'a> Screen<Game<'a>> for GamePlay {
+	fn enter(&mut self, game: &mut Game<'a>) {
+		let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_nanos() as u64);
+		game.play_playlist(Playlist::new(GAMEPLAY_MUSIC).shuffled(seed));
+	}
+
+	fn update(&mut self, game: &mut Game<'a>, _delta_time: f32) -> Transition<Game<'a>> {
+		if game.input.pressed("pause") {
+			Transition::Push(Box::new(Pause))
+		} else if game.input.pressed("confirm") {
+			Transition::Replace(Box::new(Ending))
+		} else {
+			Transition::None
+		}
+	}
+
+	fn draw(&self, backend: &mut dyn Backend, _game: &Game<'a>) {
+		backend.draw_rectangle(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT, PURPLE);
+		backend.draw_text("GAMEPLAY SCREEN", 20, 20, 40, MAROON);
+		backend.draw_text("PRESS ENTER or TAP to JUMP to GAMEPLAY SCREEN", 120, 220, 20, MAROON);
+		backend.draw_text("PRESS P to PAUSE", 120, 250, 20, MAROON);
+	}
+}
+
+struct Pause;
+
+impl<'a> Screen<Game<'a>> for Pause {
+	fn enter(&mut self, game: &mut Game<'a>) {
+		if let Some(mixer) = &mut game.mixer {
+			mixer.pause_music();
+		}
+	}
+
+	fn exit(&mut self, game: &mut Game<'a>) {
+		if let Some(mixer) = &mut game.mixer {
+			mixer.resume_music();
+		}
+	}
+
+	fn update(&mut self, game: &mut Game<'a>, _delta_time: f32) -> Transition<Game<'a>> {
+		if game.input.pressed("pause") {
+			Transition::Pop
+		} else {
+			Transition::None
+		}
+	}
+
+	fn draw(&self, backend: &mut dyn Backend, _game: &Game<'a>) {
+		backend.draw_rectangle(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT, fade(BLACK, 0.6));
+		backend.draw_text("PAUSED", 340, 200, 40, RAYWHITE);
+		backend.draw_text("PRESS P to RESUME", 310, 250, 20, RAYWHITE);
+	}
+
+	fn is_overlay(&self) -> bool {
+		true
+	}
+}
+
+struct Ending;
+
+impl<'a> Screen<Game<'a>> for Ending {
+	fn enter(&mut self, game: &mut Game<'a>) {
+		if let Some(mixer) = &mut game.mixer {
+			mixer.stop_music(MUSIC_FADE);
+		}
+	}
+
+	fn update(&mut self, game: &mut Game<'a>, _delta_time: f32) -> Transition<Game<'a>> {
+		if game.input.pressed("confirm") {
+			Transition::Replace(Box::new(Title))
+		} else {
+			Transition::None
+		}
+	}
+
+	fn draw(&self, backend: &mut dyn Backend, _game: &Game<'a>) {
+		backend.draw_rectangle(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT, BLUE);
+		backend.draw_text("ENDING SCREEN", 20, 20, 40, DARKBLUE);
+		backend.draw_text("PRESS ENTER or TAP to JUMP to GAMEPLAY SCREEN", 120, 220, 20, DARKBLUE);
+	}
+}
+
+fn frame<'a>(backend: &mut impl Backend, screens: &mut ScreenStack<Game<'a>>, game: &mut Game<'a>) {
+	let delta_time = backend.frame_time();
+	game.input.update(backend, delta_time);
+	if let Some(mixer) = &mut game.mixer {
+		let step = if game.input.pressed("volume_up") {
+			0.1
+		} else if game.input.pressed("volume_down") {
+			-0.1
+		} else {
+			0.0
+		};
+		if step != 0.0 {
+			mixer.set_volume(MixBus::Master, mixer.volume(MixBus::Master) + step);
+		}
+		mixer.update(delta_time);
+		for err in mixer.drain_errors() {
+			eprintln!("playlist: {err}");
+		}
+	}
+	screens.update(game, delta_time);
+
+	backend.begin_drawing();
+	backend.clear_background(RAYWHITE);
+	screens.draw(backend, game);
+	backend.end_drawing();
+}
+
+// the whole game against any backend, `HeadlessBackend` runs it without a window
+fn screens<'a>(game: &mut Game<'a>) -> ScreenStack<Game<'a>> {
+	let mut screens = ScreenStack::new(SCREEN_WIDTH, SCREEN_HEIGHT, TransitionEffect::Fade { color: BLACK, duration: 0.5 });
+	screens.push(Box::new(Logo { frames_counter: 0 }), game);
+	screens
+}
+
+fn run(backend: &mut impl Backend) {
+	backend.init_window(SCREEN_WIDTH, SCREEN_HEIGHT, "raylib [core] example - basic screen manager");
+
+	let device = AudioDevice::init().map_err(|err| eprintln!("no audio: {err}")).ok();
+	let mut game = Game {
+		input: input_map(),
+		mixer: device.as_ref().map(AudioMixer::new),
+	};
+	if let (Some(mixer), Ok(file)) = (&mut game.mixer, File::open(SETTINGS_PATH)) {
+		if let Err(err) = mixer.load(BufReader::new(file)) {
+			eprintln!("settings {SETTINGS_PATH}: {err}");
+		}
+	}
+	let mut screens = screens(&mut game);
+
+	backend.set_target_fps(60);
+
+	while !backend.window_should_close() && screens.is_running() {
+		frame(backend, &mut screens, &mut game);
+	}
+
+	if let Some(mixer) = &game.mixer {
+		let saved = File::create(SETTINGS_PATH).and_then(|file| {
+			let mut writer = BufWriter::new(file);
+			mixer.save(&mut writer)?;
+			writer.flush()
+		});
+		if let Err(err) = saved {
+			eprintln!("could not save settings {SETTINGS_PATH}: {err}");
+		}
+	}
+	drop(screens);
+	drop(game);
+	backend.close_window();
 }
 
 fn main() {
-    unsafe {
-        const SCREEN_WIDTH: i32 = 800;
-        const SCREEN_HEIGHT: i32 = 450;
-
-        InitWindow(
-            SCREEN_WIDTH,
-            SCREEN_HEIGHT,
-            c"raylib [core] example - basic screen manager".as_ptr(),
-        );
-
-        let mut current_screen = GameScreen::Logo;
-        let mut frames_counter = 0;
-
-        SetTargetFPS(60);
-
-        while WindowShouldClose() == false {
-            match current_screen {
-                GameScreen::Logo => {
-                    frames_counter += 1;
-
-                    // ~2 seconds @60 fps
-                    if frames_counter > 120 {
-                        current_screen = GameScreen::Title;
-                    }
-                }
-                GameScreen::Title => {
-                    if IsKeyPressed(KeyboardKey_KEY_ENTER)
-                        || IsGestureDetected(Gesture_GESTURE_TAP as u32)
-                    {
-                        current_screen = GameScreen::GamePlay;
-                    }
-                }
-                GameScreen::GamePlay => {
-                    if IsKeyPressed(KeyboardKey_KEY_ENTER)
-                        || IsGestureDetected(Gesture_GESTURE_TAP as u32)
-                    {
-                        current_screen = GameScreen::Ending;
-                    }
-                }
-                GameScreen::Ending => {
-                    if IsKeyPressed(KeyboardKey_KEY_ENTER)
-                        || IsGestureDetected(Gesture_GESTURE_TAP as u32)
-                    {
-                        current_screen = GameScreen::Title;
-                    }
-                }
-            }
-
-            BeginDrawing();
-            match current_screen {
-                GameScreen::Logo => {
-                    DrawText(c"LOGO SCREEN".as_ptr(), 20, 20, 40, LIGHTGRAY);
-                    DrawText(c"WAIT for 2 SECONDS".as_ptr(), 290, 220, 10, GRAY);
-                }
-                GameScreen::Title => {
-                    DrawRectangle(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT, GREEN);
-                    DrawText(c"TITLE SCREEN".as_ptr(), 20, 20, 40, DARKGREEN);
-                    DrawText(
-                        c"PRESS ENTER or TAP to JUMP to GAMEPLAY SCREEN".as_ptr(),
-                        120,
-                        220,
-                        20,
-                        DARKGREEN,
-                    );
-                }
-                GameScreen::GamePlay => {
-                    DrawRectangle(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT, PURPLE);
-                    DrawText(c"GAMEPLAY SCREEN".as_ptr(), 20, 20, 40, MAROON);
-                    DrawText(
-                        c"PRESS ENTER or TAP to JUMP to GAMEPLAY SCREEN".as_ptr(),
-                        120,
-                        220,
-                        20,
-                        MAROON,
-                    );
-                }
-                GameScreen::Ending => {
-                    DrawRectangle(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT, BLUE);
-                    DrawText(c"ENDING SCREEN".as_ptr(), 20, 20, 40, DARKBLUE);
-                    DrawText(
-                        c"PRESS ENTER or TAP to JUMP to GAMEPLAY SCREEN".as_ptr(),
-                        120,
-                        220,
-                        20,
-                        DARKBLUE,
-                    );
-                }
-            }
-
-            EndDrawing();
-        }
-
-        CloseWindow();
-    }
+	if backend::HEADLESS {
+		// nothing closes a headless window, so stop after ten seconds of frames
+		run(&mut HeadlessBackend::new(600));
+	} else {
+		run(&mut WindowBackend::default());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use raylib::recording::{check_golden, RecordingBackend};
+
+	use super::*;
+
+	// Plays through every screen with scripted key presses and keeps one frame of each, plus one
+	// halfway through the first fade.
+	#[test]
+	fn screens_match_golden() {
+		let presses = [
+			(210, KeyboardKey_KEY_ENTER),
+			(270, KeyboardKey_KEY_P),
+			(320, KeyboardKey_KEY_P),
+			(360, KeyboardKey_KEY_ENTER),
+		];
+		let snapshots = [60, 136, 200, 260, 310, 340, 420];
+
+		let mut backend = RecordingBackend::new(HeadlessBackend::new(421));
+		backend.init_window(SCREEN_WIDTH, SCREEN_HEIGHT, "test");
+		let mut game = Game {
+			input: input_map(),
+			mixer: None,
+		};
+		let mut screens = screens(&mut game);
+		let mut frames = Vec::new();
+		for number in 0..=420 {
+			backend.inner.input.keys.clear();
+			if let Some((_, key)) = presses.iter().find(|(at, _)| *at == number) {
+				backend.inner.input.keys.insert(*key);
+			}
+			frame(&mut backend, &mut screens, &mut game);
+			if snapshots.contains(&number) {
+				frames.extend_from_slice(backend.last_frame());
+			}
+		}
+
+		if let Err(diff) = check_golden(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/screens.txt"), &frames) {
+			panic!("{diff}");
+		}
+	}
 }
@@ -1,7 +1,34 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use raylib::*;
-
+use std::{
+    env,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    time::Duration,
+};
+
+use raylib::{
+    assets::{Assets, Handle, LoadState},
+    backend::{self, Backend, HeadlessBackend, WindowBackend},
+    camera::{screen_to_world, world_to_screen},
+    console::Console,
+    debug::{CountingBackend, DebugOverlay},
+    game_loop::{GameLoop, ManualClock},
+    input_map::{AxisBinding, Binding, InputMap, SyntheticInput},
+    profile, profile_scope,
+    raster::Canvas,
+    recording::{DrawCommand, RecordingBackend},
+    replay::{Replay, ReplayFrame},
+    tween::{Ease, Timeline, Tween},
+    *,
+};
+
+const SCREEN_WIDTH: i32 = 800;
+const SCREEN_HEIGHT: i32 = 450;
+const LEVEL_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/level.txt");
+// used when the level file is missing or broken
+const BUILT_IN_LEVEL: &str = include_str!("../assets/level.txt");
+const TIMESTEP: Duration = Duration::from_nanos(16_666_667);
 const G: i32 = 400;
 const PLAYER_JUMP_SPEED: f32 = 350.0;
 const PLAYER_HORIZONTAL_SPEED: f32 = 200.0;
@@ -18,6 +45,7 @@ struct EnvItem {
     color: Color,
 }
 
+#[derive(Clone, Copy, PartialEq)]
 enum CameraOption {
     FollowCenter,
     FollowCenterClamp,
@@ -26,19 +54,180 @@ enum CameraOption {
     PlayerPush,
 }
 
+// in the order C cycles through them, the console's camera_mode is an index into it
+const CAMERA_OPTIONS: [CameraOption; 5] = [
+    CameraOption::FollowCenter,
+    CameraOption::FollowCenterClamp,
+    CameraOption::FollowCenterSmooth,
+    CameraOption::FollowCenterHorizontal,
+    CameraOption::PlayerPush,
+];
+
+// `--record <file>` saves the session on exit, `--replay <file>` plays one back and quits at its
+// end, add `--headless` to replay without a window and `--screenshot <file>` to save its last frame
+// as a PNG. `--trace <file>` saves the last frames' profile as a Chrome trace on exit, when built
+// with the `profile` feature. Builds with `raylib/headless` have no window and only replay
+enum Session {
+    Live,
+    Recording(String, Replay),
+    Playing(std::vec::IntoIter<ReplayFrame>),
+}
+
 fn main() {
-    unsafe { render() };
+    let args: Vec<String> = env::args().collect();
+    let session = match (args.get(1).map(String::as_str), args.get(2)) {
+        (Some("--record"), Some(path)) => Session::Recording(path.clone(), Replay::new(0)),
+        (Some("--replay"), Some(path)) => {
+            let replay = File::open(path).and_then(|file| Replay::load(BufReader::new(file)));
+            match replay {
+                Ok(replay) => Session::Playing(replay.into_iter()),
+                Err(err) => {
+                    eprintln!("could not load replay {path}: {err}");
+                    return;
+                }
+            }
+        }
+        _ => Session::Live,
+    };
+    let replaying = matches!(session, Session::Playing(_));
+    if backend::HEADLESS && !replaying {
+        eprintln!("this build has no window, pass --replay <file>");
+        return;
+    }
+    let headless = replaying && (backend::HEADLESS || args.iter().any(|arg| arg == "--headless"));
+    let screenshot = args.iter().position(|arg| arg == "--screenshot").and_then(|index| args.get(index + 1));
+    let mut session = session;
+    let player = if let (true, Some(path)) = (headless, screenshot) {
+        let mut backend = RecordingBackend::default();
+        let player = unsafe { render(&mut backend, &mut session) };
+        save_screenshot(backend.last_frame(), path);
+        player
+    } else if headless {
+        unsafe { render(&mut HeadlessBackend::default(), &mut session) }
+    } else {
+        unsafe { render(&mut WindowBackend::default(), &mut session) }
+    };
+    match &session {
+        Session::Live => {}
+        // compare these between a recording and its replay
+        Session::Recording(..) | Session::Playing(_) => println!(
+            "player at {} {}, speed {}",
+            player.position.x, player.position.y, player.speed
+        ),
+    }
+    if let Session::Recording(path, replay) = &session {
+        let saved = File::create(path).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            replay.save(&mut writer)?;
+            writer.flush()
+        });
+        match saved {
+            Ok(()) => println!("recorded {} frames to {path}", replay.len()),
+            Err(err) => eprintln!("could not save replay {path}: {err}"),
+        }
+    }
+    if let Some(path) = args.iter().position(|arg| arg == "--trace").and_then(|index| args.get(index + 1)) {
+        save_trace(path);
+    }
 }
 
-unsafe fn render() {
-    const SCREEN_WIDTH: i32 = 800;
-    const SCREEN_HEIGHT: i32 = 450;
+// Draws the last frame of a headless replay on the CPU, to compare against a stored screenshot.
+fn save_screenshot(commands: &[DrawCommand], path: &str) {
+    let mut canvas = Canvas::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+    canvas.run(commands);
+    let saved = File::create(path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        canvas.write_png(&mut writer)?;
+        writer.flush()
+    });
+    match saved {
+        Ok(()) => println!("saved screenshot to {path}"),
+        Err(err) => eprintln!("could not save screenshot {path}: {err}"),
+    }
+}
 
-    InitWindow(
-        SCREEN_WIDTH,
-        SCREEN_HEIGHT,
-        c"raylib [core] example - 2d camera".as_ptr(),
-    );
+fn save_trace(path: &str) {
+    if !profile::ENABLED {
+        eprintln!("no profile to save, build with --features profile");
+        return;
+    }
+    let frames = profile::frames();
+    let saved = File::create(path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        profile::write_chrome_trace(&frames, &mut writer)?;
+        writer.flush()
+    });
+    match saved {
+        Ok(()) => println!("saved {} frames of profile to {path}", frames.len()),
+        Err(err) => eprintln!("could not save trace {path}: {err}"),
+    }
+}
+
+// One rectangle per line: x y width height blocking r g b. Empty lines and `#` comments are skipped.
+fn parse_level(text: &str) -> Result<Vec<EnvItem>, String> {
+    let mut env_items = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let values: Vec<f32> = line
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|err| format!("line {}: {err}", number + 1))?;
+        let [x, y, width, height, blocking, r, g, b] = values[..] else {
+            return Err(format!("line {}: expected 8 numbers", number + 1));
+        };
+        env_items.push(EnvItem {
+            rect: Rectangle { x, y, width, height },
+            blocking: blocking as i32,
+            color: Color { r: r as u8, g: g as u8, b: b as u8, a: 255 },
+        });
+    }
+    Ok(env_items)
+}
+
+// the level file's text, or why it could not be read
+fn level_text<'a>(assets: &'a Assets, level: &Handle<String>) -> Result<&'a str, String> {
+    match assets.state(level) {
+        LoadState::Failed(err) => Err(err),
+        _ => assets.get(level).map(String::as_str).ok_or_else(|| "not loaded".to_string()),
+    }
+}
+
+fn load_level(text: Result<&str, String>) -> Vec<EnvItem> {
+    text.and_then(parse_level).unwrap_or_else(|err| {
+        eprintln!("level {LEVEL_PATH}: {err}, using the built-in level");
+        parse_level(BUILT_IN_LEVEL).expect("built-in level")
+    })
+}
+
+// Screen-space text drawn after the camera mode ends.
+fn draw_hud(backend: &mut impl Backend, camera_option: &CameraOption) {
+    backend.draw_text("Controls:", 20, 20, 10, BLACK);
+    backend.draw_text("- Right/Left to move", 40, 40, 10, DARKGRAY);
+    backend.draw_text("- Space to jump", 40, 60, 10, DARKGRAY);
+    backend.draw_text("- Mouse Wheel to Zoom in-out, R to reset zoom", 40, 80, 10, DARKGRAY);
+    backend.draw_text("- C to change camera mode", 40, 100, 10, DARKGRAY);
+    backend.draw_text("Current camera mode:", 20, 120, 10, BLACK);
+    let desc = match camera_option {
+        CameraOption::FollowCenter => "Follow player center",
+        CameraOption::FollowCenterClamp => "Follow player center, but clamp to map edges",
+        CameraOption::FollowCenterSmooth => "Follow player center; smoothed",
+        CameraOption::FollowCenterHorizontal => {
+            "Follow player center horizontally; update player center vertically after landing"
+        }
+        CameraOption::PlayerPush => "Player push camera on getting too close to screen edge",
+    };
+    backend.draw_text(desc, 40, 140, 10, DARKGRAY);
+}
+
+// Runs the game until the window closes or the replay ends, and returns where the player ended up.
+unsafe fn render(backend: &mut impl Backend, session: &mut Session) -> Player {
+    let backend = &mut CountingBackend::new(backend);
+
+    backend.init_window(SCREEN_WIDTH, SCREEN_HEIGHT, "raylib [core] example - 2d camera");
 
     let mut player = Player {
         position: Vector2 { x: 400.0, y: 280.0 },
@@ -46,58 +235,13 @@ unsafe fn render() {
         can_jump: false,
     };
 
-    let env_items = [
-        EnvItem {
-            rect: Rectangle {
-                x: 0.0,
-                y: 0.0,
-                width: 1000.0,
-                height: 400.0,
-            },
-            blocking: 0,
-            color: LIGHTGRAY,
-        },
-        EnvItem {
-            rect: Rectangle {
-                x: 0.0,
-                y: 400.0,
-                width: 1000.0,
-                height: 200.0,
-            },
-            blocking: 1,
-            color: GRAY,
-        },
-        EnvItem {
-            rect: Rectangle {
-                x: 300.0,
-                y: 200.0,
-                width: 400.0,
-                height: 10.0,
-            },
-            blocking: 1,
-            color: GRAY,
-        },
-        EnvItem {
-            rect: Rectangle {
-                x: 250.0,
-                y: 300.0,
-                width: 100.0,
-                height: 10.0,
-            },
-            blocking: 1,
-            color: GRAY,
-        },
-        EnvItem {
-            rect: Rectangle {
-                x: 650.0,
-                y: 300.0,
-                width: 100.0,
-                height: 10.0,
-            },
-            blocking: 1,
-            color: GRAY,
-        },
-    ];
+    // levels reload when the file changes, except in replays so they play back the same
+    let mut assets = Assets::new(0);
+    if matches!(session, Session::Live) {
+        assets.reload_interval = Some(Duration::from_millis(500));
+    }
+    let level: Handle<String> = assets.load(LEVEL_PATH);
+    let mut env_items = load_level(level_text(&assets, &level));
 
     let mut camera = Camera2D {
         offset: Vector2 {
@@ -108,142 +252,264 @@ unsafe fn render() {
         rotation: 0.0,
         zoom: 1.0,
     };
+    // the vertical move of FollowCenterHorizontal after a landing
+    let mut even_out: Option<Tween<f32>> = None;
 
     let mut camera_option = CameraOption::FollowCenter;
-    let camera_descriptions = [
-        c"Follow player center".as_ptr(),
-        c"Follow player center, but clamp to map edges".as_ptr(),
-        c"Follow player center; smoothed".as_ptr(),
-        c"Follow player center horizontally; update player center vertically after landing"
-            .as_ptr(),
-        c"Player push camera on getting too close to screen edge".as_ptr(),
-    ];
-
-    SetTargetFPS(60);
-    while WindowShouldClose() == false {
-        let delta_time = GetFrameTime();
-
-        update_player(&mut player, &env_items, delta_time);
-
-        camera.zoom += GetMouseWheelMove() * 0.05;
-        if camera.zoom > 3.0 {
-            camera.zoom = 3.0;
+
+    let mut input = InputMap::new();
+    input
+        .bind("jump", Binding::Key(KeyboardKey_KEY_SPACE))
+        .bind("jump", Binding::GamepadButton(GamepadButton_GAMEPAD_BUTTON_RIGHT_FACE_DOWN))
+        .bind("reset", Binding::Key(KeyboardKey_KEY_R))
+        .bind("camera_mode", Binding::Key(KeyboardKey_KEY_C))
+        .bind("debug_overlay", Binding::Key(KeyboardKey_KEY_F3))
+        .bind("profile", Binding::Key(KeyboardKey_KEY_F4))
+        .bind_axis(
+            "move",
+            AxisBinding::Buttons {
+                negative: Binding::Key(KeyboardKey_KEY_LEFT),
+                positive: Binding::Key(KeyboardKey_KEY_RIGHT),
+            },
+        )
+        .bind_axis("move", AxisBinding::Gamepad(GamepadAxis_GAMEPAD_AXIS_LEFT_X))
+        .bind_axis("zoom", AxisBinding::MouseWheel);
+
+    // F3 shows the overlay, F4 the profile, ` opens the console
+    let mut debug = DebugOverlay::new();
+    let mut console = Console::new();
+    console
+        .register("teleport", "teleport <x> <y>")
+        .register("reload", "reload level")
+        .add_cvar("camera_mode", 0, "0 to 4, same as pressing C");
+    let mut draw_calls = 0;
+
+    backend.set_target_fps(60);
+    // time comes from the backend, so headless runs step at its fixed frame time
+    let mut game_loop = GameLoop::with_clock(TIMESTEP, ManualClock::default());
+    let mut previous_position = player.position;
+    while !backend.window_should_close() {
+        assets.update();
+        let mut level_changed = false;
+        for event in assets.drain_reloads().filter(|event| event.is(&level)) {
+            match event.result {
+                Ok(()) => level_changed = true,
+                Err(err) => eprintln!("level {LEVEL_PATH}: {err}, keeping the current level"),
+            }
         }
-        if camera.zoom < 0.25 {
-            camera.zoom = 0.25;
+        if level_changed {
+            env_items = load_level(level_text(&assets, &level));
         }
 
-        if IsKeyPressed(KeyboardKey_KEY_R) {
+        // physics runs at a fixed rate, everything else once per frame
+        game_loop.clock.advance(Duration::from_secs_f32(backend.frame_time()));
+        let frame = match &mut *session {
+            Session::Live => {
+                let frame = game_loop.advance();
+                console.update(&backend.gui_input(input.gamepad));
+                // typing into the console doesn't move the player
+                if console.open {
+                    input.update(&SyntheticInput::default(), frame.real_delta);
+                } else {
+                    input.update(backend, frame.real_delta);
+                }
+                frame
+            }
+            Session::Recording(_, replay) => {
+                let frame = game_loop.advance();
+                let snapshot = SyntheticInput::capture(backend, input.gamepad);
+                input.update(&snapshot, frame.real_delta);
+                replay.record(frame, snapshot);
+                frame
+            }
+            Session::Playing(frames) => {
+                let Some(ReplayFrame { frame, input: snapshot }) = frames.next() else {
+                    break;
+                };
+                input.update(&snapshot, frame.real_delta);
+                frame
+            }
+        };
+        for command in console.drain_commands().collect::<Vec<_>>() {
+            match (command.name.as_str(), command.arg::<f32>(0), command.arg::<f32>(1)) {
+                ("teleport", Some(x), Some(y)) => {
+                    player.position = Vector2 { x, y };
+                    player.speed = 0.0;
+                    previous_position = player.position;
+                }
+                ("teleport", _, _) => console.print("usage: teleport <x> <y>"),
+                ("reload", _, _) => match fs::read_to_string(LEVEL_PATH) {
+                    Ok(text) => {
+                        env_items = load_level(Ok(&text));
+                        console.print(format!("loaded {} items", env_items.len()));
+                    }
+                    Err(err) => console.print(format!("could not read {LEVEL_PATH}: {err}")),
+                },
+                _ => {}
+            }
+        }
+        for name in console.drain_changed_cvars().collect::<Vec<_>>() {
+            if name == "camera_mode" {
+                let index = console.cvar_as::<usize>(&name).filter(|index| *index < CAMERA_OPTIONS.len());
+                match index {
+                    Some(index) => camera_option = CAMERA_OPTIONS[index],
+                    None => console.print("camera_mode goes from 0 to 4"),
+                }
+            }
+        }
+        if input.pressed("debug_overlay") {
+            debug.toggle();
+        }
+        if input.pressed("profile") {
+            debug.show_profile = !debug.show_profile;
+        }
+
+        for _ in 0..frame.fixed_steps {
+            profile_scope!("physics");
+            previous_position = player.position;
+            update_player(&mut player, &input, &env_items, frame.fixed_delta);
+        }
+        let delta_time = frame.delta;
+
+        camera.zoom = (camera.zoom + input.axis("zoom") * 0.05).clamp(0.25, 3.0);
+
+        if input.pressed("reset") {
             camera.zoom = 1.0;
             player.position = Vector2 { x: 400.0, y: 280.0 };
+            previous_position = player.position;
         }
 
-        if IsKeyPressed(KeyboardKey_KEY_C) {
-            camera_option = match camera_option {
-                CameraOption::FollowCenter => CameraOption::FollowCenterClamp,
-                CameraOption::FollowCenterClamp => CameraOption::FollowCenterSmooth,
-                CameraOption::FollowCenterSmooth => CameraOption::FollowCenterHorizontal,
-                CameraOption::FollowCenterHorizontal => CameraOption::PlayerPush,
-                CameraOption::PlayerPush => CameraOption::FollowCenter,
-            };
+        // the player as seen this frame, between the last two physics steps
+        let shown_player = Player {
+            position: Vector2Lerp(previous_position, player.position, frame.alpha),
+            speed: player.speed,
+            can_jump: player.can_jump,
+        };
+
+        if input.pressed("camera_mode") {
+            let index = CAMERA_OPTIONS.iter().position(|option| *option == camera_option).unwrap_or(0);
+            let next = (index + 1) % CAMERA_OPTIONS.len();
+            camera_option = CAMERA_OPTIONS[next];
+            console.set_cvar("camera_mode", next);
         }
 
-        match camera_option {
-            CameraOption::FollowCenter => update_camera_center(
-                &mut camera,
-                &player,
-                &env_items,
-                delta_time,
-                SCREEN_WIDTH,
-                SCREEN_HEIGHT,
-            ),
-            CameraOption::FollowCenterClamp => update_camera_center_inside_map(
-                &mut camera,
-                &player,
-                &env_items,
-                delta_time,
-                SCREEN_WIDTH,
-                SCREEN_HEIGHT,
-            ),
-            CameraOption::FollowCenterSmooth => update_camera_center_smooth_follow(
-                &mut camera,
-                &player,
-                &env_items,
-                delta_time,
-                SCREEN_WIDTH,
-                SCREEN_HEIGHT,
-            ),
-            CameraOption::FollowCenterHorizontal => update_camera_even_out_on_landing(
-                &mut camera,
-                &player,
-                &env_items,
-                delta_time,
-                SCREEN_WIDTH,
-                SCREEN_HEIGHT,
-            ),
-            CameraOption::PlayerPush => update_camera_player_bounds_push(
-                &mut camera,
-                &player,
-                &env_items,
-                delta_time,
-                SCREEN_WIDTH,
-                SCREEN_HEIGHT,
-            ),
+        {
+            profile_scope!("camera");
+            match camera_option {
+                CameraOption::FollowCenter => update_camera_center(
+                    &mut camera,
+                    &shown_player,
+                    &env_items,
+                    delta_time,
+                    SCREEN_WIDTH,
+                    SCREEN_HEIGHT,
+                ),
+                CameraOption::FollowCenterClamp => update_camera_center_inside_map(
+                    &mut camera,
+                    &shown_player,
+                    &env_items,
+                    delta_time,
+                    SCREEN_WIDTH,
+                    SCREEN_HEIGHT,
+                ),
+                CameraOption::FollowCenterSmooth => update_camera_center_smooth_follow(
+                    &mut camera,
+                    &shown_player,
+                    &env_items,
+                    delta_time,
+                    SCREEN_WIDTH,
+                    SCREEN_HEIGHT,
+                ),
+                CameraOption::FollowCenterHorizontal => update_camera_even_out_on_landing(
+                    &mut camera,
+                    &mut even_out,
+                    &shown_player,
+                    &env_items,
+                    delta_time,
+                    SCREEN_WIDTH,
+                    SCREEN_HEIGHT,
+                ),
+                CameraOption::PlayerPush => update_camera_player_bounds_push(
+                    &mut camera,
+                    &shown_player,
+                    &env_items,
+                    delta_time,
+                    SCREEN_WIDTH,
+                    SCREEN_HEIGHT,
+                ),
+            }
         }
-        BeginDrawing();
+        {
+            profile_scope!("draw");
+            backend.begin_drawing();
 
-        ClearBackground(LIGHTGRAY);
-        BeginMode2D(camera);
+            backend.clear_background(LIGHTGRAY);
+            backend.begin_mode_2d(camera);
 
-        for env_item in &env_items {
-            DrawRectangleRec(env_item.rect, env_item.color);
-        }
+            for env_item in &env_items {
+                backend.draw_rectangle_rec(env_item.rect, env_item.color);
+            }
 
-        let player_rect = Rectangle {
-            x: player.position.x - 20.0,
-            y: player.position.y - 40.0,
-            width: 40.0,
-            height: 40.0,
-        };
-        DrawRectangleRec(player_rect, RED);
-        DrawCircleV(player.position, 5.0, GOLD);
-        EndMode2D();
-
-        DrawText(c"Controls:".as_ptr(), 20, 20, 10, BLACK);
-        DrawText(c"- Right/Left to move".as_ptr(), 40, 40, 10, DARKGRAY);
-        DrawText(c"- Space to jump".as_ptr(), 40, 60, 10, DARKGRAY);
-        DrawText(
-            c"- Mouse Wheel to Zoom in-out, R to reset zoom".as_ptr(),
-            40,
-            80,
-            10,
-            DARKGRAY,
-        );
-        DrawText(c"- C to change camera mode".as_ptr(), 40, 100, 10, DARKGRAY);
-        DrawText(c"Current camera mode:".as_ptr(), 20, 120, 10, BLACK);
-        let desc = match camera_option {
-            CameraOption::FollowCenter => camera_descriptions[0],
-            CameraOption::FollowCenterClamp => camera_descriptions[1],
-            CameraOption::FollowCenterSmooth => camera_descriptions[2],
-            CameraOption::FollowCenterHorizontal => camera_descriptions[3],
-            CameraOption::PlayerPush => camera_descriptions[4],
-        };
-        DrawText(desc, 40, 140, 10, DARKGRAY);
+            let player_rect = Rectangle {
+                x: shown_player.position.x - 20.0,
+                y: shown_player.position.y - 40.0,
+                width: 40.0,
+                height: 40.0,
+            };
+            backend.draw_rectangle_rec(player_rect, RED);
+            backend.draw_circle_v(shown_player.position, 5.0, GOLD);
 
-        EndDrawing();
+            for env_item in env_items.iter().filter(|env_item| env_item.blocking != 0) {
+                debug.rectangle(env_item.rect, BLUE);
+            }
+            debug.rectangle(player_rect, MAROON);
+            let view_min = screen_to_world(Vector2 { x: 0.0, y: 0.0 }, &camera);
+            let view_max = screen_to_world(
+                Vector2 {
+                    x: SCREEN_WIDTH as f32,
+                    y: SCREEN_HEIGHT as f32,
+                },
+                &camera,
+            );
+            debug.rectangle(
+                Rectangle {
+                    x: view_min.x + 2.0,
+                    y: view_min.y + 2.0,
+                    width: view_max.x - view_min.x - 4.0,
+                    height: view_max.y - view_min.y - 4.0,
+                },
+                DARKGREEN,
+            );
+            debug.draw_world(backend);
+            backend.end_mode_2d();
+
+            draw_hud(backend, &camera_option);
+
+            debug.record_frame(frame.real_delta);
+            debug.counter("draw calls", draw_calls);
+            debug.counter("env items", env_items.len());
+            debug.counter("camera", format!("{:.0} {:.0} x{:.2}", camera.target.x, camera.target.y, camera.zoom));
+            debug.draw(backend);
+            console.draw(backend);
+        }
+        {
+            // includes waiting for the frame rate limit
+            profile_scope!("end_drawing");
+            backend.end_drawing();
+        }
+        draw_calls = backend.take_draw_calls();
+        if profile::ENABLED {
+            profile::finish_frame();
+        }
     }
 
-    CloseWindow();
+    backend.close_window();
+    player
 }
 
-unsafe fn update_player(player: &mut Player, env_items: &[EnvItem], delta_time: f32) {
-    if IsKeyDown(KeyboardKey_KEY_LEFT) {
-        player.position.x -= PLAYER_HORIZONTAL_SPEED * delta_time;
-    }
-    if IsKeyDown(KeyboardKey_KEY_RIGHT) {
-        player.position.x += PLAYER_HORIZONTAL_SPEED * delta_time;
-    }
-    if IsKeyDown(KeyboardKey_KEY_SPACE) && player.can_jump {
+fn update_player(player: &mut Player, input: &InputMap, env_items: &[EnvItem], delta_time: f32) {
+    player.position.x += input.axis("move") * PLAYER_HORIZONTAL_SPEED * delta_time;
+    if input.down("jump") && player.can_jump {
         player.speed = -PLAYER_JUMP_SPEED;
         player.can_jump = false;
     }
@@ -263,7 +529,7 @@ unsafe fn update_player(player: &mut Player, env_items: &[EnvItem], delta_time:
         }
     }
 
-    if hit_obsticle == false {
+    if !hit_obsticle {
         player.position.y += player.speed * delta_time;
         player.speed += G as f32 * delta_time;
         player.can_jump = false;
@@ -306,15 +572,15 @@ unsafe fn update_camera_center_inside_map(
     let mut max_x = -1000.0f32;
     let mut max_y = -1000.0f32;
 
-    for env_item in env_items.into_iter().skip(1) {
+    for env_item in env_items.iter().skip(1) {
         min_x = min_x.min(env_item.rect.x);
         max_x = max_x.max(env_item.rect.x + env_item.rect.width);
         min_y = min_y.min(env_item.rect.y);
         max_y = max_y.max(env_item.rect.y + env_item.rect.height);
     }
 
-    let max = GetWorldToScreen2D(Vector2 { x: max_x, y: max_y }, *camera);
-    let min = GetWorldToScreen2D(Vector2 { x: min_x, y: min_y }, *camera);
+    let max = world_to_screen(Vector2 { x: max_x, y: max_y }, camera);
+    let min = world_to_screen(Vector2 { x: min_x, y: min_y }, camera);
 
     if max.x < width as f32 {
         camera.offset.x = width as f32 - (max.x - (width as f32 / 2.0));
@@ -361,15 +627,15 @@ unsafe fn update_camera_center_smooth_follow(
 
 unsafe fn update_camera_even_out_on_landing(
     camera: &mut Camera2D,
+    even_out: &mut Option<Tween<f32>>,
     player: &Player,
     _env_items: &[EnvItem],
     delta_time: f32,
     width: i32,
     height: i32,
 ) {
+    // average speed, the move eases in and out
     const EVEN_OUT_SPEED: f32 = 700.0;
-    static mut EVENING_OUT: bool = false;
-    static mut EVENING_OUT_TARGET: f32 = 0.0;
 
     camera.offset = Vector2 {
         x: width as f32 / 2.0,
@@ -377,24 +643,16 @@ unsafe fn update_camera_even_out_on_landing(
     };
     camera.target.x = player.position.x;
 
-    if EVENING_OUT {
-        if EVENING_OUT_TARGET > camera.target.y {
-            camera.target.y += EVEN_OUT_SPEED * delta_time;
-            if camera.target.y > EVENING_OUT_TARGET {
-                camera.target.y = EVENING_OUT_TARGET;
-                EVENING_OUT = false;
-            }
-        } else {
-            camera.target.y -= EVEN_OUT_SPEED * delta_time;
-            if camera.target.y < EVENING_OUT_TARGET {
-                camera.target.y = EVENING_OUT_TARGET;
-                EVENING_OUT = false;
-            }
+    if let Some(tween) = even_out {
+        tween.advance(delta_time);
+        camera.target.y = tween.value();
+        if tween.is_finished() {
+            *even_out = None;
         }
     } else {
         if player.can_jump && player.speed == 0.0 && player.position.y != camera.target.y {
-            EVENING_OUT = true;
-            EVENING_OUT_TARGET = player.position.y;
+            let (from, to) = (camera.target.y, player.position.y);
+            *even_out = Some(Tween::new(from, to, (to - from).abs() / EVEN_OUT_SPEED).ease(Ease::SineInOut));
         }
     }
 }
@@ -409,20 +667,20 @@ unsafe fn update_camera_player_bounds_push(
 ) {
     static mut BBOX: Vector2 = Vector2 { x: 0.2, y: 0.2 };
 
-    let bbox_world_min = GetScreenToWorld2D(
+    let bbox_world_min = screen_to_world(
         Vector2 {
             x: (1.0 - BBOX.x) * 0.5 * (width as f32),
             y: (1.0 - BBOX.y) * 0.5 * (height as f32),
         },
-        *camera,
+        camera,
     );
 
-    let bbox_world_max = GetScreenToWorld2D(
+    let bbox_world_max = screen_to_world(
         Vector2 {
             x: (1.0 + BBOX.x) * 0.5 * (width as f32),
             y: (1.0 + BBOX.y) * 0.5 * (height as f32),
         },
-        *camera,
+        camera,
     );
 
     camera.offset = Vector2 {
@@ -443,3 +701,92 @@ unsafe fn update_camera_player_bounds_push(
         camera.target.y = bbox_world_min.y + (player.position.y - bbox_world_max.y);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use raylib::recording::check_golden;
+
+    use super::*;
+
+    fn golden(name: &str) -> String {
+        format!("{}/tests/golden/{name}", env!("CARGO_MANIFEST_DIR"))
+    }
+
+    #[test]
+    fn hud_matches_golden() {
+        let mut backend = RecordingBackend::default();
+        for camera_option in &CAMERA_OPTIONS {
+            draw_hud(&mut backend, camera_option);
+        }
+        if let Err(diff) = check_golden(golden("hud.txt"), backend.commands()) {
+            panic!("{diff}");
+        }
+    }
+
+    #[test]
+    fn replay_ends_where_the_recording_did() {
+        // run right, jump, then back left and stand still
+        let mut backend = HeadlessBackend::new(240);
+        for frame in 0..240 {
+            let mut input = SyntheticInput::default();
+            match frame {
+                0..=79 => input.keys.extend([KeyboardKey_KEY_RIGHT]),
+                80..=89 => input.keys.extend([KeyboardKey_KEY_RIGHT, KeyboardKey_KEY_SPACE]),
+                90..=179 => input.keys.extend([KeyboardKey_KEY_LEFT]),
+                _ => {}
+            }
+            backend.script.push_back(input);
+        }
+        backend.input = backend.script.pop_front().unwrap();
+        let mut session = Session::Recording(String::new(), Replay::new(0));
+        let recorded = unsafe { render(&mut backend, &mut session) };
+        let Session::Recording(_, replay) = session else { unreachable!() };
+
+        let mut saved = Vec::new();
+        replay.save(&mut saved).unwrap();
+        let loaded = Replay::load(saved.as_slice()).unwrap();
+        assert_eq!(loaded.len(), 240);
+
+        let mut session = Session::Playing(loaded.into_iter());
+        let replayed = unsafe { render(&mut HeadlessBackend::default(), &mut session) };
+
+        assert_ne!(recorded.position.x, 400.0);
+        assert_eq!(replayed.position.x.to_bits(), recorded.position.x.to_bits());
+        assert_eq!(replayed.position.y.to_bits(), recorded.position.y.to_bits());
+        assert_eq!(replayed.speed.to_bits(), recorded.speed.to_bits());
+    }
+
+    #[test]
+    fn landing_evens_the_camera_out() {
+        let mut camera = Camera2D {
+            offset: Vector2 { x: 0.0, y: 0.0 },
+            target: Vector2 { x: 0.0, y: 200.0 },
+            rotation: 0.0,
+            zoom: 1.0,
+        };
+        let mut even_out = None;
+        let mut player = Player {
+            position: Vector2 { x: 50.0, y: 340.0 },
+            speed: 0.0,
+            can_jump: true,
+        };
+        let mut heights = Vec::new();
+        for _ in 0..20 {
+            unsafe { update_camera_even_out_on_landing(&mut camera, &mut even_out, &player, &[], 0.05, 800, 450) };
+            heights.push(camera.target.y);
+        }
+        // 140 at 700 per second takes 0.2 seconds, starting the frame after the landing
+        assert_eq!(heights[0], 200.0);
+        assert!(heights[1] > 200.0 && heights[1] < heights[2] && heights[3] < 340.0);
+        assert_eq!(heights[4], 340.0);
+        assert!(even_out.is_none());
+        assert_eq!(camera.target.x, 50.0);
+
+        // in the air the camera only follows sideways
+        player.position = Vector2 { x: 80.0, y: 100.0 };
+        player.can_jump = false;
+        unsafe { update_camera_even_out_on_landing(&mut camera, &mut even_out, &player, &[], 0.05, 800, 450) };
+        assert_eq!(camera.target, Vector2 { x: 80.0, y: 340.0 });
+        assert!(even_out.is_none());
+    }
+}
@@ -0,0 +1,28 @@
+#version 330
+
+// Copy of the fog renderer's built-in shader, loaded by the example and reloaded when it changes.
+in vec2 fragTexCoord;
+in vec4 fragColor;
+
+uniform sampler2D texture0;
+uniform vec4 colDiffuse;
+uniform vec2 texelSize;
+uniform float softness;
+
+out vec4 finalColor;
+
+void main()
+{
+	vec4 sum = vec4(0.0);
+	float total = 0.0;
+	for (int y = -2; y <= 2; y++)
+	{
+		for (int x = -2; x <= 2; x++)
+		{
+			float weight = (3.0 - abs(float(x)))*(3.0 - abs(float(y)));
+			sum += texture(texture0, fragTexCoord + vec2(x, y)*texelSize*softness*0.5)*weight;
+			total += weight;
+		}
+	}
+	finalColor = (sum/total)*colDiffuse*fragColor;
+}
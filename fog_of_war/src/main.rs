@@ -3,305 +3,425 @@
 
 use std::{
 	ffi::CString,
+	fs::File,
+	io::{BufWriter, Write},
 	thread,
-	time::{Duration, Instant},
+	time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use bevy_ecs::{
-	prelude::*,
-	schedule::{ExecutorKind, ScheduleLabel},
+use bevy_ecs::prelude::*;
+use raylib::{
+	assets::{Assets, Handle},
+	backend::Backend,
+	console::Console,
+	debug::DebugOverlay,
+	fog::{FogOfWar, TeamId, ViewerId},
+	fog_render::{FogRenderer, FogStyle},
+	input_map::{AxisBinding, Binding, InputMap},
+	mapgen::{self, Algorithm, CaveParams, Tile},
+	profile, profile_scope,
+	tilemap::TileMap,
+	*,
 };
-use raylib::*;
+use raylib_ecs::prelude::*;
 
 const SCREEN_WIDTH: i32 = 800;
 const SCREEN_HEIGHT: i32 = 450;
 const MAX_FPS: i32 = 120;
 const TIMESTEP: Duration = Duration::from_micros(15625);
 
-#[derive(ScheduleLabel, Clone, PartialEq, Eq, Debug, Hash)]
-struct Update;
-
-#[derive(ScheduleLabel, Clone, PartialEq, Eq, Debug, Hash)]
-struct FixedUpdate;
-
-#[derive(ScheduleLabel, Clone, PartialEq, Eq, Debug, Hash)]
-struct Render;
-
+const MAP_TILES_X: i32 = 25;
+const MAP_TILES_Y: i32 = 15;
 const MAP_TILE_SIZE: i32 = 32;
 const PLAYER_SIZE: i32 = 16;
 const PLAYER_TILE_VISIBILITY: i32 = 2;
+const PLAYER_TEAM: TeamId = TeamId(0);
+// edit it while the example runs to see the change
+const FOG_SHADER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/fog.fs");
 
 #[derive(Component)]
 struct Map {
+	seed: u64,
 	tiles_x: i32,
 	tiles_y: i32,
 	tile_ids: Box<[i8]>,
-	tile_fog: Box<[i8]>,
-	fog_of_war: RenderTexture2D,
+	spawn_tile: (i32, i32),
+	fog: FogOfWar<i8>,
+	explored_tiles: i32,
 }
 
-impl Drop for Map {
-	fn drop(&mut self) {
-		unsafe { UnloadRenderTexture(self.fog_of_war) };
+impl TileMap for Map {
+	fn width(&self) -> i32 {
+		self.tiles_x
+	}
+
+	fn height(&self) -> i32 {
+		self.tiles_y
 	}
 }
 
 impl Map {
-	fn new() -> Self {
-		unsafe {
-			let mut map = Self {
-				tiles_x: 25,
-				tiles_y: 15,
-				tile_ids: Box::new([0i8; (25 * 15)]),
-				tile_fog: Box::new([0i8; (25 * 15)]),
-				fog_of_war: LoadRenderTexture(25, 15),
-			};
-			for i in 0..(map.tiles_x * map.tiles_y) as usize {
-				map.tile_ids[i] = (GetRandomValue(0, 1) & 0x0000FF) as i8;
-			}
-			SetTextureFilter(map.fog_of_war.texture, TextureFilter_TEXTURE_FILTER_BILINEAR);
-			map
+	fn new(seed: u64) -> Self {
+		// walls are tile 1, every floor tile is reachable. A cave can fill up with wall, the following seeds
+		// are tried then so there is always a floor tile to spawn on
+		let (seed, tiles, spawn_tile) = (0..)
+			.map(|n| seed.wrapping_add(n))
+			.find_map(|seed| {
+				let tiles = mapgen::generate(MAP_TILES_X, MAP_TILES_Y, seed, &Algorithm::Caves(CaveParams::default()));
+				let spawn_tile = tiles.nearest(6, 4, Tile::Floor)?;
+				Some((seed, tiles, spawn_tile))
+			})
+			.expect("caves with floor");
+		Self {
+			seed,
+			tiles_x: MAP_TILES_X,
+			tiles_y: MAP_TILES_Y,
+			tile_ids: tiles.to_ids(1, 0),
+			spawn_tile,
+			fog: FogOfWar::new(MAP_TILES_X, MAP_TILES_Y),
+			explored_tiles: 0,
 		}
 	}
 
-	fn render(&self) {
-		unsafe {
-			for y in 0..self.tiles_y {
-				for x in 0..self.tiles_x {
-					let tile_color = if self.tile_ids[(y * self.tiles_x + x) as usize] == 1 {
-						BLUE
-					} else {
-						Fade(BLUE, 0.9)
-					};
-					DrawRectangle(x * MAP_TILE_SIZE, y * MAP_TILE_SIZE, MAP_TILE_SIZE, MAP_TILE_SIZE, tile_color);
-					DrawRectangleLines(x * MAP_TILE_SIZE, y * MAP_TILE_SIZE, MAP_TILE_SIZE, MAP_TILE_SIZE, Fade(DARKBLUE, 0.5));
-				}
+	// the tiles go through the backend so they can be recorded, the fog is drawn separately
+	fn render(&self, backend: &mut dyn Backend) {
+		for y in 0..self.tiles_y {
+			for x in 0..self.tiles_x {
+				let tile_color = if self.tile_ids[(y * self.tiles_x + x) as usize] == 1 {
+					BLUE
+				} else {
+					fade(BLUE, 0.9)
+				};
+				let tile = Rectangle {
+					x: (x * MAP_TILE_SIZE) as f32,
+					y: (y * MAP_TILE_SIZE) as f32,
+					width: MAP_TILE_SIZE as f32,
+					height: MAP_TILE_SIZE as f32,
+				};
+				backend.draw_rectangle_rec(tile, tile_color);
+				backend.draw_rectangle_lines_ex(tile, 1.0, fade(DARKBLUE, 0.5));
 			}
-
-			// draw fog of war (scaled to full map, bilinear filtering)
-			DrawTexturePro(
-				self.fog_of_war.texture,
-				Rectangle {
-					x: 0.0,
-					y: 0.0,
-					width: self.fog_of_war.texture.width as f32,
-					height: -self.fog_of_war.texture.height as f32,
-				},
-				Rectangle {
-					x: 0.0,
-					y: 0.0,
-					width: (self.tiles_x * MAP_TILE_SIZE) as f32,
-					height: (self.tiles_y * MAP_TILE_SIZE) as f32,
-				},
-				Vector2 { x: 0.0, y: 0.0 },
-				0.0,
-				WHITE,
-			);
 		}
 	}
 
-	fn prepare_fow_texture(&self) {
-		unsafe {
-			BeginTextureMode(self.fog_of_war);
-			ClearBackground(BLANK);
-			for y in 0..self.tiles_y {
-				for x in 0..self.tiles_x {
-					if self.tile_fog[(y * self.tiles_x + x) as usize] == 0 {
-						DrawRectangle(x, y, 1, 1, BLACK);
-					} else if self.tile_fog[(y * self.tiles_x + x) as usize] == 2 {
-						DrawRectangle(x, y, 1, 1, Fade(BLACK, 0.8));
-					}
-				}
-			}
-			EndTextureMode();
+	// the built-in fog shader stands in until the one from disk is loaded
+	fn render_fog(&self, fog_renderer: &FogRenderer, shader: Option<&Shader>) {
+		// scaled to full map, soft edges
+		let dest = Rectangle {
+			x: 0.0,
+			y: 0.0,
+			width: (self.tiles_x * MAP_TILE_SIZE) as f32,
+			height: (self.tiles_y * MAP_TILE_SIZE) as f32,
+		};
+		match shader {
+			Some(shader) => fog_renderer.draw_with_shader(dest, *shader),
+			None => fog_renderer.draw(dest),
 		}
 	}
+
+	fn update_fog_texture(&self, fog_renderer: &mut FogRenderer, delta_time: f32) {
+		fog_renderer.update(self.fog.states(PLAYER_TEAM), delta_time);
+	}
 }
 
 #[derive(Component)]
 struct Renderable;
 
-#[derive(Component)]
-struct Position {
-	x: f32,
-	y: f32,
-}
-
-impl Position {
-	fn to_vector2(&self) -> Vector2 {
-		Vector2 { x: self.x, y: self.y }
-	}
-
-	fn get_tile_position(&self) -> (i32, i32) {
-		(
-			(self.x as i32 + (MAP_TILE_SIZE / 2)) / MAP_TILE_SIZE,
-			(self.y as i32 + (MAP_TILE_SIZE / 2)) / MAP_TILE_SIZE,
-		)
-	}
+fn get_tile_position(position: Vector2) -> (i32, i32) {
+	(
+		(position.x as i32 + (MAP_TILE_SIZE / 2)) / MAP_TILE_SIZE,
+		(position.y as i32 + (MAP_TILE_SIZE / 2)) / MAP_TILE_SIZE,
+	)
 }
 
 #[derive(Component)]
 struct Player;
 
+#[derive(Component)]
+struct FogViewer(ViewerId);
+
 #[derive(Bundle)]
 struct BundlePlayer {
 	player: Player,
-	position: Position,
+	transform: Transform2D,
+	shape: Shape,
+	z_index: ZIndex,
+	viewer: FogViewer,
 }
 
 impl BundlePlayer {
-	fn new() -> Self {
+	fn new(viewer: ViewerId, (tile_x, tile_y): (i32, i32)) -> Self {
+		// centered on the tile
+		let offset = (MAP_TILE_SIZE - PLAYER_SIZE) / 2;
 		Self {
 			player: Player,
-			position: Position { x: 180.0, y: 130.0 },
+			transform: Transform2D::from_xy((tile_x * MAP_TILE_SIZE + offset) as f32, (tile_y * MAP_TILE_SIZE + offset) as f32),
+			shape: Shape::rectangle(PLAYER_SIZE as f32, PLAYER_SIZE as f32, RED),
+			// above the map tiles
+			z_index: ZIndex(1),
+			viewer: FogViewer(viewer),
 		}
 	}
 }
 
-fn render_map(mut map_query: Query<&mut Map>) {
+struct FogShader(Handle<Shader>);
+
+fn render_map(
+	map_query: Query<&Map>,
+	time: Res<Time>,
+	mut assets: NonSendMut<Assets>,
+	fog_shader: NonSend<FogShader>,
+	mut fog_renderer: NonSendMut<FogRenderer>,
+	mut window: NonSendMut<Window>,
+) {
+	assets.update();
+	for event in assets.drain_reloads() {
+		match event.result {
+			Ok(()) => println!("reloaded {}", event.path),
+			Err(error) => eprintln!("could not reload {}, keeping the previous version: {error}", event.path),
+		}
+	}
+
 	let map = map_query.single();
-	map.prepare_fow_texture();
-	map.render();
+	{
+		profile_scope!("fog texture");
+		map.update_fog_texture(&mut fog_renderer, time.delta());
+	}
+	map.render(window.backend());
+	map.render_fog(&fog_renderer, assets.get(&fog_shader.0));
 }
 
-fn render_player(pos_query: Query<&Position, With<Player>>) {
-	unsafe {
-		let pos = pos_query.single();
-		DrawRectangleV(
-			pos.to_vector2(),
-			Vector2 {
-				x: PLAYER_SIZE as f32,
-				y: PLAYER_SIZE as f32,
-			},
-			RED,
-		);
-	}
+fn render_overlay(
+	transform_query: Query<&Transform2D, With<Player>>,
+	map_query: Query<&Map>,
+	entities: Query<Entity>,
+	time: Res<Time>,
+	mut debug: NonSendMut<DebugOverlay>,
+	console: NonSend<Console>,
+	fog_renderer: NonSend<FogRenderer>,
+	mut window: NonSendMut<Window>,
+) {
+	let player = transform_query.single().translation;
+	let (player_tile_x, player_tile_y) = get_tile_position(player);
+	let map = map_query.single();
+
+	debug.record_frame(time.real_delta());
+	debug.counter("entities", entities.iter().count());
+	debug.counter("fog softness", fog_renderer.style.softness);
+	let collider = Rectangle {
+		x: player.x,
+		y: player.y,
+		width: PLAYER_SIZE as f32,
+		height: PLAYER_SIZE as f32,
+	};
+	debug.rectangle(collider, LIME);
+	let backend = window.backend();
+	debug.draw_world(backend);
+	debug.draw(backend);
+
+	backend.draw_text(&format!("Current tile: [{player_tile_x},{player_tile_y}]"), 10, 10, 20, RAYWHITE);
+	backend.draw_text(
+		&format!("Explored: {}/{}  Seed: {}", map.explored_tiles, map.tiles_x * map.tiles_y, map.seed),
+		10,
+		70,
+		20,
+		RAYWHITE,
+	);
+	backend.draw_text("ARROW KEYS to move", 10, SCREEN_HEIGHT - 25, 20, RAYWHITE);
+	console.draw(backend);
 }
 
-fn render_overlay(pos_query: Query<&Position, With<Player>>) {
-	let (player_tile_x, player_tile_y) = pos_query.single().get_tile_position();
-	unsafe {
-		DrawFPS(10, 40);
-		DrawText(
-			TextFormat(c"Current tile: [%i,%i]".as_ptr(), player_tile_x, player_tile_y),
-			10,
-			10,
-			20,
-			RAYWHITE,
-		);
-		DrawText(c"ARROW KEYS to move".as_ptr(), 10, SCREEN_HEIGHT - 25, 20, RAYWHITE);
+// F3 toggles the overlay, F4 its profile, ` the console
+fn handle_console(
+	input: Res<Input>,
+	mut console: NonSendMut<Console>,
+	mut debug: NonSendMut<DebugOverlay>,
+	mut transform_query: Query<&mut Transform2D, With<Player>>,
+	map_query: Query<&Map>,
+	mut fog_renderer: NonSendMut<FogRenderer>,
+) {
+	if input.key_pressed(KeyboardKey_KEY_F3) {
+		debug.toggle();
 	}
-}
+	if input.key_pressed(KeyboardKey_KEY_F4) {
+		debug.show_profile = !debug.show_profile;
+	}
+	console.update(&input.gui_input());
 
-fn handle_input(mut pos_query: Query<&mut Position, With<Player>>, map: Query<&Map>) {
-	unsafe {
-		let mut pos = pos_query.single_mut();
-		let map = map.single();
-		// player movement
-		if IsKeyDown(KeyboardKey_KEY_RIGHT) {
-			pos.x += 5.0;
-		}
-		if IsKeyDown(KeyboardKey_KEY_LEFT) {
-			pos.x -= 5.0;
-		}
-		if IsKeyDown(KeyboardKey_KEY_DOWN) {
-			pos.y += 5.0;
+	let map = map_query.single();
+	for command in console.drain_commands().collect::<Vec<_>>() {
+		match (command.name.as_str(), command.arg::<i32>(0), command.arg::<i32>(1)) {
+			("teleport", Some(x), Some(y)) if (0..map.tiles_x).contains(&x) && (0..map.tiles_y).contains(&y) => {
+				let offset = (MAP_TILE_SIZE - PLAYER_SIZE) / 2;
+				transform_query.single_mut().translation = Vector2 {
+					x: (x * MAP_TILE_SIZE + offset) as f32,
+					y: (y * MAP_TILE_SIZE + offset) as f32,
+				};
+			}
+			("trace", _, _) => match command.args.first() {
+				Some(path) => {
+					let written = File::create(path).and_then(|file| {
+						let mut writer = BufWriter::new(file);
+						profile::write_chrome_trace(&profile::frames(), &mut writer)?;
+						writer.flush()
+					});
+					match written {
+						Ok(()) => console.print(format!("wrote {path}")),
+						Err(err) => console.print(format!("could not write {path}: {err}")),
+					}
+				}
+				None => console.print("usage: trace <file>"),
+			},
+			("teleport", _, _) => console.print(format!("usage: teleport <x> <y>, tiles inside {}x{}", map.tiles_x, map.tiles_y)),
+			_ => {}
 		}
-		if IsKeyDown(KeyboardKey_KEY_UP) {
-			pos.y -= 5.0;
+	}
+	for name in console.drain_changed_cvars().collect::<Vec<_>>() {
+		if name == "fog_softness" {
+			fog_renderer.style.softness = console.cvar_as::<f32>(&name).unwrap_or(0.0).max(0.0);
 		}
+	}
+}
 
-		// collisions
-		// x axis
-		if pos.x < 0.0 {
-			pos.x = 0.0;
-		} else if pos.x + PLAYER_SIZE as f32 > (map.tiles_x * MAP_TILE_SIZE) as f32 {
-			pos.x = ((map.tiles_x * MAP_TILE_SIZE) - PLAYER_SIZE) as f32;
-		}
-		// y axis
-		if pos.y < 0.0 {
-			pos.y = 0.0;
-		} else if pos.y + PLAYER_SIZE as f32 > (map.tiles_y * MAP_TILE_SIZE) as f32 {
-			pos.y = ((map.tiles_y * MAP_TILE_SIZE) - PLAYER_SIZE) as f32;
-		}
+fn input_map() -> InputMap {
+	let mut input = InputMap::new();
+	for (axis, negative, positive, gamepad_axis) in [
+		("move_x", KeyboardKey_KEY_LEFT, KeyboardKey_KEY_RIGHT, GamepadAxis_GAMEPAD_AXIS_LEFT_X),
+		("move_y", KeyboardKey_KEY_UP, KeyboardKey_KEY_DOWN, GamepadAxis_GAMEPAD_AXIS_LEFT_Y),
+	] {
+		input
+			.bind_axis(
+				axis,
+				AxisBinding::Buttons {
+					negative: Binding::Key(negative),
+					positive: Binding::Key(positive),
+				},
+			)
+			.bind_axis(axis, AxisBinding::Gamepad(gamepad_axis));
 	}
+	input
 }
 
-fn handle_fog(mut map_query: Query<&mut Map>, pos_query: Query<&Position, With<Player>>) {
-	let mut map = map_query.single_mut();
-	let player = pos_query.single();
-	// previous visited tiles are set to partial fog
-	for i in 0..(map.tiles_x * map.tiles_y) as usize {
-		if map.tile_fog[i] == 1 {
-			map.tile_fog[i] = 2;
-		}
+fn handle_input(mut transform_query: Query<&mut Transform2D, With<Player>>, map: Query<&Map>, actions: Res<Actions>, console: NonSend<Console>) {
+	// the arrow keys browse the console history while it is open
+	if console.open {
+		return;
+	}
+	let pos = &mut transform_query.single_mut().translation;
+	let map = map.single();
+	// player movement
+	let movement = actions.axis_pair("move_x", "move_y");
+	pos.x += movement.x * 5.0;
+	pos.y += movement.y * 5.0;
+
+	// collisions
+	// x axis
+	if pos.x < 0.0 {
+		pos.x = 0.0;
+	} else if pos.x + PLAYER_SIZE as f32 > (map.tiles_x * MAP_TILE_SIZE) as f32 {
+		pos.x = ((map.tiles_x * MAP_TILE_SIZE) - PLAYER_SIZE) as f32;
 	}
+	// y axis
+	if pos.y < 0.0 {
+		pos.y = 0.0;
+	} else if pos.y + PLAYER_SIZE as f32 > (map.tiles_y * MAP_TILE_SIZE) as f32 {
+		pos.y = ((map.tiles_y * MAP_TILE_SIZE) - PLAYER_SIZE) as f32;
+	}
+}
 
-	let tiles_x = map.tiles_x;
-	let tiles_y = map.tiles_y;
-	let (player_tile_x, player_tile_y) = player.get_tile_position();
-	// check visibility and update fog
-	for y in (player_tile_y - PLAYER_TILE_VISIBILITY)..(player_tile_y + PLAYER_TILE_VISIBILITY) {
-		for x in (player_tile_x - PLAYER_TILE_VISIBILITY)..(player_tile_x + PLAYER_TILE_VISIBILITY) {
-			if x >= 0 && x < tiles_x && y >= 0 && y < tiles_y {
-				map.tile_fog[(y * tiles_x + x) as usize] = 1;
-			}
-		}
+fn handle_fog(mut map_query: Query<&mut Map>, viewer_query: Query<(&Transform2D, &FogViewer)>) {
+	let mut map = map_query.single_mut();
+	let Map {
+		tiles_x,
+		tile_ids,
+		fog,
+		explored_tiles,
+		..
+	} = &mut *map;
+
+	for (transform, viewer) in &viewer_query {
+		fog.set_viewer_position(viewer.0, get_tile_position(transform.translation));
 	}
+	// visible tiles remember their tile id, previously visible tiles fall back to explored
+	profile_scope!("fog update");
+	fog.update(
+		|x, y| tile_ids[(y * *tiles_x + x) as usize] == 1,
+		|x, y| tile_ids[(y * *tiles_x + x) as usize],
+	);
+	*explored_tiles += fog.drain_events().filter(|event| event.is_discovery()).count() as i32;
 }
 
-fn main() {
-	unsafe {
-		InitWindow(SCREEN_WIDTH, SCREEN_HEIGHT, c"raylib [textures] example - fog of war".as_ptr());
-
-		SetTargetFPS(MAX_FPS);
-
-		let map = Map::new();
-		let player = BundlePlayer::new();
-
-		let mut world = World::new();
-		world.spawn(player);
-		world.spawn(map);
-
-		let mut update = Schedule::new(Update);
-		update.set_executor_kind(ExecutorKind::MultiThreaded);
-		update.add_systems(handle_input);
-		update.add_systems(handle_fog);
-		world.add_schedule(update);
-
-		let mut fixed_update = Schedule::new(FixedUpdate);
-		fixed_update.set_executor_kind(ExecutorKind::MultiThreaded);
-		world.add_schedule(fixed_update);
-
-		let mut render = Schedule::new(Render);
-		render.set_executor_kind(ExecutorKind::MultiThreaded);
-		render.add_systems(render_map);
-		render.add_systems(render_overlay.after(render_map));
-		render.add_systems(render_player.after(render_map));
-		world.add_schedule(render);
-
-		let mut time_acc = Duration::ZERO;
-		let mut frame_time = Duration::ZERO;
-		while WindowShouldClose() == false {
-			time_acc = time_acc.saturating_add(frame_time);
-			while let Some(time) = time_acc.checked_sub(TIMESTEP) {
-				world.run_schedule(FixedUpdate);
-				time_acc = time;
-			}
+fn setup(mut commands: Commands) {
+	let time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_nanos() as u64);
+	let mut map = Map::new(time % 100_000);
+	let viewer = map.fog.add_viewer(PLAYER_TEAM, map.spawn_tile, PLAYER_TILE_VISIBILITY);
+	commands.spawn(BundlePlayer::new(viewer, map.spawn_tile));
+	commands.spawn(map);
+}
 
-			world.run_schedule(Update);
+// assets and the fog renderer hold GPU resources, so they stay on the main thread
+fn load_assets(world: &mut World) {
+	world.insert_non_send_resource(FogRenderer::new(MAP_TILES_X, MAP_TILES_Y, FogStyle::default()));
+	let mut assets = Assets::default();
+	assets.reload_interval = Some(Duration::from_millis(500));
+	let fog_shader = FogShader(assets.load(FOG_SHADER_PATH));
+	world.insert_non_send_resource(assets);
+	world.insert_non_send_resource(fog_shader);
+}
+
+fn setup_debug(world: &mut World) {
+	let mut console = Console::new();
+	console
+		.register("teleport", "teleport <x> <y>, in tiles")
+		.register("trace", "trace <file>, the last frames as a Chrome trace")
+		.add_cvar("fog_softness", FogStyle::default().softness, "blur radius of the fog edges in fog texels");
+	world.insert_non_send_resource(console);
+	let mut debug = DebugOverlay::new();
+	debug.visible = true;
+	world.insert_non_send_resource(debug);
+}
+
+fn main() {
+	App::new(WindowConfig {
+		width: SCREEN_WIDTH,
+		height: SCREEN_HEIGHT,
+		title: "raylib [textures] example - fog of war".to_string(),
+		target_fps: MAX_FPS,
+		..Default::default()
+	})
+	// movement runs at a fixed rate so the player speed does not depend on the frame rate
+	.timestep(TIMESTEP)
+	.insert_resource(Actions(input_map()))
+	.add_systems(Startup, (load_assets, setup_debug, setup))
+	.add_systems(FixedUpdate, handle_input)
+	.add_systems(Update, (handle_console, handle_fog))
+	// the map goes under the drawn entities, the overlay on top
+	.add_systems(Render, (render_map.before(draw_2d), render_overlay.after(draw_2d)))
+	.run();
+}
 
-			let render_time = Instant::now();
-			BeginDrawing();
-			ClearBackground(RAYWHITE);
+#[cfg(test)]
+mod tests {
+	use raylib::recording::{check_golden, RecordingBackend};
 
-			world.run_schedule(Render);
+	use super::*;
 
-			EndDrawing();
-			frame_time = render_time.elapsed();
+	#[test]
+	fn map_render_matches_golden() {
+		let mut backend = RecordingBackend::default();
+		Map::new(7).render(&mut backend);
+		if let Err(diff) = check_golden(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/map_render.txt"), backend.commands()) {
+			panic!("{diff}");
 		}
+	}
 
-		drop(world);
-		CloseWindow();
+	#[test]
+	fn maps_always_have_a_floor_to_spawn_on() {
+		for seed in 0..500 {
+			let map = Map::new(seed);
+			let (x, y) = map.spawn_tile;
+			assert!(map.in_bounds(x, y));
+			assert_eq!(map.tile_ids[map.index(x, y)], 0, "seed {seed}");
+		}
 	}
 }
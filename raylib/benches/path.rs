@@ -0,0 +1,89 @@
+// Compares the path finders on a large open map and a maze. Plain timing loops rather than a bench framework,
+// so it runs on stable without extra dependencies:
+//   cargo bench -p raylib --features headless --bench path
+use std::{
+	hint::black_box,
+	time::{Duration, Instant},
+};
+
+use raylib::{
+	mapgen::{Tile, TileGrid},
+	path::{astar, jump_point_search, Diagonal, DijkstraMap, FlowField},
+	rng::Rng,
+	tilemap::TileMap,
+};
+
+const SIZE: i32 = 512;
+
+// open floor with a sprinkle of single wall tiles, so JPS has some jump points to find
+fn open_map() -> TileGrid {
+	let mut grid = TileGrid::new(SIZE, SIZE, Tile::Floor);
+	let mut rng = Rng::new(7);
+	for _ in 0..SIZE * SIZE / 50 {
+		grid.set(rng.range_i32(0, SIZE - 1), rng.range_i32(0, SIZE - 1), Tile::Wall);
+	}
+	grid.set(0, 0, Tile::Floor);
+	grid.set(SIZE - 1, SIZE - 1, Tile::Floor);
+	grid
+}
+
+// recursive backtracker over odd tiles, every pair of tiles is joined by exactly one path
+fn maze() -> TileGrid {
+	let mut grid = TileGrid::new(SIZE - 1, SIZE - 1, Tile::Wall);
+	let mut rng = Rng::new(7);
+	let mut stack = vec![(1, 1)];
+	grid.set(1, 1, Tile::Floor);
+	while let Some(&(x, y)) = stack.last() {
+		let mut options = [(2, 0), (-2, 0), (0, 2), (0, -2)];
+		rng.shuffle(&mut options);
+		let next = options
+			.into_iter()
+			.map(|(dx, dy)| (x + dx, y + dy, x + dx / 2, y + dy / 2))
+			.find(|&(nx, ny, _, _)| nx > 0 && ny > 0 && nx < grid.width() - 1 && ny < grid.height() - 1 && !grid.is_floor(nx, ny));
+		match next {
+			Some((nx, ny, wx, wy)) => {
+				grid.set(wx, wy, Tile::Floor);
+				grid.set(nx, ny, Tile::Floor);
+				stack.push((nx, ny));
+			}
+			None => {
+				stack.pop();
+			}
+		}
+	}
+	grid
+}
+
+fn bench(name: &str, mut run: impl FnMut()) {
+	// warm up once, then repeat for at least half a second
+	run();
+	let start = Instant::now();
+	let mut iterations = 0;
+	while start.elapsed() < Duration::from_millis(500) || iterations < 3 {
+		run();
+		iterations += 1;
+	}
+	let per_iteration = start.elapsed() / iterations;
+	println!("{name:<32} {:>10.3} ms  ({iterations} runs)", per_iteration.as_secs_f64() * 1000.0);
+}
+
+fn main() {
+	for (map_name, grid, start, goal) in [
+		("open", open_map(), (0, 0), (SIZE - 1, SIZE - 1)),
+		("maze", maze(), (1, 1), (SIZE - 3, SIZE - 3)),
+	] {
+		bench(&format!("{map_name}/astar"), || {
+			black_box(astar(&grid, start, goal, Diagonal::NoCornerCutting));
+		});
+		bench(&format!("{map_name}/jump_point_search"), || {
+			black_box(jump_point_search(&grid, start, goal));
+		});
+		bench(&format!("{map_name}/dijkstra_map"), || {
+			black_box(DijkstraMap::new(&grid, &[goal], Diagonal::NoCornerCutting));
+		});
+		let dijkstra = DijkstraMap::new(&grid, &[goal], Diagonal::NoCornerCutting);
+		bench(&format!("{map_name}/flow_field"), || {
+			black_box(FlowField::new(&grid, &dijkstra));
+		});
+	}
+}
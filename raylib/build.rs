@@ -19,6 +19,10 @@ fn main() {
 
     let lib_path = Path::new(&manifest_dir).join("external\\lib");
     println!("cargo:rustc-link-search={}", lib_path.display());
+    // headless builds never call into the C library, so they run without it
+    if env::var_os("CARGO_FEATURE_HEADLESS").is_some() {
+        return;
+    }
     println!("cargo:rustc-link-lib=dylib=raylibdll");
 
     let input_path = lib_path.join("raylib.dll");
@@ -0,0 +1,890 @@
+use std::{collections::HashMap, io};
+
+use crate::{backend::Backend, *};
+
+// Shortest frame duration, keeps zero length frames from stalling `Animator::update`.
+const MIN_FRAME_DURATION: f32 = 0.001;
+// for atlases without per frame durations
+pub const DEFAULT_FRAME_DURATION: f32 = 0.1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlayMode {
+	// stops on the last frame
+	Once,
+	Loop,
+	// forwards then backwards, without repeating the end frames
+	PingPong,
+}
+
+// A sequence of sheet frames with how long each one shows, in seconds.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Clip {
+	pub frames: Vec<usize>,
+	pub durations: Vec<f32>,
+	pub mode: PlayMode,
+	// (position in `frames`, event name), sent when that frame starts showing
+	pub events: Vec<(usize, String)>,
+}
+
+impl Clip {
+	pub fn new(frames: Vec<usize>, frame_duration: f32, mode: PlayMode) -> Self {
+		Self {
+			durations: vec![frame_duration; frames.len()],
+			frames,
+			mode,
+			events: Vec::new(),
+		}
+	}
+
+	// `frames` as (sheet frame, duration) pairs
+	pub fn with_durations(frames: &[(usize, f32)], mode: PlayMode) -> Self {
+		Self {
+			frames: frames.iter().map(|(frame, _)| *frame).collect(),
+			durations: frames.iter().map(|(_, duration)| *duration).collect(),
+			mode,
+			events: Vec::new(),
+		}
+	}
+
+	pub fn with_event(mut self, position: usize, name: &str) -> Self {
+		self.events.push((position, name.to_string()));
+		self
+	}
+
+	pub fn len(&self) -> usize {
+		self.frames.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.frames.is_empty()
+	}
+
+	// total length of one pass through the frames
+	pub fn duration(&self) -> f32 {
+		self.durations.iter().map(|duration| duration.max(MIN_FRAME_DURATION)).sum()
+	}
+}
+
+// A texture cut into frames, with named clips.
+#[derive(Clone, Debug)]
+pub struct SpriteSheet {
+	pub texture: Texture2D,
+	frames: Vec<Rectangle>,
+	names: HashMap<String, usize>,
+	// from the atlas, DEFAULT_FRAME_DURATION for grids
+	durations: Vec<f32>,
+	clips: HashMap<String, Clip>,
+}
+
+impl SpriteSheet {
+	// Frames of `frame_width` x `frame_height` filling the texture, row by row.
+	pub fn from_grid(texture: Texture2D, frame_width: i32, frame_height: i32) -> Self {
+		let columns = texture.width / frame_width.max(1);
+		let rows = texture.height / frame_height.max(1);
+		let frames: Vec<Rectangle> = (0..rows)
+			.flat_map(|row| (0..columns).map(move |column| (column, row)))
+			.map(|(column, row)| Rectangle {
+				x: (column * frame_width) as f32,
+				y: (row * frame_height) as f32,
+				width: frame_width as f32,
+				height: frame_height as f32,
+			})
+			.collect();
+		Self {
+			texture,
+			durations: vec![DEFAULT_FRAME_DURATION; frames.len()],
+			frames,
+			names: HashMap::new(),
+			clips: HashMap::new(),
+		}
+	}
+
+	// Reads the JSON that Aseprite (`--format json-hash` or `json-array`) or TexturePacker (JSON
+	// hash or array) write next to the sheet. Aseprite frame durations and tags become clips,
+	// forward and reverse tags loop, pingpong tags play back and forth. The `animations` table
+	// of Pixi style atlases is read as well, lists of frame names that loop.
+	pub fn from_json(texture: Texture2D, text: &str) -> io::Result<Self> {
+		let json = Parser::new(text).parse().map_err(invalid_data)?;
+		let frames = json.get("frames").ok_or_else(|| invalid_data("no frames".to_string()))?;
+		// hashes are keyed by name, arrays carry it in `filename`
+		let named: Vec<(String, &Json)> = match frames {
+			Json::Object(entries) => entries.iter().map(|(name, frame)| (name.clone(), frame)).collect(),
+			Json::Array(frames) => frames
+				.iter()
+				.enumerate()
+				.map(|(index, frame)| {
+					let name = frame
+						.get("filename")
+						.and_then(Json::as_str)
+						.map_or_else(|| index.to_string(), str::to_string);
+					(name, frame)
+				})
+				.collect(),
+			_ => return Err(invalid_data("frames is not an object or array".to_string())),
+		};
+
+		let mut sheet = Self {
+			texture,
+			frames: Vec::new(),
+			names: HashMap::new(),
+			durations: Vec::new(),
+			clips: HashMap::new(),
+		};
+		for (name, frame) in named {
+			let rec = frame.get("frame").ok_or_else(|| invalid_data(format!("frame {name} has no rectangle")))?;
+			let number = |key: &str| {
+				rec
+					.get(key)
+					.and_then(Json::as_f64)
+					.ok_or_else(|| invalid_data(format!("frame {name} has no {key}")))
+			};
+			let mut source = Rectangle {
+				x: number("x")? as f32,
+				y: number("y")? as f32,
+				width: number("w")? as f32,
+				height: number("h")? as f32,
+			};
+			// TexturePacker rotates frames 90 degrees clockwise to pack them, drawing them upright
+			// would need a rotated quad, so keep the packed size
+			if frame.get("rotated") == Some(&Json::Bool(true)) {
+				std::mem::swap(&mut source.width, &mut source.height);
+			}
+			// milliseconds in Aseprite
+			let duration = frame
+				.get("duration")
+				.and_then(Json::as_f64)
+				.map_or(DEFAULT_FRAME_DURATION, |ms| ms as f32 / 1000.0);
+			sheet.names.insert(name, sheet.frames.len());
+			sheet.frames.push(source);
+			sheet.durations.push(duration);
+		}
+
+		let tags = json.get("meta").and_then(|meta| meta.get("frameTags")).and_then(Json::as_array);
+		for tag in tags.unwrap_or_default() {
+			let name = tag
+				.get("name")
+				.and_then(Json::as_str)
+				.ok_or_else(|| invalid_data("frame tag without a name".to_string()))?;
+			let from = tag.get("from").and_then(Json::as_f64).unwrap_or(0.0) as usize;
+			let to = tag.get("to").and_then(Json::as_f64).unwrap_or(0.0) as usize;
+			if from > to || to >= sheet.frames.len() {
+				return Err(invalid_data(format!("frame tag {name} is out of range")));
+			}
+			let mut frames: Vec<usize> = (from..=to).collect();
+			let mode = match tag.get("direction").and_then(Json::as_str).unwrap_or("forward") {
+				"forward" => PlayMode::Loop,
+				"reverse" => {
+					frames.reverse();
+					PlayMode::Loop
+				}
+				"pingpong" => PlayMode::PingPong,
+				"pingpong_reverse" => {
+					frames.reverse();
+					PlayMode::PingPong
+				}
+				direction => return Err(invalid_data(format!("frame tag {name} has unknown direction {direction}"))),
+			};
+			let frames: Vec<(usize, f32)> = frames.into_iter().map(|frame| (frame, sheet.durations[frame])).collect();
+			sheet.clips.insert(name.to_string(), Clip::with_durations(&frames, mode));
+		}
+
+		if let Some(Json::Object(animations)) = json.get("animations") {
+			for (name, frame_names) in animations {
+				let mut frames = Vec::new();
+				for frame_name in frame_names.as_array().unwrap_or_default() {
+					let frame = frame_name
+						.as_str()
+						.and_then(|frame_name| sheet.frame_index(frame_name))
+						.ok_or_else(|| invalid_data(format!("animation {name} names an unknown frame")))?;
+					frames.push((frame, sheet.durations[frame]));
+				}
+				sheet.clips.insert(name.clone(), Clip::with_durations(&frames, PlayMode::Loop));
+			}
+		}
+		Ok(sheet)
+	}
+
+	pub fn len(&self) -> usize {
+		self.frames.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.frames.is_empty()
+	}
+
+	pub fn frame(&self, index: usize) -> Option<Rectangle> {
+		self.frames.get(index).copied()
+	}
+
+	// atlas frames only, grid frames have no names
+	pub fn frame_index(&self, name: &str) -> Option<usize> {
+		self.names.get(name).copied()
+	}
+
+	pub fn frame_duration(&self, index: usize) -> Option<f32> {
+		self.durations.get(index).copied()
+	}
+
+	pub fn add_clip(&mut self, name: &str, clip: Clip) {
+		self.clips.insert(name.to_string(), clip);
+	}
+
+	pub fn clip(&self, name: &str) -> Option<&Clip> {
+		self.clips.get(name)
+	}
+
+	pub fn clip_names(&self) -> impl Iterator<Item = &str> {
+		self.clips.keys().map(String::as_str)
+	}
+
+	// The source rectangle for DrawTexturePro, negative sizes flip the frame.
+	pub fn source(&self, frame: usize, flip_x: bool, flip_y: bool) -> Rectangle {
+		let mut source = self.frame(frame).unwrap_or(Rectangle {
+			x: 0.0,
+			y: 0.0,
+			width: 0.0,
+			height: 0.0,
+		});
+		if flip_x {
+			source.width = -source.width;
+		}
+		if flip_y {
+			source.height = -source.height;
+		}
+		source
+	}
+
+	pub fn draw(&self, backend: &mut dyn Backend, frame: usize, dest: Rectangle, flip_x: bool, flip_y: bool, tint: Color) {
+		let source = self.source(frame, flip_x, flip_y);
+		backend.draw_texture_pro(self.texture, source, dest, Vector2::default(), 0.0, tint);
+	}
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AnimationEvent {
+	pub clip: String,
+	pub name: String,
+	// position in the clip's frames
+	pub position: usize,
+}
+
+// Plays one clip at a time and tracks which frame shows. Only timing, so it runs without a window.
+#[derive(Clone, Debug)]
+pub struct Animator {
+	// playback rate, 2.0 plays twice as fast
+	pub speed: f32,
+	pub flip_x: bool,
+	pub flip_y: bool,
+	name: String,
+	clip: Option<Clip>,
+	position: usize,
+	// seconds into the current frame
+	time: f32,
+	// PingPong goes backwards while false
+	forwards: bool,
+	finished: bool,
+	events: Vec<AnimationEvent>,
+}
+
+impl Default for Animator {
+	fn default() -> Self {
+		Self {
+			speed: 1.0,
+			flip_x: false,
+			flip_y: false,
+			name: String::new(),
+			clip: None,
+			position: 0,
+			time: 0.0,
+			forwards: true,
+			finished: false,
+			events: Vec::new(),
+		}
+	}
+}
+
+impl Animator {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	// Switches to the sheet's clip `name`, keeps playing if it already is. False when the sheet has
+	// no such clip.
+	pub fn play(&mut self, sheet: &SpriteSheet, name: &str) -> bool {
+		if self.clip.is_some() && self.name == name {
+			return true;
+		}
+		match sheet.clip(name) {
+			Some(clip) => {
+				self.play_clip(name, clip.clone());
+				true
+			}
+			None => false,
+		}
+	}
+
+	// Starts `clip` from its first frame.
+	pub fn play_clip(&mut self, name: &str, clip: Clip) {
+		self.name = name.to_string();
+		self.clip = Some(clip);
+		self.restart();
+	}
+
+	pub fn restart(&mut self) {
+		self.position = 0;
+		self.time = 0.0;
+		self.forwards = true;
+		self.finished = false;
+		self.send_events();
+	}
+
+	pub fn stop(&mut self) {
+		self.name.clear();
+		self.clip = None;
+	}
+
+	pub fn clip_name(&self) -> Option<&str> {
+		self.clip.as_ref().map(|_| self.name.as_str())
+	}
+
+	// position in the clip's frames
+	pub fn position(&self) -> usize {
+		self.position
+	}
+
+	// the sheet frame showing now
+	pub fn frame(&self) -> Option<usize> {
+		self.clip.as_ref()?.frames.get(self.position).copied()
+	}
+
+	// true once a `PlayMode::Once` clip reached the end of its last frame
+	pub fn is_finished(&self) -> bool {
+		self.finished
+	}
+
+	pub fn update(&mut self, delta_time: f32) {
+		let Some(clip) = &self.clip else {
+			return;
+		};
+		if clip.is_empty() || self.finished {
+			return;
+		}
+		self.time += delta_time * self.speed;
+		loop {
+			let Some(clip) = &self.clip else {
+				return;
+			};
+			let duration = clip
+				.durations
+				.get(self.position)
+				.copied()
+				.unwrap_or(DEFAULT_FRAME_DURATION)
+				.max(MIN_FRAME_DURATION);
+			if self.time < duration {
+				return;
+			}
+			self.time -= duration;
+			let last = clip.len() - 1;
+			match clip.mode {
+				PlayMode::Once if self.position == last => {
+					self.finished = true;
+					self.time = 0.0;
+					return;
+				}
+				PlayMode::Once => self.position += 1,
+				PlayMode::Loop => self.position = if self.position == last { 0 } else { self.position + 1 },
+				PlayMode::PingPong if last == 0 => {}
+				PlayMode::PingPong => {
+					if self.forwards && self.position == last || !self.forwards && self.position == 0 {
+						self.forwards = !self.forwards;
+					}
+					self.position = if self.forwards { self.position + 1 } else { self.position - 1 };
+				}
+			}
+			self.send_events();
+		}
+	}
+
+	pub fn drain_events(&mut self) -> impl Iterator<Item = AnimationEvent> + '_ {
+		self.events.drain(..)
+	}
+
+	// Draws the current frame stretched over `dest`, with the animator's flips.
+	pub fn draw(&self, backend: &mut dyn Backend, sheet: &SpriteSheet, dest: Rectangle, tint: Color) {
+		if let Some(frame) = self.frame() {
+			sheet.draw(backend, frame, dest, self.flip_x, self.flip_y, tint);
+		}
+	}
+
+	fn send_events(&mut self) {
+		let Some(clip) = &self.clip else {
+			return;
+		};
+		for (position, name) in &clip.events {
+			if *position == self.position {
+				self.events.push(AnimationEvent {
+					clip: self.name.clone(),
+					name: name.clone(),
+					position: *position,
+				});
+			}
+		}
+	}
+}
+
+fn invalid_data(message: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// Just enough JSON for atlas files, objects keep their key order.
+#[derive(Clone, PartialEq, Debug)]
+enum Json {
+	Null,
+	Bool(bool),
+	Number(f64),
+	String(String),
+	Array(Vec<Json>),
+	Object(Vec<(String, Json)>),
+}
+
+impl Json {
+	fn get(&self, key: &str) -> Option<&Json> {
+		match self {
+			Json::Object(entries) => entries.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+			_ => None,
+		}
+	}
+
+	fn as_f64(&self) -> Option<f64> {
+		match self {
+			Json::Number(number) => Some(*number),
+			_ => None,
+		}
+	}
+
+	fn as_str(&self) -> Option<&str> {
+		match self {
+			Json::String(text) => Some(text),
+			_ => None,
+		}
+	}
+
+	fn as_array(&self) -> Option<&[Json]> {
+		match self {
+			Json::Array(values) => Some(values),
+			_ => None,
+		}
+	}
+}
+
+struct Parser<'a> {
+	text: &'a str,
+	position: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn new(text: &'a str) -> Self {
+		Self { text, position: 0 }
+	}
+
+	fn parse(mut self) -> Result<Json, String> {
+		let value = self.value()?;
+		self.skip_whitespace();
+		match self.position == self.text.len() {
+			true => Ok(value),
+			false => Err(self.error("trailing characters")),
+		}
+	}
+
+	fn error(&self, message: &str) -> String {
+		format!("json at byte {}: {message}", self.position)
+	}
+
+	fn peek(&self) -> Option<char> {
+		self.text[self.position..].chars().next()
+	}
+
+	fn next(&mut self) -> Option<char> {
+		let c = self.peek()?;
+		self.position += c.len_utf8();
+		Some(c)
+	}
+
+	fn skip_whitespace(&mut self) {
+		while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+			self.position += 1;
+		}
+	}
+
+	fn expect(&mut self, expected: char) -> Result<(), String> {
+		self.skip_whitespace();
+		match self.next() {
+			Some(c) if c == expected => Ok(()),
+			_ => Err(self.error(&format!("expected {expected}"))),
+		}
+	}
+
+	fn value(&mut self) -> Result<Json, String> {
+		self.skip_whitespace();
+		match self.peek() {
+			Some('{') => self.object(),
+			Some('[') => self.array(),
+			Some('"') => self.string().map(Json::String),
+			Some('t') => self.literal("true", Json::Bool(true)),
+			Some('f') => self.literal("false", Json::Bool(false)),
+			Some('n') => self.literal("null", Json::Null),
+			Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+			_ => Err(self.error("expected a value")),
+		}
+	}
+
+	fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+		match self.text[self.position..].starts_with(word) {
+			true => {
+				self.position += word.len();
+				Ok(value)
+			}
+			false => Err(self.error("unknown literal")),
+		}
+	}
+
+	fn number(&mut self) -> Result<Json, String> {
+		let start = self.position;
+		while self
+			.peek()
+			.is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+		{
+			self.position += 1;
+		}
+		self.text[start..self.position]
+			.parse()
+			.map(Json::Number)
+			.map_err(|_| self.error("bad number"))
+	}
+
+	fn string(&mut self) -> Result<String, String> {
+		self.expect('"')?;
+		let mut text = String::new();
+		loop {
+			match self.next().ok_or_else(|| self.error("unterminated string"))? {
+				'"' => return Ok(text),
+				'\\' => {
+					let c = match self.next().ok_or_else(|| self.error("unterminated string"))? {
+						'n' => '\n',
+						't' => '\t',
+						'r' => '\r',
+						'b' => '\u{8}',
+						'f' => '\u{c}',
+						'u' => self.unicode_escape()?,
+						c => c,
+					};
+					text.push(c);
+				}
+				c => text.push(c),
+			}
+		}
+	}
+
+	// after `\u`, including surrogate pairs
+	fn unicode_escape(&mut self) -> Result<char, String> {
+		let high = self.hex4()?;
+		if !(0xd800..0xdc00).contains(&high) {
+			return char::from_u32(high).ok_or_else(|| self.error("bad escape"));
+		}
+		if !self.text[self.position..].starts_with("\\u") {
+			return Err(self.error("unpaired surrogate"));
+		}
+		self.position += 2;
+		let low = self.hex4()?;
+		char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff)).ok_or_else(|| self.error("bad escape"))
+	}
+
+	fn hex4(&mut self) -> Result<u32, String> {
+		let digits = self.text.get(self.position..self.position + 4).ok_or_else(|| self.error("bad escape"))?;
+		let value = u32::from_str_radix(digits, 16).map_err(|_| self.error("bad escape"))?;
+		self.position += 4;
+		Ok(value)
+	}
+
+	fn array(&mut self) -> Result<Json, String> {
+		self.expect('[')?;
+		let mut values = Vec::new();
+		self.skip_whitespace();
+		if self.peek() == Some(']') {
+			self.position += 1;
+			return Ok(Json::Array(values));
+		}
+		loop {
+			values.push(self.value()?);
+			self.skip_whitespace();
+			match self.next() {
+				Some(',') => continue,
+				Some(']') => return Ok(Json::Array(values)),
+				_ => return Err(self.error("expected , or ]")),
+			}
+		}
+	}
+
+	fn object(&mut self) -> Result<Json, String> {
+		self.expect('{')?;
+		let mut entries = Vec::new();
+		self.skip_whitespace();
+		if self.peek() == Some('}') {
+			self.position += 1;
+			return Ok(Json::Object(entries));
+		}
+		loop {
+			self.skip_whitespace();
+			let key = self.string()?;
+			self.expect(':')?;
+			entries.push((key, self.value()?));
+			self.skip_whitespace();
+			match self.next() {
+				Some(',') => continue,
+				Some('}') => return Ok(Json::Object(entries)),
+				_ => return Err(self.error("expected , or }")),
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn texture() -> Texture2D {
+		Texture2D {
+			id: 1,
+			width: 64,
+			height: 32,
+			mipmaps: 1,
+			format: 7,
+		}
+	}
+
+	fn rect(x: f32, y: f32, width: f32, height: f32) -> Rectangle {
+		Rectangle { x, y, width, height }
+	}
+
+	// the positions after each of `steps` updates
+	fn positions(animator: &mut Animator, delta_time: f32, steps: usize) -> Vec<usize> {
+		(0..steps)
+			.map(|_| {
+				animator.update(delta_time);
+				animator.position()
+			})
+			.collect()
+	}
+
+	fn playing(clip: Clip) -> Animator {
+		let mut animator = Animator::new();
+		animator.play_clip("test", clip);
+		animator
+	}
+
+	#[test]
+	fn grid_cuts_whole_frames_row_by_row() {
+		let sheet = SpriteSheet::from_grid(texture(), 16, 16);
+		assert_eq!(sheet.len(), 8);
+		assert_eq!(sheet.frame(5), Some(rect(16.0, 16.0, 16.0, 16.0)));
+		assert_eq!(sheet.frame(8), None);
+		assert_eq!(sheet.frame_duration(0), Some(DEFAULT_FRAME_DURATION));
+		assert_eq!(sheet.source(1, true, true), rect(16.0, 0.0, -16.0, -16.0));
+		// leftover pixels make no frame
+		assert_eq!(SpriteSheet::from_grid(texture(), 20, 20).len(), 3);
+	}
+
+	#[test]
+	fn once_stops_on_the_last_frame() {
+		let mut animator = playing(Clip::new(vec![4, 5, 6], 0.125, PlayMode::Once));
+		assert_eq!(animator.frame(), Some(4));
+		assert_eq!(positions(&mut animator, 0.125, 2), [1, 2]);
+		assert!(!animator.is_finished());
+		animator.update(0.125);
+		assert!(animator.is_finished());
+		assert_eq!(animator.frame(), Some(6));
+
+		animator.restart();
+		assert!(!animator.is_finished());
+		// one long update walks through every frame
+		animator.update(10.0);
+		assert!(animator.is_finished());
+		assert_eq!(animator.position(), 2);
+	}
+
+	#[test]
+	fn loop_wraps_around() {
+		let mut animator = playing(Clip::new(vec![0, 1, 2], 0.125, PlayMode::Loop));
+		assert_eq!(positions(&mut animator, 0.125, 4), [1, 2, 0, 1]);
+		// eight frames ahead
+		animator.update(1.0);
+		assert_eq!(animator.position(), 0);
+		assert!(!animator.is_finished());
+	}
+
+	#[test]
+	fn ping_pong_turns_without_repeating_the_ends() {
+		let mut animator = playing(Clip::new(vec![0, 1, 2, 3], 0.125, PlayMode::PingPong));
+		assert_eq!(positions(&mut animator, 0.125, 8), [1, 2, 3, 2, 1, 0, 1, 2]);
+
+		let mut single = playing(Clip::new(vec![7], 0.125, PlayMode::PingPong));
+		assert_eq!(positions(&mut single, 0.125, 3), [0, 0, 0]);
+	}
+
+	#[test]
+	fn durations_and_speed_set_the_pace() {
+		let clip = Clip::with_durations(&[(0, 0.25), (1, 0.125), (2, 0.0)], PlayMode::Loop);
+		assert_eq!(clip.duration(), 0.25 + 0.125 + MIN_FRAME_DURATION);
+		let mut animator = playing(clip);
+		assert_eq!(positions(&mut animator, 0.125, 3), [0, 1, 2]);
+		// the zero length frame shows for the shortest duration instead of stalling
+		animator.update(0.0625);
+		assert_eq!(animator.position(), 0);
+
+		animator.restart();
+		animator.speed = 2.0;
+		assert_eq!(positions(&mut animator, 0.0625, 2), [0, 1]);
+		animator.speed = 0.0;
+		assert_eq!(positions(&mut animator, 1.0, 2), [1, 1]);
+	}
+
+	#[test]
+	fn events_fire_when_their_frame_starts() {
+		let clip = Clip::new(vec![0, 1, 2], 0.125, PlayMode::Loop)
+			.with_event(0, "start")
+			.with_event(2, "step");
+		let mut animator = playing(clip);
+		let names = |animator: &mut Animator| animator.drain_events().map(|event| event.name).collect::<Vec<_>>();
+		assert_eq!(names(&mut animator), ["start"]);
+
+		animator.update(0.125);
+		assert!(names(&mut animator).is_empty());
+		animator.update(0.125);
+		assert_eq!(
+			animator.drain_events().collect::<Vec<_>>(),
+			[AnimationEvent {
+				clip: "test".to_string(),
+				name: "step".to_string(),
+				position: 2,
+			}]
+		);
+		// frames skipped over in one update still send theirs
+		animator.update(0.375);
+		assert_eq!(names(&mut animator), ["start", "step"]);
+	}
+
+	#[test]
+	fn play_keeps_a_running_clip_going() {
+		let mut sheet = SpriteSheet::from_grid(texture(), 16, 16);
+		sheet.add_clip("walk", Clip::new(vec![0, 1, 2], 0.125, PlayMode::Loop));
+		sheet.add_clip("idle", Clip::new(vec![3], 0.125, PlayMode::Loop));
+		let mut animator = Animator::new();
+		assert!(!animator.play(&sheet, "run"));
+		assert_eq!(animator.clip_name(), None);
+
+		assert!(animator.play(&sheet, "walk"));
+		animator.update(0.125);
+		assert!(animator.play(&sheet, "walk"));
+		assert_eq!(animator.frame(), Some(1));
+		assert!(animator.play(&sheet, "idle"));
+		assert_eq!((animator.clip_name(), animator.frame()), (Some("idle"), Some(3)));
+
+		animator.stop();
+		assert_eq!((animator.clip_name(), animator.frame()), (None, None));
+	}
+
+	const ASEPRITE_HASH: &str = r#"{
+		"frames": {
+			"hero 0.aseprite": { "frame": { "x": 0, "y": 0, "w": 16, "h": 16 }, "rotated": false, "duration": 100 },
+			"hero 1.aseprite": { "frame": { "x": 16, "y": 0, "w": 16, "h": 16 }, "rotated": false, "duration": 250 },
+			"hero 2.aseprite": { "frame": { "x": 32, "y": 0, "w": 16, "h": 16 }, "rotated": false, "duration": 100 }
+		},
+		"meta": {
+			"app": "https://www.aseprite.org/",
+			"size": { "w": 64, "h": 32 },
+			"frameTags": [
+				{ "name": "walk", "from": 0, "to": 2, "direction": "pingpong" },
+				{ "name": "back", "from": 1, "to": 2, "direction": "reverse" }
+			]
+		}
+	}"#;
+
+	#[test]
+	fn reads_aseprite_hashes() {
+		let sheet = SpriteSheet::from_json(texture(), ASEPRITE_HASH).unwrap();
+		assert_eq!(sheet.len(), 3);
+		assert_eq!(sheet.frame_index("hero 1.aseprite"), Some(1));
+		assert_eq!(sheet.frame(1), Some(rect(16.0, 0.0, 16.0, 16.0)));
+		assert_eq!(sheet.frame_duration(1), Some(0.25));
+		assert_eq!(
+			sheet.clip("walk"),
+			Some(&Clip::with_durations(&[(0, 0.1), (1, 0.25), (2, 0.1)], PlayMode::PingPong))
+		);
+		assert_eq!(sheet.clip("back"), Some(&Clip::with_durations(&[(2, 0.1), (1, 0.25)], PlayMode::Loop)));
+		let mut names: Vec<&str> = sheet.clip_names().collect();
+		names.sort();
+		assert_eq!(names, ["back", "walk"]);
+	}
+
+	#[test]
+	fn reads_aseprite_arrays() {
+		let text = r#"{
+			"frames": [
+				{ "filename": "idle \"a\"", "frame": { "x": 0, "y": 16, "w": 8, "h": 16 }, "duration": 500 },
+				{ "frame": { "x": 8, "y": 16, "w": 8, "h": 16 }, "duration": 0 }
+			],
+			"meta": { "frameTags": [{ "name": "idle", "from": 0, "to": 1, "direction": "pingpong_reverse" }] }
+		}"#;
+		let sheet = SpriteSheet::from_json(texture(), text).unwrap();
+		assert_eq!(sheet.frame_index("idle \"a\""), Some(0));
+		// unnamed frames go by their index
+		assert_eq!(sheet.frame_index("1"), Some(1));
+		assert_eq!(sheet.frame(0), Some(rect(0.0, 16.0, 8.0, 16.0)));
+		assert_eq!(sheet.clip("idle"), Some(&Clip::with_durations(&[(1, 0.0), (0, 0.5)], PlayMode::PingPong)));
+	}
+
+	#[test]
+	fn reads_texture_packer_atlases() {
+		let text = r#"{"frames":{
+			"coin_1.png":{"frame":{"x":0,"y":0,"w":10,"h":12},"rotated":false,"trimmed":false,
+				"spriteSourceSize":{"x":0,"y":0,"w":10,"h":12},"sourceSize":{"w":10,"h":12}},
+			"coin_2.png":{"frame":{"x":10,"y":0,"w":10,"h":12},"rotated":true,"trimmed":false,
+				"spriteSourceSize":{"x":0,"y":0,"w":12,"h":10},"sourceSize":{"w":12,"h":10}}
+		},
+		"animations":{"spin":["coin_1.png","coin_2.png","coin_1.png"]},
+		"meta":{"app":"https://www.codeandweb.com/texturepacker","scale":"1"}}"#;
+		let sheet = SpriteSheet::from_json(texture(), text).unwrap();
+		assert_eq!(sheet.frame_duration(0), Some(DEFAULT_FRAME_DURATION));
+		// rotated frames keep their packed size
+		assert_eq!(sheet.frame(1), Some(rect(10.0, 0.0, 12.0, 10.0)));
+		assert_eq!(
+			sheet.clip("spin"),
+			Some(&Clip::new(vec![0, 1, 0], DEFAULT_FRAME_DURATION, PlayMode::Loop))
+		);
+
+		let array = r#"{"frames":[{"filename":"coin_1.png","frame":{"x":0,"y":0,"w":10,"h":12}}]}"#;
+		let sheet = SpriteSheet::from_json(texture(), array).unwrap();
+		assert_eq!(sheet.frame_index("coin_1.png"), Some(0));
+		assert_eq!(sheet.clip_names().count(), 0);
+	}
+
+	#[test]
+	fn rejects_broken_atlases() {
+		for text in [
+			"",
+			"{\"frames\": [}",
+			"{\"frames\": {}} trailing",
+			"{\"meta\": {}}",
+			"{\"frames\": 3}",
+			r#"{"frames": [{"frame": {"x": 0, "y": 0, "w": 1}}]}"#,
+			r#"{"frames": [{"frame": {"x": 0, "y": 0, "w": 1, "h": 1}}],
+				"meta": {"frameTags": [{"name": "a", "from": 0, "to": 1}]}}"#,
+			r#"{"frames": [{"frame": {"x": 0, "y": 0, "w": 1, "h": 1}}],
+				"meta": {"frameTags": [{"name": "a", "from": 0, "to": 0, "direction": "sideways"}]}}"#,
+			r#"{"frames": [{"frame": {"x": 0, "y": 0, "w": 1, "h": 1}}], "animations": {"a": ["missing"]}}"#,
+		] {
+			let error = SpriteSheet::from_json(texture(), text).unwrap_err();
+			assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{text}");
+		}
+	}
+}
@@ -0,0 +1,886 @@
+use std::{
+	any::{Any, TypeId},
+	collections::HashMap,
+	ffi::CString,
+	fs,
+	marker::PhantomData,
+	path::Path,
+	rc::Rc,
+	sync::{
+		mpsc::{self, Receiver, Sender},
+		Arc, Mutex,
+	},
+	thread::{self, JoinHandle},
+	time::{Duration, Instant, SystemTime},
+};
+
+use crate::*;
+
+// raylib's LoadFont size for ttf/otf files
+const FONT_SIZE: i32 = 32;
+
+// A GPU or audio resource the manager can free.
+pub trait Asset: 'static {
+	fn unload(self);
+}
+
+// An asset that loads from a file in two steps: `decode` runs on a worker thread and only
+// touches the CPU, `upload` runs on the main thread in `Assets::update`.
+pub trait LoadAsset: Asset + Sized {
+	type Decoded: Send + 'static;
+
+	fn decode(path: &Path) -> Result<Self::Decoded, String>;
+	fn upload(decoded: Self::Decoded) -> Result<Self, String>;
+}
+
+// Images and waves decoded off the main thread, freed if they never get uploaded.
+pub struct DecodedImage(Image);
+
+// raylib's image loaders keep no global state
+unsafe impl Send for DecodedImage {}
+
+impl Drop for DecodedImage {
+	fn drop(&mut self) {
+		unsafe { UnloadImage(self.0) };
+	}
+}
+
+pub struct DecodedWave(Wave);
+
+unsafe impl Send for DecodedWave {}
+
+impl Drop for DecodedWave {
+	fn drop(&mut self) {
+		unsafe { UnloadWave(self.0) };
+	}
+}
+
+pub enum DecodedFont {
+	// ttf and otf, rasterized when uploaded
+	Vector { file_type: CString, data: Vec<u8> },
+	// a bitmap font image, glyphs separated by magenta like LoadFont expects
+	Image(DecodedImage),
+}
+
+pub struct ShaderSource {
+	pub vertex: Option<CString>,
+	pub fragment: CString,
+}
+
+fn file_type(path: &Path) -> CString {
+	let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+	CString::new(format!(".{extension}")).unwrap_or_default()
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, String> {
+	fs::read(path).map_err(|err| format!("could not read {}: {err}", path.display()))
+}
+
+fn read_text(path: &Path) -> Result<CString, String> {
+	CString::new(read(path)?).map_err(|_| format!("{} contains a nul byte", path.display()))
+}
+
+fn decode_image(path: &Path) -> Result<DecodedImage, String> {
+	let data = read(path)?;
+	let image = unsafe { LoadImageFromMemory(file_type(path).as_ptr(), data.as_ptr(), data.len() as i32) };
+	match unsafe { IsImageReady(image) } {
+		true => Ok(DecodedImage(image)),
+		false => Err(format!("could not decode image {}", path.display())),
+	}
+}
+
+impl Asset for Texture2D {
+	fn unload(self) {
+		unsafe { UnloadTexture(self) };
+	}
+}
+
+impl LoadAsset for Texture2D {
+	type Decoded = DecodedImage;
+
+	fn decode(path: &Path) -> Result<DecodedImage, String> {
+		decode_image(path)
+	}
+
+	fn upload(image: DecodedImage) -> Result<Self, String> {
+		let texture = unsafe { LoadTextureFromImage(image.0) };
+		match unsafe { IsTextureReady(texture) } {
+			true => Ok(texture),
+			false => Err("could not create texture".to_string()),
+		}
+	}
+}
+
+// Made in code, e.g. with LoadRenderTexture, and handed over with `Assets::add`.
+impl Asset for RenderTexture2D {
+	fn unload(self) {
+		unsafe { UnloadRenderTexture(self) };
+	}
+}
+
+impl Asset for Font {
+	fn unload(self) {
+		unsafe { UnloadFont(self) };
+	}
+}
+
+impl LoadAsset for Font {
+	type Decoded = DecodedFont;
+
+	fn decode(path: &Path) -> Result<DecodedFont, String> {
+		let file_type = file_type(path);
+		match file_type.to_bytes() {
+			b".ttf" | b".otf" => Ok(DecodedFont::Vector {
+				data: read(path)?,
+				file_type,
+			}),
+			_ => decode_image(path).map(DecodedFont::Image),
+		}
+	}
+
+	fn upload(decoded: DecodedFont) -> Result<Self, String> {
+		let font = match &decoded {
+			DecodedFont::Vector { file_type, data } => unsafe {
+				LoadFontFromMemory(file_type.as_ptr(), data.as_ptr(), data.len() as i32, FONT_SIZE, std::ptr::null_mut(), 0)
+			},
+			DecodedFont::Image(image) => unsafe { LoadFontFromImage(image.0, MAGENTA, ' ' as i32) },
+		};
+		match unsafe { IsFontReady(font) } {
+			true => Ok(font),
+			false => Err("could not create font".to_string()),
+		}
+	}
+}
+
+// Needs InitAudioDevice before the upload.
+impl Asset for Sound {
+	fn unload(self) {
+		unsafe { UnloadSound(self) };
+	}
+}
+
+impl LoadAsset for Sound {
+	type Decoded = DecodedWave;
+
+	fn decode(path: &Path) -> Result<DecodedWave, String> {
+		let data = read(path)?;
+		let wave = unsafe { LoadWaveFromMemory(file_type(path).as_ptr(), data.as_ptr(), data.len() as i32) };
+		match unsafe { IsWaveReady(wave) } {
+			true => Ok(DecodedWave(wave)),
+			false => Err(format!("could not decode sound {}", path.display())),
+		}
+	}
+
+	fn upload(wave: DecodedWave) -> Result<Self, String> {
+		let sound = unsafe { LoadSoundFromWave(wave.0) };
+		match unsafe { IsSoundReady(sound) } {
+			true => Ok(sound),
+			false => Err("could not create sound".to_string()),
+		}
+	}
+}
+
+// The path names the fragment shader, a file next to it with the `vs` extension is used as the
+// vertex shader when there is one.
+impl Asset for Shader {
+	fn unload(self) {
+		unsafe { UnloadShader(self) };
+	}
+}
+
+impl LoadAsset for Shader {
+	type Decoded = ShaderSource;
+
+	fn decode(path: &Path) -> Result<ShaderSource, String> {
+		let vertex_path = path.with_extension("vs");
+		let vertex = match vertex_path.exists() {
+			true => Some(read_text(&vertex_path)?),
+			false => None,
+		};
+		Ok(ShaderSource {
+			vertex,
+			fragment: read_text(path)?,
+		})
+	}
+
+	fn upload(source: ShaderSource) -> Result<Self, String> {
+		let vertex = source.vertex.as_ref().map_or(std::ptr::null(), |vertex| vertex.as_ptr());
+		unsafe {
+			// raylib falls back to its default shader when compiling fails, the compiler errors
+			// are in its log
+			let default = LoadShaderFromMemory(std::ptr::null(), std::ptr::null());
+			let shader = LoadShaderFromMemory(vertex, source.fragment.as_ptr());
+			let compiled = IsShaderReady(shader) && shader.id != default.id;
+			UnloadShader(default);
+			match compiled {
+				true => Ok(shader),
+				false => {
+					UnloadShader(shader);
+					Err("could not compile shader".to_string())
+				}
+			}
+		}
+	}
+}
+
+// Text files such as levels, react to their `ReloadEvent`s to pick up edits.
+impl Asset for String {
+	fn unload(self) {}
+}
+
+impl LoadAsset for String {
+	type Decoded = String;
+
+	fn decode(path: &Path) -> Result<String, String> {
+		fs::read_to_string(path).map_err(|err| format!("could not read {}: {err}", path.display()))
+	}
+
+	fn upload(text: String) -> Result<Self, String> {
+		Ok(text)
+	}
+}
+
+struct HandleData {
+	id: u64,
+	path: String,
+}
+
+// Keeps an asset alive. The asset is unloaded by the `Assets::update` after the last handle to
+// it is dropped.
+pub struct Handle<T> {
+	data: Rc<HandleData>,
+	marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+	pub fn id(&self) -> u64 {
+		self.data.id
+	}
+
+	// the path it was loaded from or the name it was added under
+	pub fn path(&self) -> &str {
+		&self.data.path
+	}
+}
+
+impl<T> Clone for Handle<T> {
+	fn clone(&self) -> Self {
+		Self {
+			data: self.data.clone(),
+			marker: PhantomData,
+		}
+	}
+}
+
+impl<T> PartialEq for Handle<T> {
+	fn eq(&self, other: &Self) -> bool {
+		self.data.id == other.data.id
+	}
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> std::hash::Hash for Handle<T> {
+	fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+		self.data.id.hash(state);
+	}
+}
+
+impl<T> std::fmt::Debug for Handle<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "Handle({}, {:?})", self.data.id, self.data.path)
+	}
+}
+
+// A file of a loaded asset changed on disk and was loaded again. On failure the asset keeps its
+// previous version.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReloadEvent {
+	pub id: u64,
+	pub path: String,
+	pub result: Result<(), String>,
+}
+
+impl ReloadEvent {
+	pub fn is<T>(&self, handle: &Handle<T>) -> bool {
+		self.id == handle.id()
+	}
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LoadState {
+	Loading,
+	Loaded,
+	Failed(String),
+}
+
+// Counts over every asset the manager holds, for loading screens.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct LoadProgress {
+	pub loading: usize,
+	pub loaded: usize,
+	pub failed: usize,
+}
+
+impl LoadProgress {
+	// 0.0 to 1.0, failed assets count as done
+	pub fn fraction(&self) -> f32 {
+		let total = self.loading + self.loaded + self.failed;
+		match total {
+			0 => 1.0,
+			_ => (self.loaded + self.failed) as f32 / total as f32,
+		}
+	}
+
+	pub fn is_done(&self) -> bool {
+		self.loading == 0
+	}
+}
+
+enum Slot<T> {
+	Loading,
+	Loaded(T),
+	Failed(String),
+}
+
+// where a loaded asset came from, to notice when the file changes
+struct Source {
+	modified: Option<SystemTime>,
+	reload: fn(u64, String) -> Job,
+}
+
+struct Entry<T> {
+	handle: Rc<HandleData>,
+	slot: Slot<T>,
+	source: Option<Source>,
+}
+
+struct Storage<T> {
+	ids: HashMap<String, u64>,
+	entries: HashMap<u64, Entry<T>>,
+}
+
+impl<T> Default for Storage<T> {
+	fn default() -> Self {
+		Self {
+			ids: HashMap::new(),
+			entries: HashMap::new(),
+		}
+	}
+}
+
+// lets `Assets` walk the storages of every asset type
+trait AnyStorage {
+	fn as_any(&self) -> &dyn Any;
+	fn as_any_mut(&mut self) -> &mut dyn Any;
+	// unloads assets nothing else holds a handle to
+	fn collect(&mut self);
+	fn unload_all(&mut self);
+	fn count(&self, progress: &mut LoadProgress);
+	// reload jobs for loaded files that changed on disk
+	fn poll(&mut self, jobs: &mut Vec<Job>);
+}
+
+impl<T: Asset> AnyStorage for Storage<T> {
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+
+	fn as_any_mut(&mut self) -> &mut dyn Any {
+		self
+	}
+
+	fn collect(&mut self) {
+		let unused: Vec<u64> = self
+			.entries
+			.iter()
+			.filter(|(_, entry)| Rc::strong_count(&entry.handle) == 1)
+			.map(|(id, _)| *id)
+			.collect();
+		for id in unused {
+			if let Some(entry) = self.entries.remove(&id) {
+				self.ids.remove(&entry.handle.path);
+				if let Slot::Loaded(asset) = entry.slot {
+					asset.unload();
+				}
+			}
+		}
+	}
+
+	fn unload_all(&mut self) {
+		self.ids.clear();
+		for (_, entry) in self.entries.drain() {
+			if let Slot::Loaded(asset) = entry.slot {
+				asset.unload();
+			}
+		}
+	}
+
+	fn count(&self, progress: &mut LoadProgress) {
+		for entry in self.entries.values() {
+			match entry.slot {
+				Slot::Loading => progress.loading += 1,
+				Slot::Loaded(_) => progress.loaded += 1,
+				Slot::Failed(_) => progress.failed += 1,
+			}
+		}
+	}
+
+	fn poll(&mut self, jobs: &mut Vec<Job>) {
+		for (id, entry) in &mut self.entries {
+			let Some(source) = &mut entry.source else {
+				continue;
+			};
+			if matches!(entry.slot, Slot::Loading) {
+				continue;
+			}
+			let modified = modified(&entry.handle.path);
+			if modified != source.modified {
+				source.modified = modified;
+				jobs.push((source.reload)(*id, entry.handle.path.clone()));
+			}
+		}
+	}
+}
+
+// same as GetFileModTime, None when the file is missing
+fn modified(path: &str) -> Option<SystemTime> {
+	fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn load_job<T: LoadAsset>(id: u64, path: String, reload: bool) -> Job {
+	Box::new(move || {
+		let decoded = T::decode(Path::new(&path));
+		Box::new(move |assets: &mut Assets| match reload {
+			true => assets.finish_reload::<T>(id, decoded),
+			false => assets.finish::<T>(id, decoded),
+		})
+	})
+}
+
+fn reload_job<T: LoadAsset>(id: u64, path: String) -> Job {
+	load_job::<T>(id, path, true)
+}
+
+// runs on the main thread once a worker has decoded a file
+type Completion = Box<dyn FnOnce(&mut Assets) + Send>;
+type Job = Box<dyn FnOnce() -> Completion + Send>;
+
+// Loads assets by path, once per path, and frees them when their handles are gone. Files are
+// decoded on worker threads and uploaded in `update`, call it every frame on the main thread.
+// Drop it before closing the window.
+//
+// With `reload_interval` set, `update` also checks that often whether the files of loaded assets
+// changed and loads those again, see `drain_reloads`. The new version replaces the old one behind
+// the same handles, so look assets up with `get` every frame instead of keeping copies.
+pub struct Assets {
+	pub reload_interval: Option<Duration>,
+	last_poll: Instant,
+	reloads: Vec<ReloadEvent>,
+	storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+	next_id: u64,
+	jobs: Option<Sender<Job>>,
+	completed: Receiver<Completion>,
+	workers: Vec<JoinHandle<()>>,
+}
+
+impl Default for Assets {
+	fn default() -> Self {
+		Self::new(2)
+	}
+}
+
+impl Assets {
+	// With no workers `load` decodes and uploads right away.
+	pub fn new(workers: usize) -> Self {
+		let (completed_sender, completed) = mpsc::channel::<Completion>();
+		let mut assets = Self {
+			reload_interval: None,
+			last_poll: Instant::now(),
+			reloads: Vec::new(),
+			storages: HashMap::new(),
+			next_id: 0,
+			jobs: None,
+			completed,
+			workers: Vec::new(),
+		};
+		if workers > 0 {
+			let (jobs, receiver) = mpsc::channel::<Job>();
+			let receiver = Arc::new(Mutex::new(receiver));
+			for _ in 0..workers {
+				let receiver = receiver.clone();
+				let completed = completed_sender.clone();
+				assets.workers.push(thread::spawn(move || loop {
+					let job = receiver.lock().map_err(|_| ()).and_then(|receiver| receiver.recv().map_err(|_| ()));
+					let Ok(job) = job else {
+						break;
+					};
+					if completed.send(job()).is_err() {
+						break;
+					}
+				}));
+			}
+			assets.jobs = Some(jobs);
+		}
+		assets
+	}
+
+	pub fn load<T: LoadAsset>(&mut self, path: &str) -> Handle<T> {
+		if let Some(handle) = self.find(path) {
+			return handle;
+		}
+		let source = Source {
+			modified: modified(path),
+			reload: reload_job::<T>,
+		};
+		let handle = self.insert(path, Slot::<T>::Loading, Some(source));
+		self.submit(load_job::<T>(handle.id(), path.to_string(), false));
+		handle
+	}
+
+	// Hands over an asset made in code, keyed by `name`. An asset already under that name is
+	// replaced and unloaded, its handles now point to the new one.
+	pub fn add<T: Asset>(&mut self, name: &str, asset: T) -> Handle<T> {
+		if let Some(handle) = self.find::<T>(name) {
+			if let Some(entry) = self.storage_mut::<T>().entries.get_mut(&handle.id()) {
+				if let Slot::Loaded(old) = std::mem::replace(&mut entry.slot, Slot::Loaded(asset)) {
+					old.unload();
+				}
+			}
+			return handle;
+		}
+		self.insert(name, Slot::Loaded(asset), None)
+	}
+
+	// None while loading or when loading failed.
+	pub fn get<T: Asset>(&self, handle: &Handle<T>) -> Option<&T> {
+		match &self.storage::<T>()?.entries.get(&handle.id())?.slot {
+			Slot::Loaded(asset) => Some(asset),
+			_ => None,
+		}
+	}
+
+	pub fn state<T: Asset>(&self, handle: &Handle<T>) -> LoadState {
+		let slot = self
+			.storage::<T>()
+			.and_then(|storage| storage.entries.get(&handle.id()))
+			.map(|entry| &entry.slot);
+		match slot {
+			Some(Slot::Loaded(_)) => LoadState::Loaded,
+			Some(Slot::Failed(error)) => LoadState::Failed(error.clone()),
+			_ => LoadState::Loading,
+		}
+	}
+
+	pub fn progress(&self) -> LoadProgress {
+		let mut progress = LoadProgress::default();
+		for storage in self.storages.values() {
+			storage.count(&mut progress);
+		}
+		progress
+	}
+
+	// Uploads what the workers decoded since the last call, starts reloads of changed files, then
+	// unloads assets without handles.
+	pub fn update(&mut self) {
+		while let Ok(completion) = self.completed.try_recv() {
+			completion(self);
+		}
+		if self.reload_interval.is_some_and(|interval| self.last_poll.elapsed() >= interval) {
+			self.last_poll = Instant::now();
+			let mut jobs = Vec::new();
+			for storage in self.storages.values_mut() {
+				storage.poll(&mut jobs);
+			}
+			for job in jobs {
+				self.submit(job);
+			}
+		}
+		for storage in self.storages.values_mut() {
+			storage.collect();
+		}
+	}
+
+	// Blocks until every pending load is uploaded, e.g. behind a loading screen that has nothing
+	// left to show.
+	pub fn wait(&mut self) {
+		while !self.progress().is_done() {
+			match self.completed.recv() {
+				Ok(completion) => completion(self),
+				Err(_) => break,
+			}
+		}
+	}
+
+	// Reloads finished since the last call.
+	pub fn drain_reloads(&mut self) -> impl Iterator<Item = ReloadEvent> + '_ {
+		self.reloads.drain(..)
+	}
+
+	fn submit(&mut self, job: Job) {
+		match &self.jobs {
+			Some(jobs) => {
+				if let Err(mpsc::SendError(job)) = jobs.send(job) {
+					job()(self);
+				}
+			}
+			None => job()(self),
+		}
+	}
+
+	fn finish<T: LoadAsset>(&mut self, id: u64, decoded: Result<T::Decoded, String>) {
+		// dropped while it was loading, `decoded` frees itself
+		let Some(entry) = self.storage_mut::<T>().entries.get_mut(&id) else {
+			return;
+		};
+		entry.slot = match decoded.and_then(T::upload) {
+			Ok(asset) => Slot::Loaded(asset),
+			// reported by `state`
+			Err(error) => Slot::Failed(error),
+		};
+	}
+
+	// a failed reload keeps the asset that was there, a failed first load gets another chance
+	fn finish_reload<T: LoadAsset>(&mut self, id: u64, decoded: Result<T::Decoded, String>) {
+		let Some(entry) = self.storage_mut::<T>().entries.get_mut(&id) else {
+			return;
+		};
+		let path = entry.handle.path.clone();
+		let result = match decoded.and_then(T::upload) {
+			Ok(asset) => {
+				if let Slot::Loaded(old) = std::mem::replace(&mut entry.slot, Slot::Loaded(asset)) {
+					old.unload();
+				}
+				Ok(())
+			}
+			Err(error) => {
+				if let Slot::Failed(previous) = &mut entry.slot {
+					previous.clone_from(&error);
+				}
+				Err(error)
+			}
+		};
+		self.reloads.push(ReloadEvent { id, path, result });
+	}
+
+	fn find<T: Asset>(&self, path: &str) -> Option<Handle<T>> {
+		let storage = self.storage::<T>()?;
+		let entry = storage.entries.get(storage.ids.get(path)?)?;
+		Some(Handle {
+			data: entry.handle.clone(),
+			marker: PhantomData,
+		})
+	}
+
+	fn insert<T: Asset>(&mut self, path: &str, slot: Slot<T>, source: Option<Source>) -> Handle<T> {
+		self.next_id += 1;
+		let data = Rc::new(HandleData {
+			id: self.next_id,
+			path: path.to_string(),
+		});
+		let storage = self.storage_mut::<T>();
+		storage.ids.insert(path.to_string(), data.id);
+		storage.entries.insert(
+			data.id,
+			Entry {
+				handle: data.clone(),
+				slot,
+				source,
+			},
+		);
+		Handle { data, marker: PhantomData }
+	}
+
+	fn storage<T: Asset>(&self) -> Option<&Storage<T>> {
+		self.storages.get(&TypeId::of::<T>())?.as_any().downcast_ref()
+	}
+
+	fn storage_mut<T: Asset>(&mut self) -> &mut Storage<T> {
+		let storage = self.storages.entry(TypeId::of::<T>()).or_insert_with(|| Box::<Storage<T>>::default());
+		storage.as_any_mut().downcast_mut().expect("storage of the wrong type")
+	}
+}
+
+impl Drop for Assets {
+	fn drop(&mut self) {
+		// workers stop once the job channel closes
+		self.jobs = None;
+		for worker in self.workers.drain(..) {
+			let _ = worker.join();
+		}
+		for storage in self.storages.values_mut() {
+			storage.unload_all();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{cell::Cell, path::PathBuf};
+
+	use super::*;
+
+	// a file of its own under the temp dir, removed when dropped
+	struct TempFile(PathBuf);
+
+	impl TempFile {
+		fn new(name: &str, text: &str) -> Self {
+			let path = std::env::temp_dir().join(format!("raylib-assets-{}-{name}", std::process::id()));
+			fs::write(&path, text).unwrap();
+			Self(path)
+		}
+
+		fn path(&self) -> &str {
+			self.0.to_str().unwrap()
+		}
+
+		// with a later modification time, so the change is seen however coarse the file system's clock
+		fn rewrite(&self, text: &str, seconds_later: u64) {
+			fs::write(&self.0, text).unwrap();
+			let file = fs::File::options().write(true).open(&self.0).unwrap();
+			file.set_modified(SystemTime::now() + Duration::from_secs(seconds_later)).unwrap();
+		}
+	}
+
+	impl Drop for TempFile {
+		fn drop(&mut self) {
+			let _ = fs::remove_file(&self.0);
+		}
+	}
+
+	// counts its unloads
+	struct Counted(Rc<Cell<u32>>);
+
+	impl Asset for Counted {
+		fn unload(self) {
+			self.0.set(self.0.get() + 1);
+		}
+	}
+
+	fn text(assets: &Assets, handle: &Handle<String>) -> Option<String> {
+		assets.get(handle).cloned()
+	}
+
+	#[test]
+	fn a_path_loads_once() {
+		let (first, second) = (TempFile::new("dedup-first", "one"), TempFile::new("dedup-second", "two"));
+		let mut assets = Assets::new(0);
+		let a = assets.load::<String>(first.path());
+		let b = assets.load::<String>(first.path());
+		let other = assets.load::<String>(second.path());
+		assert_eq!(a, b);
+		assert_ne!(a, other);
+		assert_eq!(a.path(), first.path());
+		assert_eq!(text(&assets, &a).as_deref(), Some("one"));
+		assert_eq!(text(&assets, &other).as_deref(), Some("two"));
+		assert_eq!(assets.progress().loaded, 2);
+	}
+
+	#[test]
+	fn assets_unload_with_their_last_handle() {
+		let file = TempFile::new("unload", "text");
+		let unloads = Rc::new(Cell::new(0));
+		let mut assets = Assets::new(0);
+		let counted = assets.add("counted", Counted(unloads.clone()));
+		let copy = counted.clone();
+		let loaded = assets.load::<String>(file.path());
+		let id = loaded.id();
+
+		drop(counted);
+		assets.update();
+		assert_eq!(unloads.get(), 0);
+		assert!(assets.get(&copy).is_some());
+
+		drop((copy, loaded));
+		// unloading waits for `update`
+		assert_eq!(assets.progress().loaded, 2);
+		assets.update();
+		assert_eq!(unloads.get(), 1);
+		assert_eq!(assets.progress(), LoadProgress::default());
+		assert_ne!(assets.load::<String>(file.path()).id(), id);
+
+		// replacing an asset unloads the old one but keeps its handles
+		let first = assets.add("counted", Counted(unloads.clone()));
+		let second = assets.add("counted", Counted(unloads.clone()));
+		assert_eq!(first, second);
+		assert_eq!(unloads.get(), 2);
+		drop(assets);
+		assert_eq!(unloads.get(), 3);
+	}
+
+	#[test]
+	fn progress_counts_loading_loaded_and_failed() {
+		let file = TempFile::new("progress", "text");
+		let missing = std::env::temp_dir().join("raylib-assets-missing-file");
+		let mut assets = Assets::new(1);
+		assert_eq!(assets.progress().fraction(), 1.0);
+
+		let loaded = assets.load::<String>(file.path());
+		let failed = assets.load::<String>(missing.to_str().unwrap());
+		// workers hand their results over in `update` or `wait`
+		let progress = assets.progress();
+		assert_eq!((progress.loading, progress.fraction(), progress.is_done()), (2, 0.0, false));
+		assert_eq!(assets.state(&loaded), LoadState::Loading);
+		assert_eq!(text(&assets, &loaded), None);
+
+		assets.wait();
+		let progress = assets.progress();
+		assert_eq!((progress.loading, progress.loaded, progress.failed), (0, 1, 1));
+		assert_eq!((progress.fraction(), progress.is_done()), (1.0, true));
+		assert_eq!(assets.state(&loaded), LoadState::Loaded);
+		assert!(matches!(assets.state(&failed), LoadState::Failed(error) if error.starts_with("could not read")));
+		assert_eq!(text(&assets, &failed), None);
+	}
+
+	#[test]
+	fn reloads_replace_the_asset_or_keep_the_old_one() {
+		let file = TempFile::new("reload", "first");
+		let mut assets = Assets::new(0);
+		assets.reload_interval = Some(Duration::ZERO);
+		let handle = assets.load::<String>(file.path());
+		assets.update();
+		assert_eq!(assets.drain_reloads().count(), 0, "unchanged files aren't reloaded");
+
+		file.rewrite("second", 10);
+		assets.update();
+		let events: Vec<ReloadEvent> = assets.drain_reloads().collect();
+		assert_eq!(
+			events,
+			[ReloadEvent {
+				id: handle.id(),
+				path: file.path().to_string(),
+				result: Ok(()),
+			}]
+		);
+		assert!(events[0].is(&handle));
+		assert_eq!(text(&assets, &handle).as_deref(), Some("second"));
+		assert_eq!(assets.drain_reloads().count(), 0);
+
+		fs::remove_file(&file.0).unwrap();
+		assets.update();
+		let event = assets.drain_reloads().next().unwrap();
+		assert!(event.result.is_err());
+		assert_eq!(text(&assets, &handle).as_deref(), Some("second"));
+		assert_eq!(assets.state(&handle), LoadState::Loaded);
+
+		file.rewrite("third", 20);
+		assets.update();
+		assert_eq!(assets.drain_reloads().map(|event| event.result).collect::<Vec<_>>(), [Ok(())]);
+		assert_eq!(text(&assets, &handle).as_deref(), Some("third"));
+	}
+
+	#[test]
+	fn a_failed_load_is_tried_again_once_the_file_appears() {
+		let file = TempFile::new("appears", "");
+		fs::remove_file(&file.0).unwrap();
+		let mut assets = Assets::new(0);
+		assets.reload_interval = Some(Duration::ZERO);
+		let handle = assets.load::<String>(file.path());
+		assert!(matches!(assets.state(&handle), LoadState::Failed(_)));
+
+		file.rewrite("here now", 0);
+		assets.update();
+		assert_eq!(assets.drain_reloads().next().map(|event| event.result), Some(Ok(())));
+		assert_eq!(text(&assets, &handle).as_deref(), Some("here now"));
+	}
+}
@@ -0,0 +1,678 @@
+use std::{
+	cell::RefCell,
+	ffi::CString,
+	io,
+	marker::PhantomData,
+	path::Path,
+	sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::audio_sys as sys;
+
+// raylib has one audio device per process
+static DEVICE_OPEN: AtomicBool = AtomicBool::new(false);
+
+// Volume, pitch and pan of a sound, a music track or a bus. A voice plays at its own levels
+// combined with those of its bus and the bus's parents.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Levels {
+	// 0.0 silent to 1.0 full
+	pub volume: f32,
+	// 1.0 plays at the recorded pitch, 2.0 an octave up
+	pub pitch: f32,
+	// -1.0 left, 0.0 center, 1.0 right
+	pub pan: f32,
+}
+
+impl Default for Levels {
+	fn default() -> Self {
+		Self {
+			volume: 1.0,
+			pitch: 1.0,
+			pan: 0.0,
+		}
+	}
+}
+
+impl Levels {
+	pub fn volume(volume: f32) -> Self {
+		Self { volume, ..Self::default() }
+	}
+
+	// volumes and pitches multiply, pans add up
+	pub fn combine(self, other: Levels) -> Levels {
+		Levels {
+			volume: self.volume * other.volume,
+			pitch: self.pitch * other.pitch,
+			pan: (self.pan + other.pan).clamp(-1.0, 1.0),
+		}
+	}
+}
+
+// raylib 5.0 takes pans from 1.0 left to 0.0 right, 0.5 is center
+fn raylib_pan(pan: f32) -> f32 {
+	0.5 - pan.clamp(-1.0, 1.0) * 0.5
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct BusId(usize);
+
+#[derive(Clone, Debug)]
+struct Bus {
+	name: String,
+	levels: Levels,
+	// None only for master
+	parent: Option<BusId>,
+}
+
+#[derive(Clone, Copy)]
+enum RawVoice {
+	Sound(crate::Sound),
+	// shares the sample data of another sound, unloaded before it
+	Alias(crate::Sound),
+	Music(crate::Music),
+}
+
+struct Voice {
+	raw: RawVoice,
+	levels: Levels,
+	bus: BusId,
+}
+
+// Everything loaded through the device, so bus changes reach every voice and music streams are
+// pumped in `update`.
+struct Mixer {
+	buses: Vec<Bus>,
+	voices: Vec<Option<Voice>>,
+	free: Vec<usize>,
+}
+
+impl Mixer {
+	fn bus_levels(&self, bus: BusId) -> Levels {
+		let mut levels = Levels::default();
+		let mut next = Some(bus);
+		while let Some(BusId(index)) = next {
+			levels = levels.combine(self.buses[index].levels);
+			next = self.buses[index].parent;
+		}
+		levels
+	}
+
+	// whether `bus` is `ancestor` or one of its children
+	fn feeds(&self, bus: BusId, ancestor: BusId) -> bool {
+		let mut next = Some(bus);
+		while let Some(bus) = next {
+			if bus == ancestor {
+				return true;
+			}
+			next = self.buses[bus.0].parent;
+		}
+		false
+	}
+
+	fn add(&mut self, raw: RawVoice) -> usize {
+		let voice = Voice {
+			raw,
+			levels: Levels::default(),
+			bus: BusId(0),
+		};
+		let slot = match self.free.pop() {
+			Some(slot) => {
+				self.voices[slot] = Some(voice);
+				slot
+			}
+			None => {
+				self.voices.push(Some(voice));
+				self.voices.len() - 1
+			}
+		};
+		self.apply(slot);
+		slot
+	}
+
+	fn voice(&mut self, slot: usize) -> &mut Voice {
+		self.voices[slot].as_mut().expect("voice released while in use")
+	}
+
+	// hands the voice's effective levels to raylib
+	fn apply(&mut self, slot: usize) {
+		let Some(voice) = &self.voices[slot] else {
+			return;
+		};
+		let levels = voice.levels.combine(self.bus_levels(voice.bus));
+		unsafe {
+			match voice.raw {
+				RawVoice::Sound(sound) | RawVoice::Alias(sound) => {
+					sys::SetSoundVolume(sound, levels.volume);
+					sys::SetSoundPitch(sound, levels.pitch);
+					sys::SetSoundPan(sound, raylib_pan(levels.pan));
+				}
+				RawVoice::Music(music) => {
+					sys::SetMusicVolume(music, levels.volume);
+					sys::SetMusicPitch(music, levels.pitch);
+					sys::SetMusicPan(music, raylib_pan(levels.pan));
+				}
+			}
+		}
+	}
+
+	fn apply_all(&mut self) {
+		for slot in 0..self.voices.len() {
+			self.apply(slot);
+		}
+	}
+
+	fn release(&mut self, slot: usize) {
+		if let Some(voice) = self.voices[slot].take() {
+			unsafe {
+				match voice.raw {
+					RawVoice::Sound(sound) => sys::UnloadSound(sound),
+					RawVoice::Alias(alias) => sys::UnloadSoundAlias(alias),
+					RawVoice::Music(music) => sys::UnloadMusicStream(music),
+				}
+			}
+			self.free.push(slot);
+		}
+	}
+}
+
+// Opens raylib's audio device and closes it when dropped. Sounds, music and waves borrow it, so
+// none of them can outlive it. Call `update` once per frame to keep music streaming.
+pub struct AudioDevice {
+	mixer: RefCell<Mixer>,
+	// raylib's audio calls belong on one thread
+	_not_send: PhantomData<*const ()>,
+}
+
+impl AudioDevice {
+	pub fn init() -> io::Result<Self> {
+		if DEVICE_OPEN.swap(true, Ordering::SeqCst) {
+			return Err(io::Error::new(io::ErrorKind::AlreadyExists, "the audio device is already open"));
+		}
+		unsafe { sys::InitAudioDevice() };
+		if !unsafe { sys::IsAudioDeviceReady() } {
+			DEVICE_OPEN.store(false, Ordering::SeqCst);
+			return Err(io::Error::other("could not open the audio device"));
+		}
+		let master = Bus {
+			name: "master".to_string(),
+			levels: Levels::default(),
+			parent: None,
+		};
+		Ok(Self {
+			mixer: RefCell::new(Mixer {
+				buses: vec![master],
+				voices: Vec::new(),
+				free: Vec::new(),
+			}),
+			_not_send: PhantomData,
+		})
+	}
+
+	// Pumps every music stream, call once per frame.
+	pub fn update(&self) {
+		let mixer = self.mixer.borrow();
+		for voice in mixer.voices.iter().flatten() {
+			if let RawVoice::Music(music) = voice.raw {
+				unsafe { sys::UpdateMusicStream(music) };
+			}
+		}
+	}
+
+	// every bus ends up here, voices start on it
+	pub fn master(&self) -> BusId {
+		BusId(0)
+	}
+
+	pub fn add_bus(&self, name: &str, parent: BusId) -> BusId {
+		let mut mixer = self.mixer.borrow_mut();
+		mixer.buses.push(Bus {
+			name: name.to_string(),
+			levels: Levels::default(),
+			parent: Some(parent),
+		});
+		BusId(mixer.buses.len() - 1)
+	}
+
+	pub fn bus(&self, name: &str) -> Option<BusId> {
+		self.mixer.borrow().buses.iter().position(|bus| bus.name == name).map(BusId)
+	}
+
+	pub fn bus_name(&self, bus: BusId) -> String {
+		self.mixer.borrow().buses[bus.0].name.clone()
+	}
+
+	pub fn bus_levels(&self, bus: BusId) -> Levels {
+		self.mixer.borrow().buses[bus.0].levels
+	}
+
+	pub fn set_bus_levels(&self, bus: BusId, levels: Levels) {
+		let mut mixer = self.mixer.borrow_mut();
+		mixer.buses[bus.0].levels = levels;
+		mixer.apply_all();
+	}
+
+	pub fn set_bus_volume(&self, bus: BusId, volume: f32) {
+		let levels = self.bus_levels(bus);
+		self.set_bus_levels(bus, Levels { volume, ..levels });
+	}
+
+	// whether anything on the bus or its children is playing, e.g. to duck music under dialogue
+	pub fn is_bus_playing(&self, bus: BusId) -> bool {
+		let mixer = self.mixer.borrow();
+		mixer
+			.voices
+			.iter()
+			.flatten()
+			.filter(|voice| mixer.feeds(voice.bus, bus))
+			.any(|voice| unsafe {
+				match voice.raw {
+					RawVoice::Sound(sound) | RawVoice::Alias(sound) => sys::IsSoundPlaying(sound),
+					RawVoice::Music(music) => sys::IsMusicStreamPlaying(music),
+				}
+			})
+	}
+
+	pub fn load_sound(&self, path: impl AsRef<Path>) -> io::Result<Sound<'_>> {
+		let path = path.as_ref();
+		let sound = unsafe { sys::LoadSound(c_path(path)?.as_ptr()) };
+		if !unsafe { sys::IsSoundReady(sound) } {
+			return Err(invalid_data(format!("could not load sound {}", path.display())));
+		}
+		Ok(Sound {
+			device: self,
+			slot: self.mixer.borrow_mut().add(RawVoice::Sound(sound)),
+		})
+	}
+
+	pub fn sound_from_wave(&self, wave: &Wave<'_>) -> io::Result<Sound<'_>> {
+		let sound = unsafe { sys::LoadSoundFromWave(wave.raw) };
+		if !unsafe { sys::IsSoundReady(sound) } {
+			return Err(invalid_data("could not create a sound from the wave".to_string()));
+		}
+		Ok(Sound {
+			device: self,
+			slot: self.mixer.borrow_mut().add(RawVoice::Sound(sound)),
+		})
+	}
+
+	// Streams from the file while playing, for long tracks.
+	pub fn load_music(&self, path: impl AsRef<Path>) -> io::Result<Music<'_>> {
+		let path = path.as_ref();
+		let music = unsafe { sys::LoadMusicStream(c_path(path)?.as_ptr()) };
+		if !unsafe { sys::IsMusicReady(music) } {
+			return Err(invalid_data(format!("could not load music {}", path.display())));
+		}
+		Ok(Music {
+			device: self,
+			slot: self.mixer.borrow_mut().add(RawVoice::Music(music)),
+		})
+	}
+
+	pub fn load_wave(&self, path: impl AsRef<Path>) -> io::Result<Wave<'_>> {
+		let path = path.as_ref();
+		let wave = unsafe { sys::LoadWave(c_path(path)?.as_ptr()) };
+		if !unsafe { sys::IsWaveReady(wave) } {
+			return Err(invalid_data(format!("could not load wave {}", path.display())));
+		}
+		Ok(Wave {
+			raw: wave,
+			_device: PhantomData,
+		})
+	}
+
+	// `voices` copies of the sound sharing one sample buffer, so a one-shot can play over itself.
+	pub fn load_sound_pool(&self, path: impl AsRef<Path>, voices: usize) -> io::Result<SoundPool<'_>> {
+		let source = self.load_sound(path)?;
+		let raw = match self.mixer.borrow_mut().voice(source.slot).raw {
+			RawVoice::Sound(sound) => sound,
+			_ => unreachable!("load_sound makes sounds"),
+		};
+		let aliases = (0..voices.max(1))
+			.map(|_| self.mixer.borrow_mut().add(RawVoice::Alias(unsafe { sys::LoadSoundAlias(raw) })))
+			.collect();
+		Ok(SoundPool {
+			device: self,
+			aliases,
+			source,
+			levels: Levels::default(),
+			bus: BusId(0),
+			next: 0,
+		})
+	}
+
+	fn with_voice<R>(&self, slot: usize, f: impl FnOnce(RawVoice) -> R) -> R {
+		f(self.mixer.borrow_mut().voice(slot).raw)
+	}
+
+	fn set_levels(&self, slot: usize, levels: Levels) {
+		let mut mixer = self.mixer.borrow_mut();
+		mixer.voice(slot).levels = levels;
+		mixer.apply(slot);
+	}
+
+	fn set_bus(&self, slot: usize, bus: BusId) {
+		let mut mixer = self.mixer.borrow_mut();
+		mixer.voice(slot).bus = bus;
+		mixer.apply(slot);
+	}
+}
+
+impl Drop for AudioDevice {
+	fn drop(&mut self) {
+		unsafe { sys::CloseAudioDevice() };
+		DEVICE_OPEN.store(false, Ordering::SeqCst);
+	}
+}
+
+fn c_path(path: &Path) -> io::Result<CString> {
+	if !path.exists() {
+		return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display())));
+	}
+	CString::new(path.to_string_lossy().as_bytes()).map_err(|_| invalid_data(format!("{} contains a nul byte", path.display())))
+}
+
+fn invalid_data(message: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// A sound held in memory, for effects. Playing it again restarts it, see `SoundPool` for overlaps.
+pub struct Sound<'a> {
+	device: &'a AudioDevice,
+	slot: usize,
+}
+
+impl Sound<'_> {
+	fn raw(&self) -> crate::Sound {
+		match self.device.with_voice(self.slot, |raw| raw) {
+			RawVoice::Sound(sound) | RawVoice::Alias(sound) => sound,
+			RawVoice::Music(_) => unreachable!("sounds hold sounds"),
+		}
+	}
+
+	pub fn play(&self) {
+		unsafe { sys::PlaySound(self.raw()) };
+	}
+
+	pub fn stop(&self) {
+		unsafe { sys::StopSound(self.raw()) };
+	}
+
+	pub fn pause(&self) {
+		unsafe { sys::PauseSound(self.raw()) };
+	}
+
+	pub fn resume(&self) {
+		unsafe { sys::ResumeSound(self.raw()) };
+	}
+
+	pub fn is_playing(&self) -> bool {
+		unsafe { sys::IsSoundPlaying(self.raw()) }
+	}
+
+	pub fn levels(&self) -> Levels {
+		self.device.mixer.borrow_mut().voice(self.slot).levels
+	}
+
+	pub fn set_levels(&self, levels: Levels) {
+		self.device.set_levels(self.slot, levels);
+	}
+
+	pub fn set_volume(&self, volume: f32) {
+		self.set_levels(Levels { volume, ..self.levels() });
+	}
+
+	pub fn set_pitch(&self, pitch: f32) {
+		self.set_levels(Levels { pitch, ..self.levels() });
+	}
+
+	pub fn set_pan(&self, pan: f32) {
+		self.set_levels(Levels { pan, ..self.levels() });
+	}
+
+	pub fn set_bus(&self, bus: BusId) {
+		self.device.set_bus(self.slot, bus);
+	}
+}
+
+impl Drop for Sound<'_> {
+	fn drop(&mut self) {
+		self.device.mixer.borrow_mut().release(self.slot);
+	}
+}
+
+// A track streamed from its file while it plays, `AudioDevice::update` keeps it fed.
+pub struct Music<'a> {
+	device: &'a AudioDevice,
+	slot: usize,
+}
+
+impl Music<'_> {
+	fn raw(&self) -> crate::Music {
+		match self.device.with_voice(self.slot, |raw| raw) {
+			RawVoice::Music(music) => music,
+			_ => unreachable!("music holds music"),
+		}
+	}
+
+	// from the start
+	pub fn play(&self) {
+		unsafe { sys::PlayMusicStream(self.raw()) };
+	}
+
+	pub fn stop(&self) {
+		unsafe { sys::StopMusicStream(self.raw()) };
+	}
+
+	pub fn pause(&self) {
+		unsafe { sys::PauseMusicStream(self.raw()) };
+	}
+
+	pub fn resume(&self) {
+		unsafe { sys::ResumeMusicStream(self.raw()) };
+	}
+
+	pub fn is_playing(&self) -> bool {
+		unsafe { sys::IsMusicStreamPlaying(self.raw()) }
+	}
+
+	pub fn seek(&self, seconds: f32) {
+		unsafe { sys::SeekMusicStream(self.raw(), seconds) };
+	}
+
+	// seconds
+	pub fn length(&self) -> f32 {
+		unsafe { sys::GetMusicTimeLength(self.raw()) }
+	}
+
+	pub fn played(&self) -> f32 {
+		unsafe { sys::GetMusicTimePlayed(self.raw()) }
+	}
+
+	pub fn looping(&self) -> bool {
+		self.raw().looping
+	}
+
+	// music loops by default
+	pub fn set_looping(&self, looping: bool) {
+		if let RawVoice::Music(music) = &mut self.device.mixer.borrow_mut().voice(self.slot).raw {
+			music.looping = looping;
+		}
+	}
+
+	pub fn levels(&self) -> Levels {
+		self.device.mixer.borrow_mut().voice(self.slot).levels
+	}
+
+	pub fn set_levels(&self, levels: Levels) {
+		self.device.set_levels(self.slot, levels);
+	}
+
+	pub fn set_volume(&self, volume: f32) {
+		self.set_levels(Levels { volume, ..self.levels() });
+	}
+
+	pub fn set_pitch(&self, pitch: f32) {
+		self.set_levels(Levels { pitch, ..self.levels() });
+	}
+
+	pub fn set_pan(&self, pan: f32) {
+		self.set_levels(Levels { pan, ..self.levels() });
+	}
+
+	pub fn set_bus(&self, bus: BusId) {
+		self.device.set_bus(self.slot, bus);
+	}
+}
+
+impl Drop for Music<'_> {
+	fn drop(&mut self) {
+		self.device.mixer.borrow_mut().release(self.slot);
+	}
+}
+
+// Samples in memory, to edit before making a sound of them.
+pub struct Wave<'a> {
+	raw: crate::Wave,
+	_device: PhantomData<&'a AudioDevice>,
+}
+
+impl Wave<'_> {
+	pub fn frame_count(&self) -> u32 {
+		self.raw.frameCount
+	}
+
+	pub fn sample_rate(&self) -> u32 {
+		self.raw.sampleRate
+	}
+
+	pub fn channels(&self) -> u32 {
+		self.raw.channels
+	}
+
+	// seconds
+	pub fn duration(&self) -> f32 {
+		self.raw.frameCount as f32 / self.raw.sampleRate.max(1) as f32
+	}
+
+	// Interleaved samples from -1.0 to 1.0.
+	pub fn samples(&self) -> Vec<f32> {
+		let len = (self.raw.frameCount * self.raw.channels) as usize;
+		unsafe {
+			let samples = sys::LoadWaveSamples(self.raw);
+			if samples.is_null() {
+				return Vec::new();
+			}
+			let copy = std::slice::from_raw_parts(samples, len).to_vec();
+			sys::UnloadWaveSamples(samples);
+			copy
+		}
+	}
+
+	// keeps the frames from `start` up to `end`
+	pub fn crop(&mut self, start: u32, end: u32) {
+		let end = end.min(self.raw.frameCount);
+		if start < end {
+			unsafe { sys::WaveCrop(&mut self.raw, start as i32, end as i32) };
+		}
+	}
+
+	// converts to `sample_rate` Hz, `sample_size` bits (8, 16 or 32) and 1 or 2 `channels`
+	pub fn format(&mut self, sample_rate: u32, sample_size: u32, channels: u32) {
+		unsafe { sys::WaveFormat(&mut self.raw, sample_rate as i32, sample_size as i32, channels as i32) };
+	}
+}
+
+impl Clone for Wave<'_> {
+	fn clone(&self) -> Self {
+		Self {
+			raw: unsafe { sys::WaveCopy(self.raw) },
+			_device: PhantomData,
+		}
+	}
+}
+
+impl Drop for Wave<'_> {
+	fn drop(&mut self) {
+		unsafe { sys::UnloadWave(self.raw) };
+	}
+}
+
+// Aliases of one sound for effects that overlap, like footsteps or gunshots. `play` takes a free
+// voice, or cuts off the one started longest ago when all are busy.
+pub struct SoundPool<'a> {
+	device: &'a AudioDevice,
+	// released in drop, before the source they share samples with
+	aliases: Vec<usize>,
+	source: Sound<'a>,
+	levels: Levels,
+	bus: BusId,
+	next: usize,
+}
+
+impl SoundPool<'_> {
+	pub fn play(&mut self) {
+		self.play_with(Levels::default());
+	}
+
+	// plays at the pool's levels combined with `levels`, e.g. a random pitch per shot
+	pub fn play_with(&mut self, levels: Levels) {
+		let playing = |slot: usize| match self.device.with_voice(slot, |raw| raw) {
+			RawVoice::Alias(sound) => unsafe { sys::IsSoundPlaying(sound) },
+			_ => false,
+		};
+		let free = (0..self.aliases.len())
+			.map(|i| (self.next + i) % self.aliases.len())
+			.find(|&i| !playing(self.aliases[i]));
+		let index = free.unwrap_or(self.next);
+		self.next = (index + 1) % self.aliases.len();
+
+		let slot = self.aliases[index];
+		self.device.set_levels(slot, self.levels.combine(levels));
+		if let RawVoice::Alias(sound) = self.device.with_voice(slot, |raw| raw) {
+			unsafe { sys::PlaySound(sound) };
+		}
+	}
+
+	pub fn stop(&self) {
+		for &slot in &self.aliases {
+			if let RawVoice::Alias(sound) = self.device.with_voice(slot, |raw| raw) {
+				unsafe { sys::StopSound(sound) };
+			}
+		}
+	}
+
+	pub fn voices(&self) -> usize {
+		self.aliases.len()
+	}
+
+	pub fn levels(&self) -> Levels {
+		self.levels
+	}
+
+	// for the next plays, voices already playing keep theirs
+	pub fn set_levels(&mut self, levels: Levels) {
+		self.levels = levels;
+	}
+
+	pub fn set_bus(&mut self, bus: BusId) {
+		self.bus = bus;
+		self.source.set_bus(bus);
+		for &slot in &self.aliases {
+			self.device.set_bus(slot, bus);
+		}
+	}
+
+	pub fn bus(&self) -> BusId {
+		self.bus
+	}
+}
+
+impl Drop for SoundPool<'_> {
+	fn drop(&mut self) {
+		let mut mixer = self.device.mixer.borrow_mut();
+		for &slot in &self.aliases {
+			mixer.release(slot);
+		}
+	}
+}
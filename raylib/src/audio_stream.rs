@@ -0,0 +1,214 @@
+use std::{
+	ffi::{c_uint, c_void},
+	io,
+	marker::PhantomData,
+	panic::{self, AssertUnwindSafe},
+	sync::Mutex,
+};
+
+use crate::{audio::AudioDevice, audio_sys as sys, dsp::Processor};
+
+// raylib's callbacks get no user data, so each of these slots has its own C function that finds
+// its processor here
+const SLOTS: usize = 16;
+// what raylib mixes in and hands to stream and mixed processors
+const MIXER_CHANNELS: usize = 2;
+
+struct Slot {
+	processor: Box<dyn Processor>,
+	channels: usize,
+}
+
+static REGISTRY: [Mutex<Option<Slot>>; SLOTS] = [const { Mutex::new(None) }; SLOTS];
+
+type Trampoline = unsafe extern "C" fn(*mut c_void, c_uint);
+
+unsafe extern "C" fn trampoline<const SLOT: usize>(data: *mut c_void, frames: c_uint) {
+	let mut slot = REGISTRY[SLOT].lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+	if let Some(Slot { processor, channels }) = slot.as_mut() {
+		let samples = std::slice::from_raw_parts_mut(data as *mut f32, frames as usize * *channels);
+		// a panic must not unwind into the C audio thread, the buffer plays as it was left
+		let _ = panic::catch_unwind(AssertUnwindSafe(|| processor.process(samples, *channels)));
+	}
+}
+
+const TRAMPOLINES: [Trampoline; SLOTS] = [
+	trampoline::<0>,
+	trampoline::<1>,
+	trampoline::<2>,
+	trampoline::<3>,
+	trampoline::<4>,
+	trampoline::<5>,
+	trampoline::<6>,
+	trampoline::<7>,
+	trampoline::<8>,
+	trampoline::<9>,
+	trampoline::<10>,
+	trampoline::<11>,
+	trampoline::<12>,
+	trampoline::<13>,
+	trampoline::<14>,
+	trampoline::<15>,
+];
+
+// Puts `processor` in a free slot, the returned index picks its trampoline.
+fn register(processor: Box<dyn Processor>, channels: usize) -> io::Result<usize> {
+	for (index, slot) in REGISTRY.iter().enumerate() {
+		let mut slot = slot.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+		if slot.is_none() {
+			*slot = Some(Slot { processor, channels });
+			return Ok(index);
+		}
+	}
+	Err(io::Error::other(format!("all {SLOTS} audio callback slots are taken")))
+}
+
+// Once raylib no longer calls the trampoline, drops the processor.
+fn unregister(index: usize) {
+	REGISTRY[index].lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take();
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ProcessorId(usize);
+
+// A stream of 32 bit float samples, filled by a Rust generator on the audio thread. Processors
+// attached to it see the samples after raylib converted them to stereo. Wrap a generator or
+// processor in `dsp::Shared` to change it while it plays.
+pub struct AudioStream<'a> {
+	raw: crate::AudioStream,
+	channels: usize,
+	callback: Option<usize>,
+	processors: Vec<usize>,
+	_device: PhantomData<&'a AudioDevice>,
+}
+
+impl<'a> AudioStream<'a> {
+	pub fn new(_device: &'a AudioDevice, sample_rate: u32, channels: u32) -> io::Result<Self> {
+		let raw = unsafe { sys::LoadAudioStream(sample_rate, 32, channels) };
+		if !unsafe { sys::IsAudioStreamReady(raw) } {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("could not create a {channels} channel stream at {sample_rate} Hz"),
+			));
+		}
+		Ok(Self {
+			raw,
+			channels: channels as usize,
+			callback: None,
+			processors: Vec::new(),
+			_device: PhantomData,
+		})
+	}
+
+	pub fn sample_rate(&self) -> u32 {
+		self.raw.sampleRate
+	}
+
+	pub fn channels(&self) -> usize {
+		self.channels
+	}
+
+	// Fills the stream whenever raylib needs more, in place of the previous generator. It gets
+	// silence to overwrite.
+	pub fn set_callback(&mut self, mut generator: impl Processor + 'static) -> io::Result<()> {
+		let silenced = move |samples: &mut [f32], channels: usize| {
+			samples.fill(0.0);
+			generator.process(samples, channels);
+		};
+		let index = register(Box::new(silenced), self.channels)?;
+		unsafe { sys::SetAudioStreamCallback(self.raw, Some(TRAMPOLINES[index])) };
+		if let Some(old) = self.callback.replace(index) {
+			unregister(old);
+		}
+		Ok(())
+	}
+
+	pub fn clear_callback(&mut self) {
+		if let Some(index) = self.callback.take() {
+			unsafe { sys::SetAudioStreamCallback(self.raw, None) };
+			unregister(index);
+		}
+	}
+
+	// Runs `processor` on the stream's samples, after the ones attached before it.
+	pub fn attach_processor(&mut self, processor: impl Processor + 'static) -> io::Result<ProcessorId> {
+		let index = register(Box::new(processor), MIXER_CHANNELS)?;
+		unsafe { sys::AttachAudioStreamProcessor(self.raw, Some(TRAMPOLINES[index])) };
+		self.processors.push(index);
+		Ok(ProcessorId(index))
+	}
+
+	pub fn detach_processor(&mut self, id: ProcessorId) {
+		if let Some(position) = self.processors.iter().position(|&index| index == id.0) {
+			self.processors.remove(position);
+			unsafe { sys::DetachAudioStreamProcessor(self.raw, Some(TRAMPOLINES[id.0])) };
+			unregister(id.0);
+		}
+	}
+
+	pub fn play(&self) {
+		unsafe { sys::PlayAudioStream(self.raw) };
+	}
+
+	pub fn stop(&self) {
+		unsafe { sys::StopAudioStream(self.raw) };
+	}
+
+	pub fn pause(&self) {
+		unsafe { sys::PauseAudioStream(self.raw) };
+	}
+
+	pub fn resume(&self) {
+		unsafe { sys::ResumeAudioStream(self.raw) };
+	}
+
+	pub fn is_playing(&self) -> bool {
+		unsafe { sys::IsAudioStreamPlaying(self.raw) }
+	}
+
+	pub fn set_volume(&self, volume: f32) {
+		unsafe { sys::SetAudioStreamVolume(self.raw, volume) };
+	}
+
+	// -1.0 left to 1.0 right, raylib 5.0 takes 1.0 as left
+	pub fn set_pan(&self, pan: f32) {
+		unsafe { sys::SetAudioStreamPan(self.raw, 0.5 - pan.clamp(-1.0, 1.0) * 0.5) };
+	}
+
+	pub fn set_pitch(&self, pitch: f32) {
+		unsafe { sys::SetAudioStreamPitch(self.raw, pitch) };
+	}
+}
+
+impl Drop for AudioStream<'_> {
+	fn drop(&mut self) {
+		for index in std::mem::take(&mut self.processors) {
+			unsafe { sys::DetachAudioStreamProcessor(self.raw, Some(TRAMPOLINES[index])) };
+			unregister(index);
+		}
+		self.clear_callback();
+		unsafe { sys::UnloadAudioStream(self.raw) };
+	}
+}
+
+// Runs on everything raylib mixed, before it reaches the speakers, until dropped. Samples are
+// stereo at the device's rate, 48 kHz on most systems.
+pub struct MixedProcessor<'a> {
+	index: usize,
+	_device: PhantomData<&'a AudioDevice>,
+}
+
+impl<'a> MixedProcessor<'a> {
+	pub fn attach(_device: &'a AudioDevice, processor: impl Processor + 'static) -> io::Result<Self> {
+		let index = register(Box::new(processor), MIXER_CHANNELS)?;
+		unsafe { sys::AttachAudioMixedProcessor(Some(TRAMPOLINES[index])) };
+		Ok(Self { index, _device: PhantomData })
+	}
+}
+
+impl Drop for MixedProcessor<'_> {
+	fn drop(&mut self) {
+		unsafe { sys::DetachAudioMixedProcessor(Some(TRAMPOLINES[self.index])) };
+		unregister(self.index);
+	}
+}
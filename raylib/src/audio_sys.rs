@@ -0,0 +1,94 @@
+#[cfg(feature = "headless")]
+use std::ffi::{c_char, c_int, c_uint};
+
+#[cfg(feature = "headless")]
+use crate::{AudioCallback, AudioStream, Music, Sound, Wave};
+
+// The raylib calls behind `audio` and `audio_stream`. Headless builds don't link raylib: there the
+// device never opens, so `AudioDevice::init` fails and nothing that needs a device can be made.
+macro_rules! audio_calls {
+	($($name:ident($($arg:ty),*) $(-> $ret:ty)?;)*) => {
+		#[cfg(not(feature = "headless"))]
+		pub(crate) use crate::{$($name),*};
+
+		$(
+			#[cfg(feature = "headless")]
+			pub(crate) unsafe fn $name($(_: $arg),*) $(-> $ret)? {
+				unreachable!(concat!(stringify!($name), " without an audio device"))
+			}
+		)*
+	};
+}
+
+#[cfg(not(feature = "headless"))]
+pub(crate) use crate::{CloseAudioDevice, InitAudioDevice, IsAudioDeviceReady};
+
+#[cfg(feature = "headless")]
+pub(crate) unsafe fn InitAudioDevice() {}
+
+#[cfg(feature = "headless")]
+pub(crate) unsafe fn CloseAudioDevice() {}
+
+#[cfg(feature = "headless")]
+pub(crate) unsafe fn IsAudioDeviceReady() -> bool {
+	false
+}
+
+audio_calls! {
+	LoadSound(*const c_char) -> Sound;
+	LoadSoundFromWave(Wave) -> Sound;
+	LoadSoundAlias(Sound) -> Sound;
+	IsSoundReady(Sound) -> bool;
+	UnloadSound(Sound);
+	UnloadSoundAlias(Sound);
+	PlaySound(Sound);
+	StopSound(Sound);
+	PauseSound(Sound);
+	ResumeSound(Sound);
+	IsSoundPlaying(Sound) -> bool;
+	SetSoundVolume(Sound, f32);
+	SetSoundPitch(Sound, f32);
+	SetSoundPan(Sound, f32);
+
+	LoadMusicStream(*const c_char) -> Music;
+	IsMusicReady(Music) -> bool;
+	UnloadMusicStream(Music);
+	PlayMusicStream(Music);
+	IsMusicStreamPlaying(Music) -> bool;
+	UpdateMusicStream(Music);
+	StopMusicStream(Music);
+	PauseMusicStream(Music);
+	ResumeMusicStream(Music);
+	SeekMusicStream(Music, f32);
+	SetMusicVolume(Music, f32);
+	SetMusicPitch(Music, f32);
+	SetMusicPan(Music, f32);
+	GetMusicTimeLength(Music) -> f32;
+	GetMusicTimePlayed(Music) -> f32;
+
+	LoadWave(*const c_char) -> Wave;
+	IsWaveReady(Wave) -> bool;
+	UnloadWave(Wave);
+	WaveCopy(Wave) -> Wave;
+	WaveCrop(*mut Wave, c_int, c_int);
+	WaveFormat(*mut Wave, c_int, c_int, c_int);
+	LoadWaveSamples(Wave) -> *mut f32;
+	UnloadWaveSamples(*mut f32);
+
+	LoadAudioStream(c_uint, c_uint, c_uint) -> AudioStream;
+	IsAudioStreamReady(AudioStream) -> bool;
+	UnloadAudioStream(AudioStream);
+	PlayAudioStream(AudioStream);
+	StopAudioStream(AudioStream);
+	PauseAudioStream(AudioStream);
+	ResumeAudioStream(AudioStream);
+	IsAudioStreamPlaying(AudioStream) -> bool;
+	SetAudioStreamVolume(AudioStream, f32);
+	SetAudioStreamPitch(AudioStream, f32);
+	SetAudioStreamPan(AudioStream, f32);
+	SetAudioStreamCallback(AudioStream, AudioCallback);
+	AttachAudioStreamProcessor(AudioStream, AudioCallback);
+	DetachAudioStreamProcessor(AudioStream, AudioCallback);
+	AttachAudioMixedProcessor(AudioCallback);
+	DetachAudioMixedProcessor(AudioCallback);
+}
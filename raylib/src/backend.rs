@@ -0,0 +1,459 @@
+use std::{collections::VecDeque, ffi::CString};
+
+use crate::{
+	gui::GuiInput,
+	input_map::{InputSource, SyntheticInput},
+	*,
+};
+
+// Set by the `headless` feature, such builds have no window and no raylib library.
+pub const HEADLESS: bool = cfg!(feature = "headless");
+
+// What a game opens its window with, `HeadlessBackend` in headless builds.
+#[cfg(not(feature = "headless"))]
+pub type WindowBackend = RaylibBackend;
+#[cfg(feature = "headless")]
+pub type WindowBackend = HeadlessBackend;
+
+// The window, frame and drawing calls a game makes, so the same game code can run against
+// the real window or without one. Mirrors the raylib functions of the same name.
+pub trait Backend: InputSource {
+	// `ConfigFlags`, before `init_window`
+	fn set_config_flags(&mut self, flags: u32);
+	fn init_window(&mut self, width: i32, height: i32, title: &str);
+	fn close_window(&mut self);
+	fn window_should_close(&self) -> bool;
+	fn set_window_title(&mut self, title: &str);
+	fn toggle_fullscreen(&mut self);
+	fn set_target_fps(&mut self, fps: i32);
+	fn fps(&self) -> i32;
+	// seconds the previous frame took
+	fn frame_time(&self) -> f32;
+	fn screen_width(&self) -> i32;
+	fn screen_height(&self) -> i32;
+	// Mouse, typed text and navigation keys for `gui` and `console`, call once per frame.
+	fn gui_input(&mut self, gamepad: i32) -> GuiInput;
+
+	fn begin_drawing(&mut self);
+	fn end_drawing(&mut self);
+	fn clear_background(&mut self, color: Color);
+	fn begin_mode_2d(&mut self, camera: Camera2D);
+	fn end_mode_2d(&mut self);
+	fn begin_scissor_mode(&mut self, x: i32, y: i32, width: i32, height: i32);
+	fn end_scissor_mode(&mut self);
+	fn load_render_texture(&mut self, width: i32, height: i32) -> RenderTexture2D;
+	fn unload_render_texture(&mut self, target: RenderTexture2D);
+	fn begin_texture_mode(&mut self, target: RenderTexture2D);
+	fn end_texture_mode(&mut self);
+
+	fn draw_rectangle(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color);
+	fn draw_rectangle_rec(&mut self, rec: Rectangle, color: Color);
+	fn draw_rectangle_lines_ex(&mut self, rec: Rectangle, thickness: f32, color: Color);
+	// `rec` rotated by `rotation` degrees around `origin`, which is relative to `rec`
+	fn draw_rectangle_pro(&mut self, rec: Rectangle, origin: Vector2, rotation: f32, color: Color);
+	fn draw_circle_v(&mut self, center: Vector2, radius: f32, color: Color);
+	fn draw_circle_lines_v(&mut self, center: Vector2, radius: f32, color: Color);
+	fn draw_poly(&mut self, center: Vector2, sides: i32, radius: f32, rotation: f32, color: Color);
+	fn draw_line_ex(&mut self, start: Vector2, end: Vector2, thickness: f32, color: Color);
+	fn draw_text(&mut self, text: &str, x: i32, y: i32, font_size: i32, color: Color);
+	// DrawTextPro with the default font, rotated around `position`
+	fn draw_text_pro(&mut self, text: &str, position: Vector2, rotation: f32, font_size: f32, spacing: f32, color: Color);
+	fn draw_texture_rec(&mut self, texture: Texture2D, source: Rectangle, position: Vector2, tint: Color);
+	fn draw_texture_pro(&mut self, texture: Texture2D, source: Rectangle, dest: Rectangle, origin: Vector2, rotation: f32, tint: Color);
+	fn draw_fps(&mut self, x: i32, y: i32);
+}
+
+// raylib stops at the first nul anyway
+fn c_text(text: &str) -> CString {
+	CString::new(text.split('\0').next().unwrap_or_default()).unwrap_or_default()
+}
+
+// The real raylib window.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct RaylibBackend;
+
+impl InputSource for RaylibBackend {
+	fn key_down(&self, key: KeyboardKey) -> bool {
+		input_map::RaylibInput.key_down(key)
+	}
+
+	fn mouse_button_down(&self, button: MouseButton) -> bool {
+		input_map::RaylibInput.mouse_button_down(button)
+	}
+
+	fn mouse_wheel(&self) -> f32 {
+		input_map::RaylibInput.mouse_wheel()
+	}
+
+	fn gamepad_button_down(&self, gamepad: i32, button: GamepadButton) -> bool {
+		input_map::RaylibInput.gamepad_button_down(gamepad, button)
+	}
+
+	fn gamepad_axis(&self, gamepad: i32, axis: GamepadAxis) -> f32 {
+		input_map::RaylibInput.gamepad_axis(gamepad, axis)
+	}
+
+	fn gesture_detected(&self, gesture: Gesture) -> bool {
+		input_map::RaylibInput.gesture_detected(gesture)
+	}
+}
+
+impl Backend for RaylibBackend {
+	fn set_config_flags(&mut self, flags: u32) {
+		unsafe { SetConfigFlags(flags) };
+	}
+
+	fn init_window(&mut self, width: i32, height: i32, title: &str) {
+		unsafe { InitWindow(width, height, c_text(title).as_ptr()) };
+	}
+
+	fn close_window(&mut self) {
+		unsafe { CloseWindow() };
+	}
+
+	fn window_should_close(&self) -> bool {
+		unsafe { WindowShouldClose() }
+	}
+
+	fn set_window_title(&mut self, title: &str) {
+		unsafe { SetWindowTitle(c_text(title).as_ptr()) };
+	}
+
+	fn toggle_fullscreen(&mut self) {
+		unsafe { ToggleFullscreen() };
+	}
+
+	fn set_target_fps(&mut self, fps: i32) {
+		unsafe { SetTargetFPS(fps) };
+	}
+
+	fn fps(&self) -> i32 {
+		unsafe { GetFPS() }
+	}
+
+	fn frame_time(&self) -> f32 {
+		unsafe { GetFrameTime() }
+	}
+
+	fn screen_width(&self) -> i32 {
+		unsafe { GetScreenWidth() }
+	}
+
+	fn screen_height(&self) -> i32 {
+		unsafe { GetScreenHeight() }
+	}
+
+	fn gui_input(&mut self, gamepad: i32) -> GuiInput {
+		GuiInput::capture(gamepad)
+	}
+
+	fn begin_drawing(&mut self) {
+		unsafe { BeginDrawing() };
+	}
+
+	fn end_drawing(&mut self) {
+		unsafe { EndDrawing() };
+	}
+
+	fn clear_background(&mut self, color: Color) {
+		unsafe { ClearBackground(color) };
+	}
+
+	fn begin_mode_2d(&mut self, camera: Camera2D) {
+		unsafe { BeginMode2D(camera) };
+	}
+
+	fn end_mode_2d(&mut self) {
+		unsafe { EndMode2D() };
+	}
+
+	fn begin_scissor_mode(&mut self, x: i32, y: i32, width: i32, height: i32) {
+		unsafe { BeginScissorMode(x, y, width, height) };
+	}
+
+	fn end_scissor_mode(&mut self) {
+		unsafe { EndScissorMode() };
+	}
+
+	fn load_render_texture(&mut self, width: i32, height: i32) -> RenderTexture2D {
+		unsafe { LoadRenderTexture(width, height) }
+	}
+
+	fn unload_render_texture(&mut self, target: RenderTexture2D) {
+		unsafe { UnloadRenderTexture(target) };
+	}
+
+	fn begin_texture_mode(&mut self, target: RenderTexture2D) {
+		unsafe { BeginTextureMode(target) };
+	}
+
+	fn end_texture_mode(&mut self) {
+		unsafe { EndTextureMode() };
+	}
+
+	fn draw_rectangle(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color) {
+		unsafe { DrawRectangle(x, y, width, height, color) };
+	}
+
+	fn draw_rectangle_rec(&mut self, rec: Rectangle, color: Color) {
+		unsafe { DrawRectangleRec(rec, color) };
+	}
+
+	fn draw_rectangle_lines_ex(&mut self, rec: Rectangle, thickness: f32, color: Color) {
+		unsafe { DrawRectangleLinesEx(rec, thickness, color) };
+	}
+
+	fn draw_rectangle_pro(&mut self, rec: Rectangle, origin: Vector2, rotation: f32, color: Color) {
+		unsafe { DrawRectanglePro(rec, origin, rotation, color) };
+	}
+
+	fn draw_circle_v(&mut self, center: Vector2, radius: f32, color: Color) {
+		unsafe { DrawCircleV(center, radius, color) };
+	}
+
+	fn draw_circle_lines_v(&mut self, center: Vector2, radius: f32, color: Color) {
+		unsafe { DrawCircleLinesV(center, radius, color) };
+	}
+
+	fn draw_poly(&mut self, center: Vector2, sides: i32, radius: f32, rotation: f32, color: Color) {
+		unsafe { DrawPoly(center, sides, radius, rotation, color) };
+	}
+
+	fn draw_line_ex(&mut self, start: Vector2, end: Vector2, thickness: f32, color: Color) {
+		unsafe { DrawLineEx(start, end, thickness, color) };
+	}
+
+	fn draw_text(&mut self, text: &str, x: i32, y: i32, font_size: i32, color: Color) {
+		unsafe { DrawText(c_text(text).as_ptr(), x, y, font_size, color) };
+	}
+
+	fn draw_text_pro(&mut self, text: &str, position: Vector2, rotation: f32, font_size: f32, spacing: f32, color: Color) {
+		unsafe {
+			DrawTextPro(
+				GetFontDefault(),
+				c_text(text).as_ptr(),
+				position,
+				Vector2::default(),
+				rotation,
+				font_size,
+				spacing,
+				color,
+			)
+		};
+	}
+
+	fn draw_texture_rec(&mut self, texture: Texture2D, source: Rectangle, position: Vector2, tint: Color) {
+		unsafe { DrawTextureRec(texture, source, position, tint) };
+	}
+
+	fn draw_texture_pro(&mut self, texture: Texture2D, source: Rectangle, dest: Rectangle, origin: Vector2, rotation: f32, tint: Color) {
+		unsafe { DrawTexturePro(texture, source, dest, origin, rotation, tint) };
+	}
+
+	fn draw_fps(&mut self, x: i32, y: i32) {
+		unsafe { DrawFPS(x, y) };
+	}
+}
+
+// No window and no drawing, every frame takes exactly `frame_time`. Input comes from `input` and
+// `gui_input`. Works without the C library, see the `headless` feature.
+#[derive(Clone, Debug)]
+pub struct HeadlessBackend {
+	pub input: SyntheticInput,
+	// typed text, keys and wheel are taken by the next `Backend::gui_input`, the mouse stays
+	pub gui_input: GuiInput,
+	// input for the following frames, each `end_drawing` moves the next one into `input`
+	pub script: VecDeque<SyntheticInput>,
+	pub frame_time: f32,
+	// `window_should_close` turns true once this many frames were drawn
+	pub max_frames: Option<u64>,
+	width: i32,
+	height: i32,
+	frames: u64,
+	open: bool,
+	// last render texture id handed out, raylib never uses 0
+	last_id: u32,
+}
+
+impl Default for HeadlessBackend {
+	fn default() -> Self {
+		Self {
+			input: SyntheticInput::default(),
+			gui_input: GuiInput::default(),
+			script: VecDeque::new(),
+			frame_time: 1.0 / 60.0,
+			max_frames: None,
+			width: 0,
+			height: 0,
+			frames: 0,
+			open: false,
+			last_id: 0,
+		}
+	}
+}
+
+impl HeadlessBackend {
+	pub fn new(max_frames: u64) -> Self {
+		Self {
+			max_frames: Some(max_frames),
+			..Default::default()
+		}
+	}
+
+	// frames completed with end_drawing
+	pub fn frames(&self) -> u64 {
+		self.frames
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.open
+	}
+}
+
+impl InputSource for HeadlessBackend {
+	fn key_down(&self, key: KeyboardKey) -> bool {
+		self.input.key_down(key)
+	}
+
+	fn mouse_button_down(&self, button: MouseButton) -> bool {
+		self.input.mouse_button_down(button)
+	}
+
+	fn mouse_wheel(&self) -> f32 {
+		self.input.mouse_wheel()
+	}
+
+	fn gamepad_button_down(&self, gamepad: i32, button: GamepadButton) -> bool {
+		self.input.gamepad_button_down(gamepad, button)
+	}
+
+	fn gamepad_axis(&self, gamepad: i32, axis: GamepadAxis) -> f32 {
+		self.input.gamepad_axis(gamepad, axis)
+	}
+
+	fn gesture_detected(&self, gesture: Gesture) -> bool {
+		self.input.gesture_detected(gesture)
+	}
+}
+
+impl Backend for HeadlessBackend {
+	fn set_config_flags(&mut self, _flags: u32) {}
+
+	fn init_window(&mut self, width: i32, height: i32, _title: &str) {
+		self.width = width;
+		self.height = height;
+		self.open = true;
+	}
+
+	fn close_window(&mut self) {
+		self.open = false;
+	}
+
+	fn window_should_close(&self) -> bool {
+		self.max_frames.is_some_and(|max_frames| self.frames >= max_frames)
+	}
+
+	fn set_window_title(&mut self, _title: &str) {}
+
+	fn toggle_fullscreen(&mut self) {}
+
+	fn set_target_fps(&mut self, _fps: i32) {}
+
+	// what `frame_time` works out to
+	fn fps(&self) -> i32 {
+		if self.frame_time > 0.0 {
+			(1.0 / self.frame_time).round() as i32
+		} else {
+			0
+		}
+	}
+
+	fn frame_time(&self) -> f32 {
+		self.frame_time
+	}
+
+	fn screen_width(&self) -> i32 {
+		self.width
+	}
+
+	fn screen_height(&self) -> i32 {
+		self.height
+	}
+
+	fn gui_input(&mut self, _gamepad: i32) -> GuiInput {
+		let input = self.gui_input.clone();
+		self.gui_input.wheel = 0.0;
+		self.gui_input.text.clear();
+		self.gui_input.keys.clear();
+		input
+	}
+
+	fn begin_drawing(&mut self) {}
+
+	fn end_drawing(&mut self) {
+		self.frames += 1;
+		if let Some(input) = self.script.pop_front() {
+			self.input = input;
+		}
+	}
+
+	fn clear_background(&mut self, _color: Color) {}
+
+	fn begin_mode_2d(&mut self, _camera: Camera2D) {}
+
+	fn end_mode_2d(&mut self) {}
+
+	fn begin_scissor_mode(&mut self, _x: i32, _y: i32, _width: i32, _height: i32) {}
+
+	fn end_scissor_mode(&mut self) {}
+
+	// id 0, nothing on the GPU to free
+	fn load_render_texture(&mut self, width: i32, height: i32) -> RenderTexture2D {
+		// unique ids, so recordings and the canvas can tell render textures apart
+		self.last_id += 1;
+		let texture = Texture2D {
+			id: self.last_id,
+			width,
+			height,
+			mipmaps: 1,
+			format: PixelFormat_PIXELFORMAT_UNCOMPRESSED_R8G8B8A8,
+		};
+		RenderTexture2D {
+			id: self.last_id,
+			texture,
+			depth: texture,
+		}
+	}
+
+	fn unload_render_texture(&mut self, _target: RenderTexture2D) {}
+
+	fn begin_texture_mode(&mut self, _target: RenderTexture2D) {}
+
+	fn end_texture_mode(&mut self) {}
+
+	fn draw_rectangle(&mut self, _x: i32, _y: i32, _width: i32, _height: i32, _color: Color) {}
+
+	fn draw_rectangle_rec(&mut self, _rec: Rectangle, _color: Color) {}
+
+	fn draw_rectangle_lines_ex(&mut self, _rec: Rectangle, _thickness: f32, _color: Color) {}
+
+	fn draw_rectangle_pro(&mut self, _rec: Rectangle, _origin: Vector2, _rotation: f32, _color: Color) {}
+
+	fn draw_circle_v(&mut self, _center: Vector2, _radius: f32, _color: Color) {}
+
+	fn draw_circle_lines_v(&mut self, _center: Vector2, _radius: f32, _color: Color) {}
+
+	fn draw_poly(&mut self, _center: Vector2, _sides: i32, _radius: f32, _rotation: f32, _color: Color) {}
+
+	fn draw_line_ex(&mut self, _start: Vector2, _end: Vector2, _thickness: f32, _color: Color) {}
+
+	fn draw_text(&mut self, _text: &str, _x: i32, _y: i32, _font_size: i32, _color: Color) {}
+
+	fn draw_text_pro(&mut self, _text: &str, _position: Vector2, _rotation: f32, _font_size: f32, _spacing: f32, _color: Color) {}
+
+	fn draw_texture_rec(&mut self, _texture: Texture2D, _source: Rectangle, _position: Vector2, _tint: Color) {}
+
+	fn draw_texture_pro(&mut self, _texture: Texture2D, _source: Rectangle, _dest: Rectangle, _origin: Vector2, _rotation: f32, _tint: Color) {}
+
+	fn draw_fps(&mut self, _x: i32, _y: i32) {}
+}
@@ -0,0 +1,24 @@
+use crate::*;
+
+// Same as GetWorldToScreen2D, in Rust so camera logic also runs without the C library.
+pub fn world_to_screen(position: Vector2, camera: &Camera2D) -> Vector2 {
+	let (sin, cos) = camera.rotation.to_radians().sin_cos();
+	let x = (position.x - camera.target.x) * camera.zoom;
+	let y = (position.y - camera.target.y) * camera.zoom;
+	Vector2 {
+		x: x * cos - y * sin + camera.offset.x,
+		y: x * sin + y * cos + camera.offset.y,
+	}
+}
+
+// Same as GetScreenToWorld2D.
+pub fn screen_to_world(position: Vector2, camera: &Camera2D) -> Vector2 {
+	let (sin, cos) = camera.rotation.to_radians().sin_cos();
+	let x = position.x - camera.offset.x;
+	let y = position.y - camera.offset.y;
+	let zoom = if camera.zoom != 0.0 { camera.zoom } else { 1.0 };
+	Vector2 {
+		x: (x * cos + y * sin) / zoom + camera.target.x,
+		y: (-x * sin + y * cos) / zoom + camera.target.y,
+	}
+}
@@ -0,0 +1,482 @@
+use std::{
+	collections::{BTreeMap, VecDeque},
+	str::FromStr,
+};
+
+use crate::{
+	backend::Backend,
+	gui::{GuiInput, GuiKey},
+	*,
+};
+
+const MAX_LINES: usize = 200;
+const FONT_SIZE: i32 = 10;
+const LINE_HEIGHT: i32 = 12;
+// typing it opens and closes the console
+const TOGGLE: char = '`';
+
+// A line typed into the console, split on whitespace. Double quotes keep spaces in one argument.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ConsoleCommand {
+	pub name: String,
+	pub args: Vec<String>,
+}
+
+impl ConsoleCommand {
+	pub fn parse(line: &str) -> Option<Self> {
+		let mut words = Vec::new();
+		let mut word = String::new();
+		let mut quoted = false;
+		let mut started = false;
+		for c in line.chars() {
+			match c {
+				'"' => {
+					quoted = !quoted;
+					started = true;
+				}
+				c if c.is_whitespace() && !quoted => {
+					if started {
+						words.push(std::mem::take(&mut word));
+						started = false;
+					}
+				}
+				c => {
+					word.push(c);
+					started = true;
+				}
+			}
+		}
+		if started {
+			words.push(word);
+		}
+		let mut words = words.into_iter();
+		Some(Self {
+			name: words.next()?,
+			args: words.collect(),
+		})
+	}
+
+	pub fn arg<T: FromStr>(&self, index: usize) -> Option<T> {
+		self.args.get(index)?.parse().ok()
+	}
+}
+
+// what `set` accepts for a cvar
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CvarKind {
+	Bool,
+	// the range of the registered type
+	Int { min: i128, max: i128 },
+	Float,
+	Text,
+}
+
+// Types a cvar can hold. The kind comes from the type, 1.0 prints as "1" but still takes fractions.
+pub trait CvarValue: ToString {
+	const KIND: CvarKind;
+}
+
+macro_rules! cvar_values {
+	($kind:ident: $($ty:ty),*) => {
+		$(impl CvarValue for $ty {
+			const KIND: CvarKind = CvarKind::$kind;
+		})*
+	};
+}
+
+macro_rules! cvar_ints {
+	($($ty:ty),*) => {
+		$(impl CvarValue for $ty {
+			const KIND: CvarKind = CvarKind::Int {
+				min: <$ty>::MIN as i128,
+				max: <$ty>::MAX as i128,
+			};
+		})*
+	};
+}
+
+cvar_values!(Bool: bool);
+cvar_ints!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+cvar_values!(Float: f32, f64);
+cvar_values!(Text: String, &str);
+
+impl CvarKind {
+	fn accepts(self, value: &str) -> bool {
+		match self {
+			CvarKind::Bool => value.parse::<bool>().is_ok(),
+			CvarKind::Int { min, max } => value.parse::<i128>().is_ok_and(|value| (min..=max).contains(&value)),
+			CvarKind::Float => value.parse::<f64>().is_ok(),
+			CvarKind::Text => true,
+		}
+	}
+
+	fn name(self) -> String {
+		match self {
+			CvarKind::Bool => "true or false".to_string(),
+			CvarKind::Int { min, max } => format!("a whole number from {min} to {max}"),
+			CvarKind::Float => "a number".to_string(),
+			CvarKind::Text => "text".to_string(),
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+struct Cvar {
+	value: String,
+	kind: CvarKind,
+	help: String,
+}
+
+// Drop-down developer console. Games register commands and cvars, then each frame feed it input
+// and drain what was typed: commands come out of `drain_commands`, cvar edits with `set` are applied
+// here and reported by `drain_changed_cvars`. Built in: help, cvars, get, set and clear.
+#[derive(Clone, Default, Debug)]
+pub struct Console {
+	pub open: bool,
+	input: String,
+	lines: VecDeque<String>,
+	history: Vec<String>,
+	// position while browsing the history with Up and Down
+	history_index: Option<usize>,
+	// name -> usage
+	commands: BTreeMap<String, String>,
+	cvars: BTreeMap<String, Cvar>,
+	pending: Vec<ConsoleCommand>,
+	changed: Vec<String>,
+}
+
+impl Console {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	// `usage` is shown by help, e.g. "teleport <x> <y>"
+	pub fn register(&mut self, name: &str, usage: &str) -> &mut Self {
+		self.commands.insert(name.to_string(), usage.to_string());
+		self
+	}
+
+	// Sets will only take values of the same kind as `default`: booleans, whole numbers, numbers or text.
+	pub fn add_cvar<T: CvarValue>(&mut self, name: &str, default: T, help: &str) -> &mut Self {
+		self.cvars.insert(
+			name.to_string(),
+			Cvar {
+				kind: T::KIND,
+				value: default.to_string(),
+				help: help.to_string(),
+			},
+		);
+		self
+	}
+
+	pub fn cvar(&self, name: &str) -> Option<&str> {
+		self.cvars.get(name).map(|cvar| cvar.value.as_str())
+	}
+
+	pub fn cvar_as<T: FromStr>(&self, name: &str) -> Option<T> {
+		self.cvar(name)?.parse().ok()
+	}
+
+	// For the game to keep a cvar in sync, e.g. after a key changed the setting. Not reported as a change.
+	pub fn set_cvar(&mut self, name: &str, value: impl ToString) -> bool {
+		let value = value.to_string();
+		match self.cvars.get_mut(name) {
+			Some(cvar) if cvar.kind.accepts(&value) => {
+				cvar.value = value;
+				true
+			}
+			_ => false,
+		}
+	}
+
+	pub fn print(&mut self, line: impl Into<String>) {
+		if self.lines.len() == MAX_LINES {
+			self.lines.pop_front();
+		}
+		self.lines.push_back(line.into());
+	}
+
+	pub fn lines(&self) -> impl Iterator<Item = &str> {
+		self.lines.iter().map(String::as_str)
+	}
+
+	// the line being typed
+	pub fn input(&self) -> &str {
+		&self.input
+	}
+
+	// Runs `line` as if it was typed, e.g. from a startup script.
+	pub fn execute(&mut self, line: &str) {
+		self.print(format!("> {line}"));
+		let Some(command) = ConsoleCommand::parse(line) else {
+			return;
+		};
+		match command.name.as_str() {
+			"help" => {
+				let usages: Vec<String> = self.commands.values().cloned().collect();
+				self.print("help, cvars, get <cvar>, set <cvar> <value>, clear");
+				for usage in usages {
+					self.print(usage);
+				}
+			}
+			"cvars" => {
+				let cvars: Vec<String> = self
+					.cvars
+					.iter()
+					.map(|(name, cvar)| format!("{name} = {}  {}", cvar.value, cvar.help))
+					.collect();
+				for cvar in cvars {
+					self.print(cvar);
+				}
+			}
+			"get" => match command.args.first().and_then(|name| self.cvars.get(name).map(|cvar| (name, cvar))) {
+				Some((name, cvar)) => self.print(format!("{name} = {}", cvar.value)),
+				None => self.print("usage: get <cvar>"),
+			},
+			"set" => self.set(&command),
+			"clear" => self.lines.clear(),
+			name if self.commands.contains_key(name) => self.pending.push(command),
+			name => self.print(format!("unknown command {name}, try help")),
+		}
+	}
+
+	// Handles one frame of input. Typing ` opens or closes the console, Escape closes it, Enter runs the
+	// line and Up and Down walk the history.
+	pub fn update(&mut self, input: &GuiInput) {
+		if !self.open {
+			self.open = input.text.contains(TOGGLE);
+			return;
+		}
+		for c in input.text.chars() {
+			match c {
+				TOGGLE => {
+					self.open = false;
+					return;
+				}
+				c if !c.is_control() => self.input.push(c),
+				_ => {}
+			}
+		}
+		for key in &input.keys {
+			match key {
+				GuiKey::Activate => {
+					let line = std::mem::take(&mut self.input);
+					if !line.trim().is_empty() {
+						self.history.push(line.clone());
+					}
+					self.history_index = None;
+					self.execute(&line);
+				}
+				GuiKey::Backspace => {
+					self.input.pop();
+				}
+				GuiKey::Back => self.open = false,
+				GuiKey::Up if !self.history.is_empty() => {
+					let index = self.history_index.map_or(self.history.len() - 1, |index| index.saturating_sub(1));
+					self.history_index = Some(index);
+					self.input = self.history[index].clone();
+				}
+				GuiKey::Down => {
+					let index = self.history_index.map(|index| index + 1).filter(|index| *index < self.history.len());
+					self.history_index = index;
+					self.input = index.map(|index| self.history[index].clone()).unwrap_or_default();
+				}
+				_ => {}
+			}
+		}
+	}
+
+	// registered commands typed since the last call
+	pub fn drain_commands(&mut self) -> impl Iterator<Item = ConsoleCommand> + '_ {
+		self.pending.drain(..)
+	}
+
+	// names of cvars changed with `set` since the last call
+	pub fn drain_changed_cvars(&mut self) -> impl Iterator<Item = String> + '_ {
+		self.changed.drain(..)
+	}
+
+	// Drops down from the top of the screen while open.
+	pub fn draw(&self, backend: &mut dyn Backend) {
+		if !self.open {
+			return;
+		}
+		let width = backend.screen_width();
+		let height = backend.screen_height() * 2 / 5;
+		backend.draw_rectangle(0, 0, width, height, fade(BLACK, 0.8));
+		backend.draw_rectangle(0, height - LINE_HEIGHT - 6, width, 1, GRAY);
+		let input_y = height - LINE_HEIGHT - 2;
+		backend.draw_text(&format!("> {}_", self.input), 6, input_y, FONT_SIZE, RAYWHITE);
+		let mut y = input_y - LINE_HEIGHT - 6;
+		for line in self.lines.iter().rev() {
+			if y < 0 {
+				break;
+			}
+			backend.draw_text(line, 6, y, FONT_SIZE, LIGHTGRAY);
+			y -= LINE_HEIGHT;
+		}
+	}
+
+	fn set(&mut self, command: &ConsoleCommand) {
+		let [name, value] = &command.args[..] else {
+			self.print("usage: set <cvar> <value>");
+			return;
+		};
+		let Some(cvar) = self.cvars.get_mut(name) else {
+			self.print(format!("unknown cvar {name}, try cvars"));
+			return;
+		};
+		if !cvar.kind.accepts(value) {
+			let expected = cvar.kind.name();
+			self.print(format!("{name} takes {expected}"));
+			return;
+		}
+		cvar.value = value.clone();
+		self.changed.push(name.clone());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn command(name: &str, args: &[&str]) -> Option<ConsoleCommand> {
+		Some(ConsoleCommand {
+			name: name.to_string(),
+			args: args.iter().map(|arg| arg.to_string()).collect(),
+		})
+	}
+
+	#[test]
+	fn commands_split_on_whitespace_outside_quotes() {
+		assert_eq!(ConsoleCommand::parse("teleport 10 -2.5"), command("teleport", &["10", "-2.5"]));
+		assert_eq!(ConsoleCommand::parse("  say \t hello   world  "), command("say", &["hello", "world"]));
+		assert_eq!(ConsoleCommand::parse(r#"say "hello   world" x"#), command("say", &["hello   world", "x"]));
+		assert_eq!(ConsoleCommand::parse(r#"name pre"fix suf"fix"#), command("name", &["prefix suffix"]));
+		assert_eq!(ConsoleCommand::parse(r#"set title """#), command("set", &["title", ""]));
+		assert_eq!(ConsoleCommand::parse(r#"say "unclosed  quote"#), command("say", &["unclosed  quote"]));
+		assert_eq!(ConsoleCommand::parse(""), None);
+		assert_eq!(ConsoleCommand::parse("   \t "), None);
+
+		let teleport = ConsoleCommand::parse("teleport 10 x").unwrap();
+		assert_eq!(teleport.arg::<i32>(0), Some(10));
+		assert_eq!(teleport.arg::<i32>(1), None);
+		assert_eq!(teleport.arg::<i32>(2), None);
+	}
+
+	fn console() -> Console {
+		let mut console = Console::new();
+		console
+			.add_cvar("god", false, "")
+			.add_cvar("volume", 255u8, "")
+			.add_cvar("offset", -3i64, "")
+			.add_cvar("gravity", 9.8f32, "")
+			.add_cvar("name", "player", "");
+		console
+	}
+
+	#[test]
+	fn set_checks_the_cvar_type() {
+		let mut console = console();
+		for (line, value) in [
+			("set god true", "true"),
+			("set volume 0", "0"),
+			("set offset -9223372036854775808", "-9223372036854775808"),
+			("set gravity 1.5", "1.5"),
+			("set gravity 2", "2"),
+			("set name \"two words\"", "two words"),
+		] {
+			console.execute(line);
+			let name = line.split(' ').nth(1).unwrap();
+			assert_eq!(console.cvar(name), Some(value), "{line}");
+		}
+		assert_eq!(console.cvar_as::<f32>("gravity"), Some(2.0));
+
+		for line in [
+			"set god 1",
+			"set volume 256",
+			"set volume -1",
+			"set volume 1.5",
+			"set offset 9223372036854775808",
+			"set gravity fast",
+		] {
+			console.execute(line);
+			assert!(console.lines().last().unwrap().contains(" takes "), "{line}");
+		}
+		assert_eq!(console.lines().last(), Some("gravity takes a number"));
+		console.execute("set volume 300");
+		assert_eq!(console.lines().last(), Some("volume takes a whole number from 0 to 255"));
+		assert_eq!(console.cvar("volume"), Some("0"));
+
+		console.execute("set missing 1");
+		assert_eq!(console.lines().last(), Some("unknown cvar missing, try cvars"));
+		console.execute("set volume");
+		assert_eq!(console.lines().last(), Some("usage: set <cvar> <value>"));
+
+		assert!(console.set_cvar("volume", 200));
+		assert!(!console.set_cvar("volume", 1000));
+		assert!(!console.set_cvar("missing", 1));
+		assert_eq!(console.cvar_as::<u8>("volume"), Some(200));
+	}
+
+	#[test]
+	fn only_sets_count_as_changes() {
+		let mut console = console();
+		console.register("spawn", "spawn <what>");
+		console.execute("set god true");
+		console.execute("set volume 999");
+		console.set_cvar("gravity", 1.0);
+		console.execute("set name bob");
+		console.execute("spawn crate");
+		assert_eq!(console.drain_changed_cvars().collect::<Vec<_>>(), ["god", "name"]);
+		assert_eq!(console.drain_changed_cvars().count(), 0);
+		assert_eq!(console.drain_commands().collect::<Vec<_>>(), [command("spawn", &["crate"]).unwrap()]);
+	}
+
+	fn keys(keys: &[GuiKey]) -> GuiInput {
+		GuiInput {
+			keys: keys.to_vec(),
+			..GuiInput::default()
+		}
+	}
+
+	fn typed(text: &str) -> GuiInput {
+		GuiInput {
+			text: text.to_string(),
+			..GuiInput::default()
+		}
+	}
+
+	#[test]
+	fn up_and_down_walk_the_history() {
+		let mut console = Console::new();
+		console.update(&typed("`"));
+		assert!(console.open);
+		for line in ["first", "   ", "second"] {
+			console.update(&typed(line));
+			console.update(&keys(&[GuiKey::Activate]));
+		}
+		assert_eq!(console.input(), "");
+
+		let mut walk = |key| {
+			console.update(&keys(&[key]));
+			console.input().to_string()
+		};
+		assert_eq!(walk(GuiKey::Up), "second");
+		assert_eq!(walk(GuiKey::Up), "first");
+		assert_eq!(walk(GuiKey::Up), "first");
+		assert_eq!(walk(GuiKey::Down), "second");
+		assert_eq!(walk(GuiKey::Down), "");
+		assert_eq!(walk(GuiKey::Down), "");
+		assert_eq!(walk(GuiKey::Up), "second");
+
+		// running a line from the history adds it again and starts over from the newest
+		console.update(&keys(&[GuiKey::Up, GuiKey::Up, GuiKey::Activate, GuiKey::Up, GuiKey::Up]));
+		assert_eq!(console.input(), "second");
+		console.update(&keys(&[GuiKey::Down, GuiKey::Up, GuiKey::Up, GuiKey::Up]));
+		assert_eq!(console.input(), "first");
+
+		console.update(&typed("`"));
+		assert!(!console.open);
+	}
+}
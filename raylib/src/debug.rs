@@ -0,0 +1,486 @@
+use std::{collections::VecDeque, fmt::Display};
+
+use crate::{backend::Backend, gui::GuiInput, input_map::InputSource, profile, *};
+
+const PANEL_WIDTH: i32 = 220;
+const FONT_SIZE: i32 = 10;
+const LINE_HEIGHT: i32 = 12;
+const GRAPH_HEIGHT: i32 = 40;
+// the graph's full height, two 30 fps frames
+const GRAPH_SECONDS: f32 = 2.0 / 30.0;
+const FLAME_HEIGHT: i32 = 160;
+
+// Frame times of the last `capacity` frames.
+#[derive(Clone, Debug)]
+pub struct FrameStats {
+	times: VecDeque<f32>,
+	capacity: usize,
+}
+
+impl Default for FrameStats {
+	fn default() -> Self {
+		Self::new(120)
+	}
+}
+
+impl FrameStats {
+	pub fn new(capacity: usize) -> Self {
+		Self {
+			times: VecDeque::with_capacity(capacity),
+			capacity: capacity.max(1),
+		}
+	}
+
+	pub fn record(&mut self, frame_time: f32) {
+		if self.times.len() == self.capacity {
+			self.times.pop_front();
+		}
+		self.times.push_back(frame_time);
+	}
+
+	// oldest first
+	pub fn frame_times(&self) -> impl Iterator<Item = f32> + '_ {
+		self.times.iter().copied()
+	}
+
+	pub fn last(&self) -> Option<f32> {
+		self.times.back().copied()
+	}
+
+	// frames per second of the slowest frame
+	pub fn fps_min(&self) -> f32 {
+		fps(self.times.iter().copied().fold(0.0, f32::max))
+	}
+
+	pub fn fps_avg(&self) -> f32 {
+		fps(self.times.iter().sum::<f32>() / self.times.len().max(1) as f32)
+	}
+
+	// frames per second of the fastest frame
+	pub fn fps_max(&self) -> f32 {
+		fps(self.times.iter().copied().fold(f32::INFINITY, f32::min))
+	}
+}
+
+fn fps(frame_time: f32) -> f32 {
+	if frame_time > 0.0 && frame_time.is_finite() {
+		1.0 / frame_time
+	} else {
+		0.0
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum DebugShape {
+	Rectangle { rec: Rectangle, color: Color },
+	Circle { center: Vector2, radius: f32, color: Color },
+	Line { start: Vector2, end: Vector2, color: Color },
+}
+
+// Toggleable diagnostics: frame rate and a frame time graph, counters the game reports (draw calls,
+// entities, ...) and a layer of world space outlines such as colliders and camera bounds.
+#[derive(Clone, Debug)]
+pub struct DebugOverlay {
+	pub visible: bool,
+	// draw the world space layer while visible
+	pub show_shapes: bool,
+	// the last frame of `profile` as a flame graph along the bottom of the screen
+	pub show_profile: bool,
+	pub stats: FrameStats,
+	counters: Vec<(String, String)>,
+	shapes: Vec<DebugShape>,
+}
+
+impl Default for DebugOverlay {
+	fn default() -> Self {
+		Self {
+			visible: false,
+			show_shapes: true,
+			show_profile: false,
+			stats: FrameStats::default(),
+			counters: Vec::new(),
+			shapes: Vec::new(),
+		}
+	}
+}
+
+impl DebugOverlay {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn toggle(&mut self) {
+		self.visible = !self.visible;
+	}
+
+	pub fn record_frame(&mut self, frame_time: f32) {
+		self.stats.record(frame_time);
+	}
+
+	// Shows `name: value`, replacing the last value reported under `name`.
+	pub fn counter(&mut self, name: &str, value: impl Display) {
+		let value = value.to_string();
+		match self.counters.iter_mut().find(|(counter, _)| counter == name) {
+			Some((_, old)) => *old = value,
+			None => self.counters.push((name.to_string(), value)),
+		}
+	}
+
+	// world space outlines for this frame, cleared by `draw_world`
+	pub fn rectangle(&mut self, rec: Rectangle, color: Color) {
+		self.shapes.push(DebugShape::Rectangle { rec, color });
+	}
+
+	pub fn circle(&mut self, center: Vector2, radius: f32, color: Color) {
+		self.shapes.push(DebugShape::Circle { center, radius, color });
+	}
+
+	pub fn line(&mut self, start: Vector2, end: Vector2, color: Color) {
+		self.shapes.push(DebugShape::Line { start, end, color });
+	}
+
+	// Outlines the world space shapes, inside BeginMode2D so they line up with the world. Clears them
+	// even while hidden.
+	pub fn draw_world(&mut self, backend: &mut dyn Backend) {
+		if self.visible && self.show_shapes {
+			for shape in &self.shapes {
+				match *shape {
+					DebugShape::Rectangle { rec, color } => backend.draw_rectangle_lines_ex(rec, 1.0, color),
+					DebugShape::Circle { center, radius, color } => {
+						backend.draw_circle_v(center, radius, fade(color, 0.3));
+						backend.draw_circle_v(center, 1.5, color);
+					}
+					DebugShape::Line { start, end, color } => backend.draw_line_ex(start, end, 1.0, color),
+				}
+			}
+		}
+		self.shapes.clear();
+	}
+
+	// The stats panel in the top right corner, in screen space.
+	pub fn draw(&self, backend: &mut dyn Backend) {
+		if !self.visible {
+			return;
+		}
+		let x = backend.screen_width() - PANEL_WIDTH - 10;
+		let lines = 2 + self.counters.len() as i32;
+		let height = lines * LINE_HEIGHT + GRAPH_HEIGHT + 16;
+		backend.draw_rectangle(x, 10, PANEL_WIDTH, height, fade(BLACK, 0.7));
+
+		let mut y = 16;
+		let stats = &self.stats;
+		let fps_line = format!(
+			"FPS {:.0}  min {:.0}  avg {:.0}  max {:.0}",
+			fps(stats.last().unwrap_or(0.0)),
+			stats.fps_min(),
+			stats.fps_avg(),
+			stats.fps_max()
+		);
+		backend.draw_text(&fps_line, x + 6, y, FONT_SIZE, LIME);
+		y += LINE_HEIGHT;
+		let frame_line = format!("frame {:.2} ms", stats.last().unwrap_or(0.0) * 1000.0);
+		backend.draw_text(&frame_line, x + 6, y, FONT_SIZE, RAYWHITE);
+		y += LINE_HEIGHT;
+		for (name, value) in &self.counters {
+			backend.draw_text(&format!("{name}: {value}"), x + 6, y, FONT_SIZE, RAYWHITE);
+			y += LINE_HEIGHT;
+		}
+
+		// one bar per frame, newest on the right
+		let graph = Rectangle {
+			x: (x + 6) as f32,
+			y: (y + 4) as f32,
+			width: (PANEL_WIDTH - 12) as f32,
+			height: GRAPH_HEIGHT as f32,
+		};
+		backend.draw_rectangle_rec(graph, fade(DARKGRAY, 0.5));
+		let bar_width = graph.width / stats.capacity as f32;
+		let skipped = stats.capacity - stats.times.len();
+		for (i, time) in stats.frame_times().enumerate() {
+			let bar_height = (time / GRAPH_SECONDS).min(1.0) * graph.height;
+			let color = if time <= 1.0 / 55.0 {
+				LIME
+			} else if time <= 1.0 / 28.0 {
+				YELLOW
+			} else {
+				RED
+			};
+			let bar = Rectangle {
+				x: graph.x + (skipped + i) as f32 * bar_width,
+				y: graph.y + graph.height - bar_height,
+				width: bar_width.max(1.0),
+				height: bar_height,
+			};
+			backend.draw_rectangle_rec(bar, color);
+		}
+		// 60 fps line
+		let target_y = graph.y + graph.height * (1.0 - 1.0 / 60.0 / GRAPH_SECONDS);
+		backend.draw_line_ex(
+			Vector2 { x: graph.x, y: target_y },
+			Vector2 {
+				x: graph.x + graph.width,
+				y: target_y,
+			},
+			1.0,
+			fade(RAYWHITE, 0.6),
+		);
+
+		if self.show_profile {
+			let area = Rectangle {
+				x: 10.0,
+				y: (backend.screen_height() - FLAME_HEIGHT - 10) as f32,
+				width: (backend.screen_width() - 20) as f32,
+				height: FLAME_HEIGHT as f32,
+			};
+			match profile::last_frame() {
+				Some(frame) => profile::draw_flame(&frame, backend, area),
+				None => {
+					backend.draw_rectangle_rec(area, fade(BLACK, 0.7));
+					let hint = "no profile, build with --features profile";
+					backend.draw_text(hint, area.x as i32 + 4, area.y as i32 + 4, FONT_SIZE, RAYWHITE);
+				}
+			}
+		}
+	}
+}
+
+// Forwards to `inner` and counts the draw calls, for the overlay's draw call counter.
+pub struct CountingBackend<'a, B: Backend + ?Sized> {
+	pub inner: &'a mut B,
+	draw_calls: usize,
+}
+
+impl<'a, B: Backend + ?Sized> CountingBackend<'a, B> {
+	pub fn new(inner: &'a mut B) -> Self {
+		Self { inner, draw_calls: 0 }
+	}
+
+	// draw calls since the last call, read it after end_drawing
+	pub fn take_draw_calls(&mut self) -> usize {
+		std::mem::take(&mut self.draw_calls)
+	}
+}
+
+impl<B: Backend + ?Sized> InputSource for CountingBackend<'_, B> {
+	fn key_down(&self, key: KeyboardKey) -> bool {
+		self.inner.key_down(key)
+	}
+
+	fn mouse_button_down(&self, button: MouseButton) -> bool {
+		self.inner.mouse_button_down(button)
+	}
+
+	fn mouse_wheel(&self) -> f32 {
+		self.inner.mouse_wheel()
+	}
+
+	fn gamepad_button_down(&self, gamepad: i32, button: GamepadButton) -> bool {
+		self.inner.gamepad_button_down(gamepad, button)
+	}
+
+	fn gamepad_axis(&self, gamepad: i32, axis: GamepadAxis) -> f32 {
+		self.inner.gamepad_axis(gamepad, axis)
+	}
+
+	fn gesture_detected(&self, gesture: Gesture) -> bool {
+		self.inner.gesture_detected(gesture)
+	}
+}
+
+impl<B: Backend + ?Sized> Backend for CountingBackend<'_, B> {
+	fn set_config_flags(&mut self, flags: u32) {
+		self.inner.set_config_flags(flags);
+	}
+
+	fn init_window(&mut self, width: i32, height: i32, title: &str) {
+		self.inner.init_window(width, height, title);
+	}
+
+	fn close_window(&mut self) {
+		self.inner.close_window();
+	}
+
+	fn window_should_close(&self) -> bool {
+		self.inner.window_should_close()
+	}
+
+	fn set_window_title(&mut self, title: &str) {
+		self.inner.set_window_title(title);
+	}
+
+	fn toggle_fullscreen(&mut self) {
+		self.inner.toggle_fullscreen();
+	}
+
+	fn set_target_fps(&mut self, fps: i32) {
+		self.inner.set_target_fps(fps);
+	}
+
+	fn fps(&self) -> i32 {
+		self.inner.fps()
+	}
+
+	fn frame_time(&self) -> f32 {
+		self.inner.frame_time()
+	}
+
+	fn screen_width(&self) -> i32 {
+		self.inner.screen_width()
+	}
+
+	fn screen_height(&self) -> i32 {
+		self.inner.screen_height()
+	}
+
+	fn gui_input(&mut self, gamepad: i32) -> GuiInput {
+		self.inner.gui_input(gamepad)
+	}
+
+	fn begin_drawing(&mut self) {
+		self.inner.begin_drawing();
+	}
+
+	fn end_drawing(&mut self) {
+		self.inner.end_drawing();
+	}
+
+	fn clear_background(&mut self, color: Color) {
+		self.inner.clear_background(color);
+	}
+
+	fn begin_mode_2d(&mut self, camera: Camera2D) {
+		self.inner.begin_mode_2d(camera);
+	}
+
+	fn end_mode_2d(&mut self) {
+		self.inner.end_mode_2d();
+	}
+
+	fn begin_scissor_mode(&mut self, x: i32, y: i32, width: i32, height: i32) {
+		self.inner.begin_scissor_mode(x, y, width, height);
+	}
+
+	fn end_scissor_mode(&mut self) {
+		self.inner.end_scissor_mode();
+	}
+
+	fn load_render_texture(&mut self, width: i32, height: i32) -> RenderTexture2D {
+		self.inner.load_render_texture(width, height)
+	}
+
+	fn unload_render_texture(&mut self, target: RenderTexture2D) {
+		self.inner.unload_render_texture(target);
+	}
+
+	fn begin_texture_mode(&mut self, target: RenderTexture2D) {
+		self.inner.begin_texture_mode(target);
+	}
+
+	fn end_texture_mode(&mut self) {
+		self.inner.end_texture_mode();
+	}
+
+	fn draw_rectangle(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color) {
+		self.draw_calls += 1;
+		self.inner.draw_rectangle(x, y, width, height, color);
+	}
+
+	fn draw_rectangle_rec(&mut self, rec: Rectangle, color: Color) {
+		self.draw_calls += 1;
+		self.inner.draw_rectangle_rec(rec, color);
+	}
+
+	fn draw_rectangle_lines_ex(&mut self, rec: Rectangle, thickness: f32, color: Color) {
+		self.draw_calls += 1;
+		self.inner.draw_rectangle_lines_ex(rec, thickness, color);
+	}
+
+	fn draw_rectangle_pro(&mut self, rec: Rectangle, origin: Vector2, rotation: f32, color: Color) {
+		self.draw_calls += 1;
+		self.inner.draw_rectangle_pro(rec, origin, rotation, color);
+	}
+
+	fn draw_circle_v(&mut self, center: Vector2, radius: f32, color: Color) {
+		self.draw_calls += 1;
+		self.inner.draw_circle_v(center, radius, color);
+	}
+
+	fn draw_circle_lines_v(&mut self, center: Vector2, radius: f32, color: Color) {
+		self.draw_calls += 1;
+		self.inner.draw_circle_lines_v(center, radius, color);
+	}
+
+	fn draw_poly(&mut self, center: Vector2, sides: i32, radius: f32, rotation: f32, color: Color) {
+		self.draw_calls += 1;
+		self.inner.draw_poly(center, sides, radius, rotation, color);
+	}
+
+	fn draw_line_ex(&mut self, start: Vector2, end: Vector2, thickness: f32, color: Color) {
+		self.draw_calls += 1;
+		self.inner.draw_line_ex(start, end, thickness, color);
+	}
+
+	fn draw_text(&mut self, text: &str, x: i32, y: i32, font_size: i32, color: Color) {
+		self.draw_calls += 1;
+		self.inner.draw_text(text, x, y, font_size, color);
+	}
+
+	fn draw_text_pro(&mut self, text: &str, position: Vector2, rotation: f32, font_size: f32, spacing: f32, color: Color) {
+		self.draw_calls += 1;
+		self.inner.draw_text_pro(text, position, rotation, font_size, spacing, color);
+	}
+
+	fn draw_texture_rec(&mut self, texture: Texture2D, source: Rectangle, position: Vector2, tint: Color) {
+		self.draw_calls += 1;
+		self.inner.draw_texture_rec(texture, source, position, tint);
+	}
+
+	fn draw_texture_pro(&mut self, texture: Texture2D, source: Rectangle, dest: Rectangle, origin: Vector2, rotation: f32, tint: Color) {
+		self.draw_calls += 1;
+		self.inner.draw_texture_pro(texture, source, dest, origin, rotation, tint);
+	}
+
+	fn draw_fps(&mut self, x: i32, y: i32) {
+		self.draw_calls += 1;
+		self.inner.draw_fps(x, y);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn frame_stats_keep_the_last_frames() {
+		let mut stats = FrameStats::new(3);
+		assert_eq!((stats.fps_min(), stats.fps_avg(), stats.fps_max(), stats.last()), (0.0, 0.0, 0.0, None));
+
+		for frame_time in [0.1, 0.02, 0.05, 0.025] {
+			stats.record(frame_time);
+		}
+		assert_eq!(stats.frame_times().collect::<Vec<_>>(), [0.02, 0.05, 0.025]);
+		assert_eq!(stats.last(), Some(0.025));
+		assert_eq!(stats.fps_min(), 20.0);
+		assert_eq!(stats.fps_max(), 50.0);
+		assert!((stats.fps_avg() - 1.0 / (0.095 / 3.0)).abs() < 1e-3);
+
+		// paused frames don't count as infinitely fast
+		let mut paused = FrameStats::new(0);
+		paused.record(0.0);
+		assert_eq!((paused.fps_min(), paused.fps_avg(), paused.fps_max()), (0.0, 0.0, 0.0));
+		paused.record(0.5);
+		assert_eq!(paused.frame_times().collect::<Vec<_>>(), [0.5]);
+	}
+
+	#[test]
+	fn counters_replace_their_last_value() {
+		let mut overlay = DebugOverlay::new();
+		overlay.counter("entities", 10);
+		overlay.counter("state", "menu");
+		overlay.counter("entities", 12);
+		assert_eq!(
+			overlay.counters,
+			[("entities".to_string(), "12".to_string()), ("state".to_string(), "menu".to_string())]
+		);
+	}
+}
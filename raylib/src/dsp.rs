@@ -0,0 +1,674 @@
+use std::{
+	f32::consts::{PI, TAU},
+	sync::{Arc, Mutex, MutexGuard},
+};
+
+// Works on interleaved samples from -1.0 to 1.0, `channels` per frame. Implemented by closures too,
+// so `|samples: &mut [f32], channels: usize| ...` is a processor. Runs on the audio thread when
+// attached to a stream, so it must not block.
+pub trait Processor: Send {
+	fn process(&mut self, samples: &mut [f32], channels: usize);
+
+	// forget filter state and delay lines, e.g. after a seek
+	fn reset(&mut self) {}
+}
+
+impl<F: FnMut(&mut [f32], usize) + Send> Processor for F {
+	fn process(&mut self, samples: &mut [f32], channels: usize) {
+		self(samples, channels)
+	}
+}
+
+// A processor the game keeps a handle to while the audio thread runs it, e.g. to retune an
+// oscillator. Keep the lock short, the audio thread waits for it.
+pub struct Shared<P>(Arc<Mutex<P>>);
+
+impl<P> Shared<P> {
+	pub fn new(processor: P) -> Self {
+		Self(Arc::new(Mutex::new(processor)))
+	}
+
+	pub fn lock(&self) -> MutexGuard<'_, P> {
+		self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+}
+
+impl<P> Clone for Shared<P> {
+	fn clone(&self) -> Self {
+		Self(self.0.clone())
+	}
+}
+
+impl<P: Processor> Processor for Shared<P> {
+	fn process(&mut self, samples: &mut [f32], channels: usize) {
+		self.lock().process(samples, channels);
+	}
+
+	fn reset(&mut self) {
+		self.lock().reset();
+	}
+}
+
+pub fn db_to_gain(db: f32) -> f32 {
+	10f32.powf(db / 20.0)
+}
+
+pub fn gain_to_db(gain: f32) -> f32 {
+	20.0 * gain.max(1e-9).log10()
+}
+
+// Processors run one after another.
+#[derive(Default)]
+pub struct Chain {
+	processors: Vec<Box<dyn Processor>>,
+}
+
+impl Chain {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with(mut self, processor: impl Processor + 'static) -> Self {
+		self.processors.push(Box::new(processor));
+		self
+	}
+
+	pub fn push(&mut self, processor: impl Processor + 'static) {
+		self.processors.push(Box::new(processor));
+	}
+
+	pub fn len(&self) -> usize {
+		self.processors.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.processors.is_empty()
+	}
+}
+
+impl Processor for Chain {
+	fn process(&mut self, samples: &mut [f32], channels: usize) {
+		for processor in &mut self.processors {
+			processor.process(samples, channels);
+		}
+	}
+
+	fn reset(&mut self) {
+		for processor in &mut self.processors {
+			processor.reset();
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Gain {
+	pub gain: f32,
+}
+
+impl Gain {
+	pub fn new(gain: f32) -> Self {
+		Self { gain }
+	}
+
+	pub fn db(db: f32) -> Self {
+		Self { gain: db_to_gain(db) }
+	}
+}
+
+impl Processor for Gain {
+	fn process(&mut self, samples: &mut [f32], _channels: usize) {
+		for sample in samples {
+			*sample *= self.gain;
+		}
+	}
+}
+
+// Second order filter with the coefficients of the Audio EQ Cookbook, state kept per channel.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Biquad {
+	b0: f32,
+	b1: f32,
+	b2: f32,
+	a1: f32,
+	a2: f32,
+	// x1, x2, y1, y2 of each channel
+	state: Vec<[f32; 4]>,
+}
+
+impl Biquad {
+	// `q` of 0.707 is the flattest pass band
+	pub fn low_pass(sample_rate: f32, cutoff: f32, q: f32) -> Self {
+		let (cos, alpha) = Self::angle(sample_rate, cutoff, q);
+		Self::normalized((1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+	}
+
+	pub fn high_pass(sample_rate: f32, cutoff: f32, q: f32) -> Self {
+		let (cos, alpha) = Self::angle(sample_rate, cutoff, q);
+		Self::normalized((1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+	}
+
+	fn angle(sample_rate: f32, cutoff: f32, q: f32) -> (f32, f32) {
+		let omega = TAU * cutoff.clamp(1.0, sample_rate * 0.49) / sample_rate;
+		(omega.cos(), omega.sin() / (2.0 * q.max(0.01)))
+	}
+
+	fn normalized(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+		Self {
+			b0: b0 / a0,
+			b1: b1 / a0,
+			b2: b2 / a0,
+			a1: a1 / a0,
+			a2: a2 / a0,
+			state: Vec::new(),
+		}
+	}
+
+	// one sample of `channel`, for processing outside interleaved buffers
+	pub fn filter(&mut self, channel: usize, x: f32) -> f32 {
+		if self.state.len() <= channel {
+			self.state.resize(channel + 1, [0.0; 4]);
+		}
+		let [x1, x2, y1, y2] = self.state[channel];
+		let y = self.b0 * x + self.b1 * x1 + self.b2 * x2 - self.a1 * y1 - self.a2 * y2;
+		self.state[channel] = [x, x1, y, y1];
+		y
+	}
+}
+
+impl Processor for Biquad {
+	fn process(&mut self, samples: &mut [f32], channels: usize) {
+		let channels = channels.max(1);
+		for (i, sample) in samples.iter_mut().enumerate() {
+			*sample = self.filter(i % channels, *sample);
+		}
+	}
+
+	fn reset(&mut self) {
+		self.state.clear();
+	}
+}
+
+// Echo: the input mixed with itself `time` earlier, fed back for repeats.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Delay {
+	// 0.0 one echo to below 1.0 repeating for long
+	pub feedback: f32,
+	// 0.0 dry to 1.0 only echoes
+	pub mix: f32,
+	frames: usize,
+	// one line per channel, interleaved like the samples
+	line: Vec<f32>,
+	position: usize,
+}
+
+impl Delay {
+	pub fn new(sample_rate: f32, time: f32, feedback: f32, mix: f32) -> Self {
+		Self {
+			feedback,
+			mix,
+			frames: ((sample_rate * time) as usize).max(1),
+			line: Vec::new(),
+			position: 0,
+		}
+	}
+}
+
+impl Processor for Delay {
+	fn process(&mut self, samples: &mut [f32], channels: usize) {
+		let channels = channels.max(1);
+		if self.line.len() != self.frames * channels {
+			self.line = vec![0.0; self.frames * channels];
+			self.position = 0;
+		}
+		for frame in samples.chunks_mut(channels) {
+			for (channel, sample) in frame.iter_mut().enumerate() {
+				let index = self.position * channels + channel;
+				let delayed = self.line[index];
+				self.line[index] = *sample + delayed * self.feedback;
+				*sample = *sample * (1.0 - self.mix) + delayed * self.mix;
+			}
+			self.position = (self.position + 1) % self.frames;
+		}
+	}
+
+	fn reset(&mut self) {
+		self.line.fill(0.0);
+	}
+}
+
+// Feedback comb with damping, part of the reverb.
+#[derive(Clone, PartialEq, Debug)]
+struct Comb {
+	buffer: Vec<f32>,
+	position: usize,
+	low: f32,
+}
+
+impl Comb {
+	fn new(len: usize) -> Self {
+		Self {
+			buffer: vec![0.0; len.max(1)],
+			position: 0,
+			low: 0.0,
+		}
+	}
+
+	fn tick(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+		let output = self.buffer[self.position];
+		self.low = output * (1.0 - damping) + self.low * damping;
+		self.buffer[self.position] = input + self.low * feedback;
+		self.position = (self.position + 1) % self.buffer.len();
+		output
+	}
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct AllPass {
+	buffer: Vec<f32>,
+	position: usize,
+}
+
+impl AllPass {
+	fn new(len: usize) -> Self {
+		Self {
+			buffer: vec![0.0; len.max(1)],
+			position: 0,
+		}
+	}
+
+	fn tick(&mut self, input: f32) -> f32 {
+		let delayed = self.buffer[self.position];
+		self.buffer[self.position] = input + delayed * 0.5;
+		self.position = (self.position + 1) % self.buffer.len();
+		delayed - input
+	}
+}
+
+// comb and all-pass lengths of Freeverb at 44.1 kHz, scaled to the sample rate
+const COMB_TUNING: [usize; 4] = [1116, 1188, 1277, 1356];
+const ALL_PASS_TUNING: [usize; 2] = [556, 441];
+// extra length of the right channel's lines, so the two sides differ
+const STEREO_SPREAD: usize = 23;
+
+// A small Freeverb style room: parallel damped combs into all-pass diffusers, per channel.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Reverb {
+	// 0.0 small to 1.0 large
+	pub room_size: f32,
+	// 0.0 bright to 1.0 dull
+	pub damping: f32,
+	// 0.0 dry to 1.0 only the room
+	pub mix: f32,
+	sample_rate: f32,
+	channels: Vec<(Vec<Comb>, Vec<AllPass>)>,
+}
+
+impl Reverb {
+	pub fn new(sample_rate: f32, room_size: f32, damping: f32, mix: f32) -> Self {
+		Self {
+			room_size,
+			damping,
+			mix,
+			sample_rate,
+			channels: Vec::new(),
+		}
+	}
+
+	fn lines(&self, channel: usize) -> (Vec<Comb>, Vec<AllPass>) {
+		let scale = |len: usize| ((len + STEREO_SPREAD * channel) as f32 * self.sample_rate / 44100.0) as usize;
+		(
+			COMB_TUNING.iter().map(|&len| Comb::new(scale(len))).collect(),
+			ALL_PASS_TUNING.iter().map(|&len| AllPass::new(scale(len))).collect(),
+		)
+	}
+}
+
+impl Processor for Reverb {
+	fn process(&mut self, samples: &mut [f32], channels: usize) {
+		let channels = channels.max(1);
+		while self.channels.len() < channels {
+			let lines = self.lines(self.channels.len());
+			self.channels.push(lines);
+		}
+		let feedback = 0.7 + self.room_size.clamp(0.0, 1.0) * 0.28;
+		let damping = self.damping.clamp(0.0, 1.0) * 0.4;
+		for frame in samples.chunks_mut(channels) {
+			for (sample, (combs, all_passes)) in frame.iter_mut().zip(&mut self.channels) {
+				// scaled down so the parallel combs don't clip
+				let input = *sample * 0.015 * COMB_TUNING.len() as f32;
+				let mut wet: f32 = combs.iter_mut().map(|comb| comb.tick(input, feedback, damping)).sum();
+				for all_pass in all_passes.iter_mut() {
+					wet = all_pass.tick(wet);
+				}
+				*sample = *sample * (1.0 - self.mix) + wet * self.mix;
+			}
+		}
+	}
+
+	fn reset(&mut self) {
+		self.channels.clear();
+	}
+}
+
+// Keeps peaks under `threshold`: the gain drops at once on a louder sample and recovers over
+// `release` seconds. All channels share one gain so the stereo image holds.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Limiter {
+	pub threshold: f32,
+	release: f32,
+	gain: f32,
+}
+
+impl Limiter {
+	pub fn new(sample_rate: f32, threshold: f32, release: f32) -> Self {
+		Self {
+			threshold,
+			// per frame recovery towards 1.0
+			release: (-1.0 / (release.max(0.001) * sample_rate)).exp(),
+			gain: 1.0,
+		}
+	}
+
+	// the gain applied to the last frame
+	pub fn gain(&self) -> f32 {
+		self.gain
+	}
+}
+
+impl Processor for Limiter {
+	fn process(&mut self, samples: &mut [f32], channels: usize) {
+		for frame in samples.chunks_mut(channels.max(1)) {
+			let peak = frame.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+			let target = if peak > self.threshold { self.threshold / peak } else { 1.0 };
+			self.gain = if target < self.gain {
+				target
+			} else {
+				target + (self.gain - target) * self.release
+			};
+			for sample in frame {
+				*sample *= self.gain;
+			}
+		}
+	}
+
+	fn reset(&mut self) {
+		self.gain = 1.0;
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Waveform {
+	Sine,
+	Square,
+	Saw,
+	Triangle,
+	// white noise, the frequency is ignored
+	Noise,
+}
+
+// A tone generator. As a processor it writes the same sample to every channel of a frame,
+// replacing what was there.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Oscillator {
+	pub waveform: Waveform,
+	pub frequency: f32,
+	pub amplitude: f32,
+	sample_rate: f32,
+	// 0.0 to 1.0 through one period
+	phase: f32,
+	noise: u32,
+}
+
+impl Oscillator {
+	pub fn new(waveform: Waveform, sample_rate: f32, frequency: f32, amplitude: f32) -> Self {
+		Self {
+			waveform,
+			frequency,
+			amplitude,
+			sample_rate,
+			phase: 0.0,
+			noise: 0x9e37_79b9,
+		}
+	}
+
+	pub fn next_sample(&mut self) -> f32 {
+		let phase = self.phase;
+		self.phase = (self.phase + self.frequency / self.sample_rate).fract();
+		let value = match self.waveform {
+			Waveform::Sine => (phase * TAU).sin(),
+			Waveform::Square => {
+				if phase < 0.5 {
+					1.0
+				} else {
+					-1.0
+				}
+			}
+			Waveform::Saw => 2.0 * phase - 1.0,
+			Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+			Waveform::Noise => {
+				// xorshift, cheap and good enough for noise
+				self.noise ^= self.noise << 13;
+				self.noise ^= self.noise >> 17;
+				self.noise ^= self.noise << 5;
+				self.noise as f32 / u32::MAX as f32 * 2.0 - 1.0
+			}
+		};
+		value * self.amplitude
+	}
+}
+
+impl Processor for Oscillator {
+	fn process(&mut self, samples: &mut [f32], channels: usize) {
+		for frame in samples.chunks_mut(channels.max(1)) {
+			frame.fill(self.next_sample());
+		}
+	}
+
+	fn reset(&mut self) {
+		self.phase = 0.0;
+	}
+}
+
+// Equal power pan of a mono sample, -1.0 left to 1.0 right.
+pub fn pan_gains(pan: f32) -> (f32, f32) {
+	let angle = (pan.clamp(-1.0, 1.0) + 1.0) * PI / 4.0;
+	(angle.cos(), angle.sin())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SAMPLE_RATE: f32 = 48000.0;
+
+	fn close(a: f32, b: f32) -> bool {
+		(a - b).abs() < 1e-4
+	}
+
+	fn sine(frequency: f32, frames: usize) -> Vec<f32> {
+		(0..frames).map(|i| (TAU * frequency * i as f32 / SAMPLE_RATE).sin()).collect()
+	}
+
+	// loudness of the second half, after the filter settled
+	fn rms(samples: &[f32]) -> f32 {
+		let tail = &samples[samples.len() / 2..];
+		(tail.iter().map(|sample| sample * sample).sum::<f32>() / tail.len() as f32).sqrt()
+	}
+
+	fn impulse(frames: usize, channels: usize) -> Vec<f32> {
+		let mut samples = vec![0.0; frames * channels];
+		samples[0] = 1.0;
+		samples
+	}
+
+	#[test]
+	fn gain_scales_every_sample() {
+		let mut samples = vec![1.0, -0.5, 0.25, 0.0];
+		Gain::new(0.5).process(&mut samples, 2);
+		assert_eq!(samples, [0.5, -0.25, 0.125, 0.0]);
+
+		assert!(close(Gain::db(-6.0206).gain, 0.5));
+		assert!(close(gain_to_db(db_to_gain(-12.0)), -12.0));
+		assert_eq!(db_to_gain(0.0), 1.0);
+		// silence has a floor instead of minus infinity
+		assert!(gain_to_db(0.0).is_finite());
+	}
+
+	#[test]
+	fn pan_keeps_the_power() {
+		let (left, right) = pan_gains(0.0);
+		assert!(close(left, right) && close(left, 0.70710677));
+		let (left, right) = pan_gains(-1.0);
+		assert!(close(left, 1.0) && close(right, 0.0));
+		let (left, right) = pan_gains(5.0);
+		assert!(close(left, 0.0) && close(right, 1.0));
+		for pan in [-0.75, -0.3, 0.2, 0.9] {
+			let (left, right) = pan_gains(pan);
+			assert!(close(left * left + right * right, 1.0));
+		}
+	}
+
+	#[test]
+	fn low_pass_keeps_the_lows() {
+		let mut low = sine(100.0, 4800);
+		let mut high = sine(8000.0, 4800);
+		Biquad::low_pass(SAMPLE_RATE, 1000.0, 0.707).process(&mut low, 1);
+		Biquad::low_pass(SAMPLE_RATE, 1000.0, 0.707).process(&mut high, 1);
+		// a full scale sine has an rms of 0.707
+		assert!(rms(&low) > 0.69);
+		assert!(rms(&high) < 0.02);
+	}
+
+	#[test]
+	fn high_pass_keeps_the_highs() {
+		let mut low = sine(100.0, 4800);
+		let mut high = sine(8000.0, 4800);
+		Biquad::high_pass(SAMPLE_RATE, 1000.0, 0.707).process(&mut low, 1);
+		Biquad::high_pass(SAMPLE_RATE, 1000.0, 0.707).process(&mut high, 1);
+		assert!(rms(&low) < 0.02);
+		assert!(rms(&high) > 0.69);
+	}
+
+	#[test]
+	fn biquad_filters_channels_apart() {
+		// steady left, silent right
+		let mut samples: Vec<f32> = (0..2000).flat_map(|_| [1.0, 0.0]).collect();
+		Biquad::low_pass(SAMPLE_RATE, 500.0, 0.707).process(&mut samples, 2);
+		assert!(samples.chunks(2).all(|frame| frame[1] == 0.0));
+		assert!(close(samples[samples.len() - 2], 1.0));
+	}
+
+	#[test]
+	fn delay_echoes_after_its_time() {
+		// 10 frames at 100 Hz, stereo with the impulse on the left
+		let mut delay = Delay::new(100.0, 0.1, 0.5, 1.0);
+		let mut samples = impulse(35, 2);
+		delay.process(&mut samples, 2);
+		let left: Vec<(usize, f32)> = samples
+			.chunks(2)
+			.enumerate()
+			.filter(|(_, frame)| frame[0] != 0.0)
+			.map(|(i, frame)| (i, frame[0]))
+			.collect();
+		assert_eq!(left, [(10, 1.0), (20, 0.5), (30, 0.25)]);
+		assert!(samples.chunks(2).all(|frame| frame[1] == 0.0));
+
+		// half dry, half the echo
+		let mut delay = Delay::new(100.0, 0.1, 0.0, 0.5);
+		let mut samples = impulse(25, 1);
+		delay.process(&mut samples, 1);
+		assert_eq!((samples[0], samples[10], samples[20]), (0.5, 0.5, 0.0));
+	}
+
+	#[test]
+	fn delay_carries_over_between_buffers() {
+		let mut delay = Delay::new(100.0, 0.1, 0.0, 1.0);
+		let mut first = impulse(6, 1);
+		let mut second = vec![0.0; 6];
+		delay.process(&mut first, 1);
+		delay.process(&mut second, 1);
+		assert_eq!(second, [0.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+	}
+
+	#[test]
+	fn limiter_holds_peaks_and_recovers() {
+		let mut limiter = Limiter::new(1000.0, 0.5, 0.01);
+		let mut samples = vec![1.0, -0.25, 0.2, 0.1];
+		limiter.process(&mut samples, 2);
+		assert_eq!(samples[..2], [0.5, -0.125]);
+		assert!(limiter.gain() > 0.5 && limiter.gain() < 1.0);
+
+		let mut quiet = vec![0.1; 200];
+		limiter.process(&mut quiet, 1);
+		assert!(close(limiter.gain(), 1.0));
+	}
+
+	#[test]
+	fn oscillators_draw_their_shapes() {
+		// four samples per period
+		let shape = |waveform| {
+			let mut oscillator = Oscillator::new(waveform, 400.0, 100.0, 0.5);
+			(0..4).map(|_| oscillator.next_sample()).collect::<Vec<_>>()
+		};
+		assert_eq!(shape(Waveform::Square), [0.5, 0.5, -0.5, -0.5]);
+		assert_eq!(shape(Waveform::Saw), [-0.5, -0.25, 0.0, 0.25]);
+		assert_eq!(shape(Waveform::Triangle), [-0.5, 0.0, 0.5, 0.0]);
+		let sine = shape(Waveform::Sine);
+		assert!(close(sine[1], 0.5) && close(sine[3], -0.5));
+		let noise = shape(Waveform::Noise);
+		assert!(noise.iter().all(|sample| sample.abs() <= 0.5));
+		assert_ne!(noise[0], noise[1]);
+
+		// same sample on every channel, replacing the input
+		let mut samples = vec![9.0; 6];
+		Oscillator::new(Waveform::Saw, 400.0, 100.0, 1.0).process(&mut samples, 2);
+		assert_eq!(samples, [-1.0, -1.0, -0.5, -0.5, 0.0, 0.0]);
+	}
+
+	#[test]
+	fn reset_starts_over() {
+		fn fresh_after_reset(mut processor: impl Processor, mut fresh: impl Processor) {
+			let mut used = impulse(3000, 2);
+			processor.process(&mut used, 2);
+			processor.reset();
+
+			let input: Vec<f32> = (0..1000).map(|i| ((i * 7 % 13) as f32 - 6.0) / 6.0).collect();
+			let (mut expected, mut actual) = (input.clone(), input);
+			fresh.process(&mut expected, 2);
+			processor.process(&mut actual, 2);
+			assert_eq!(actual, expected);
+		}
+
+		let low_pass = || Biquad::low_pass(SAMPLE_RATE, 800.0, 0.707);
+		fresh_after_reset(low_pass(), low_pass());
+		let high_pass = || Biquad::high_pass(SAMPLE_RATE, 800.0, 0.707);
+		fresh_after_reset(high_pass(), high_pass());
+		let delay = || Delay::new(SAMPLE_RATE, 0.01, 0.6, 0.5);
+		fresh_after_reset(delay(), delay());
+		let reverb = || Reverb::new(SAMPLE_RATE, 0.5, 0.5, 0.3);
+		fresh_after_reset(reverb(), reverb());
+		let limiter = || Limiter::new(SAMPLE_RATE, 0.5, 0.1);
+		fresh_after_reset(limiter(), limiter());
+		let oscillator = || Oscillator::new(Waveform::Triangle, SAMPLE_RATE, 440.0, 1.0);
+		fresh_after_reset(oscillator(), oscillator());
+		let chain = || Chain::new().with(low_pass()).with(delay());
+		fresh_after_reset(chain(), chain());
+	}
+
+	#[test]
+	fn chain_runs_in_order_and_shared_retunes() {
+		let mut chain = Chain::new()
+			.with(Gain::new(2.0))
+			.with(|samples: &mut [f32], _channels: usize| samples.iter_mut().for_each(|sample| *sample += 1.0));
+		let mut samples = vec![1.0, 2.0];
+		chain.process(&mut samples, 1);
+		assert_eq!((samples, chain.len()), (vec![3.0, 5.0], 2));
+
+		let gain = Shared::new(Gain::new(1.0));
+		let mut playing = gain.clone();
+		gain.lock().gain = 0.25;
+		let mut samples = vec![1.0];
+		playing.process(&mut samples, 1);
+		assert_eq!(samples, [0.25]);
+	}
+}
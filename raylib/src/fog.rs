@@ -0,0 +1,510 @@
+use std::{
+	collections::HashMap,
+	io::{self, BufRead, Write},
+};
+
+use crate::{
+	fov::{compute_fov_into, FovAlgorithm, VisibleTiles},
+	tilemap::TileMap,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum FogState {
+	// never seen by the team
+	#[default]
+	Unexplored,
+	// seen before but not in view right now
+	Explored,
+	// in view of at least one viewer of the team
+	Visible,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord)]
+pub struct TeamId(pub u32);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ViewerId(u32);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Viewer {
+	pub team: TeamId,
+	pub position: (i32, i32),
+	pub radius: i32,
+}
+
+// A tile of a team changed state during `FogOfWar::update`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FogEvent {
+	pub team: TeamId,
+	pub x: i32,
+	pub y: i32,
+	pub from: FogState,
+	pub to: FogState,
+}
+
+impl FogEvent {
+	// the tile was seen by the team for the first time
+	pub fn is_discovery(&self) -> bool {
+		self.from == FogState::Unexplored
+	}
+}
+
+struct TeamFog<T> {
+	states: Box<[FogState]>,
+	// what the tile looked like the last time the team saw it
+	remembered: Box<[Option<T>]>,
+}
+
+impl<T> TeamFog<T> {
+	fn new(len: usize) -> Self {
+		Self {
+			states: vec![FogState::Unexplored; len].into_boxed_slice(),
+			remembered: (0..len).map(|_| None).collect(),
+		}
+	}
+}
+
+// Fog of war state for any number of teams, each team sees the union of what its viewers see.
+// `T` is the snapshot kept for explored tiles, e.g. a tile id.
+pub struct FogOfWar<T = ()> {
+	width: i32,
+	height: i32,
+	algorithm: FovAlgorithm,
+	teams: HashMap<TeamId, TeamFog<T>>,
+	viewers: HashMap<ViewerId, Viewer>,
+	next_viewer: u32,
+	events: Vec<FogEvent>,
+	// scratch sets reused between updates
+	team_visible: VisibleTiles,
+	viewer_visible: VisibleTiles,
+}
+
+impl<T> TileMap for FogOfWar<T> {
+	fn width(&self) -> i32 {
+		self.width
+	}
+
+	fn height(&self) -> i32 {
+		self.height
+	}
+}
+
+impl<T: Clone> FogOfWar<T> {
+	pub fn new(width: i32, height: i32) -> Self {
+		let (width, height) = (width.max(0), height.max(0));
+		Self {
+			width,
+			height,
+			algorithm: FovAlgorithm::Shadowcasting,
+			teams: HashMap::new(),
+			viewers: HashMap::new(),
+			next_viewer: 0,
+			events: Vec::new(),
+			team_visible: VisibleTiles::new(width, height),
+			viewer_visible: VisibleTiles::new(width, height),
+		}
+	}
+
+	pub fn algorithm(&self) -> FovAlgorithm {
+		self.algorithm
+	}
+
+	pub fn set_algorithm(&mut self, algorithm: FovAlgorithm) {
+		self.algorithm = algorithm;
+	}
+
+	pub fn add_viewer(&mut self, team: TeamId, position: (i32, i32), radius: i32) -> ViewerId {
+		let id = ViewerId(self.next_viewer);
+		self.next_viewer += 1;
+		self.viewers.insert(id, Viewer { team, position, radius });
+		self.team_mut(team);
+		id
+	}
+
+	pub fn remove_viewer(&mut self, id: ViewerId) -> Option<Viewer> {
+		self.viewers.remove(&id)
+	}
+
+	pub fn viewer(&self, id: ViewerId) -> Option<&Viewer> {
+		self.viewers.get(&id)
+	}
+
+	pub fn viewer_mut(&mut self, id: ViewerId) -> Option<&mut Viewer> {
+		self.viewers.get_mut(&id)
+	}
+
+	pub fn set_viewer_position(&mut self, id: ViewerId, position: (i32, i32)) {
+		if let Some(viewer) = self.viewers.get_mut(&id) {
+			viewer.position = position;
+		}
+	}
+
+	pub fn teams(&self) -> impl Iterator<Item = TeamId> + '_ {
+		self.teams.keys().copied()
+	}
+
+	pub fn state(&self, team: TeamId, x: i32, y: i32) -> FogState {
+		match self.teams.get(&team) {
+			Some(fog) if self.in_bounds(x, y) => fog.states[self.index(x, y)],
+			_ => FogState::Unexplored,
+		}
+	}
+
+	pub fn is_visible(&self, team: TeamId, x: i32, y: i32) -> bool {
+		self.state(team, x, y) == FogState::Visible
+	}
+
+	pub fn is_explored(&self, team: TeamId, x: i32, y: i32) -> bool {
+		self.state(team, x, y) != FogState::Unexplored
+	}
+
+	// Row-major states of the whole map for a team, `None` if the team has no viewers and was never loaded.
+	pub fn states(&self, team: TeamId) -> Option<&[FogState]> {
+		self.teams.get(&team).map(|fog| &*fog.states)
+	}
+
+	// Snapshot of the tile from the last time the team saw it.
+	pub fn remembered(&self, team: TeamId, x: i32, y: i32) -> Option<&T> {
+		match self.teams.get(&team) {
+			Some(fog) if self.in_bounds(x, y) => fog.remembered[self.index(x, y)].as_ref(),
+			_ => None,
+		}
+	}
+
+	// Recomputes visibility for every team. `snapshot` is called for each visible tile to refresh what the team remembers.
+	pub fn update<O, S>(&mut self, is_opaque: O, snapshot: S)
+	where
+		O: Fn(i32, i32) -> bool,
+		S: Fn(i32, i32) -> T,
+	{
+		let bounds = Bounds(self.width, self.height);
+		let mut teams: Vec<TeamId> = self.teams.keys().copied().collect();
+		teams.sort();
+
+		for team in teams {
+			self.team_visible.clear();
+			for viewer in self.viewers.values().filter(|viewer| viewer.team == team) {
+				compute_fov_into(
+					&bounds,
+					viewer.position,
+					viewer.radius,
+					self.algorithm,
+					&is_opaque,
+					&mut self.viewer_visible,
+				);
+				for (x, y) in self.viewer_visible.iter() {
+					self.team_visible.insert(x, y);
+				}
+			}
+
+			let fog = self.teams.get_mut(&team).expect("team");
+			for y in 0..self.height {
+				for x in 0..self.width {
+					let i = (y * self.width + x) as usize;
+					let from = fog.states[i];
+					let to = if self.team_visible.contains(x, y) {
+						fog.remembered[i] = Some(snapshot(x, y));
+						FogState::Visible
+					} else if from == FogState::Unexplored {
+						FogState::Unexplored
+					} else {
+						FogState::Explored
+					};
+
+					if from != to {
+						fog.states[i] = to;
+						self.events.push(FogEvent { team, x, y, from, to });
+					}
+				}
+			}
+		}
+	}
+
+	// Events produced by the updates since the last drain.
+	pub fn drain_events(&mut self) -> impl Iterator<Item = FogEvent> + '_ {
+		self.events.drain(..)
+	}
+
+	// Forgets everything a team has explored.
+	pub fn reset(&mut self, team: TeamId) {
+		let len = self.len();
+		if let Some(fog) = self.teams.get_mut(&team) {
+			*fog = TeamFog::new(len);
+		}
+	}
+
+	// Writes the explored state of every team. Snapshots are not saved, they come back the next time a tile is seen.
+	//
+	// fog <width> <height>
+	// team <id>
+	// <one line per row, '#' explored, '.' unexplored>
+	pub fn save(&self, writer: &mut impl Write) -> io::Result<()> {
+		writeln!(writer, "fog {} {}", self.width, self.height)?;
+
+		let mut teams: Vec<_> = self.teams.iter().collect();
+		teams.sort_by_key(|(team, _)| **team);
+		for (team, fog) in teams {
+			writeln!(writer, "team {}", team.0)?;
+			// row by row, a map without columns still has its empty rows
+			let width = self.width as usize;
+			for y in 0..self.height as usize {
+				let row = &fog.states[y * width..(y + 1) * width];
+				let line: String = row.iter().map(|state| if *state == FogState::Unexplored { '.' } else { '#' }).collect();
+				writeln!(writer, "{}", line)?;
+			}
+		}
+		Ok(())
+	}
+
+	// Restores the explored state written by `save`, replacing the state of the teams it contains.
+	// Loaded tiles are explored until the next `update` marks them visible again.
+	pub fn load(&mut self, reader: impl BufRead) -> io::Result<()> {
+		let mut lines = reader.lines();
+		let header = lines.next().transpose()?.unwrap_or_default();
+		if header != format!("fog {} {}", self.width, self.height) {
+			return Err(invalid_data(format!(
+				"fog header '{}' does not match a {}x{} map",
+				header, self.width, self.height
+			)));
+		}
+
+		let mut loaded = Vec::new();
+		while let Some(line) = lines.next().transpose()? {
+			if line.is_empty() {
+				continue;
+			}
+			let team = line
+				.strip_prefix("team ")
+				.and_then(|id| id.parse().ok())
+				.map(TeamId)
+				.ok_or_else(|| invalid_data(format!("expected 'team <id>', found '{}'", line)))?;
+
+			let mut fog = TeamFog::new(self.len());
+			for y in 0..self.height {
+				let row = lines
+					.next()
+					.transpose()?
+					.ok_or_else(|| invalid_data(format!("team {} is missing row {}", team.0, y)))?;
+				if row.len() != self.width as usize {
+					return Err(invalid_data(format!(
+						"team {} row {} has {} tiles, expected {}",
+						team.0,
+						y,
+						row.len(),
+						self.width
+					)));
+				}
+				for (x, c) in row.chars().enumerate() {
+					fog.states[(y * self.width) as usize + x] = match c {
+						'#' => FogState::Explored,
+						'.' => FogState::Unexplored,
+						_ => return Err(invalid_data(format!("unexpected fog tile '{}'", c))),
+					};
+				}
+			}
+			loaded.push((team, fog));
+		}
+
+		for (team, fog) in loaded {
+			self.teams.insert(team, fog);
+		}
+		Ok(())
+	}
+
+	fn team_mut(&mut self, team: TeamId) -> &mut TeamFog<T> {
+		let len = self.len();
+		self.teams.entry(team).or_insert_with(|| TeamFog::new(len))
+	}
+
+	// tiles per team, the size is never negative
+	fn len(&self) -> usize {
+		(self.width * self.height) as usize
+	}
+}
+
+struct Bounds(i32, i32);
+
+impl TileMap for Bounds {
+	fn width(&self) -> i32 {
+		self.0
+	}
+
+	fn height(&self) -> i32 {
+		self.1
+	}
+}
+
+fn invalid_data(message: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// two rooms split by a wall
+	const MAP: [&str; 5] = ["....#....", "....#....", "....#....", "....#....", "....#...."];
+	const RED: TeamId = TeamId(0);
+	const BLUE: TeamId = TeamId(1);
+
+	fn is_opaque(x: i32, y: i32) -> bool {
+		MAP[y as usize].as_bytes()[x as usize] == b'#'
+	}
+
+	fn tile(x: i32, y: i32) -> char {
+		MAP[y as usize].as_bytes()[x as usize] as char
+	}
+
+	fn fog() -> FogOfWar<char> {
+		FogOfWar::new(MAP[0].len() as i32, MAP.len() as i32)
+	}
+
+	// the states of a team as rows of 'v'isible, 'e'xplored and '.' unexplored
+	fn picture(fog: &FogOfWar<char>, team: TeamId) -> Vec<String> {
+		(0..fog.height())
+			.map(|y| {
+				(0..fog.width())
+					.map(|x| match fog.state(team, x, y) {
+						FogState::Visible => 'v',
+						FogState::Explored => 'e',
+						FogState::Unexplored => '.',
+					})
+					.collect()
+			})
+			.collect()
+	}
+
+	fn saved(fog: &FogOfWar<char>) -> String {
+		let mut text = Vec::new();
+		fog.save(&mut text).unwrap();
+		String::from_utf8(text).unwrap()
+	}
+
+	#[test]
+	fn teams_see_what_any_of_their_viewers_see() {
+		let mut fog = fog();
+		fog.add_viewer(RED, (1, 2), 10);
+		fog.add_viewer(RED, (7, 2), 10);
+		fog.add_viewer(BLUE, (1, 2), 10);
+		fog.update(is_opaque, tile);
+
+		assert_eq!(picture(&fog, RED), ["vvvvvvvvv"; 5]);
+		// the wall hides the other room
+		assert_eq!(picture(&fog, BLUE), ["vvvvv...."; 5]);
+		assert_eq!(fog.remembered(BLUE, 4, 0), Some(&'#'));
+		assert_eq!(fog.remembered(BLUE, 5, 0), None);
+		// teams without viewers see nothing
+		assert_eq!(fog.state(TeamId(7), 1, 2), FogState::Unexplored);
+		assert!(fog.states(TeamId(7)).is_none());
+		let mut teams: Vec<TeamId> = fog.teams().collect();
+		teams.sort();
+		assert_eq!(teams, [RED, BLUE]);
+	}
+
+	#[test]
+	fn updates_report_every_change() {
+		use FogState::*;
+
+		let mut fog = fog();
+		let scout = fog.add_viewer(RED, (0, 0), 1);
+		fog.update(is_opaque, tile);
+		let events: Vec<FogEvent> = fog.drain_events().collect();
+		assert_eq!(events.len(), 4);
+		assert!(events.iter().all(|event| event.is_discovery() && event.to == FogState::Visible));
+
+		// nothing changes, nothing to report
+		fog.update(is_opaque, tile);
+		assert_eq!(fog.drain_events().count(), 0);
+
+		fog.set_viewer_position(scout, (2, 0));
+		fog.update(is_opaque, tile);
+		let mut events: Vec<(i32, i32, FogState, FogState)> = fog.drain_events().map(|event| (event.x, event.y, event.from, event.to)).collect();
+		events.sort_by_key(|(x, y, ..)| (*y, *x));
+		assert_eq!(
+			events,
+			[
+				(0, 0, Visible, Explored),
+				(2, 0, Unexplored, Visible),
+				(3, 0, Unexplored, Visible),
+				(0, 1, Visible, Explored),
+				(2, 1, Unexplored, Visible),
+				(3, 1, Unexplored, Visible),
+			]
+		);
+
+		// explored tiles keep what was seen
+		fog.remove_viewer(scout);
+		fog.update(is_opaque, tile);
+		assert_eq!(fog.drain_events().count(), 6);
+		assert_eq!(picture(&fog, RED)[..2], ["eeee.....", "eeee....."]);
+		assert_eq!(fog.remembered(RED, 0, 0), Some(&'.'));
+
+		fog.reset(RED);
+		assert_eq!(picture(&fog, RED), ["........."; 5]);
+		assert_eq!(fog.remembered(RED, 0, 0), None);
+	}
+
+	#[test]
+	fn save_and_load_keep_what_was_explored() {
+		let mut fog = fog();
+		fog.add_viewer(RED, (1, 2), 10);
+		fog.add_viewer(BLUE, (8, 4), 1);
+		fog.update(is_opaque, tile);
+		let text = saved(&fog);
+		assert_eq!(
+			text,
+			"fog 9 5\nteam 0\n#####....\n#####....\n#####....\n#####....\n#####....\n\
+			 team 1\n.........\n.........\n.........\n.......##\n.......##\n"
+		);
+
+		let mut loaded = FogOfWar::<char>::new(9, 5);
+		loaded.load(text.as_bytes()).unwrap();
+		assert_eq!(picture(&loaded, RED), ["eeeee...."; 5]);
+		assert_eq!(picture(&loaded, BLUE)[4], ".......ee");
+		assert_eq!(saved(&loaded), text);
+		// snapshots are not saved
+		assert_eq!(loaded.remembered(RED, 1, 2), None);
+	}
+
+	#[test]
+	fn bad_saves_are_rejected_and_change_nothing() {
+		let mut fog = fog();
+		fog.add_viewer(RED, (1, 2), 10);
+		fog.update(is_opaque, tile);
+		let before = saved(&fog);
+		let row = ".........\n";
+		for text in [
+			String::new(),
+			"fog 8 5\n".to_string(),
+			format!("fog 9 5\nside 1\n{}", row.repeat(5)),
+			format!("fog 9 5\nteam 1\n{}", row.repeat(4)),
+			format!("fog 9 5\nteam 1\n{}....\n", row.repeat(4)),
+			format!("fog 9 5\nteam 1\n{}....?....\n", row.repeat(4)),
+			// the first team is fine, the second is not
+			format!("fog 9 5\nteam 0\n{}team 1\n", row.repeat(5)),
+		] {
+			let error = fog.load(text.as_bytes()).unwrap_err();
+			assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{text}");
+			assert_eq!(saved(&fog), before);
+		}
+	}
+
+	#[test]
+	fn empty_and_negative_sizes_hold_no_tiles() {
+		for (width, height) in [(-3, 5), (0, 5), (4, -1), (0, 0)] {
+			let mut fog = FogOfWar::<()>::new(width, height);
+			let viewer = fog.add_viewer(RED, (0, 0), 3);
+			fog.update(|_, _| false, |_, _| ());
+			assert_eq!(fog.states(RED).map(<[FogState]>::len), Some(0));
+			assert_eq!(fog.drain_events().count(), 0);
+			fog.remove_viewer(viewer);
+
+			let mut text = Vec::new();
+			fog.save(&mut text).unwrap();
+			let mut loaded = FogOfWar::<()>::new(width, height);
+			loaded.load(text.as_slice()).unwrap();
+			assert_eq!(loaded.states(RED).map(<[FogState]>::len), Some(0));
+			fog.reset(RED);
+		}
+	}
+}
@@ -0,0 +1,391 @@
+use std::{cell::Cell, ffi::CStr, ptr};
+
+use crate::{fog::FogState, *};
+
+// Soft edged fog: samples the fog texture in a 5x5 tent around each fragment, `softness` scales the kernel in texels.
+const FOG_FRAGMENT_SHADER: &CStr = c"#version 330
+in vec2 fragTexCoord;
+in vec4 fragColor;
+
+uniform sampler2D texture0;
+uniform vec4 colDiffuse;
+uniform vec2 texelSize;
+uniform float softness;
+
+out vec4 finalColor;
+
+void main()
+{
+	vec4 sum = vec4(0.0);
+	float total = 0.0;
+	for (int y = -2; y <= 2; y++)
+	{
+		for (int x = -2; x <= 2; x++)
+		{
+			float weight = (3.0 - abs(float(x)))*(3.0 - abs(float(y)));
+			sum += texture(texture0, fragTexCoord + vec2(x, y)*texelSize*softness*0.5)*weight;
+			total += weight;
+		}
+	}
+	finalColor = (sum/total)*colDiffuse*fragColor;
+}
+";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FogStyle {
+	pub color: Color,
+	// fog opacity for each state, 0.0 clear to 1.0 solid
+	pub unexplored: f32,
+	pub explored: f32,
+	pub visible: f32,
+	// opacity change per second while a tile fades between states, 0.0 disables fading
+	pub fade_speed: f32,
+	// blur radius of the soft edges in fog texels, 0.0 gives hard (bilinear) edges
+	pub softness: f32,
+}
+
+impl Default for FogStyle {
+	fn default() -> Self {
+		Self {
+			color: BLACK,
+			unexplored: 1.0,
+			explored: 0.8,
+			visible: 0.0,
+			fade_speed: 4.0,
+			softness: 1.0,
+		}
+	}
+}
+
+impl FogStyle {
+	pub fn opacity(&self, state: FogState) -> f32 {
+		match state {
+			FogState::Unexplored => self.unexplored,
+			FogState::Explored => self.explored,
+			FogState::Visible => self.visible,
+		}
+	}
+}
+
+// CPU side of the fog renderer: per tile opacity fading towards the opacity of its fog state, and the
+// pixels that get uploaded to the fog texture. Needs no GPU so it can be driven on its own.
+pub struct FogFade {
+	width: i32,
+	height: i32,
+	current: Box<[f32]>,
+	target: Box<[f32]>,
+	pixels: Box<[Color]>,
+	dirty: bool,
+}
+
+impl FogFade {
+	pub fn new(width: i32, height: i32, style: &FogStyle) -> Self {
+		let len = (width * height) as usize;
+		let mut fade = Self {
+			width,
+			height,
+			current: vec![style.unexplored; len].into_boxed_slice(),
+			target: vec![style.unexplored; len].into_boxed_slice(),
+			pixels: vec![BLANK; len].into_boxed_slice(),
+			dirty: true,
+		};
+		fade.refresh_pixels(style.color);
+		fade
+	}
+
+	pub fn width(&self) -> i32 {
+		self.width
+	}
+
+	pub fn height(&self) -> i32 {
+		self.height
+	}
+
+	// Sets the opacity every tile fades towards, `states` is row-major and must cover the whole grid.
+	pub fn set_states(&mut self, states: &[FogState], style: &FogStyle) {
+		assert_eq!(states.len(), self.target.len(), "fog states do not match the fade grid");
+		for (target, state) in self.target.iter_mut().zip(states) {
+			*target = style.opacity(*state);
+		}
+	}
+
+	// Jumps every tile straight to its target opacity.
+	pub fn snap(&mut self, style: &FogStyle) {
+		self.current.copy_from_slice(&self.target);
+		self.refresh_pixels(style.color);
+	}
+
+	// Advances the fades by `delta_time` seconds, returns true if any pixel changed.
+	pub fn update(&mut self, delta_time: f32, style: &FogStyle) -> bool {
+		if style.fade_speed <= 0.0 {
+			self.current.copy_from_slice(&self.target);
+		} else {
+			let step = style.fade_speed * delta_time;
+			for (current, target) in self.current.iter_mut().zip(self.target.iter()) {
+				if *current < *target {
+					*current = (*current + step).min(*target);
+				} else if *current > *target {
+					*current = (*current - step).max(*target);
+				}
+			}
+		}
+		self.refresh_pixels(style.color)
+	}
+
+	pub fn is_fading(&self) -> bool {
+		self.current != self.target
+	}
+
+	pub fn opacity(&self, x: i32, y: i32) -> f32 {
+		self.current[(y * self.width + x) as usize]
+	}
+
+	pub fn pixels(&self) -> &[Color] {
+		&self.pixels
+	}
+
+	// Returns whether the pixels changed since the last call.
+	pub fn take_dirty(&mut self) -> bool {
+		std::mem::take(&mut self.dirty)
+	}
+
+	fn refresh_pixels(&mut self, color: Color) -> bool {
+		let mut changed = false;
+		for (pixel, opacity) in self.pixels.iter_mut().zip(self.current.iter()) {
+			let next = Color {
+				a: (opacity.clamp(0.0, 1.0) * color.a as f32).round() as u8,
+				..color
+			};
+			if *pixel != next {
+				*pixel = next;
+				changed = true;
+			}
+		}
+		self.dirty |= changed;
+		changed
+	}
+}
+
+// GPU side: one texel per tile, uploaded only when the fade changes, drawn through the soft edge shader.
+// Must be created after `InitWindow` and only used on that thread, e.g. as a bevy `NonSend` resource.
+pub struct FogRenderer {
+	pub style: FogStyle,
+	fade: FogFade,
+	texture: Texture2D,
+	shader: Shader,
+	texel_size_loc: i32,
+	softness_loc: i32,
+	// shader id and uniform locations of the last shader given to `draw_with_shader`
+	custom_locs: Cell<Option<(u32, i32, i32)>>,
+}
+
+impl Drop for FogRenderer {
+	fn drop(&mut self) {
+		unsafe {
+			UnloadShader(self.shader);
+			UnloadTexture(self.texture);
+		}
+	}
+}
+
+impl FogRenderer {
+	pub fn new(width: i32, height: i32, style: FogStyle) -> Self {
+		unsafe {
+			let mut fade = FogFade::new(width, height, &style);
+			let image = Image {
+				data: fade.pixels.as_mut_ptr() as *mut _,
+				width,
+				height,
+				mipmaps: 1,
+				format: PixelFormat_PIXELFORMAT_UNCOMPRESSED_R8G8B8A8,
+			};
+			let texture = LoadTextureFromImage(image);
+			SetTextureFilter(texture, TextureFilter_TEXTURE_FILTER_BILINEAR);
+			SetTextureWrap(texture, TextureWrap_TEXTURE_WRAP_CLAMP);
+			fade.take_dirty();
+
+			let shader = LoadShaderFromMemory(ptr::null(), FOG_FRAGMENT_SHADER.as_ptr());
+			Self {
+				style,
+				fade,
+				texture,
+				texel_size_loc: GetShaderLocation(shader, c"texelSize".as_ptr()),
+				softness_loc: GetShaderLocation(shader, c"softness".as_ptr()),
+				shader,
+				custom_locs: Cell::new(None),
+			}
+		}
+	}
+
+	pub fn fade(&self) -> &FogFade {
+		&self.fade
+	}
+
+	// Retargets the fade when the fog changed, advances it and uploads the texture if any pixel changed.
+	pub fn update(&mut self, states: Option<&[FogState]>, delta_time: f32) {
+		if let Some(states) = states {
+			self.fade.set_states(states, &self.style);
+		}
+		self.fade.update(delta_time, &self.style);
+		self.upload();
+	}
+
+	// Shows the fog state immediately, e.g. after loading a save.
+	pub fn snap(&mut self, states: &[FogState]) {
+		self.fade.set_states(states, &self.style);
+		self.fade.snap(&self.style);
+		self.upload();
+	}
+
+	// Draws the fog stretched over `dest`, normally the whole map in world units.
+	pub fn draw(&self, dest: Rectangle) {
+		self.draw_shaded(dest, self.shader, self.texel_size_loc, self.softness_loc);
+	}
+
+	// Draws with another fog shader, e.g. one loaded through `Assets` so edits to it show up while
+	// the game runs. It gets the same `texelSize` and `softness` uniforms, their locations are
+	// looked up when the shader is first drawn and again after a reload gives it a new id.
+	pub fn draw_with_shader(&self, dest: Rectangle, shader: Shader) {
+		let (_, texel_size_loc, softness_loc) = match self.custom_locs.get() {
+			Some(locs) if locs.0 == shader.id => locs,
+			_ => {
+				let locs = unsafe {
+					(
+						shader.id,
+						GetShaderLocation(shader, c"texelSize".as_ptr()),
+						GetShaderLocation(shader, c"softness".as_ptr()),
+					)
+				};
+				self.custom_locs.set(Some(locs));
+				locs
+			}
+		};
+		self.draw_shaded(dest, shader, texel_size_loc, softness_loc);
+	}
+
+	fn draw_shaded(&self, dest: Rectangle, shader: Shader, texel_size_loc: i32, softness_loc: i32) {
+		unsafe {
+			let texel_size = [1.0 / self.fade.width as f32, 1.0 / self.fade.height as f32];
+			SetShaderValue(
+				shader,
+				texel_size_loc,
+				texel_size.as_ptr() as *const _,
+				ShaderUniformDataType_SHADER_UNIFORM_VEC2,
+			);
+			SetShaderValue(
+				shader,
+				softness_loc,
+				&self.style.softness as *const f32 as *const _,
+				ShaderUniformDataType_SHADER_UNIFORM_FLOAT,
+			);
+
+			BeginShaderMode(shader);
+			DrawTexturePro(
+				self.texture,
+				Rectangle {
+					x: 0.0,
+					y: 0.0,
+					width: self.texture.width as f32,
+					height: self.texture.height as f32,
+				},
+				dest,
+				Vector2 { x: 0.0, y: 0.0 },
+				0.0,
+				WHITE,
+			);
+			EndShaderMode();
+		}
+	}
+
+	fn upload(&mut self) {
+		if self.fade.take_dirty() {
+			unsafe { UpdateTexture(self.texture, self.fade.pixels.as_ptr() as *const _) };
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const STYLE: FogStyle = FogStyle {
+		color: BLACK,
+		unexplored: 1.0,
+		explored: 0.5,
+		visible: 0.0,
+		fade_speed: 2.0,
+		softness: 1.0,
+	};
+
+	#[test]
+	fn starts_unexplored() {
+		let mut fade = FogFade::new(3, 2, &STYLE);
+		assert_eq!((fade.width(), fade.height()), (3, 2));
+		assert!(fade.pixels().iter().all(|pixel| *pixel == BLACK));
+		assert!(fade.take_dirty());
+		assert!(!fade.take_dirty());
+		assert!(!fade.is_fading());
+	}
+
+	#[test]
+	fn fades_towards_the_state_opacity() {
+		let mut fade = FogFade::new(2, 1, &STYLE);
+		fade.take_dirty();
+		fade.set_states(&[FogState::Visible, FogState::Explored], &STYLE);
+		assert!(fade.is_fading());
+
+		// 2.0 per second, a quarter second in
+		assert!(fade.update(0.25, &STYLE));
+		assert_eq!(fade.opacity(0, 0), 0.5);
+		assert_eq!(fade.opacity(1, 0), 0.5);
+		assert_eq!(fade.pixels()[0].a, 128);
+		assert!(fade.take_dirty());
+
+		// the explored tile is done and stays put, the visible one keeps going and stops at 0.0
+		fade.update(1.0, &STYLE);
+		assert_eq!(fade.opacity(0, 0), 0.0);
+		assert_eq!(fade.opacity(1, 0), 0.5);
+		assert!(!fade.is_fading());
+		assert_eq!(fade.pixels()[0], BLANK);
+		assert!(!fade.update(1.0, &STYLE));
+
+		// back up again
+		fade.set_states(&[FogState::Explored, FogState::Explored], &STYLE);
+		fade.update(0.1, &STYLE);
+		assert!((fade.opacity(0, 0) - 0.2).abs() < 1e-6);
+	}
+
+	#[test]
+	fn snap_and_no_fade_speed_jump_to_the_target() {
+		let mut fade = FogFade::new(1, 1, &STYLE);
+		fade.set_states(&[FogState::Visible], &STYLE);
+		fade.snap(&STYLE);
+		assert_eq!(fade.opacity(0, 0), 0.0);
+		assert!(!fade.is_fading());
+
+		let instant = FogStyle { fade_speed: 0.0, ..STYLE };
+		fade.set_states(&[FogState::Explored], &instant);
+		assert!(fade.update(0.0, &instant));
+		assert_eq!(fade.opacity(0, 0), 0.5);
+	}
+
+	#[test]
+	fn pixels_use_the_fog_color() {
+		let style = FogStyle {
+			color: Color { r: 10, g: 20, b: 30, a: 200 },
+			..STYLE
+		};
+		let mut fade = FogFade::new(1, 1, &style);
+		assert_eq!(fade.pixels()[0], Color { r: 10, g: 20, b: 30, a: 200 });
+		fade.set_states(&[FogState::Explored], &style);
+		fade.snap(&style);
+		assert_eq!(fade.pixels()[0].a, 100);
+	}
+
+	#[test]
+	#[should_panic(expected = "fog states do not match")]
+	fn states_must_cover_the_grid() {
+		let mut fade = FogFade::new(2, 2, &STYLE);
+		fade.set_states(&[FogState::Visible], &STYLE);
+	}
+}
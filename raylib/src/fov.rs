@@ -0,0 +1,503 @@
+use crate::tilemap::TileMap;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FovAlgorithm {
+	// recursive symmetric shadowcasting, walls block sight
+	Shadowcasting,
+	// bresenham lines from the origin to the edge of the radius, walls block sight
+	Raycast,
+	// a tile is visible when any line between points of it and of the origin tile misses every wall,
+	// sees further around corners and pillars than shadowcasting
+	Permissive,
+	// every tile inside the radius, walls are ignored
+	Radius,
+}
+
+// Set of visible tiles for a map of a given size.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct VisibleTiles {
+	width: i32,
+	height: i32,
+	tiles: Box<[bool]>,
+}
+
+impl VisibleTiles {
+	pub fn new(width: i32, height: i32) -> Self {
+		Self {
+			width,
+			height,
+			tiles: vec![false; (width.max(0) * height.max(0)) as usize].into_boxed_slice(),
+		}
+	}
+
+	pub fn clear(&mut self) {
+		self.tiles.fill(false);
+	}
+
+	pub fn insert(&mut self, x: i32, y: i32) {
+		if self.in_bounds(x, y) {
+			let i = self.index(x, y);
+			self.tiles[i] = true;
+		}
+	}
+
+	pub fn contains(&self, x: i32, y: i32) -> bool {
+		self.in_bounds(x, y) && self.tiles[self.index(x, y)]
+	}
+
+	pub fn len(&self) -> usize {
+		self.tiles.iter().filter(|visible| **visible).count()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		!self.tiles.contains(&true)
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+		let width = self.width;
+		self
+			.tiles
+			.iter()
+			.enumerate()
+			.filter(|(_, visible)| **visible)
+			.map(move |(i, _)| (i as i32 % width, i as i32 / width))
+	}
+}
+
+impl TileMap for VisibleTiles {
+	fn width(&self) -> i32 {
+		self.width
+	}
+
+	fn height(&self) -> i32 {
+		self.height
+	}
+}
+
+// Computes the tiles visible from `origin` within `radius` tiles. Tiles outside the map are treated as opaque.
+pub fn compute_fov<M, F>(map: &M, origin: (i32, i32), radius: i32, algorithm: FovAlgorithm, is_opaque: F) -> VisibleTiles
+where
+	M: TileMap + ?Sized,
+	F: Fn(i32, i32) -> bool,
+{
+	let mut visible = VisibleTiles::new(map.width(), map.height());
+	compute_fov_into(map, origin, radius, algorithm, is_opaque, &mut visible);
+	visible
+}
+
+// Same as `compute_fov` but reuses an existing set, which is cleared first.
+pub fn compute_fov_into<M, F>(map: &M, origin: (i32, i32), radius: i32, algorithm: FovAlgorithm, is_opaque: F, visible: &mut VisibleTiles)
+where
+	M: TileMap + ?Sized,
+	F: Fn(i32, i32) -> bool,
+{
+	visible.clear();
+	if !map.in_bounds(origin.0, origin.1) || radius < 0 {
+		return;
+	}
+
+	let is_opaque = |x: i32, y: i32| !map.in_bounds(x, y) || is_opaque(x, y);
+	match algorithm {
+		FovAlgorithm::Shadowcasting => shadowcast(origin, radius, &is_opaque, visible),
+		FovAlgorithm::Raycast => raycast(origin, radius, &is_opaque, visible),
+		FovAlgorithm::Permissive => permissive(origin, radius, &is_opaque, visible),
+		FovAlgorithm::Radius => circle(map, origin, radius, visible),
+	}
+}
+
+// circular falloff, the extra `radius` keeps single tiles from poking out on the axes
+fn in_radius(dx: i32, dy: i32, radius: i32) -> bool {
+	dx * dx + dy * dy <= radius * radius + radius
+}
+
+fn circle<M: TileMap + ?Sized>(map: &M, origin: (i32, i32), radius: i32, visible: &mut VisibleTiles) {
+	for y in (origin.1 - radius)..=(origin.1 + radius) {
+		for x in (origin.0 - radius)..=(origin.0 + radius) {
+			if map.in_bounds(x, y) && in_radius(x - origin.0, y - origin.1, radius) {
+				visible.insert(x, y);
+			}
+		}
+	}
+}
+
+fn raycast(origin: (i32, i32), radius: i32, is_opaque: &dyn Fn(i32, i32) -> bool, visible: &mut VisibleTiles) {
+	visible.insert(origin.0, origin.1);
+	for i in -radius..=radius {
+		cast_ray(origin, (origin.0 + i, origin.1 - radius), radius, is_opaque, visible);
+		cast_ray(origin, (origin.0 + i, origin.1 + radius), radius, is_opaque, visible);
+		cast_ray(origin, (origin.0 - radius, origin.1 + i), radius, is_opaque, visible);
+		cast_ray(origin, (origin.0 + radius, origin.1 + i), radius, is_opaque, visible);
+	}
+}
+
+// bresenham walk from origin towards target, the first opaque tile is visible and ends the ray
+fn cast_ray(origin: (i32, i32), target: (i32, i32), radius: i32, is_opaque: &dyn Fn(i32, i32) -> bool, visible: &mut VisibleTiles) {
+	let (mut x, mut y) = origin;
+	let dx = (target.0 - origin.0).abs();
+	let dy = -(target.1 - origin.1).abs();
+	let sx = if origin.0 < target.0 { 1 } else { -1 };
+	let sy = if origin.1 < target.1 { 1 } else { -1 };
+	let mut err = dx + dy;
+
+	while (x, y) != target {
+		let e2 = 2 * err;
+		if e2 >= dy {
+			err += dy;
+			x += sx;
+		}
+		if e2 <= dx {
+			err += dx;
+			y += sy;
+		}
+
+		if !in_radius(x - origin.0, y - origin.1, radius) {
+			return;
+		}
+		visible.insert(x, y);
+		if is_opaque(x, y) {
+			return;
+		}
+	}
+}
+
+// Points inside a tile that the permissive lines run between, in 1/8 tile units: the center and
+// near each corner. Lines through them never run along a tile edge.
+const SUBTILE: i32 = 8;
+const SAMPLES: [(i32, i32); 5] = [(4, 4), (1, 1), (7, 1), (1, 7), (7, 7)];
+
+fn permissive(origin: (i32, i32), radius: i32, is_opaque: &dyn Fn(i32, i32) -> bool, visible: &mut VisibleTiles) {
+	visible.insert(origin.0, origin.1);
+	for y in (origin.1 - radius)..=(origin.1 + radius) {
+		for x in (origin.0 - radius)..=(origin.0 + radius) {
+			if (x, y) == origin || !in_radius(x - origin.0, y - origin.1, radius) {
+				continue;
+			}
+			let seen = SAMPLES.iter().any(|from| {
+				SAMPLES.iter().any(|to| {
+					let from = (origin.0 * SUBTILE + from.0, origin.1 * SUBTILE + from.1);
+					let to = (x * SUBTILE + to.0, y * SUBTILE + to.1);
+					is_clear(from, to, is_opaque)
+				})
+			});
+			if seen {
+				visible.insert(x, y);
+			}
+		}
+	}
+}
+
+// Walks the tiles the segment passes through, in subtile units, and checks the ones between its
+// end tiles. Integer only, so a line and its reverse cross the same tiles. A line through a tile
+// corner passes diagonally between the two tiles beside it.
+fn is_clear(from: (i32, i32), to: (i32, i32), is_opaque: &dyn Fn(i32, i32) -> bool) -> bool {
+	let mut tile = (from.0.div_euclid(SUBTILE), from.1.div_euclid(SUBTILE));
+	let end = (to.0.div_euclid(SUBTILE), to.1.div_euclid(SUBTILE));
+	let (dx, dy) = ((to.0 - from.0).abs(), (to.1 - from.1).abs());
+	let (sx, sy) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+	// distance along each axis to the next tile edge, the line crosses the x edge first while
+	// next_x / dx < next_y / dy
+	let to_edge = |position: i32, step: i32| match step {
+		1 => SUBTILE - position.rem_euclid(SUBTILE),
+		_ => position.rem_euclid(SUBTILE),
+	};
+	let mut next_x = to_edge(from.0, sx);
+	let mut next_y = to_edge(from.1, sy);
+
+	while tile != end {
+		let (cross_x, cross_y) = if dy == 0 {
+			(true, false)
+		} else if dx == 0 {
+			(false, true)
+		} else {
+			let x_at = next_x * dy;
+			let y_at = next_y * dx;
+			(x_at <= y_at, y_at <= x_at)
+		};
+		if cross_x {
+			tile.0 += sx;
+			next_x += SUBTILE;
+		}
+		if cross_y {
+			tile.1 += sy;
+			next_y += SUBTILE;
+		}
+		if tile != end && is_opaque(tile.0, tile.1) {
+			return false;
+		}
+	}
+	true
+}
+
+// Symmetric shadowcasting, see https://www.albertford.com/shadowcasting/
+// Slopes are kept as exact fractions so the result does not depend on float rounding.
+#[derive(Clone, Copy)]
+struct Slope {
+	num: i32,
+	den: i32,
+}
+
+impl Slope {
+	fn new(num: i32, den: i32) -> Self {
+		Self { num, den }
+	}
+
+	// depth * slope rounded to the nearest column, ties go up
+	fn round_up(self, depth: i32) -> i32 {
+		(2 * depth * self.num + self.den).div_euclid(2 * self.den)
+	}
+
+	// depth * slope rounded to the nearest column, ties go down
+	fn round_down(self, depth: i32) -> i32 {
+		-(self.den - 2 * depth * self.num).div_euclid(2 * self.den)
+	}
+}
+
+#[derive(Clone, Copy)]
+enum Quadrant {
+	North,
+	East,
+	South,
+	West,
+}
+
+impl Quadrant {
+	fn transform(self, origin: (i32, i32), depth: i32, col: i32) -> (i32, i32) {
+		match self {
+			Quadrant::North => (origin.0 + col, origin.1 - depth),
+			Quadrant::South => (origin.0 + col, origin.1 + depth),
+			Quadrant::East => (origin.0 + depth, origin.1 + col),
+			Quadrant::West => (origin.0 - depth, origin.1 + col),
+		}
+	}
+}
+
+struct Row {
+	depth: i32,
+	start: Slope,
+	end: Slope,
+}
+
+impl Row {
+	fn next(&self) -> Row {
+		Row {
+			depth: self.depth + 1,
+			start: self.start,
+			end: self.end,
+		}
+	}
+
+	fn is_symmetric(&self, col: i32) -> bool {
+		col * self.start.den >= self.depth * self.start.num && col * self.end.den <= self.depth * self.end.num
+	}
+}
+
+fn shadowcast(origin: (i32, i32), radius: i32, is_opaque: &dyn Fn(i32, i32) -> bool, visible: &mut VisibleTiles) {
+	visible.insert(origin.0, origin.1);
+	for quadrant in [Quadrant::North, Quadrant::East, Quadrant::South, Quadrant::West] {
+		let row = Row {
+			depth: 1,
+			start: Slope::new(-1, 1),
+			end: Slope::new(1, 1),
+		};
+		scan(quadrant, origin, radius, row, is_opaque, visible);
+	}
+}
+
+fn scan(quadrant: Quadrant, origin: (i32, i32), radius: i32, mut row: Row, is_opaque: &dyn Fn(i32, i32) -> bool, visible: &mut VisibleTiles) {
+	if row.depth > radius {
+		return;
+	}
+
+	let mut prev_opaque = None;
+	for col in row.start.round_up(row.depth)..=row.end.round_down(row.depth) {
+		let (x, y) = quadrant.transform(origin, row.depth, col);
+		let opaque = is_opaque(x, y);
+
+		if (opaque || row.is_symmetric(col)) && in_radius(col, row.depth, radius) {
+			visible.insert(x, y);
+		}
+		if prev_opaque == Some(true) && !opaque {
+			row.start = Slope::new(2 * col - 1, 2 * row.depth);
+		}
+		if prev_opaque == Some(false) && opaque {
+			let mut next = row.next();
+			next.end = Slope::new(2 * col - 1, 2 * row.depth);
+			scan(quadrant, origin, radius, next, is_opaque, visible);
+		}
+		prev_opaque = Some(opaque);
+	}
+
+	if prev_opaque == Some(false) {
+		scan(quadrant, origin, radius, row.next(), is_opaque, visible);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const ALL: [FovAlgorithm; 4] = [
+		FovAlgorithm::Shadowcasting,
+		FovAlgorithm::Raycast,
+		FovAlgorithm::Permissive,
+		FovAlgorithm::Radius,
+	];
+
+	// '#' is a wall
+	struct Fixture {
+		width: i32,
+		height: i32,
+		walls: Vec<bool>,
+	}
+
+	impl Fixture {
+		fn new(rows: &[&str]) -> Self {
+			Self {
+				width: rows[0].len() as i32,
+				height: rows.len() as i32,
+				walls: rows.iter().flat_map(|row| row.chars().map(|c| c == '#')).collect(),
+			}
+		}
+
+		fn is_wall(&self, x: i32, y: i32) -> bool {
+			self.walls[(y * self.width + x) as usize]
+		}
+
+		fn fov(&self, origin: (i32, i32), radius: i32, algorithm: FovAlgorithm) -> VisibleTiles {
+			compute_fov(self, origin, radius, algorithm, |x, y| self.is_wall(x, y))
+		}
+
+		fn floors(&self) -> Vec<(i32, i32)> {
+			(0..self.height)
+				.flat_map(|y| (0..self.width).map(move |x| (x, y)))
+				.filter(|&(x, y)| !self.is_wall(x, y))
+				.collect()
+		}
+	}
+
+	impl TileMap for Fixture {
+		fn width(&self) -> i32 {
+			self.width
+		}
+
+		fn height(&self) -> i32 {
+			self.height
+		}
+	}
+
+	fn pillars() -> Fixture {
+		Fixture::new(&[
+			"...........",
+			"..#.....#..",
+			"...........",
+			"....#.#....",
+			"...........",
+			"..#..#..#..",
+			"...........",
+		])
+	}
+
+	fn rooms() -> Fixture {
+		Fixture::new(&[
+			"#########",
+			"#...#...#",
+			"#...#...#",
+			"#.......#",
+			"#...#...#",
+			"###.#####",
+			"#.......#",
+			"#########",
+		])
+	}
+
+	#[test]
+	fn symmetric_algorithms_are_symmetric() {
+		for algorithm in [FovAlgorithm::Shadowcasting, FovAlgorithm::Permissive] {
+			for map in [pillars(), rooms()] {
+				let floors = map.floors();
+				let views: Vec<VisibleTiles> = floors.iter().map(|&origin| map.fov(origin, 6, algorithm)).collect();
+				for (a, view_a) in floors.iter().zip(&views) {
+					for (b, view_b) in floors.iter().zip(&views) {
+						assert_eq!(view_a.contains(b.0, b.1), view_b.contains(a.0, a.1), "{algorithm:?} {a:?} {b:?}");
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn open_ground_is_all_visible() {
+		let map = Fixture::new(&["......."; 7]);
+		for algorithm in ALL {
+			let visible = map.fov((3, 3), 2, algorithm);
+			assert_eq!(visible, map.fov((3, 3), 2, FovAlgorithm::Radius), "{algorithm:?}");
+		}
+	}
+
+	#[test]
+	fn radius_is_round_and_ignores_walls() {
+		let map = Fixture::new(&["#######"; 7]);
+		let visible = map.fov((3, 3), 2, FovAlgorithm::Radius);
+		// 5 + 5 + 5 columns of the inner square plus 3 each on the sides, no corners
+		assert_eq!(visible.len(), 21);
+		assert!(visible.contains(5, 3) && visible.contains(5, 4) && visible.contains(3, 1));
+		assert!(!visible.contains(5, 5) && !visible.contains(1, 1));
+
+		// clipped at the map edge
+		assert_eq!(map.fov((0, 0), 2, FovAlgorithm::Radius).len(), 8);
+	}
+
+	#[test]
+	fn walls_block_sight() {
+		let map = Fixture::new(&[".....", ".....", "..#..", ".....", "....."]);
+		for algorithm in [FovAlgorithm::Shadowcasting, FovAlgorithm::Raycast, FovAlgorithm::Permissive] {
+			let visible = map.fov((2, 4), 4, algorithm);
+			// the wall itself shows, the tile straight behind it does not
+			assert!(visible.contains(2, 2), "{algorithm:?}");
+			assert!(!visible.contains(2, 0), "{algorithm:?}");
+			assert!(visible.contains(0, 0) && visible.contains(4, 0), "{algorithm:?}");
+		}
+	}
+
+	#[test]
+	fn raycast_stops_at_the_first_wall() {
+		let map = Fixture::new(&["..#.#.."]);
+		let visible = map.fov((0, 0), 6, FovAlgorithm::Raycast);
+		assert_eq!(visible.iter().collect::<Vec<_>>(), [(0, 0), (1, 0), (2, 0)]);
+	}
+
+	#[test]
+	fn closed_rooms_stay_hidden() {
+		let map = rooms();
+		for algorithm in [FovAlgorithm::Shadowcasting, FovAlgorithm::Raycast, FovAlgorithm::Permissive] {
+			let visible = map.fov((2, 2), 8, algorithm);
+			// the other room is only visible through the doorway at (4, 3)
+			assert!(visible.contains(1, 1) && visible.contains(4, 1), "{algorithm:?}");
+			assert!(!visible.contains(6, 1), "{algorithm:?}");
+			// the corridor below is behind the wall
+			assert!(!visible.contains(1, 6) && !visible.contains(7, 6), "{algorithm:?}");
+		}
+	}
+
+	#[test]
+	fn permissive_sees_around_pillars() {
+		let map = pillars();
+		let total = |algorithm| map.floors().iter().map(|&origin| map.fov(origin, 5, algorithm).len()).sum::<usize>();
+		assert!(total(FovAlgorithm::Permissive) > total(FovAlgorithm::Shadowcasting));
+
+		// past the near edge of a wall, only the permissive lines get through
+		let map = Fixture::new(&["....", "..#."]);
+		assert!(!map.fov((0, 0), 4, FovAlgorithm::Shadowcasting).contains(3, 1));
+		assert!(map.fov((0, 0), 4, FovAlgorithm::Permissive).contains(3, 1));
+	}
+
+	#[test]
+	fn nothing_from_outside_or_with_negative_radius() {
+		let map = pillars();
+		for algorithm in ALL {
+			assert!(map.fov((-1, 0), 3, algorithm).is_empty());
+			assert!(map.fov((0, 0), -1, algorithm).is_empty());
+			assert_eq!(map.fov((0, 0), 0, algorithm).iter().collect::<Vec<_>>(), [(0, 0)]);
+		}
+	}
+}
@@ -0,0 +1,337 @@
+use std::time::{Duration, Instant};
+
+// Monotonic time source for the loop, swap it for `ManualClock` to drive a loop deterministically.
+pub trait Clock {
+	// time since some fixed point, must never go backwards
+	fn now(&mut self) -> Duration;
+}
+
+pub struct SystemClock {
+	start: Instant,
+}
+
+impl Default for SystemClock {
+	fn default() -> Self {
+		Self { start: Instant::now() }
+	}
+}
+
+impl Clock for SystemClock {
+	fn now(&mut self) -> Duration {
+		self.start.elapsed()
+	}
+}
+
+// Clock that only moves when told to.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ManualClock {
+	pub now: Duration,
+}
+
+impl ManualClock {
+	pub fn advance(&mut self, duration: Duration) {
+		self.now += duration;
+	}
+}
+
+impl Clock for ManualClock {
+	fn now(&mut self) -> Duration {
+		self.now
+	}
+}
+
+// The three phases of a frame, see `GameLoop::run_frame`.
+pub trait Game {
+	// called zero or more times per frame with the fixed timestep in seconds
+	fn fixed_update(&mut self, fixed_delta: f32);
+	// called once per frame with the scaled frame time in seconds
+	fn update(&mut self, delta: f32);
+	// `alpha` is how far the game is between the last two fixed updates, 0..1, for interpolating what is drawn
+	fn render(&mut self, alpha: f32);
+}
+
+// What a frame has to run, returned by `GameLoop::advance`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Frame {
+	pub fixed_steps: u32,
+	pub fixed_delta: f32,
+	// frame time after pause and time scale
+	pub delta: f32,
+	// wall clock frame time, not capped or scaled
+	pub real_delta: f32,
+	pub alpha: f32,
+}
+
+// fast enough for skipping ahead, and far from overflowing a `Duration` with the longest frames
+pub const MAX_TIME_SCALE: f32 = 1000.0;
+
+pub struct GameLoop<K: Clock = SystemClock> {
+	pub clock: K,
+	pub timestep: Duration,
+	// longer frames are cut to this, e.g. after a breakpoint or while the window is dragged
+	pub max_frame_time: Duration,
+	// fixed updates allowed per frame before the leftover time is dropped (spiral of death protection)
+	pub max_fixed_steps: u32,
+	time_scale: f32,
+	paused: bool,
+	pending_steps: u32,
+	accumulator: Duration,
+	last: Option<Duration>,
+	frames: u64,
+	fixed_steps: u64,
+}
+
+impl GameLoop<SystemClock> {
+	pub fn new(timestep: Duration) -> Self {
+		Self::with_clock(timestep, SystemClock::default())
+	}
+}
+
+impl<K: Clock> GameLoop<K> {
+	pub fn with_clock(timestep: Duration, clock: K) -> Self {
+		Self {
+			clock,
+			timestep,
+			max_frame_time: Duration::from_millis(250),
+			max_fixed_steps: 8,
+			time_scale: 1.0,
+			paused: false,
+			pending_steps: 0,
+			accumulator: Duration::ZERO,
+			last: None,
+			frames: 0,
+			fixed_steps: 0,
+		}
+	}
+
+	pub fn time_scale(&self) -> f32 {
+		self.time_scale
+	}
+
+	// Scales the frame time, clamped to 0..=MAX_TIME_SCALE. NaN counts as 0.
+	pub fn set_time_scale(&mut self, time_scale: f32) {
+		self.time_scale = if time_scale.is_nan() {
+			0.0
+		} else {
+			time_scale.clamp(0.0, MAX_TIME_SCALE)
+		};
+	}
+
+	pub fn is_paused(&self) -> bool {
+		self.paused
+	}
+
+	// While paused no fixed updates run and `update` gets a zero delta, rendering goes on.
+	pub fn set_paused(&mut self, paused: bool) {
+		self.paused = paused;
+		self.accumulator = Duration::ZERO;
+	}
+
+	// Runs a single fixed update on the next frame while paused, for stepping through the simulation.
+	pub fn step(&mut self) {
+		if self.paused {
+			self.pending_steps += 1;
+		}
+	}
+
+	pub fn frames(&self) -> u64 {
+		self.frames
+	}
+
+	pub fn fixed_steps(&self) -> u64 {
+		self.fixed_steps
+	}
+
+	// Measures the time since the previous call (the whole previous frame) and works out the fixed steps.
+	pub fn advance(&mut self) -> Frame {
+		let now = self.clock.now();
+		let real = self.last.map_or(Duration::ZERO, |last| now.saturating_sub(last));
+		self.last = Some(now);
+		self.frames += 1;
+
+		let fixed_delta = self.timestep.as_secs_f32();
+		if self.paused {
+			let steps = std::mem::take(&mut self.pending_steps);
+			self.fixed_steps += steps as u64;
+			return Frame {
+				fixed_steps: steps,
+				fixed_delta,
+				delta: 0.0,
+				real_delta: real.as_secs_f32(),
+				alpha: 0.0,
+			};
+		}
+
+		let scaled = real.min(self.max_frame_time).mul_f32(self.time_scale);
+		self.accumulator += scaled;
+
+		let mut steps = 0;
+		while self.accumulator >= self.timestep && !self.timestep.is_zero() {
+			if steps == self.max_fixed_steps {
+				// can't keep up, drop the backlog instead of falling further behind every frame
+				self.accumulator = Duration::from_nanos((self.accumulator.as_nanos() % self.timestep.as_nanos()) as u64);
+				break;
+			}
+			self.accumulator -= self.timestep;
+			steps += 1;
+		}
+		self.fixed_steps += steps as u64;
+
+		Frame {
+			fixed_steps: steps,
+			fixed_delta,
+			delta: scaled.as_secs_f32(),
+			real_delta: real.as_secs_f32(),
+			alpha: if self.timestep.is_zero() {
+				0.0
+			} else {
+				self.accumulator.as_secs_f32() / fixed_delta
+			},
+		}
+	}
+
+	// Advances the loop and runs the fixed updates, the variable update and the render of one frame.
+	pub fn run_frame(&mut self, game: &mut impl Game) -> Frame {
+		let frame = self.advance();
+		for _ in 0..frame.fixed_steps {
+			game.fixed_update(frame.fixed_delta);
+		}
+		game.update(frame.delta);
+		game.render(frame.alpha);
+		frame
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// 1/128 s, frame times below are multiples of 1/256 s so every conversion is exact
+	const TIMESTEP: Duration = Duration::from_nanos(7_812_500);
+
+	fn frame(game_loop: &mut GameLoop<ManualClock>, nanos: u64) -> Frame {
+		game_loop.clock.advance(Duration::from_nanos(nanos));
+		game_loop.advance()
+	}
+
+	// a loop past its first frame, which has nothing to measure
+	fn started() -> GameLoop<ManualClock> {
+		let mut game_loop = GameLoop::with_clock(TIMESTEP, ManualClock::default());
+		assert_eq!(game_loop.advance().fixed_steps, 0);
+		game_loop
+	}
+
+	#[test]
+	fn accumulates_leftover_time_into_later_steps() {
+		let mut game_loop = started();
+		// 1.5 timesteps per frame
+		let steps: Vec<u32> = (0..6).map(|_| frame(&mut game_loop, 11_718_750).fixed_steps).collect();
+		assert_eq!(steps, [1, 2, 1, 2, 1, 2]);
+		assert_eq!(game_loop.fixed_steps(), 9);
+		assert_eq!(game_loop.frames(), 7);
+
+		let frame = frame(&mut game_loop, 11_718_750);
+		assert_eq!(frame.alpha, 0.5);
+		assert_eq!(frame.fixed_delta, 0.0078125);
+		assert_eq!(frame.delta, 0.01171875);
+	}
+
+	#[test]
+	fn long_frames_are_capped_and_the_backlog_dropped() {
+		let mut game_loop = started();
+		game_loop.max_frame_time = Duration::from_nanos(97_656_250);
+
+		// a second is cut to 12.5 steps, of which 8 run and the half step stays
+		let long = frame(&mut game_loop, 1_000_000_000);
+		assert_eq!(long.fixed_steps, 8);
+		assert_eq!(long.real_delta, 1.0);
+		assert_eq!(long.delta, 0.09765625);
+		assert_eq!(long.alpha, 0.5);
+
+		// back to normal right away
+		assert_eq!(frame(&mut game_loop, 7_812_500).fixed_steps, 1);
+		assert_eq!(frame(&mut game_loop, 7_812_500).fixed_steps, 1);
+	}
+
+	#[test]
+	fn pause_stops_fixed_updates_until_stepped() {
+		let mut game_loop = started();
+		// half a step waiting in the accumulator is dropped by pausing
+		frame(&mut game_loop, 3_906_250);
+		game_loop.set_paused(true);
+		assert!(game_loop.is_paused());
+
+		let paused = frame(&mut game_loop, 15_625_000);
+		assert_eq!((paused.fixed_steps, paused.delta, paused.real_delta), (0, 0.0, 0.015625));
+
+		game_loop.step();
+		game_loop.step();
+		assert_eq!(frame(&mut game_loop, 15_625_000).fixed_steps, 2);
+		assert_eq!(frame(&mut game_loop, 15_625_000).fixed_steps, 0);
+
+		game_loop.set_paused(false);
+		// stepping does nothing while running
+		game_loop.step();
+		assert_eq!(frame(&mut game_loop, 3_906_250).fixed_steps, 0);
+		assert_eq!(frame(&mut game_loop, 3_906_250).fixed_steps, 1);
+		assert_eq!(game_loop.fixed_steps(), 3);
+	}
+
+	#[test]
+	fn time_scale_stretches_game_time() {
+		let mut game_loop = started();
+		game_loop.set_time_scale(0.5);
+		let slow = frame(&mut game_loop, 15_625_000);
+		assert_eq!((slow.fixed_steps, slow.delta, slow.real_delta), (1, 0.0078125, 0.015625));
+
+		game_loop.set_time_scale(2.0);
+		assert_eq!(frame(&mut game_loop, 15_625_000).fixed_steps, 4);
+
+		game_loop.set_time_scale(0.0);
+		let stopped = frame(&mut game_loop, 15_625_000);
+		assert_eq!((stopped.fixed_steps, stopped.delta), (0, 0.0));
+	}
+
+	#[test]
+	fn time_scale_is_clamped() {
+		let mut game_loop = started();
+		for (scale, expected) in [
+			(-1.0, 0.0),
+			(f32::NAN, 0.0),
+			(f32::NEG_INFINITY, 0.0),
+			(f32::INFINITY, MAX_TIME_SCALE),
+			(f32::MAX, MAX_TIME_SCALE),
+		] {
+			game_loop.set_time_scale(scale);
+			assert_eq!(game_loop.time_scale(), expected);
+			// the longest frame at the largest scale still fits
+			frame(&mut game_loop, 1_000_000_000);
+		}
+	}
+
+	#[derive(Default)]
+	struct Calls(Vec<String>);
+
+	impl Game for Calls {
+		fn fixed_update(&mut self, fixed_delta: f32) {
+			self.0.push(format!("fixed {fixed_delta}"));
+		}
+
+		fn update(&mut self, delta: f32) {
+			self.0.push(format!("update {delta}"));
+		}
+
+		fn render(&mut self, alpha: f32) {
+			self.0.push(format!("render {alpha}"));
+		}
+	}
+
+	#[test]
+	fn run_frame_calls_the_phases_in_order() {
+		let mut game_loop = started();
+		let mut calls = Calls::default();
+		game_loop.clock.advance(Duration::from_nanos(19_531_250));
+		game_loop.run_frame(&mut calls);
+		assert_eq!(calls.0, ["fixed 0.0078125", "fixed 0.0078125", "update 0.01953125", "render 0.5"]);
+	}
+}
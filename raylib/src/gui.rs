@@ -0,0 +1,1153 @@
+use std::ffi::CString;
+
+use crate::{backend::Backend, raster, recording::DrawCommand, *};
+
+// Navigation the widgets understand, from the keyboard or a gamepad.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GuiKey {
+	// focus the next / previous widget
+	Next,
+	Previous,
+	// move the focus, or the selection in lists and open dropdowns
+	Up,
+	Down,
+	// adjust sliders
+	Left,
+	Right,
+	// press the focused button, toggle checkboxes, open dropdowns
+	Activate,
+	// close dropdowns and drop the focus
+	Back,
+	Backspace,
+}
+
+// Everything the GUI reads in one frame. `capture` fills it from the window, tests build it by hand.
+#[derive(Clone, Default, Debug)]
+pub struct GuiInput {
+	pub mouse: Vector2,
+	pub mouse_down: bool,
+	// wheel movement this frame, positive scrolls up
+	pub wheel: f32,
+	// characters typed this frame
+	pub text: String,
+	// keys pressed this frame, held keys repeat
+	pub keys: Vec<GuiKey>,
+}
+
+impl GuiInput {
+	// Reads the mouse, keyboard and `gamepad` of the raylib window. Call once per frame, it takes the
+	// typed characters out of raylib's queue.
+	pub fn capture(gamepad: i32) -> Self {
+		unsafe {
+			let mut text = String::new();
+			loop {
+				let c = GetCharPressed();
+				if c == 0 {
+					break;
+				}
+				text.extend(char::from_u32(c as u32));
+			}
+
+			let shift = IsKeyDown(KeyboardKey_KEY_LEFT_SHIFT) || IsKeyDown(KeyboardKey_KEY_RIGHT_SHIFT);
+			let bindings = [
+				(KeyboardKey_KEY_TAB, if shift { GuiKey::Previous } else { GuiKey::Next }),
+				(KeyboardKey_KEY_UP, GuiKey::Up),
+				(KeyboardKey_KEY_DOWN, GuiKey::Down),
+				(KeyboardKey_KEY_LEFT, GuiKey::Left),
+				(KeyboardKey_KEY_RIGHT, GuiKey::Right),
+				(KeyboardKey_KEY_ENTER, GuiKey::Activate),
+				(KeyboardKey_KEY_KP_ENTER, GuiKey::Activate),
+				(KeyboardKey_KEY_ESCAPE, GuiKey::Back),
+				(KeyboardKey_KEY_BACKSPACE, GuiKey::Backspace),
+			];
+			let mut keys: Vec<GuiKey> = bindings
+				.iter()
+				.filter(|(key, _)| IsKeyPressed(*key) || IsKeyPressedRepeat(*key))
+				.map(|(_, gui_key)| *gui_key)
+				.collect();
+			let buttons = [
+				(GamepadButton_GAMEPAD_BUTTON_LEFT_FACE_UP, GuiKey::Up),
+				(GamepadButton_GAMEPAD_BUTTON_LEFT_FACE_DOWN, GuiKey::Down),
+				(GamepadButton_GAMEPAD_BUTTON_LEFT_FACE_LEFT, GuiKey::Left),
+				(GamepadButton_GAMEPAD_BUTTON_LEFT_FACE_RIGHT, GuiKey::Right),
+				(GamepadButton_GAMEPAD_BUTTON_RIGHT_FACE_DOWN, GuiKey::Activate),
+				(GamepadButton_GAMEPAD_BUTTON_RIGHT_FACE_RIGHT, GuiKey::Back),
+				(GamepadButton_GAMEPAD_BUTTON_LEFT_TRIGGER_1, GuiKey::Previous),
+				(GamepadButton_GAMEPAD_BUTTON_RIGHT_TRIGGER_1, GuiKey::Next),
+			];
+			keys.extend(
+				buttons
+					.iter()
+					.filter(|(button, _)| IsGamepadButtonPressed(gamepad, *button))
+					.map(|(_, gui_key)| *gui_key),
+			);
+
+			Self {
+				mouse: GetMousePosition(),
+				mouse_down: IsMouseButtonDown(MouseButton_MOUSE_BUTTON_LEFT),
+				wheel: GetMouseWheelMove(),
+				text,
+				keys,
+			}
+		}
+	}
+}
+
+// Colors and sizes of every widget.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Theme {
+	// panels and list backgrounds
+	pub background: Color,
+	// widget fill
+	pub base: Color,
+	pub hovered: Color,
+	pub pressed: Color,
+	pub border: Color,
+	// border of the focused widget
+	pub focus: Color,
+	// slider fill, check marks and selected rows
+	pub accent: Color,
+	pub text: Color,
+	pub font_size: i32,
+	pub padding: f32,
+	pub border_width: f32,
+	pub scrollbar_width: f32,
+}
+
+impl Default for Theme {
+	fn default() -> Self {
+		Self::light()
+	}
+}
+
+impl Theme {
+	// close to raygui's default style
+	pub fn light() -> Self {
+		Self {
+			background: rgb(245, 245, 245),
+			base: rgb(201, 201, 201),
+			hovered: rgb(201, 239, 254),
+			pressed: rgb(151, 232, 255),
+			border: rgb(131, 131, 131),
+			focus: rgb(4, 146, 199),
+			accent: rgb(4, 146, 199),
+			text: rgb(104, 104, 104),
+			font_size: 10,
+			padding: 4.0,
+			border_width: 1.0,
+			scrollbar_width: 10.0,
+		}
+	}
+
+	pub fn dark() -> Self {
+		Self {
+			background: rgb(30, 30, 34),
+			base: rgb(54, 54, 60),
+			hovered: rgb(70, 74, 84),
+			pressed: rgb(44, 96, 130),
+			border: rgb(90, 90, 98),
+			focus: rgb(102, 191, 255),
+			accent: rgb(102, 191, 255),
+			text: rgb(220, 220, 224),
+			font_size: 10,
+			padding: 4.0,
+			border_width: 1.0,
+			scrollbar_width: 10.0,
+		}
+	}
+}
+
+// MeasureText with raylib's default font, for `Gui::measure_text` once the window is open.
+pub fn measure_text_raylib(text: &str, font_size: i32) -> i32 {
+	let text = CString::new(text.split('\0').next().unwrap_or_default()).unwrap_or_default();
+	unsafe { MeasureText(text.as_ptr(), font_size) }
+}
+
+// the open dropdown list
+#[derive(Clone, Copy, Debug)]
+struct Popup {
+	id: usize,
+	list: Rectangle,
+	highlight: usize,
+	// still drawn this frame
+	seen: bool,
+}
+
+// Immediate mode widgets in the style of raygui: call them every frame between `begin` and `end`,
+// they return what the user did and add their looks to a list of draw commands. Values live with
+// the caller. Widgets are identified by call order, so focus and drags hold while the layout does.
+pub struct Gui {
+	pub theme: Theme,
+	// width of a text in pixels, `raster::measure_text` until swapped for `measure_text_raylib`
+	pub measure_text: fn(&str, i32) -> i32,
+	input: GuiInput,
+	mouse_was_down: bool,
+	commands: Vec<DrawCommand>,
+	// drawn after everything else, e.g. open dropdown lists
+	overlay: Vec<DrawCommand>,
+	next_id: usize,
+	// ids Tab and the gamepad move through, in call order
+	focus_order: Vec<usize>,
+	focus: Option<usize>,
+	// the mouse went down on it and is still down, for drags
+	active: Option<usize>,
+	hovered_any: bool,
+	clip: Vec<Rectangle>,
+	popup: Option<Popup>,
+}
+
+impl Default for Gui {
+	fn default() -> Self {
+		Self::new(Theme::default())
+	}
+}
+
+impl Gui {
+	pub fn new(theme: Theme) -> Self {
+		Self {
+			theme,
+			measure_text: raster::measure_text,
+			input: GuiInput::default(),
+			mouse_was_down: false,
+			commands: Vec::new(),
+			overlay: Vec::new(),
+			next_id: 0,
+			focus_order: Vec::new(),
+			focus: None,
+			active: None,
+			hovered_any: false,
+			clip: Vec::new(),
+			popup: None,
+		}
+	}
+
+	pub fn begin(&mut self, input: GuiInput) {
+		self.input = input;
+		self.commands.clear();
+		self.overlay.clear();
+		self.next_id = 0;
+		self.focus_order.clear();
+		self.hovered_any = false;
+		self.clip.clear();
+		if let Some(popup) = &mut self.popup {
+			popup.seen = false;
+		}
+	}
+
+	pub fn end(&mut self) {
+		// keys the widgets did not use move the focus
+		for key in std::mem::take(&mut self.input.keys) {
+			match key {
+				GuiKey::Next | GuiKey::Down => self.move_focus(1),
+				GuiKey::Previous | GuiKey::Up => self.move_focus(-1),
+				GuiKey::Back => self.focus = None,
+				_ => {}
+			}
+		}
+		if self.mouse_pressed() && !self.hovered_any {
+			self.focus = None;
+		}
+		if !self.input.mouse_down {
+			self.active = None;
+		}
+		if self.popup.is_some_and(|popup| !popup.seen) {
+			self.popup = None;
+		}
+		self.commands.append(&mut self.overlay);
+		self.mouse_was_down = self.input.mouse_down;
+	}
+
+	// The frame's draw commands, valid after `end`.
+	pub fn commands(&self) -> &[DrawCommand] {
+		&self.commands
+	}
+
+	// Draws the frame, inside BeginDrawing and outside BeginMode2D.
+	pub fn draw(&self, backend: &mut dyn Backend) {
+		for command in &self.commands {
+			match command {
+				DrawCommand::Rectangle { rec, color } => backend.draw_rectangle_rec(*rec, *color),
+				DrawCommand::RectangleLines { rec, thickness, color } => backend.draw_rectangle_lines_ex(*rec, *thickness, *color),
+				DrawCommand::Line {
+					start,
+					end,
+					thickness,
+					color,
+				} => backend.draw_line_ex(*start, *end, *thickness, *color),
+				DrawCommand::Text {
+					text,
+					x,
+					y,
+					font_size,
+					color,
+				} => backend.draw_text(text, *x, *y, *font_size, *color),
+				DrawCommand::BeginScissorMode { x, y, width, height } => backend.begin_scissor_mode(*x, *y, *width, *height),
+				DrawCommand::EndScissorMode => backend.end_scissor_mode(),
+				_ => {}
+			}
+		}
+	}
+
+	// true while the mouse is over a widget or dragging one, so the game can ignore the click
+	pub fn wants_mouse(&self) -> bool {
+		self.hovered_any || self.active.is_some() || self.popup.is_some()
+	}
+
+	// the focused widget's id, its position in the frame's calls
+	pub fn focus(&self) -> Option<usize> {
+		self.focus
+	}
+
+	pub fn set_focus(&mut self, id: Option<usize>) {
+		self.focus = id;
+	}
+
+	pub fn label(&mut self, bounds: Rectangle, text: &str) {
+		let color = self.theme.text;
+		self.text_left(bounds, text, color);
+	}
+
+	// A frame to group widgets, with an optional title over its top edge.
+	pub fn panel(&mut self, bounds: Rectangle, title: Option<&str>) {
+		let theme = self.theme;
+		self.fill(bounds, theme.background);
+		self.border(bounds, theme.border);
+		if let Some(title) = title {
+			let bar = Rectangle {
+				height: theme.font_size as f32 + 2.0 * theme.padding,
+				..bounds
+			};
+			self.fill(bar, theme.base);
+			self.border(bar, theme.border);
+			self.text_left(bar, title, theme.text);
+		}
+	}
+
+	// True on the frame it was clicked, or activated while focused.
+	pub fn button(&mut self, bounds: Rectangle, text: &str) -> bool {
+		let id = self.focusable_id();
+		let (hovered, clicked) = self.interact(id, bounds);
+		let activated = self.take_key(id, GuiKey::Activate);
+		let fill = self.fill_color(id, hovered);
+		self.frame(id, bounds, fill);
+		let color = self.theme.text;
+		self.text_centered(bounds, text, color);
+		clicked || activated
+	}
+
+	// `bounds` is the box, the text goes to its right. True when it toggled.
+	pub fn checkbox(&mut self, bounds: Rectangle, text: &str, checked: &mut bool) -> bool {
+		let id = self.focusable_id();
+		let theme = self.theme;
+		let text_width = (self.measure_text)(text, theme.font_size) as f32;
+		let hit = Rectangle {
+			width: bounds.width + theme.padding + text_width,
+			..bounds
+		};
+		let (hovered, clicked) = self.interact(id, hit);
+		let toggled = clicked | self.take_key(id, GuiKey::Activate);
+		if toggled {
+			*checked = !*checked;
+		}
+
+		let fill = self.fill_color(id, hovered);
+		self.frame(id, bounds, fill);
+		if *checked {
+			let inset = theme.padding.min(bounds.width / 4.0);
+			self.fill(shrink(bounds, inset), theme.accent);
+		}
+		let label = Rectangle {
+			x: bounds.x + bounds.width + theme.padding,
+			width: text_width,
+			..bounds
+		};
+		self.text_left(label, text, theme.text);
+		toggled
+	}
+
+	// Drag or click to set, Left and Right step by a twentieth of the range. True when it changed.
+	pub fn slider(&mut self, bounds: Rectangle, value: &mut f32, min: f32, max: f32) -> bool {
+		let id = self.focusable_id();
+		let (hovered, _) = self.interact(id, bounds);
+		let mut next = *value;
+		if self.active == Some(id) && self.input.mouse_down && bounds.width > 0.0 {
+			let t = ((self.input.mouse.x - bounds.x) / bounds.width).clamp(0.0, 1.0);
+			next = min + (max - min) * t;
+		}
+		let step = (max - min) / 20.0;
+		if self.take_key(id, GuiKey::Left) {
+			next -= step;
+		}
+		if self.take_key(id, GuiKey::Right) {
+			next += step;
+		}
+		let next = next.clamp(min.min(max), max.max(min));
+		let changed = next != *value;
+		*value = next;
+
+		let theme = self.theme;
+		let fill = self.fill_color(id, hovered);
+		self.frame(id, bounds, fill);
+		let t = if max != min { (next - min) / (max - min) } else { 0.0 };
+		let inner = shrink(bounds, theme.border_width + 1.0);
+		self.fill(
+			Rectangle {
+				width: inner.width * t,
+				..inner
+			},
+			theme.accent,
+		);
+		changed
+	}
+
+	// Single line text input, takes the typed characters while focused. Activate or Back finish the
+	// edit. True when the text changed.
+	pub fn text_box(&mut self, bounds: Rectangle, text: &mut String, max_chars: usize) -> bool {
+		let id = self.focusable_id();
+		let (hovered, _) = self.interact(id, bounds);
+		let mut changed = false;
+		if self.focus == Some(id) {
+			for c in std::mem::take(&mut self.input.text).chars().filter(|c| !c.is_control()) {
+				if text.chars().count() < max_chars {
+					text.push(c);
+					changed = true;
+				}
+			}
+			while self.take_key(id, GuiKey::Backspace) {
+				changed |= text.pop().is_some();
+			}
+			if self.take_key(id, GuiKey::Activate) || self.take_key(id, GuiKey::Back) {
+				self.focus = None;
+			}
+		}
+
+		let theme = self.theme;
+		let fill = if self.focus == Some(id) {
+			theme.background
+		} else {
+			self.fill_color(id, hovered)
+		};
+		self.frame(id, bounds, fill);
+		// the end of the text stays visible, like raygui
+		let available = (bounds.width - 2.0 * theme.padding) as i32;
+		let mut start = 0;
+		while start < text.len() && (self.measure_text)(&text[start..], theme.font_size) > available {
+			start += text[start..].chars().next().map_or(1, char::len_utf8);
+		}
+		let shown = &text[start..];
+		self.text_left(bounds, shown, theme.text);
+		if self.focus == Some(id) {
+			let x = bounds.x + theme.padding + (self.measure_text)(shown, theme.font_size) as f32 + 1.0;
+			let half = theme.font_size as f32 / 2.0 + 1.0;
+			let middle = bounds.y + bounds.height / 2.0;
+			self.commands.push(DrawCommand::Line {
+				start: Vector2 { x, y: middle - half },
+				end: Vector2 { x, y: middle + half },
+				thickness: 1.0,
+				color: theme.text,
+			});
+		}
+		changed
+	}
+
+	// Shows `items[*selected]`, opens a list below on click or Activate. True when the selection changed.
+	pub fn dropdown(&mut self, bounds: Rectangle, items: &[&str], selected: &mut usize) -> bool {
+		let id = self.focusable_id();
+		let (hovered, clicked) = self.interact(id, bounds);
+		let theme = self.theme;
+		let list = Rectangle {
+			y: bounds.y + bounds.height,
+			height: bounds.height * items.len() as f32,
+			..bounds
+		};
+		let mut changed = false;
+		let open = self.popup.is_some_and(|popup| popup.id == id);
+		if !open {
+			if clicked || self.take_key(id, GuiKey::Activate) {
+				self.popup = Some(Popup {
+					id,
+					list,
+					highlight: *selected,
+					seen: true,
+				});
+			}
+		} else if clicked || self.take_key(id, GuiKey::Back) {
+			self.popup = None;
+		} else if let Some(mut popup) = self.popup {
+			popup.list = list;
+			popup.seen = true;
+			let mouse = self.input.mouse;
+			let over_list = contains(list, mouse);
+			let row = ((mouse.y - list.y) / bounds.height).floor() as usize;
+			if over_list {
+				self.hovered_any = true;
+				popup.highlight = row;
+				if self.mouse_pressed() {
+					self.active = Some(id);
+				}
+			}
+			if self.take_key(id, GuiKey::Up) {
+				popup.highlight = popup.highlight.saturating_sub(1);
+			}
+			if self.take_key(id, GuiKey::Down) {
+				popup.highlight = (popup.highlight + 1).min(items.len().saturating_sub(1));
+			}
+			let picked = over_list && self.mouse_released() && self.active == Some(id);
+			if picked || self.take_key(id, GuiKey::Activate) {
+				changed = popup.highlight != *selected && popup.highlight < items.len();
+				*selected = popup.highlight.min(items.len().saturating_sub(1));
+				self.popup = None;
+			} else if self.mouse_pressed() && !over_list && !hovered {
+				self.popup = None;
+			} else {
+				self.popup = Some(popup);
+			}
+		}
+
+		let fill = self.fill_color(id, hovered);
+		self.frame(id, bounds, fill);
+		self.text_left(bounds, items.get(*selected).copied().unwrap_or_default(), theme.text);
+		// arrow
+		let size = theme.font_size as f32 / 2.0;
+		let center = Vector2 {
+			x: bounds.x + bounds.width - theme.padding - size,
+			y: bounds.y + bounds.height / 2.0,
+		};
+		for dx in [-1.0, 1.0] {
+			self.commands.push(DrawCommand::Line {
+				start: Vector2 {
+					x: center.x + dx * size / 2.0,
+					y: center.y - size / 4.0,
+				},
+				end: Vector2 {
+					x: center.x,
+					y: center.y + size / 4.0,
+				},
+				thickness: 2.0,
+				color: theme.text,
+			});
+		}
+
+		if let Some(popup) = self.popup.filter(|popup| popup.id == id) {
+			let mut overlay = std::mem::take(&mut self.overlay);
+			overlay.push(DrawCommand::Rectangle {
+				rec: list,
+				color: theme.background,
+			});
+			for (i, item) in items.iter().enumerate() {
+				let row = Rectangle {
+					y: list.y + i as f32 * bounds.height,
+					..bounds
+				};
+				if i == popup.highlight {
+					overlay.push(DrawCommand::Rectangle {
+						rec: row,
+						color: theme.hovered,
+					});
+				}
+				let color = if i == *selected { theme.accent } else { theme.text };
+				overlay.push(self.text_command(row, item, color));
+			}
+			overlay.push(DrawCommand::RectangleLines {
+				rec: list,
+				thickness: theme.border_width,
+				color: theme.border,
+			});
+			self.overlay = overlay;
+		}
+		changed
+	}
+
+	// Rows of `items` scrolled by `scroll` pixels, click or Up and Down to select. True when the
+	// selection changed.
+	pub fn list_view(&mut self, bounds: Rectangle, items: &[&str], selected: &mut Option<usize>, scroll: &mut f32) -> bool {
+		let id = self.focusable_id();
+		let (hovered, _) = self.interact(id, bounds);
+		let theme = self.theme;
+		let row_height = theme.font_size as f32 + 2.0 * theme.padding;
+		let before = *selected;
+
+		if hovered {
+			*scroll -= self.input.wheel * row_height;
+			if self.mouse_pressed() {
+				let row = ((self.input.mouse.y - bounds.y + *scroll) / row_height).floor();
+				if row >= 0.0 && (row as usize) < items.len() {
+					*selected = Some(row as usize);
+				}
+			}
+		}
+		if !items.is_empty() {
+			if self.take_key(id, GuiKey::Up) {
+				*selected = Some(selected.map_or(0, |row| row.saturating_sub(1)));
+			}
+			if self.take_key(id, GuiKey::Down) {
+				*selected = Some(selected.map_or(0, |row| (row + 1).min(items.len() - 1)));
+			}
+			// keep a row picked by key in view
+			if *selected != before && !hovered {
+				if let Some(row) = *selected {
+					let top = row as f32 * row_height;
+					*scroll = scroll.clamp(top + row_height - bounds.height, top);
+				}
+			}
+		}
+		let content = items.len() as f32 * row_height;
+		*scroll = scroll.clamp(0.0, (content - bounds.height).max(0.0));
+
+		self.fill(bounds, theme.background);
+		let view = Rectangle {
+			width: if content > bounds.height {
+				bounds.width - theme.scrollbar_width
+			} else {
+				bounds.width
+			},
+			..bounds
+		};
+		self.push_clip(view);
+		let first = (*scroll / row_height).floor() as usize;
+		let visible = (bounds.height / row_height).ceil() as usize + 1;
+		for (i, item) in items.iter().enumerate().skip(first).take(visible) {
+			let row = Rectangle {
+				y: bounds.y + i as f32 * row_height - *scroll,
+				height: row_height,
+				..view
+			};
+			let (fill, color) = if *selected == Some(i) {
+				(Some(theme.accent), theme.background)
+			} else if hovered && contains(row, self.input.mouse) {
+				(Some(theme.hovered), theme.text)
+			} else {
+				(None, theme.text)
+			};
+			if let Some(fill) = fill {
+				self.fill(row, fill);
+			}
+			self.text_left(row, item, color);
+		}
+		self.pop_clip();
+		if content > bounds.height {
+			let track = Rectangle {
+				x: view.x + view.width,
+				width: theme.scrollbar_width,
+				..bounds
+			};
+			self.vertical_scrollbar(track, content, *scroll);
+		}
+		let border = if self.focus == Some(id) { theme.focus } else { theme.border };
+		self.border(bounds, border);
+		*selected != before
+	}
+
+	// Starts a scrolled area showing `content_size` pixels of content through `bounds`. Place the
+	// content relative to the returned origin, then call `end_scroll_panel`. The wheel scrolls
+	// vertically, the scrollbars can be dragged.
+	pub fn begin_scroll_panel(&mut self, bounds: Rectangle, content_size: Vector2, scroll: &mut Vector2) -> Vector2 {
+		let theme = self.theme;
+		let id = self.next_id();
+		let (hovered, _) = self.interact(id, bounds);
+		let vertical = content_size.y > bounds.height;
+		let horizontal = content_size.x > bounds.width - if vertical { theme.scrollbar_width } else { 0.0 };
+		let vertical = vertical || content_size.y > bounds.height - if horizontal { theme.scrollbar_width } else { 0.0 };
+		let view = Rectangle {
+			width: bounds.width - if vertical { theme.scrollbar_width } else { 0.0 },
+			height: bounds.height - if horizontal { theme.scrollbar_width } else { 0.0 },
+			..bounds
+		};
+		if hovered {
+			scroll.y -= self.input.wheel * (theme.font_size as f32 + 2.0 * theme.padding);
+		}
+
+		let bar_ids = [self.next_id(), self.next_id()];
+		let bars = [
+			Rectangle {
+				x: view.x + view.width,
+				width: theme.scrollbar_width,
+				..view
+			},
+			Rectangle {
+				y: view.y + view.height,
+				height: theme.scrollbar_width,
+				..view
+			},
+		];
+		for (axis, (bar_id, bar)) in bar_ids.into_iter().zip(bars).enumerate() {
+			let (content, size, mouse, start, length) = match axis {
+				0 => (content_size.y, view.height, self.input.mouse.y, bar.y, bar.height),
+				_ => (content_size.x, view.width, self.input.mouse.x, bar.x, bar.width),
+			};
+			if content <= size {
+				continue;
+			}
+			self.interact(bar_id, bar);
+			if self.active == Some(bar_id) {
+				let thumb = length * size / content;
+				let t = ((mouse - start - thumb / 2.0) / (length - thumb)).clamp(0.0, 1.0);
+				*if axis == 0 { &mut scroll.y } else { &mut scroll.x } = t * (content - size);
+			}
+		}
+		scroll.x = scroll.x.clamp(0.0, (content_size.x - view.width).max(0.0));
+		scroll.y = scroll.y.clamp(0.0, (content_size.y - view.height).max(0.0));
+
+		self.fill(bounds, theme.background);
+		if vertical {
+			self.vertical_scrollbar(bars[0], content_size.y, scroll.y);
+		}
+		if horizontal {
+			self.horizontal_scrollbar(bars[1], content_size.x, scroll.x);
+		}
+		self.border(bounds, theme.border);
+		self.push_clip(view);
+		Vector2 {
+			x: view.x - scroll.x,
+			y: view.y - scroll.y,
+		}
+	}
+
+	pub fn end_scroll_panel(&mut self) {
+		self.pop_clip();
+	}
+
+	fn next_id(&mut self) -> usize {
+		self.next_id += 1;
+		self.next_id - 1
+	}
+
+	fn focusable_id(&mut self) -> usize {
+		let id = self.next_id();
+		self.focus_order.push(id);
+		id
+	}
+
+	fn move_focus(&mut self, step: isize) {
+		let count = self.focus_order.len() as isize;
+		if count == 0 {
+			return;
+		}
+		let position = self.focus.and_then(|focus| self.focus_order.iter().position(|id| *id == focus));
+		let next = match position {
+			Some(position) => (position as isize + step).rem_euclid(count),
+			None if step > 0 => 0,
+			None => count - 1,
+		};
+		self.focus = Some(self.focus_order[next as usize]);
+	}
+
+	fn mouse_pressed(&self) -> bool {
+		self.input.mouse_down && !self.mouse_was_down
+	}
+
+	fn mouse_released(&self) -> bool {
+		!self.input.mouse_down && self.mouse_was_down
+	}
+
+	// over `rec` where it is not clipped away or covered by an open dropdown
+	fn mouse_over(&self, rec: Rectangle) -> bool {
+		let mouse = self.input.mouse;
+		contains(rec, mouse) && self.clip.iter().all(|clip| contains(*clip, mouse)) && !self.popup.is_some_and(|popup| contains(popup.list, mouse))
+	}
+
+	// (hovered, clicked): pressing starts a drag and takes the focus, releasing over it clicks
+	fn interact(&mut self, id: usize, bounds: Rectangle) -> (bool, bool) {
+		let hovered = self.mouse_over(bounds);
+		self.hovered_any |= hovered;
+		if hovered && self.mouse_pressed() {
+			self.active = Some(id);
+			// focusable widgets register their id right before interacting
+			if self.focus_order.last() == Some(&id) {
+				self.focus = Some(id);
+			}
+		}
+		let clicked = hovered && self.mouse_released() && self.active == Some(id);
+		(hovered, clicked)
+	}
+
+	// a key press for the focused widget, each press is used once
+	fn take_key(&mut self, id: usize, key: GuiKey) -> bool {
+		if self.focus != Some(id) {
+			return false;
+		}
+		match self.input.keys.iter().position(|pressed| *pressed == key) {
+			Some(index) => {
+				self.input.keys.remove(index);
+				true
+			}
+			None => false,
+		}
+	}
+
+	fn fill_color(&self, id: usize, hovered: bool) -> Color {
+		if self.active == Some(id) && hovered {
+			self.theme.pressed
+		} else if hovered {
+			self.theme.hovered
+		} else {
+			self.theme.base
+		}
+	}
+
+	fn frame(&mut self, id: usize, bounds: Rectangle, fill: Color) {
+		self.fill(bounds, fill);
+		let border = if self.focus == Some(id) { self.theme.focus } else { self.theme.border };
+		self.border(bounds, border);
+	}
+
+	fn fill(&mut self, rec: Rectangle, color: Color) {
+		self.commands.push(DrawCommand::Rectangle { rec, color });
+	}
+
+	fn border(&mut self, rec: Rectangle, color: Color) {
+		self.commands.push(DrawCommand::RectangleLines {
+			rec,
+			thickness: self.theme.border_width,
+			color,
+		});
+	}
+
+	fn vertical_scrollbar(&mut self, track: Rectangle, content: f32, scroll: f32) {
+		let theme = self.theme;
+		let thumb = track.height * (track.height / content).min(1.0);
+		let max = (content - track.height).max(1.0);
+		self.fill(track, theme.base);
+		self.fill(
+			shrink(
+				Rectangle {
+					y: track.y + (track.height - thumb) * (scroll / max).clamp(0.0, 1.0),
+					height: thumb,
+					..track
+				},
+				2.0,
+			),
+			theme.border,
+		);
+	}
+
+	fn horizontal_scrollbar(&mut self, track: Rectangle, content: f32, scroll: f32) {
+		let theme = self.theme;
+		let thumb = track.width * (track.width / content).min(1.0);
+		let max = (content - track.width).max(1.0);
+		self.fill(track, theme.base);
+		self.fill(
+			shrink(
+				Rectangle {
+					x: track.x + (track.width - thumb) * (scroll / max).clamp(0.0, 1.0),
+					width: thumb,
+					..track
+				},
+				2.0,
+			),
+			theme.border,
+		);
+	}
+
+	// raylib scissors do not nest, so each level draws the intersection with the one outside it
+	fn push_clip(&mut self, rec: Rectangle) {
+		let rec = match self.clip.last() {
+			Some(outer) => intersect(*outer, rec),
+			None => rec,
+		};
+		self.clip.push(rec);
+		self.scissor(rec);
+	}
+
+	fn pop_clip(&mut self) {
+		self.clip.pop();
+		self.commands.push(DrawCommand::EndScissorMode);
+		if let Some(outer) = self.clip.last().copied() {
+			self.scissor(outer);
+		}
+	}
+
+	fn scissor(&mut self, rec: Rectangle) {
+		self.commands.push(DrawCommand::BeginScissorMode {
+			x: rec.x as i32,
+			y: rec.y as i32,
+			width: rec.width.max(0.0) as i32,
+			height: rec.height.max(0.0) as i32,
+		});
+	}
+
+	fn text_command(&self, bounds: Rectangle, text: &str, color: Color) -> DrawCommand {
+		DrawCommand::Text {
+			text: text.to_string(),
+			x: (bounds.x + self.theme.padding) as i32,
+			y: (bounds.y + (bounds.height - self.theme.font_size as f32) / 2.0) as i32,
+			font_size: self.theme.font_size,
+			color,
+		}
+	}
+
+	fn text_left(&mut self, bounds: Rectangle, text: &str, color: Color) {
+		let command = self.text_command(bounds, text, color);
+		self.commands.push(command);
+	}
+
+	fn text_centered(&mut self, bounds: Rectangle, text: &str, color: Color) {
+		let width = (self.measure_text)(text, self.theme.font_size) as f32;
+		self.commands.push(DrawCommand::Text {
+			text: text.to_string(),
+			x: (bounds.x + (bounds.width - width) / 2.0) as i32,
+			y: (bounds.y + (bounds.height - self.theme.font_size as f32) / 2.0) as i32,
+			font_size: self.theme.font_size,
+			color,
+		});
+	}
+}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+	Color { r, g, b, a: 255 }
+}
+
+fn contains(rec: Rectangle, point: Vector2) -> bool {
+	(rec.x..rec.x + rec.width).contains(&point.x) && (rec.y..rec.y + rec.height).contains(&point.y)
+}
+
+fn shrink(rec: Rectangle, amount: f32) -> Rectangle {
+	Rectangle {
+		x: rec.x + amount,
+		y: rec.y + amount,
+		width: (rec.width - 2.0 * amount).max(0.0),
+		height: (rec.height - 2.0 * amount).max(0.0),
+	}
+}
+
+fn intersect(a: Rectangle, b: Rectangle) -> Rectangle {
+	let x = a.x.max(b.x);
+	let y = a.y.max(b.y);
+	Rectangle {
+		x,
+		y,
+		width: ((a.x + a.width).min(b.x + b.width) - x).max(0.0),
+		height: ((a.y + a.height).min(b.y + b.height) - y).max(0.0),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const BUTTON: Rectangle = rect(10.0, 10.0, 100.0, 30.0);
+	const CHECKBOX: Rectangle = rect(10.0, 50.0, 20.0, 20.0);
+	const SLIDER: Rectangle = rect(10.0, 80.0, 200.0, 20.0);
+	const TEXT_BOX: Rectangle = rect(10.0, 110.0, 200.0, 30.0);
+	const DROPDOWN: Rectangle = rect(10.0, 150.0, 100.0, 30.0);
+	const ITEMS: [&str; 3] = ["low", "medium", "high"];
+
+	const fn rect(x: f32, y: f32, width: f32, height: f32) -> Rectangle {
+		Rectangle { x, y, width, height }
+	}
+
+	fn center(rec: Rectangle) -> Vector2 {
+		Vector2 {
+			x: rec.x + rec.width / 2.0,
+			y: rec.y + rec.height / 2.0,
+		}
+	}
+
+	fn mouse(position: Vector2, down: bool) -> GuiInput {
+		GuiInput {
+			mouse: position,
+			mouse_down: down,
+			..Default::default()
+		}
+	}
+
+	fn keys(keys: &[GuiKey]) -> GuiInput {
+		GuiInput {
+			mouse: Vector2 { x: -1.0, y: -1.0 },
+			keys: keys.to_vec(),
+			..Default::default()
+		}
+	}
+
+	fn typed(text: &str) -> GuiInput {
+		GuiInput {
+			text: text.to_string(),
+			..keys(&[])
+		}
+	}
+
+	// the caller's values, with what changed in the last frame
+	#[derive(Default)]
+	struct Form {
+		clicks: u32,
+		checked: bool,
+		volume: f32,
+		name: String,
+		quality: usize,
+		changed: Vec<&'static str>,
+	}
+
+	impl Form {
+		fn frame(&mut self, gui: &mut Gui, input: GuiInput) {
+			self.changed.clear();
+			gui.begin(input);
+			if gui.button(BUTTON, "Play") {
+				self.clicks += 1;
+				self.changed.push("button");
+			}
+			if gui.checkbox(CHECKBOX, "Fullscreen", &mut self.checked) {
+				self.changed.push("checkbox");
+			}
+			if gui.slider(SLIDER, &mut self.volume, 0.0, 100.0) {
+				self.changed.push("slider");
+			}
+			if gui.text_box(TEXT_BOX, &mut self.name, 8) {
+				self.changed.push("text_box");
+			}
+			if gui.dropdown(DROPDOWN, &ITEMS, &mut self.quality) {
+				self.changed.push("dropdown");
+			}
+			gui.end();
+		}
+
+		fn click(&mut self, gui: &mut Gui, position: Vector2) {
+			self.frame(gui, mouse(position, true));
+			self.frame(gui, mouse(position, false));
+		}
+	}
+
+	#[test]
+	fn buttons_click_on_release_over_them() {
+		let (mut gui, mut form) = (Gui::default(), Form::default());
+		form.frame(&mut gui, mouse(center(BUTTON), true));
+		assert!(form.changed.is_empty());
+		assert!(gui.wants_mouse());
+		form.frame(&mut gui, mouse(center(BUTTON), false));
+		assert_eq!(form.changed, ["button"]);
+		form.frame(&mut gui, mouse(center(BUTTON), false));
+		assert!(form.changed.is_empty());
+
+		// pressed elsewhere or dragged off before the release
+		form.frame(&mut gui, mouse(Vector2 { x: 300.0, y: 300.0 }, true));
+		form.frame(&mut gui, mouse(center(BUTTON), false));
+		form.frame(&mut gui, mouse(center(BUTTON), true));
+		form.frame(&mut gui, mouse(Vector2 { x: 300.0, y: 300.0 }, false));
+		assert_eq!(form.clicks, 1);
+		assert!(!gui.wants_mouse());
+	}
+
+	#[test]
+	fn checkbox_toggles_from_its_label_too() {
+		let (mut gui, mut form) = (Gui::default(), Form::default());
+		form.click(&mut gui, center(CHECKBOX));
+		assert!(form.checked);
+		form.click(&mut gui, Vector2 { x: 50.0, y: 60.0 });
+		assert_eq!(form.changed, ["checkbox"]);
+		assert!(!form.checked);
+	}
+
+	#[test]
+	fn keys_move_and_drop_the_focus() {
+		let (mut gui, mut form) = (Gui::default(), Form::default());
+		form.frame(&mut gui, keys(&[GuiKey::Next]));
+		assert_eq!(gui.focus(), Some(0));
+		form.frame(&mut gui, keys(&[GuiKey::Activate]));
+		assert_eq!(form.changed, ["button"]);
+
+		form.frame(&mut gui, keys(&[GuiKey::Previous]));
+		assert_eq!(gui.focus(), Some(4));
+		form.frame(&mut gui, keys(&[GuiKey::Next, GuiKey::Next]));
+		assert_eq!(gui.focus(), Some(1));
+		form.frame(&mut gui, keys(&[GuiKey::Activate]));
+		assert!(form.checked);
+		form.frame(&mut gui, keys(&[GuiKey::Back]));
+		assert_eq!(gui.focus(), None);
+
+		// clicking takes the focus, clicking nothing drops it
+		form.click(&mut gui, center(TEXT_BOX));
+		assert_eq!(gui.focus(), Some(3));
+		form.click(&mut gui, Vector2 { x: 300.0, y: 300.0 });
+		assert_eq!(gui.focus(), None);
+	}
+
+	#[test]
+	fn slider_follows_the_drag() {
+		let (mut gui, mut form) = (Gui::default(), Form::default());
+		form.frame(&mut gui, mouse(Vector2 { x: 60.0, y: 90.0 }, true));
+		assert_eq!((form.volume, gui.focus()), (25.0, Some(2)));
+		form.frame(&mut gui, mouse(Vector2 { x: 110.0, y: 90.0 }, true));
+		assert_eq!(form.volume, 50.0);
+		// the drag holds off the slider and clamps to the range
+		form.frame(&mut gui, mouse(Vector2 { x: 400.0, y: 0.0 }, true));
+		assert_eq!(form.volume, 100.0);
+		form.frame(&mut gui, mouse(Vector2 { x: 10.0, y: 0.0 }, false));
+		assert_eq!(form.volume, 100.0);
+		form.frame(&mut gui, mouse(Vector2 { x: 10.0, y: 0.0 }, false));
+		assert!(form.changed.is_empty());
+
+		// a twentieth of the range per press
+		form.frame(&mut gui, keys(&[GuiKey::Left]));
+		form.frame(&mut gui, keys(&[GuiKey::Left]));
+		assert_eq!((form.volume, form.changed.as_slice()), (90.0, ["slider"].as_slice()));
+		for _ in 0..3 {
+			form.frame(&mut gui, keys(&[GuiKey::Right]));
+		}
+		assert_eq!((form.volume, form.changed.as_slice()), (100.0, [].as_slice()));
+	}
+
+	#[test]
+	fn text_box_takes_typing_while_focused() {
+		let (mut gui, mut form) = (Gui::default(), Form::default());
+		form.frame(&mut gui, typed("ignored"));
+		assert_eq!(form.name, "");
+
+		form.click(&mut gui, center(TEXT_BOX));
+		form.frame(&mut gui, typed("Ana\tb"));
+		assert_eq!((form.name.as_str(), form.changed.as_slice()), ("Anab", ["text_box"].as_slice()));
+		// up to 8 characters, not bytes
+		form.frame(&mut gui, typed("élève!"));
+		assert_eq!(form.name, "Anabélèv");
+		form.frame(
+			&mut gui,
+			GuiInput {
+				keys: vec![GuiKey::Backspace, GuiKey::Backspace, GuiKey::Backspace],
+				..typed("")
+			},
+		);
+		assert_eq!(form.name, "Anabé");
+		// navigation keys are the text box's while it is focused
+		form.frame(&mut gui, keys(&[GuiKey::Activate]));
+		assert_eq!(gui.focus(), None);
+		form.frame(&mut gui, typed("x"));
+		assert_eq!(form.name, "Anabé");
+	}
+
+	#[test]
+	fn dropdown_picks_with_mouse_and_keys() {
+		let (mut gui, mut form) = (Gui::default(), Form::default());
+		form.click(&mut gui, center(DROPDOWN));
+		assert!(gui.wants_mouse());
+		// the open list covers what is below it
+		let third_row = Vector2 { x: 50.0, y: 260.0 };
+		form.click(&mut gui, third_row);
+		assert_eq!((form.quality, form.changed.as_slice()), (2, ["dropdown"].as_slice()));
+
+		form.frame(&mut gui, keys(&[GuiKey::Activate]));
+		form.frame(&mut gui, keys(&[GuiKey::Up]));
+		form.frame(&mut gui, keys(&[GuiKey::Up]));
+		form.frame(&mut gui, keys(&[GuiKey::Activate]));
+		assert_eq!(form.quality, 0);
+
+		// clicking elsewhere closes it without a change
+		form.frame(&mut gui, keys(&[GuiKey::Activate]));
+		form.click(&mut gui, Vector2 { x: 300.0, y: 300.0 });
+		assert!(form.changed.is_empty());
+		assert_eq!(form.quality, 0);
+		assert!(!gui.wants_mouse());
+	}
+
+	#[test]
+	fn open_dropdowns_draw_last() {
+		let (mut gui, mut form) = (Gui::default(), Form::default());
+		form.click(&mut gui, center(DROPDOWN));
+		let texts: Vec<&str> = gui
+			.commands()
+			.iter()
+			.filter_map(|command| match command {
+				DrawCommand::Text { text, .. } => Some(text.as_str()),
+				_ => None,
+			})
+			.collect();
+		assert_eq!(texts, ["Play", "Fullscreen", "", "low", "low", "medium", "high"]);
+	}
+}
@@ -0,0 +1,976 @@
+use std::{
+	collections::{BTreeMap, HashMap, HashSet},
+	io::{self, BufRead, Write},
+};
+
+use crate::*;
+
+// Where an `InputMap` reads the devices from. `RaylibInput` reads the real ones, `SyntheticInput`
+// is set by hand, e.g. in tests or when replaying recorded input.
+pub trait InputSource {
+	fn key_down(&self, key: KeyboardKey) -> bool;
+	fn mouse_button_down(&self, button: MouseButton) -> bool;
+	// wheel movement this frame
+	fn mouse_wheel(&self) -> f32;
+	fn gamepad_button_down(&self, gamepad: i32, button: GamepadButton) -> bool;
+	// -1..1, triggers 0..1 released to pulled
+	fn gamepad_axis(&self, gamepad: i32, axis: GamepadAxis) -> f32;
+	fn gesture_detected(&self, gesture: Gesture) -> bool;
+}
+
+// The keyboard, mouse, gamepads and touch screen of the raylib window.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct RaylibInput;
+
+impl InputSource for RaylibInput {
+	fn key_down(&self, key: KeyboardKey) -> bool {
+		unsafe { IsKeyDown(key) }
+	}
+
+	fn mouse_button_down(&self, button: MouseButton) -> bool {
+		unsafe { IsMouseButtonDown(button) }
+	}
+
+	fn mouse_wheel(&self) -> f32 {
+		unsafe { GetMouseWheelMove() }
+	}
+
+	fn gamepad_button_down(&self, gamepad: i32, button: GamepadButton) -> bool {
+		unsafe { IsGamepadAvailable(gamepad) && IsGamepadButtonDown(gamepad, button) }
+	}
+
+	fn gamepad_axis(&self, gamepad: i32, axis: GamepadAxis) -> f32 {
+		if !unsafe { IsGamepadAvailable(gamepad) } {
+			return 0.0;
+		}
+		let value = unsafe { GetGamepadAxisMovement(gamepad, axis) };
+		// raylib reports triggers from -1 released to 1 pulled
+		if axis == GamepadAxis_GAMEPAD_AXIS_LEFT_TRIGGER || axis == GamepadAxis_GAMEPAD_AXIS_RIGHT_TRIGGER {
+			(value + 1.0) / 2.0
+		} else {
+			value
+		}
+	}
+
+	fn gesture_detected(&self, gesture: Gesture) -> bool {
+		unsafe { IsGestureDetected(gesture as u32) }
+	}
+}
+
+// Input state set by hand.
+#[derive(Clone, Default, Debug)]
+pub struct SyntheticInput {
+	pub keys: HashSet<KeyboardKey>,
+	pub mouse_buttons: HashSet<MouseButton>,
+	pub mouse_wheel: f32,
+	pub gamepad_buttons: HashSet<(i32, GamepadButton)>,
+	pub gamepad_axes: HashMap<(i32, GamepadAxis), f32>,
+	pub gestures: HashSet<Gesture>,
+}
+
+impl SyntheticInput {
+	// Copies everything `source` reports, e.g. to record a frame of input. Only `gamepad` is read.
+	pub fn capture(source: &(impl InputSource + ?Sized), gamepad: i32) -> Self {
+		let mut input = SyntheticInput {
+			mouse_wheel: source.mouse_wheel(),
+			..Default::default()
+		};
+		input.keys.extend(KEYS.iter().map(|(_, key)| *key).filter(|key| source.key_down(*key)));
+		input.mouse_buttons.extend(
+			MOUSE_BUTTONS
+				.iter()
+				.map(|(_, button)| *button)
+				.filter(|button| source.mouse_button_down(*button)),
+		);
+		for (_, button) in GAMEPAD_BUTTONS {
+			if source.gamepad_button_down(gamepad, *button) {
+				input.gamepad_buttons.insert((gamepad, *button));
+			}
+		}
+		for (_, axis) in GAMEPAD_AXES {
+			let value = source.gamepad_axis(gamepad, *axis);
+			if value != 0.0 {
+				input.gamepad_axes.insert((gamepad, *axis), value);
+			}
+		}
+		input.gestures.extend(
+			GESTURES
+				.iter()
+				.map(|(_, gesture)| *gesture)
+				.filter(|gesture| source.gesture_detected(*gesture)),
+		);
+		input
+	}
+
+	pub fn set_key(&mut self, key: KeyboardKey, down: bool) {
+		if down {
+			self.keys.insert(key);
+		} else {
+			self.keys.remove(&key);
+		}
+	}
+
+	pub fn set_mouse_button(&mut self, button: MouseButton, down: bool) {
+		if down {
+			self.mouse_buttons.insert(button);
+		} else {
+			self.mouse_buttons.remove(&button);
+		}
+	}
+
+	pub fn set_gamepad_button(&mut self, gamepad: i32, button: GamepadButton, down: bool) {
+		if down {
+			self.gamepad_buttons.insert((gamepad, button));
+		} else {
+			self.gamepad_buttons.remove(&(gamepad, button));
+		}
+	}
+
+	pub fn set_gamepad_axis(&mut self, gamepad: i32, axis: GamepadAxis, value: f32) {
+		self.gamepad_axes.insert((gamepad, axis), value);
+	}
+}
+
+impl InputSource for SyntheticInput {
+	fn key_down(&self, key: KeyboardKey) -> bool {
+		self.keys.contains(&key)
+	}
+
+	fn mouse_button_down(&self, button: MouseButton) -> bool {
+		self.mouse_buttons.contains(&button)
+	}
+
+	fn mouse_wheel(&self) -> f32 {
+		self.mouse_wheel
+	}
+
+	fn gamepad_button_down(&self, gamepad: i32, button: GamepadButton) -> bool {
+		self.gamepad_buttons.contains(&(gamepad, button))
+	}
+
+	fn gamepad_axis(&self, gamepad: i32, axis: GamepadAxis) -> f32 {
+		self.gamepad_axes.get(&(gamepad, axis)).copied().unwrap_or(0.0)
+	}
+
+	fn gesture_detected(&self, gesture: Gesture) -> bool {
+		self.gestures.contains(&gesture)
+	}
+}
+
+// Something that can be down or up.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Binding {
+	Key(KeyboardKey),
+	Mouse(MouseButton),
+	GamepadButton(GamepadButton),
+	// down while the axis is pushed past `InputMap::axis_threshold` in the given direction
+	GamepadAxis { axis: GamepadAxis, positive: bool },
+	Gesture(Gesture),
+	// down while all of them are down, e.g. ctrl+s
+	Chord(Vec<Binding>),
+}
+
+// Something that gives a value in -1..1.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AxisBinding {
+	// -1 while `negative` is down, 1 while `positive` is down, 0 for both or neither
+	Buttons { negative: Binding, positive: Binding },
+	Gamepad(GamepadAxis),
+	MouseWheel,
+}
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct ActionState {
+	pub down: bool,
+	// went down this update
+	pub pressed: bool,
+	// went up this update
+	pub released: bool,
+	// on the press and then every `InputMap::repeat_interval` once held for `InputMap::repeat_delay`, for menus
+	pub repeated: bool,
+	// seconds the action has been down
+	pub held_time: f32,
+	repeat_time: f32,
+}
+
+#[derive(Clone, Default, Debug)]
+struct Action {
+	bindings: Vec<Binding>,
+	state: ActionState,
+}
+
+#[derive(Clone, Debug)]
+struct Axis {
+	bindings: Vec<AxisBinding>,
+	dead_zone: f32,
+	value: f32,
+}
+
+impl Default for Axis {
+	fn default() -> Self {
+		Self {
+			bindings: Vec::new(),
+			dead_zone: DEFAULT_DEAD_ZONE,
+			value: 0.0,
+		}
+	}
+}
+
+const DEFAULT_DEAD_ZONE: f32 = 0.15;
+
+// Named actions and axes bound to any number of inputs. Call `update` once per frame, then ask for
+// the actions by name. Unknown names are never down and always 0.
+#[derive(Clone, Debug)]
+pub struct InputMap {
+	// which gamepad the gamepad bindings read
+	pub gamepad: i32,
+	pub axis_threshold: f32,
+	pub repeat_delay: f32,
+	pub repeat_interval: f32,
+	// sorted so saved files are stable
+	actions: BTreeMap<String, Action>,
+	axes: BTreeMap<String, Axis>,
+}
+
+impl Default for InputMap {
+	fn default() -> Self {
+		Self {
+			gamepad: 0,
+			axis_threshold: 0.5,
+			repeat_delay: 0.4,
+			repeat_interval: 0.08,
+			actions: BTreeMap::new(),
+			axes: BTreeMap::new(),
+		}
+	}
+}
+
+impl InputMap {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	// An empty chord could never be down and isn't bound.
+	pub fn bind(&mut self, action: &str, binding: Binding) -> &mut Self {
+		let Some(binding) = flatten(binding) else {
+			return self;
+		};
+		let bindings = &mut self.actions.entry(action.to_string()).or_default().bindings;
+		if !bindings.contains(&binding) {
+			bindings.push(binding);
+		}
+		self
+	}
+
+	pub fn bind_axis(&mut self, axis: &str, binding: AxisBinding) -> &mut Self {
+		let binding = match binding {
+			AxisBinding::Buttons { negative, positive } => match (flatten(negative), flatten(positive)) {
+				(Some(negative), Some(positive)) => AxisBinding::Buttons { negative, positive },
+				_ => return self,
+			},
+			binding => binding,
+		};
+		let bindings = &mut self.axes.entry(axis.to_string()).or_default().bindings;
+		if !bindings.contains(&binding) {
+			bindings.push(binding);
+		}
+		self
+	}
+
+	pub fn unbind(&mut self, action: &str, binding: &Binding) {
+		if let Some(action) = self.actions.get_mut(action) {
+			action.bindings.retain(|b| b != binding);
+		}
+	}
+
+	// Removes every binding of the action, for rebinding it from scratch.
+	pub fn clear_action(&mut self, action: &str) {
+		if let Some(action) = self.actions.get_mut(action) {
+			action.bindings.clear();
+		}
+	}
+
+	pub fn clear_axis(&mut self, axis: &str) {
+		if let Some(axis) = self.axes.get_mut(axis) {
+			axis.bindings.clear();
+		}
+	}
+
+	pub fn bindings(&self, action: &str) -> &[Binding] {
+		self.actions.get(action).map_or(&[], |action| &action.bindings)
+	}
+
+	pub fn axis_bindings(&self, axis: &str) -> &[AxisBinding] {
+		self.axes.get(axis).map_or(&[], |axis| &axis.bindings)
+	}
+
+	// Gamepad values closer to 0 than this read as 0, the rest is rescaled to still cover 0..1.
+	pub fn set_dead_zone(&mut self, axis: &str, dead_zone: f32) {
+		self.axes.entry(axis.to_string()).or_default().dead_zone = dead_zone.clamp(0.0, 0.99);
+	}
+
+	pub fn update(&mut self, source: &(impl InputSource + ?Sized), delta_time: f32) {
+		let (gamepad, threshold) = (self.gamepad, self.axis_threshold);
+		for action in self.actions.values_mut() {
+			let down = action.bindings.iter().any(|binding| is_down(gamepad, threshold, binding, source));
+			let state = &mut action.state;
+			state.pressed = down && !state.down;
+			state.released = !down && state.down;
+			state.down = down;
+			state.repeated = state.pressed;
+			if !down {
+				state.held_time = 0.0;
+				state.repeat_time = 0.0;
+				continue;
+			}
+			if !state.pressed {
+				state.held_time += delta_time;
+				if state.held_time >= self.repeat_delay {
+					state.repeat_time += delta_time;
+					if state.repeat_time >= self.repeat_interval {
+						state.repeat_time %= self.repeat_interval.max(f32::EPSILON);
+						state.repeated = true;
+					}
+				}
+			}
+		}
+
+		for axis in self.axes.values_mut() {
+			// the binding pushed furthest wins
+			axis.value = axis
+				.bindings
+				.iter()
+				.map(|binding| match binding {
+					AxisBinding::Buttons { negative, positive } => {
+						is_down(gamepad, threshold, positive, source) as i32 as f32 - is_down(gamepad, threshold, negative, source) as i32 as f32
+					}
+					AxisBinding::Gamepad(gamepad_axis) => apply_dead_zone(source.gamepad_axis(gamepad, *gamepad_axis), axis.dead_zone),
+					AxisBinding::MouseWheel => source.mouse_wheel(),
+				})
+				.fold(0.0f32, |value, v| if v.abs() > value.abs() { v } else { value })
+				.clamp(-1.0, 1.0);
+		}
+	}
+
+	pub fn state(&self, action: &str) -> ActionState {
+		self.actions.get(action).map(|action| action.state).unwrap_or_default()
+	}
+
+	pub fn down(&self, action: &str) -> bool {
+		self.state(action).down
+	}
+
+	pub fn pressed(&self, action: &str) -> bool {
+		self.state(action).pressed
+	}
+
+	pub fn released(&self, action: &str) -> bool {
+		self.state(action).released
+	}
+
+	pub fn repeated(&self, action: &str) -> bool {
+		self.state(action).repeated
+	}
+
+	pub fn axis(&self, axis: &str) -> f32 {
+		self.axes.get(axis).map_or(0.0, |axis| axis.value)
+	}
+
+	// Two axes as a vector no longer than 1, so diagonals are not faster.
+	pub fn axis_pair(&self, x: &str, y: &str) -> Vector2 {
+		let (x, y) = (self.axis(x), self.axis(y));
+		let length = x.hypot(y);
+		if length > 1.0 {
+			Vector2 {
+				x: x / length,
+				y: y / length,
+			}
+		} else {
+			Vector2 { x, y }
+		}
+	}
+
+	// Writes the bindings as text, one per line:
+	//   action jump key:SPACE
+	//   action save chord:key:LEFT_CONTROL+key:S
+	//   axis move_x buttons:key:LEFT,key:RIGHT
+	//   axis move_x gamepad:LEFT_X
+	//   dead_zone move_x 0.2
+	pub fn save(&self, writer: &mut impl Write) -> io::Result<()> {
+		for (name, action) in &self.actions {
+			for binding in &action.bindings {
+				writeln!(writer, "action {name} {}", binding_to_string(binding))?;
+			}
+		}
+		for (name, axis) in &self.axes {
+			for binding in &axis.bindings {
+				let binding = match binding {
+					AxisBinding::Buttons { negative, positive } => {
+						format!("buttons:{},{}", binding_to_string(negative), binding_to_string(positive))
+					}
+					AxisBinding::Gamepad(gamepad_axis) => format!("gamepad:{}", name_of(GAMEPAD_AXES, *gamepad_axis)),
+					AxisBinding::MouseWheel => "wheel".to_string(),
+				};
+				writeln!(writer, "axis {name} {binding}")?;
+			}
+			if axis.dead_zone != DEFAULT_DEAD_ZONE {
+				writeln!(writer, "dead_zone {name} {}", axis.dead_zone)?;
+			}
+		}
+		Ok(())
+	}
+
+	// Replaces all bindings with the ones read, see `save` for the format. Empty lines and lines
+	// starting with # are skipped. On error the map is left unchanged.
+	pub fn load(&mut self, reader: impl BufRead) -> io::Result<()> {
+		let mut loaded = InputMap {
+			actions: BTreeMap::new(),
+			axes: BTreeMap::new(),
+			..self.clone()
+		};
+		for (number, line) in reader.lines().enumerate() {
+			let line = line?;
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+			let error = |message: &str| invalid_data(format!("line {}: {message}: {line}", number + 1));
+			let mut words = line.split_whitespace();
+			let (Some(kind), Some(name), Some(value), None) = (words.next(), words.next(), words.next(), words.next()) else {
+				return Err(error("expected three words"));
+			};
+			match kind {
+				"action" => {
+					let binding = parse_binding(value).ok_or_else(|| error("bad binding"))?;
+					loaded.bind(name, binding);
+				}
+				"axis" => {
+					let binding = parse_axis_binding(value).ok_or_else(|| error("bad axis binding"))?;
+					loaded.bind_axis(name, binding);
+				}
+				"dead_zone" => {
+					let dead_zone = value.parse().map_err(|_| error("bad dead zone"))?;
+					loaded.set_dead_zone(name, dead_zone);
+				}
+				_ => return Err(error("unknown entry")),
+			}
+		}
+		*self = loaded;
+		Ok(())
+	}
+}
+
+fn is_down(gamepad: i32, threshold: f32, binding: &Binding, source: &(impl InputSource + ?Sized)) -> bool {
+	match binding {
+		Binding::Key(key) => source.key_down(*key),
+		Binding::Mouse(button) => source.mouse_button_down(*button),
+		Binding::GamepadButton(button) => source.gamepad_button_down(gamepad, *button),
+		Binding::GamepadAxis { axis, positive } => {
+			let value = source.gamepad_axis(gamepad, *axis);
+			if *positive {
+				value >= threshold
+			} else {
+				value <= -threshold
+			}
+		}
+		Binding::Gesture(gesture) => source.gesture_detected(*gesture),
+		Binding::Chord(bindings) => !bindings.is_empty() && bindings.iter().all(|binding| is_down(gamepad, threshold, binding, source)),
+	}
+}
+
+// Chords inside a chord are merged into it, so it saves the way it loads. None for a chord with
+// nothing in it.
+fn flatten(binding: Binding) -> Option<Binding> {
+	let Binding::Chord(bindings) = binding else {
+		return Some(binding);
+	};
+	let mut flat = Vec::new();
+	for binding in bindings {
+		match flatten(binding)? {
+			Binding::Chord(inner) => flat.extend(inner),
+			binding => flat.push(binding),
+		}
+	}
+	(!flat.is_empty()).then_some(Binding::Chord(flat))
+}
+
+fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+	if value.abs() < dead_zone {
+		0.0
+	} else {
+		value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone)
+	}
+}
+
+fn binding_to_string(binding: &Binding) -> String {
+	match binding {
+		Binding::Key(key) => format!("key:{}", name_of(KEYS, *key)),
+		Binding::Mouse(button) => format!("mouse:{}", name_of(MOUSE_BUTTONS, *button)),
+		Binding::GamepadButton(button) => format!("gamepad:{}", name_of(GAMEPAD_BUTTONS, *button)),
+		Binding::GamepadAxis { axis, positive } => {
+			format!("gamepad_axis:{}{}", if *positive { '+' } else { '-' }, name_of(GAMEPAD_AXES, *axis))
+		}
+		Binding::Gesture(gesture) => format!("gesture:{}", name_of(GESTURES, *gesture)),
+		Binding::Chord(bindings) => format!("chord:{}", bindings.iter().map(binding_to_string).collect::<Vec<_>>().join("+")),
+	}
+}
+
+fn parse_binding(text: &str) -> Option<Binding> {
+	let (kind, value) = text.split_once(':')?;
+	Some(match kind {
+		"key" => Binding::Key(code_of(KEYS, value)?),
+		"mouse" => Binding::Mouse(code_of(MOUSE_BUTTONS, value)?),
+		"gamepad" => Binding::GamepadButton(code_of(GAMEPAD_BUTTONS, value)?),
+		"gamepad_axis" => {
+			let positive = match value.chars().next()? {
+				'+' => true,
+				'-' => false,
+				_ => return None,
+			};
+			Binding::GamepadAxis {
+				axis: code_of(GAMEPAD_AXES, &value[1..])?,
+				positive,
+			}
+		}
+		"gesture" => Binding::Gesture(code_of(GESTURES, value)?),
+		"chord" => Binding::Chord(value.split('+').map(parse_binding).collect::<Option<_>>()?),
+		_ => return None,
+	})
+}
+
+fn parse_axis_binding(text: &str) -> Option<AxisBinding> {
+	if text == "wheel" {
+		return Some(AxisBinding::MouseWheel);
+	}
+	let (kind, value) = text.split_once(':')?;
+	match kind {
+		"buttons" => {
+			let (negative, positive) = value.split_once(',')?;
+			Some(AxisBinding::Buttons {
+				negative: parse_binding(negative)?,
+				positive: parse_binding(positive)?,
+			})
+		}
+		"gamepad" => Some(AxisBinding::Gamepad(code_of(GAMEPAD_AXES, value)?)),
+		_ => None,
+	}
+}
+
+// codes without a name are written as numbers
+fn name_of(table: &[(&str, i32)], code: i32) -> String {
+	table
+		.iter()
+		.find(|(_, c)| *c == code)
+		.map_or_else(|| code.to_string(), |(name, _)| name.to_string())
+}
+
+fn code_of(table: &[(&str, i32)], name: &str) -> Option<i32> {
+	table
+		.iter()
+		.find(|(n, _)| *n == name)
+		.map(|(_, code)| *code)
+		.or_else(|| name.parse().ok())
+}
+
+fn invalid_data(message: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// names used in the config file
+const KEYS: &[(&str, KeyboardKey)] = &[
+	("APOSTROPHE", KeyboardKey_KEY_APOSTROPHE),
+	("COMMA", KeyboardKey_KEY_COMMA),
+	("MINUS", KeyboardKey_KEY_MINUS),
+	("PERIOD", KeyboardKey_KEY_PERIOD),
+	("SLASH", KeyboardKey_KEY_SLASH),
+	("ZERO", KeyboardKey_KEY_ZERO),
+	("ONE", KeyboardKey_KEY_ONE),
+	("TWO", KeyboardKey_KEY_TWO),
+	("THREE", KeyboardKey_KEY_THREE),
+	("FOUR", KeyboardKey_KEY_FOUR),
+	("FIVE", KeyboardKey_KEY_FIVE),
+	("SIX", KeyboardKey_KEY_SIX),
+	("SEVEN", KeyboardKey_KEY_SEVEN),
+	("EIGHT", KeyboardKey_KEY_EIGHT),
+	("NINE", KeyboardKey_KEY_NINE),
+	("SEMICOLON", KeyboardKey_KEY_SEMICOLON),
+	("EQUAL", KeyboardKey_KEY_EQUAL),
+	("A", KeyboardKey_KEY_A),
+	("B", KeyboardKey_KEY_B),
+	("C", KeyboardKey_KEY_C),
+	("D", KeyboardKey_KEY_D),
+	("E", KeyboardKey_KEY_E),
+	("F", KeyboardKey_KEY_F),
+	("G", KeyboardKey_KEY_G),
+	("H", KeyboardKey_KEY_H),
+	("I", KeyboardKey_KEY_I),
+	("J", KeyboardKey_KEY_J),
+	("K", KeyboardKey_KEY_K),
+	("L", KeyboardKey_KEY_L),
+	("M", KeyboardKey_KEY_M),
+	("N", KeyboardKey_KEY_N),
+	("O", KeyboardKey_KEY_O),
+	("P", KeyboardKey_KEY_P),
+	("Q", KeyboardKey_KEY_Q),
+	("R", KeyboardKey_KEY_R),
+	("S", KeyboardKey_KEY_S),
+	("T", KeyboardKey_KEY_T),
+	("U", KeyboardKey_KEY_U),
+	("V", KeyboardKey_KEY_V),
+	("W", KeyboardKey_KEY_W),
+	("X", KeyboardKey_KEY_X),
+	("Y", KeyboardKey_KEY_Y),
+	("Z", KeyboardKey_KEY_Z),
+	("LEFT_BRACKET", KeyboardKey_KEY_LEFT_BRACKET),
+	("BACKSLASH", KeyboardKey_KEY_BACKSLASH),
+	("RIGHT_BRACKET", KeyboardKey_KEY_RIGHT_BRACKET),
+	("GRAVE", KeyboardKey_KEY_GRAVE),
+	("SPACE", KeyboardKey_KEY_SPACE),
+	("ESCAPE", KeyboardKey_KEY_ESCAPE),
+	("ENTER", KeyboardKey_KEY_ENTER),
+	("TAB", KeyboardKey_KEY_TAB),
+	("BACKSPACE", KeyboardKey_KEY_BACKSPACE),
+	("INSERT", KeyboardKey_KEY_INSERT),
+	("DELETE", KeyboardKey_KEY_DELETE),
+	("RIGHT", KeyboardKey_KEY_RIGHT),
+	("LEFT", KeyboardKey_KEY_LEFT),
+	("DOWN", KeyboardKey_KEY_DOWN),
+	("UP", KeyboardKey_KEY_UP),
+	("PAGE_UP", KeyboardKey_KEY_PAGE_UP),
+	("PAGE_DOWN", KeyboardKey_KEY_PAGE_DOWN),
+	("HOME", KeyboardKey_KEY_HOME),
+	("END", KeyboardKey_KEY_END),
+	("CAPS_LOCK", KeyboardKey_KEY_CAPS_LOCK),
+	("SCROLL_LOCK", KeyboardKey_KEY_SCROLL_LOCK),
+	("NUM_LOCK", KeyboardKey_KEY_NUM_LOCK),
+	("PRINT_SCREEN", KeyboardKey_KEY_PRINT_SCREEN),
+	("PAUSE", KeyboardKey_KEY_PAUSE),
+	("F1", KeyboardKey_KEY_F1),
+	("F2", KeyboardKey_KEY_F2),
+	("F3", KeyboardKey_KEY_F3),
+	("F4", KeyboardKey_KEY_F4),
+	("F5", KeyboardKey_KEY_F5),
+	("F6", KeyboardKey_KEY_F6),
+	("F7", KeyboardKey_KEY_F7),
+	("F8", KeyboardKey_KEY_F8),
+	("F9", KeyboardKey_KEY_F9),
+	("F10", KeyboardKey_KEY_F10),
+	("F11", KeyboardKey_KEY_F11),
+	("F12", KeyboardKey_KEY_F12),
+	("LEFT_SHIFT", KeyboardKey_KEY_LEFT_SHIFT),
+	("LEFT_CONTROL", KeyboardKey_KEY_LEFT_CONTROL),
+	("LEFT_ALT", KeyboardKey_KEY_LEFT_ALT),
+	("LEFT_SUPER", KeyboardKey_KEY_LEFT_SUPER),
+	("RIGHT_SHIFT", KeyboardKey_KEY_RIGHT_SHIFT),
+	("RIGHT_CONTROL", KeyboardKey_KEY_RIGHT_CONTROL),
+	("RIGHT_ALT", KeyboardKey_KEY_RIGHT_ALT),
+	("RIGHT_SUPER", KeyboardKey_KEY_RIGHT_SUPER),
+	("KB_MENU", KeyboardKey_KEY_KB_MENU),
+	("KP_0", KeyboardKey_KEY_KP_0),
+	("KP_1", KeyboardKey_KEY_KP_1),
+	("KP_2", KeyboardKey_KEY_KP_2),
+	("KP_3", KeyboardKey_KEY_KP_3),
+	("KP_4", KeyboardKey_KEY_KP_4),
+	("KP_5", KeyboardKey_KEY_KP_5),
+	("KP_6", KeyboardKey_KEY_KP_6),
+	("KP_7", KeyboardKey_KEY_KP_7),
+	("KP_8", KeyboardKey_KEY_KP_8),
+	("KP_9", KeyboardKey_KEY_KP_9),
+	("KP_DECIMAL", KeyboardKey_KEY_KP_DECIMAL),
+	("KP_DIVIDE", KeyboardKey_KEY_KP_DIVIDE),
+	("KP_MULTIPLY", KeyboardKey_KEY_KP_MULTIPLY),
+	("KP_SUBTRACT", KeyboardKey_KEY_KP_SUBTRACT),
+	("KP_ADD", KeyboardKey_KEY_KP_ADD),
+	("KP_ENTER", KeyboardKey_KEY_KP_ENTER),
+	("KP_EQUAL", KeyboardKey_KEY_KP_EQUAL),
+	// android keys last, KEY_MENU has the same code as KEY_R
+	("BACK", KeyboardKey_KEY_BACK),
+	("MENU", KeyboardKey_KEY_MENU),
+	("VOLUME_UP", KeyboardKey_KEY_VOLUME_UP),
+	("VOLUME_DOWN", KeyboardKey_KEY_VOLUME_DOWN),
+];
+
+const MOUSE_BUTTONS: &[(&str, MouseButton)] = &[
+	("LEFT", MouseButton_MOUSE_BUTTON_LEFT),
+	("RIGHT", MouseButton_MOUSE_BUTTON_RIGHT),
+	("MIDDLE", MouseButton_MOUSE_BUTTON_MIDDLE),
+	("SIDE", MouseButton_MOUSE_BUTTON_SIDE),
+	("EXTRA", MouseButton_MOUSE_BUTTON_EXTRA),
+	("FORWARD", MouseButton_MOUSE_BUTTON_FORWARD),
+	("BACK", MouseButton_MOUSE_BUTTON_BACK),
+];
+
+const GAMEPAD_BUTTONS: &[(&str, GamepadButton)] = &[
+	("LEFT_FACE_UP", GamepadButton_GAMEPAD_BUTTON_LEFT_FACE_UP),
+	("LEFT_FACE_RIGHT", GamepadButton_GAMEPAD_BUTTON_LEFT_FACE_RIGHT),
+	("LEFT_FACE_DOWN", GamepadButton_GAMEPAD_BUTTON_LEFT_FACE_DOWN),
+	("LEFT_FACE_LEFT", GamepadButton_GAMEPAD_BUTTON_LEFT_FACE_LEFT),
+	("RIGHT_FACE_UP", GamepadButton_GAMEPAD_BUTTON_RIGHT_FACE_UP),
+	("RIGHT_FACE_RIGHT", GamepadButton_GAMEPAD_BUTTON_RIGHT_FACE_RIGHT),
+	("RIGHT_FACE_DOWN", GamepadButton_GAMEPAD_BUTTON_RIGHT_FACE_DOWN),
+	("RIGHT_FACE_LEFT", GamepadButton_GAMEPAD_BUTTON_RIGHT_FACE_LEFT),
+	("LEFT_TRIGGER_1", GamepadButton_GAMEPAD_BUTTON_LEFT_TRIGGER_1),
+	("LEFT_TRIGGER_2", GamepadButton_GAMEPAD_BUTTON_LEFT_TRIGGER_2),
+	("RIGHT_TRIGGER_1", GamepadButton_GAMEPAD_BUTTON_RIGHT_TRIGGER_1),
+	("RIGHT_TRIGGER_2", GamepadButton_GAMEPAD_BUTTON_RIGHT_TRIGGER_2),
+	("MIDDLE_LEFT", GamepadButton_GAMEPAD_BUTTON_MIDDLE_LEFT),
+	("MIDDLE", GamepadButton_GAMEPAD_BUTTON_MIDDLE),
+	("MIDDLE_RIGHT", GamepadButton_GAMEPAD_BUTTON_MIDDLE_RIGHT),
+	("LEFT_THUMB", GamepadButton_GAMEPAD_BUTTON_LEFT_THUMB),
+	("RIGHT_THUMB", GamepadButton_GAMEPAD_BUTTON_RIGHT_THUMB),
+];
+
+const GAMEPAD_AXES: &[(&str, GamepadAxis)] = &[
+	("LEFT_X", GamepadAxis_GAMEPAD_AXIS_LEFT_X),
+	("LEFT_Y", GamepadAxis_GAMEPAD_AXIS_LEFT_Y),
+	("RIGHT_X", GamepadAxis_GAMEPAD_AXIS_RIGHT_X),
+	("RIGHT_Y", GamepadAxis_GAMEPAD_AXIS_RIGHT_Y),
+	("LEFT_TRIGGER", GamepadAxis_GAMEPAD_AXIS_LEFT_TRIGGER),
+	("RIGHT_TRIGGER", GamepadAxis_GAMEPAD_AXIS_RIGHT_TRIGGER),
+];
+
+const GESTURES: &[(&str, Gesture)] = &[
+	("TAP", Gesture_GESTURE_TAP),
+	("DOUBLETAP", Gesture_GESTURE_DOUBLETAP),
+	("HOLD", Gesture_GESTURE_HOLD),
+	("DRAG", Gesture_GESTURE_DRAG),
+	("SWIPE_RIGHT", Gesture_GESTURE_SWIPE_RIGHT),
+	("SWIPE_LEFT", Gesture_GESTURE_SWIPE_LEFT),
+	("SWIPE_UP", Gesture_GESTURE_SWIPE_UP),
+	("SWIPE_DOWN", Gesture_GESTURE_SWIPE_DOWN),
+	("PINCH_IN", Gesture_GESTURE_PINCH_IN),
+	("PINCH_OUT", Gesture_GESTURE_PINCH_OUT),
+];
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// one of every kind of binding
+	fn bound() -> InputMap {
+		let mut input = InputMap::new();
+		input
+			.bind("jump", Binding::Key(KeyboardKey_KEY_SPACE))
+			.bind("jump", Binding::GamepadButton(GamepadButton_GAMEPAD_BUTTON_RIGHT_FACE_DOWN))
+			.bind("fire", Binding::Mouse(MouseButton_MOUSE_BUTTON_LEFT))
+			.bind(
+				"fire",
+				Binding::GamepadAxis {
+					axis: GamepadAxis_GAMEPAD_AXIS_RIGHT_TRIGGER,
+					positive: true,
+				},
+			)
+			.bind(
+				"crouch",
+				Binding::GamepadAxis {
+					axis: GamepadAxis_GAMEPAD_AXIS_LEFT_Y,
+					positive: false,
+				},
+			)
+			.bind("menu", Binding::Gesture(Gesture_GESTURE_DOUBLETAP))
+			.bind(
+				"save",
+				Binding::Chord(vec![Binding::Key(KeyboardKey_KEY_LEFT_CONTROL), Binding::Key(KeyboardKey_KEY_S)]),
+			)
+			// no name in the tables
+			.bind("odd", Binding::Key(1234))
+			.bind_axis(
+				"move_x",
+				AxisBinding::Buttons {
+					negative: Binding::Key(KeyboardKey_KEY_LEFT),
+					positive: Binding::Key(KeyboardKey_KEY_RIGHT),
+				},
+			)
+			.bind_axis("move_x", AxisBinding::Gamepad(GamepadAxis_GAMEPAD_AXIS_LEFT_X))
+			.bind_axis("zoom", AxisBinding::MouseWheel);
+		input.set_dead_zone("move_x", 0.25);
+		input
+	}
+
+	fn saved(input: &InputMap) -> String {
+		let mut text = Vec::new();
+		input.save(&mut text).unwrap();
+		String::from_utf8(text).unwrap()
+	}
+
+	#[test]
+	fn bindings_survive_save_and_load() {
+		let input = bound();
+		let text = saved(&input);
+		let mut loaded = InputMap::new();
+		loaded.load(text.as_bytes()).unwrap();
+
+		for action in ["jump", "fire", "crouch", "menu", "save", "odd"] {
+			assert_eq!(loaded.bindings(action), input.bindings(action), "{action}");
+		}
+		for axis in ["move_x", "zoom"] {
+			assert_eq!(loaded.axis_bindings(axis), input.axis_bindings(axis), "{axis}");
+		}
+		assert_eq!(saved(&loaded), text);
+	}
+
+	#[test]
+	fn saved_text_is_readable_and_sorted() {
+		assert_eq!(
+			saved(&bound()),
+			"action crouch gamepad_axis:-LEFT_Y
+action fire mouse:LEFT
+action fire gamepad_axis:+RIGHT_TRIGGER
+action jump key:SPACE
+action jump gamepad:RIGHT_FACE_DOWN
+action menu gesture:DOUBLETAP
+action odd key:1234
+action save chord:key:LEFT_CONTROL+key:S
+axis move_x buttons:key:LEFT,key:RIGHT
+axis move_x gamepad:LEFT_X
+dead_zone move_x 0.25
+axis zoom wheel
+"
+		);
+	}
+
+	#[test]
+	fn load_replaces_bindings_and_skips_comments() {
+		let mut input = bound();
+		input.gamepad = 2;
+		input
+			.load("# controls\n\n  action jump key:W  \naxis look gamepad:RIGHT_Y\n".as_bytes())
+			.unwrap();
+		assert_eq!(input.bindings("jump"), [Binding::Key(KeyboardKey_KEY_W)]);
+		assert!(input.bindings("fire").is_empty());
+		assert_eq!(input.axis_bindings("look"), [AxisBinding::Gamepad(GamepadAxis_GAMEPAD_AXIS_RIGHT_Y)]);
+		// settings other than bindings stay
+		assert_eq!(input.gamepad, 2);
+	}
+
+	#[test]
+	fn bad_files_leave_the_map_alone() {
+		for text in [
+			"action jump",
+			"action jump key:SPACE extra",
+			"action jump key:NOT_A_KEY",
+			"action jump keyboard:SPACE",
+			"action jump gamepad_axis:LEFT_X",
+			"action save chord:key:S+",
+			"axis move buttons:key:LEFT",
+			"axis move gamepad:SIDEWAYS",
+			"dead_zone move lots",
+			"binding jump key:SPACE",
+		] {
+			let mut input = bound();
+			let error = input.load(format!("action jump key:W\n{text}\n").as_bytes()).unwrap_err();
+			assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{text}");
+			assert!(error.to_string().starts_with("line 2: "), "{error}");
+			assert_eq!(saved(&input), saved(&bound()), "{text}");
+		}
+	}
+
+	#[test]
+	fn actions_and_axes_follow_the_source() {
+		let mut input = bound();
+		let mut source = SyntheticInput::default();
+		source.set_key(KeyboardKey_KEY_LEFT_CONTROL, true);
+		input.update(&source, 0.1);
+		assert!(!input.down("save"));
+
+		source.set_key(KeyboardKey_KEY_S, true);
+		source.set_gamepad_axis(0, GamepadAxis_GAMEPAD_AXIS_RIGHT_TRIGGER, 0.75);
+		source.set_gamepad_axis(0, GamepadAxis_GAMEPAD_AXIS_LEFT_X, -0.625);
+		input.update(&source, 0.1);
+		assert!(input.pressed("save") && input.down("fire"));
+		// past the 0.25 dead zone, rescaled
+		assert_eq!(input.axis("move_x"), -0.5);
+
+		// the keys win over the smaller stick value
+		source.set_key(KeyboardKey_KEY_RIGHT, true);
+		input.update(&source, 0.1);
+		assert_eq!(input.axis("move_x"), 1.0);
+		assert!(!input.pressed("save"));
+	}
+
+	#[test]
+	fn empty_chords_are_not_bound_and_nested_ones_are_merged() {
+		let (ctrl, shift, s) = (
+			Binding::Key(KeyboardKey_KEY_LEFT_CONTROL),
+			Binding::Key(KeyboardKey_KEY_LEFT_SHIFT),
+			Binding::Key(KeyboardKey_KEY_S),
+		);
+		let mut input = InputMap::new();
+		input
+			.bind("nothing", Binding::Chord(Vec::new()))
+			.bind("nothing", Binding::Chord(vec![ctrl.clone(), Binding::Chord(Vec::new())]))
+			.bind(
+				"save_as",
+				Binding::Chord(vec![Binding::Chord(vec![ctrl.clone(), shift.clone()]), s.clone()]),
+			)
+			.bind("move", Binding::Chord(vec![Binding::Chord(vec![Binding::Chord(vec![ctrl.clone()])])]))
+			.bind_axis(
+				"zoom",
+				AxisBinding::Buttons {
+					negative: Binding::Chord(Vec::new()),
+					positive: s.clone(),
+				},
+			);
+		assert!(input.bindings("nothing").is_empty());
+		assert!(input.axis_bindings("zoom").is_empty());
+		assert_eq!(input.bindings("save_as"), [Binding::Chord(vec![ctrl.clone(), shift, s])]);
+		assert_eq!(input.bindings("move"), [Binding::Chord(vec![ctrl])]);
+
+		let text = saved(&input);
+		assert_eq!(
+			text,
+			"action move chord:key:LEFT_CONTROL\naction save_as chord:key:LEFT_CONTROL+key:LEFT_SHIFT+key:S\n"
+		);
+		let mut loaded = InputMap::new();
+		loaded.load(text.as_bytes()).unwrap();
+		assert_eq!(saved(&loaded), text);
+	}
+
+	#[test]
+	fn held_actions_repeat_after_the_delay() {
+		let mut input = InputMap::new();
+		input.repeat_delay = 0.5;
+		input.repeat_interval = 0.25;
+		input.bind("down", Binding::Key(KeyboardKey_KEY_DOWN));
+		let mut source = SyntheticInput::default();
+		source.set_key(KeyboardKey_KEY_DOWN, true);
+
+		let mut frames = Vec::new();
+		for _ in 0..9 {
+			input.update(&source, 0.125);
+			let state = input.state("down");
+			frames.push((state.repeated, state.held_time));
+		}
+		assert_eq!(
+			frames,
+			[
+				(true, 0.0),
+				(false, 0.125),
+				(false, 0.25),
+				(false, 0.375),
+				(false, 0.5),
+				(true, 0.625),
+				(false, 0.75),
+				(true, 0.875),
+				(false, 1.0),
+			]
+		);
+		assert!(input.repeated("down") == frames[8].0 && input.down("down"));
+
+		// letting go starts over
+		source.set_key(KeyboardKey_KEY_DOWN, false);
+		input.update(&source, 0.125);
+		let state = input.state("down");
+		assert!(state.released && !state.repeated);
+		assert_eq!(state.held_time, 0.0);
+		source.set_key(KeyboardKey_KEY_DOWN, true);
+		input.update(&source, 0.125);
+		assert!(input.repeated("down"));
+		for _ in 0..4 {
+			input.update(&source, 0.125);
+			assert!(!input.repeated("down"));
+		}
+		assert_eq!(input.state("down").held_time, 0.5);
+		assert_eq!(input.state("unbound"), ActionState::default());
+	}
+}
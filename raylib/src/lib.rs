@@ -1,9 +1,64 @@
 #![allow(non_upper_case_globals)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
+#![allow(clippy::approx_constant)]
 
 include!("../bindings.rs");
 
+pub mod animation;
+pub mod assets;
+pub mod audio;
+pub mod audio_stream;
+mod audio_sys;
+pub mod backend;
+pub mod camera;
+pub mod console;
+pub mod debug;
+pub mod dsp;
+pub mod fog;
+pub mod fog_render;
+pub mod fov;
+pub mod game_loop;
+pub mod gui;
+pub mod input_map;
+pub mod mapgen;
+pub mod mixer;
+pub mod particle;
+pub mod path;
+pub mod profile;
+pub mod raster;
+pub mod recording;
+pub mod replay;
+pub mod rlgl;
+pub mod rng;
+pub mod screen;
+pub mod tilemap;
+pub mod tween;
+
+impl PartialEq for Color {
+    fn eq(&self, other: &Self) -> bool {
+        self.r == other.r && self.g == other.g && self.b == other.b && self.a == other.a
+    }
+}
+
+impl PartialEq for Vector2 {
+    fn eq(&self, other: &Self) -> bool {
+        self.x == other.x && self.y == other.y
+    }
+}
+
+impl PartialEq for Rectangle {
+    fn eq(&self, other: &Self) -> bool {
+        self.x == other.x && self.y == other.y && self.width == other.width && self.height == other.height
+    }
+}
+
+impl Default for Vector2 {
+    fn default() -> Self {
+        Vector2 { x: 0.0, y: 0.0 }
+    }
+}
+
 // raylib color defines
 pub const LIGHTGRAY: Color = Color {
     r: 200,
@@ -211,3 +266,18 @@ pub fn Vector2Scale(v: Vector2, scale: f32) -> Vector2 {
         y: v.y * scale,
     }
 }
+
+pub fn Vector2Lerp(v1: Vector2, v2: Vector2, amount: f32) -> Vector2 {
+    Vector2 {
+        x: v1.x + amount * (v2.x - v1.x),
+        y: v1.y + amount * (v2.y - v1.y),
+    }
+}
+
+// Same as Fade(), without calling into C.
+pub fn fade(color: Color, alpha: f32) -> Color {
+    Color {
+        a: (255.0 * alpha.clamp(0.0, 1.0)) as u8,
+        ..color
+    }
+}
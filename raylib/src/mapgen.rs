@@ -0,0 +1,601 @@
+use std::{collections::VecDeque, num::NonZeroU32};
+
+use crate::{path::PathGrid, rng::Rng, tilemap::TileMap};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Tile {
+	Wall,
+	Floor,
+}
+
+// Output of the generators, convert it into a game's own map with `to_ids` or by walking `TileMap`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TileGrid {
+	width: i32,
+	height: i32,
+	tiles: Box<[Tile]>,
+}
+
+impl TileMap for TileGrid {
+	fn width(&self) -> i32 {
+		self.width
+	}
+
+	fn height(&self) -> i32 {
+		self.height
+	}
+}
+
+impl PathGrid for TileGrid {
+	fn cost(&self, x: i32, y: i32) -> Option<NonZeroU32> {
+		self.is_floor(x, y).then_some(NonZeroU32::MIN)
+	}
+}
+
+impl TileGrid {
+	pub fn new(width: i32, height: i32, fill: Tile) -> Self {
+		Self {
+			width,
+			height,
+			tiles: vec![fill; (width.max(0) * height.max(0)) as usize].into_boxed_slice(),
+		}
+	}
+
+	// tiles outside the grid are walls
+	pub fn get(&self, x: i32, y: i32) -> Tile {
+		if self.in_bounds(x, y) {
+			self.tiles[self.index(x, y)]
+		} else {
+			Tile::Wall
+		}
+	}
+
+	pub fn set(&mut self, x: i32, y: i32, tile: Tile) {
+		if self.in_bounds(x, y) {
+			let i = self.index(x, y);
+			self.tiles[i] = tile;
+		}
+	}
+
+	pub fn is_floor(&self, x: i32, y: i32) -> bool {
+		self.get(x, y) == Tile::Floor
+	}
+
+	pub fn tiles(&self) -> &[Tile] {
+		&self.tiles
+	}
+
+	pub fn floor_count(&self) -> usize {
+		self.tiles.iter().filter(|tile| **tile == Tile::Floor).count()
+	}
+
+	// Row-major copy with a game specific value for walls and floors.
+	pub fn to_ids<T: Copy>(&self, wall: T, floor: T) -> Box<[T]> {
+		self
+			.tiles
+			.iter()
+			.map(|tile| match tile {
+				Tile::Wall => wall,
+				Tile::Floor => floor,
+			})
+			.collect()
+	}
+
+	// Closest tile of the given kind by manhattan distance, e.g. a floor tile to spawn on.
+	pub fn nearest(&self, x: i32, y: i32, tile: Tile) -> Option<(i32, i32)> {
+		(0..self.height)
+			.flat_map(|ty| (0..self.width).map(move |tx| (tx, ty)))
+			.filter(|&(tx, ty)| self.get(tx, ty) == tile)
+			.min_by_key(|&(tx, ty)| (tx - x).abs() + (ty - y).abs())
+	}
+
+	fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32, tile: Tile) {
+		for ty in y..y + height {
+			for tx in x..x + width {
+				self.set(tx, ty, tile);
+			}
+		}
+	}
+
+	// L shaped corridor, which leg comes first is random
+	fn carve_corridor(&mut self, from: (i32, i32), to: (i32, i32), rng: &mut Rng) {
+		let corner = if rng.chance(0.5) { (to.0, from.1) } else { (from.0, to.1) };
+		for (a, b) in [(from, corner), (corner, to)] {
+			for x in a.0.min(b.0)..=a.0.max(b.0) {
+				for y in a.1.min(b.1)..=a.1.max(b.1) {
+					self.set(x, y, Tile::Floor);
+				}
+			}
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CaveParams {
+	// chance of a tile starting as wall
+	pub fill: f32,
+	pub iterations: u32,
+	// a tile becomes wall when at least this many of the 9 tiles around it (itself included) are walls
+	pub wall_threshold: u32,
+}
+
+impl Default for CaveParams {
+	fn default() -> Self {
+		Self {
+			fill: 0.45,
+			iterations: 4,
+			wall_threshold: 5,
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BspParams {
+	// smallest room side, leaves are never split below room size plus walls
+	pub min_room: i32,
+	pub max_depth: u32,
+}
+
+impl Default for BspParams {
+	fn default() -> Self {
+		Self { min_room: 3, max_depth: 4 }
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DrunkardParams {
+	// fraction of the inner tiles to dig out
+	pub coverage: f32,
+	// stop even if the coverage was not reached
+	pub max_steps: u32,
+}
+
+impl Default for DrunkardParams {
+	fn default() -> Self {
+		Self {
+			coverage: 0.4,
+			max_steps: 100_000,
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct NoiseParams {
+	// size of the features in tiles
+	pub scale: f32,
+	pub octaves: u32,
+	// amplitude multiplier per octave
+	pub persistence: f32,
+	// frequency multiplier per octave
+	pub lacunarity: f32,
+	// noise above this value becomes wall
+	pub threshold: f32,
+}
+
+impl Default for NoiseParams {
+	fn default() -> Self {
+		Self {
+			scale: 8.0,
+			octaves: 4,
+			persistence: 0.5,
+			lacunarity: 2.0,
+			threshold: 0.55,
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Algorithm {
+	Caves(CaveParams),
+	Bsp(BspParams),
+	Drunkard(DrunkardParams),
+	Noise(NoiseParams),
+}
+
+// Generates a map surrounded by walls where every floor tile can reach every other one.
+// The same seed and parameters always give the same map.
+pub fn generate(width: i32, height: i32, seed: u64, algorithm: &Algorithm) -> TileGrid {
+	let mut rng = Rng::new(seed);
+	let mut grid = match algorithm {
+		Algorithm::Caves(params) => caves(width, height, params, &mut rng),
+		Algorithm::Bsp(params) => bsp(width, height, params, &mut rng),
+		Algorithm::Drunkard(params) => drunkard(width, height, params, &mut rng),
+		Algorithm::Noise(params) => noise(width, height, params, &mut rng),
+	};
+	connect_regions(&mut grid, &mut rng);
+	grid
+}
+
+fn caves(width: i32, height: i32, params: &CaveParams, rng: &mut Rng) -> TileGrid {
+	let mut grid = TileGrid::new(width, height, Tile::Wall);
+	for y in 1..height - 1 {
+		for x in 1..width - 1 {
+			if !rng.chance(params.fill) {
+				grid.set(x, y, Tile::Floor);
+			}
+		}
+	}
+
+	for _ in 0..params.iterations {
+		let previous = grid.clone();
+		for y in 1..height - 1 {
+			for x in 1..width - 1 {
+				let walls = (-1..=1)
+					.flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+					.filter(|(dx, dy)| previous.get(x + dx, y + dy) == Tile::Wall)
+					.count() as u32;
+				grid.set(x, y, if walls >= params.wall_threshold { Tile::Wall } else { Tile::Floor });
+			}
+		}
+	}
+	grid
+}
+
+#[derive(Clone, Copy)]
+struct Leaf {
+	x: i32,
+	y: i32,
+	width: i32,
+	height: i32,
+}
+
+fn bsp(width: i32, height: i32, params: &BspParams, rng: &mut Rng) -> TileGrid {
+	let mut grid = TileGrid::new(width, height, Tile::Wall);
+	let root = Leaf { x: 0, y: 0, width, height };
+	split_leaf(&mut grid, root, params.max_depth, params, rng);
+	grid
+}
+
+// Splits the leaf or places a room in it, returns the centre of one room inside it to hook corridors to.
+fn split_leaf(grid: &mut TileGrid, leaf: Leaf, depth: u32, params: &BspParams, rng: &mut Rng) -> Option<(i32, i32)> {
+	// a room plus a wall on each side
+	let min_leaf = params.min_room + 2;
+	let can_split_x = leaf.width >= min_leaf * 2;
+	let can_split_y = leaf.height >= min_leaf * 2;
+
+	if depth == 0 || (!can_split_x && !can_split_y) {
+		if leaf.width < min_leaf || leaf.height < min_leaf {
+			return None;
+		}
+		let room_width = rng.range_i32(params.min_room, leaf.width - 2);
+		let room_height = rng.range_i32(params.min_room, leaf.height - 2);
+		let x = rng.range_i32(leaf.x + 1, leaf.x + leaf.width - room_width - 1);
+		let y = rng.range_i32(leaf.y + 1, leaf.y + leaf.height - room_height - 1);
+		grid.fill_rect(x, y, room_width, room_height, Tile::Floor);
+		return Some((x + room_width / 2, y + room_height / 2));
+	}
+
+	// prefer cutting across the long side so rooms stay roughly square
+	let split_x = match (can_split_x, can_split_y) {
+		(true, false) => true,
+		(false, true) => false,
+		_ if leaf.width as f32 >= leaf.height as f32 * 1.25 => true,
+		_ if leaf.height as f32 >= leaf.width as f32 * 1.25 => false,
+		_ => rng.chance(0.5),
+	};
+
+	let (a, b) = if split_x {
+		let cut = rng.range_i32(min_leaf, leaf.width - min_leaf);
+		(
+			Leaf { width: cut, ..leaf },
+			Leaf {
+				x: leaf.x + cut,
+				width: leaf.width - cut,
+				..leaf
+			},
+		)
+	} else {
+		let cut = rng.range_i32(min_leaf, leaf.height - min_leaf);
+		(
+			Leaf { height: cut, ..leaf },
+			Leaf {
+				y: leaf.y + cut,
+				height: leaf.height - cut,
+				..leaf
+			},
+		)
+	};
+
+	let room_a = split_leaf(grid, a, depth - 1, params, rng);
+	let room_b = split_leaf(grid, b, depth - 1, params, rng);
+	match (room_a, room_b) {
+		(Some(from), Some(to)) => {
+			grid.carve_corridor(from, to, rng);
+			Some(if rng.chance(0.5) { from } else { to })
+		}
+		(room, None) | (None, room) => room,
+	}
+}
+
+fn drunkard(width: i32, height: i32, params: &DrunkardParams, rng: &mut Rng) -> TileGrid {
+	let mut grid = TileGrid::new(width, height, Tile::Wall);
+	if width < 3 || height < 3 {
+		return grid;
+	}
+
+	let target = ((width - 2) * (height - 2)) as f32 * params.coverage.clamp(0.0, 1.0);
+	let (mut x, mut y) = (width / 2, height / 2);
+	let mut dug = 0;
+	for _ in 0..params.max_steps {
+		if !grid.is_floor(x, y) {
+			grid.set(x, y, Tile::Floor);
+			dug += 1;
+		}
+		if dug as f32 >= target {
+			break;
+		}
+
+		let (dx, dy) = [(1, 0), (-1, 0), (0, 1), (0, -1)][rng.range_i32(0, 3) as usize];
+		x = (x + dx).clamp(1, width - 2);
+		y = (y + dy).clamp(1, height - 2);
+	}
+	grid
+}
+
+fn noise(width: i32, height: i32, params: &NoiseParams, rng: &mut Rng) -> TileGrid {
+	let heights = noise_map(width, height, rng.next_u64(), params);
+	let mut grid = TileGrid::new(width, height, Tile::Wall);
+	for y in 1..height - 1 {
+		for x in 1..width - 1 {
+			if heights[(y * width + x) as usize] <= params.threshold {
+				grid.set(x, y, Tile::Floor);
+			}
+		}
+	}
+	grid
+}
+
+// Fractal perlin noise in [0, 1] for every tile, row-major. Also usable on its own for height or biome maps.
+pub fn noise_map(width: i32, height: i32, seed: u64, params: &NoiseParams) -> Vec<f32> {
+	let perlin = Perlin::new(seed);
+	let mut heights = Vec::with_capacity((width.max(0) * height.max(0)) as usize);
+	for y in 0..height {
+		for x in 0..width {
+			let mut frequency = 1.0 / params.scale.max(f32::EPSILON);
+			let mut amplitude = 1.0;
+			let mut total = 0.0;
+			let mut range = 0.0;
+			for _ in 0..params.octaves.max(1) {
+				total += perlin.sample(x as f32 * frequency, y as f32 * frequency) * amplitude;
+				range += amplitude;
+				amplitude *= params.persistence;
+				frequency *= params.lacunarity;
+			}
+			heights.push((total / range * 0.5 + 0.5).clamp(0.0, 1.0));
+		}
+	}
+	heights
+}
+
+struct Perlin {
+	permutation: [u8; 512],
+}
+
+impl Perlin {
+	fn new(seed: u64) -> Self {
+		let mut table: Vec<u8> = (0..=255).collect();
+		Rng::new(seed).shuffle(&mut table);
+		let mut permutation = [0; 512];
+		for i in 0..512 {
+			permutation[i] = table[i & 255];
+		}
+		Self { permutation }
+	}
+
+	// roughly in [-1, 1]
+	fn sample(&self, x: f32, y: f32) -> f32 {
+		let (x0, y0) = (x.floor(), y.floor());
+		let (xi, yi) = ((x0 as i32 & 255) as usize, (y0 as i32 & 255) as usize);
+		let (xf, yf) = (x - x0, y - y0);
+		let (u, v) = (fade(xf), fade(yf));
+
+		let p = &self.permutation;
+		let aa = p[p[xi] as usize + yi];
+		let ab = p[p[xi] as usize + yi + 1];
+		let ba = p[p[xi + 1] as usize + yi];
+		let bb = p[p[xi + 1] as usize + yi + 1];
+
+		let x1 = lerp(gradient(aa, xf, yf), gradient(ba, xf - 1.0, yf), u);
+		let x2 = lerp(gradient(ab, xf, yf - 1.0), gradient(bb, xf - 1.0, yf - 1.0), u);
+		lerp(x1, x2, v) * std::f32::consts::SQRT_2
+	}
+}
+
+fn fade(t: f32) -> f32 {
+	t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+	a + (b - a) * t
+}
+
+fn gradient(hash: u8, x: f32, y: f32) -> f32 {
+	match hash & 7 {
+		0 => x + y,
+		1 => x - y,
+		2 => -x + y,
+		3 => -x - y,
+		4 => x,
+		5 => -x,
+		6 => y,
+		_ => -y,
+	}
+}
+
+// Floor regions (4-connected), largest first.
+pub fn regions(grid: &TileGrid) -> Vec<Vec<(i32, i32)>> {
+	let mut seen = vec![false; grid.tiles.len()];
+	let mut regions = Vec::new();
+	let mut queue = VecDeque::new();
+
+	for y in 0..grid.height {
+		for x in 0..grid.width {
+			if !grid.is_floor(x, y) || seen[grid.index(x, y)] {
+				continue;
+			}
+
+			let mut region = Vec::new();
+			seen[grid.index(x, y)] = true;
+			queue.push_back((x, y));
+			while let Some((cx, cy)) = queue.pop_front() {
+				region.push((cx, cy));
+				for (nx, ny) in [(cx + 1, cy), (cx - 1, cy), (cx, cy + 1), (cx, cy - 1)] {
+					if grid.is_floor(nx, ny) && !seen[grid.index(nx, ny)] {
+						seen[grid.index(nx, ny)] = true;
+						queue.push_back((nx, ny));
+					}
+				}
+			}
+			regions.push(region);
+		}
+	}
+
+	// stable sort keeps scan order between equal sizes, so the result stays deterministic
+	regions.sort_by_key(|region| std::cmp::Reverse(region.len()));
+	regions
+}
+
+pub fn is_connected(grid: &TileGrid) -> bool {
+	regions(grid).len() <= 1
+}
+
+// Digs corridors from every smaller region to the closest tile already connected to the largest one.
+pub fn connect_regions(grid: &mut TileGrid, rng: &mut Rng) {
+	let mut regions = regions(grid).into_iter();
+	let Some(mut connected) = regions.next() else { return };
+
+	for region in regions {
+		let from = region[rng.range_i32(0, region.len() as i32 - 1) as usize];
+		let to = *connected
+			.iter()
+			.min_by_key(|(x, y)| (x - from.0).abs() + (y - from.1).abs())
+			.expect("connected region is never empty");
+		grid.carve_corridor(from, to, rng);
+		connected.extend(region);
+	}
+}
+
+// Turns every floor tile outside the largest region into wall, an alternative to digging corridors.
+pub fn keep_largest_region(grid: &mut TileGrid) {
+	for region in regions(grid).into_iter().skip(1) {
+		for (x, y) in region {
+			grid.set(x, y, Tile::Wall);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::path::{Diagonal, DijkstraMap};
+
+	fn algorithms() -> [Algorithm; 4] {
+		[
+			Algorithm::Caves(CaveParams::default()),
+			Algorithm::Bsp(BspParams::default()),
+			Algorithm::Drunkard(DrunkardParams::default()),
+			Algorithm::Noise(NoiseParams::default()),
+		]
+	}
+
+	fn assert_walled(grid: &TileGrid) {
+		for y in 0..grid.height() {
+			for x in 0..grid.width() {
+				let border = x == 0 || y == 0 || x == grid.width() - 1 || y == grid.height() - 1;
+				assert!(!border || !grid.is_floor(x, y), "floor on the border at {:?}", (x, y));
+			}
+		}
+	}
+
+	// walks the map with the path finder rather than `regions`, so the check doesn't share the code it tests
+	fn assert_reachable(grid: &TileGrid) {
+		let floors: Vec<_> = (0..grid.height())
+			.flat_map(|y| (0..grid.width()).map(move |x| (x, y)))
+			.filter(|&(x, y)| grid.is_floor(x, y))
+			.collect();
+		let Some(&start) = floors.first() else { return };
+		let distances = DijkstraMap::new(grid, &[start], Diagonal::Never);
+		for &(x, y) in &floors {
+			assert!(distances.distance(x, y).is_some(), "{:?} can't reach {start:?}", (x, y));
+		}
+	}
+
+	#[test]
+	fn same_seed_same_map() {
+		for algorithm in algorithms() {
+			for (width, height) in [(48, 32), (25, 15), (17, 40)] {
+				let map = generate(width, height, 42, &algorithm);
+				assert_eq!(map, generate(width, height, 42, &algorithm), "{algorithm:?}");
+				assert_ne!(map, generate(width, height, 43, &algorithm), "{algorithm:?}");
+				assert_eq!((map.width(), map.height()), (width, height));
+			}
+		}
+	}
+
+	#[test]
+	fn floors_are_connected_and_walled_in() {
+		for algorithm in algorithms() {
+			for (width, height) in [(48, 32), (25, 15), (17, 40)] {
+				for seed in 0..20 {
+					let map = generate(width, height, seed, &algorithm);
+					assert!(map.floor_count() > 0, "{algorithm:?} seed {seed} has no floor");
+					assert_walled(&map);
+					assert_reachable(&map);
+					assert!(is_connected(&map));
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn degenerate_sizes() {
+		for algorithm in algorithms() {
+			for (width, height) in [(0, 0), (1, 1), (1, 10), (10, 1), (2, 2), (0, 5), (3, 3), (4, 4), (3, 20)] {
+				for seed in 0..10 {
+					let map = generate(width, height, seed, &algorithm);
+					assert_eq!(map.tiles().len(), (width * height) as usize);
+					assert_walled(&map);
+					assert_reachable(&map);
+					// nothing fits inside the border
+					if width < 3 || height < 3 {
+						assert_eq!(map.floor_count(), 0);
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn connect_and_keep_largest() {
+		let mut grid = TileGrid::new(9, 5, Tile::Wall);
+		grid.fill_rect(1, 1, 3, 3, Tile::Floor);
+		grid.fill_rect(6, 1, 2, 1, Tile::Floor);
+		grid.set(6, 3, Tile::Floor);
+
+		let found = regions(&grid);
+		assert_eq!(found.iter().map(Vec::len).collect::<Vec<_>>(), [9, 2, 1]);
+		assert!(!is_connected(&grid));
+
+		let mut connected = grid.clone();
+		connect_regions(&mut connected, &mut Rng::new(1));
+		assert!(is_connected(&connected));
+		assert_walled(&connected);
+		// corridors only add floor
+		assert!(grid.tiles().iter().zip(connected.tiles()).all(|(a, b)| *a == Tile::Wall || *b == Tile::Floor));
+
+		keep_largest_region(&mut grid);
+		assert_eq!(grid.floor_count(), 9);
+		assert_eq!(grid.nearest(8, 3, Tile::Floor), Some((3, 3)));
+	}
+
+	#[test]
+	fn noise_map_is_seeded_and_in_range() {
+		let params = NoiseParams::default();
+		let heights = noise_map(32, 16, 5, &params);
+		assert_eq!(heights.len(), 32 * 16);
+		assert!(heights.iter().all(|h| (0.0..=1.0).contains(h)));
+		assert_eq!(heights, noise_map(32, 16, 5, &params));
+		assert_ne!(heights, noise_map(32, 16, 6, &params));
+		assert!(noise_map(0, 4, 5, &params).is_empty());
+	}
+}
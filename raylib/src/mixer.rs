@@ -0,0 +1,619 @@
+use std::{
+	io::{self, BufRead, Write},
+	path::{Path, PathBuf},
+};
+
+use crate::{
+	audio::{AudioDevice, BusId, Levels, Music},
+	camera,
+	rng::Rng,
+	tween::Ease,
+	*,
+};
+
+// The buses every game gets, all straight under master.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MixBus {
+	Master,
+	Music,
+	Sfx,
+	Ui,
+	Voice,
+}
+
+impl MixBus {
+	pub const ALL: [MixBus; 5] = [MixBus::Master, MixBus::Music, MixBus::Sfx, MixBus::Ui, MixBus::Voice];
+
+	pub fn name(self) -> &'static str {
+		match self {
+			MixBus::Master => "master",
+			MixBus::Music => "music",
+			MixBus::Sfx => "sfx",
+			MixBus::Ui => "ui",
+			MixBus::Voice => "voice",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<MixBus> {
+		MixBus::ALL.into_iter().find(|bus| bus.name() == name)
+	}
+
+	fn index(self) -> usize {
+		self as usize
+	}
+}
+
+// Turns one bus down while anything plays on another, music under dialogue by default.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Ducking {
+	// the bus turned down
+	pub bus: MixBus,
+	// while this one plays
+	pub under: MixBus,
+	// volume of the ducked bus, 0.0 silent to 1.0 not ducked at all
+	pub level: f32,
+	// seconds to go down and to come back up
+	pub attack: f32,
+	pub release: f32,
+}
+
+impl Default for Ducking {
+	fn default() -> Self {
+		Self {
+			bus: MixBus::Music,
+			under: MixBus::Voice,
+			level: 0.3,
+			attack: 0.15,
+			release: 0.6,
+		}
+	}
+}
+
+// Tracks to play one after another, in order or shuffled anew on every pass.
+#[derive(Clone, Debug)]
+pub struct Playlist {
+	tracks: Vec<PathBuf>,
+	order: Vec<usize>,
+	// into `order`, None until the first track is picked
+	position: Option<usize>,
+	last: Option<usize>,
+	shuffle: Option<Rng>,
+}
+
+impl Playlist {
+	pub fn new(tracks: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+		let tracks: Vec<PathBuf> = tracks.into_iter().map(Into::into).collect();
+		Self {
+			order: (0..tracks.len()).collect(),
+			position: None,
+			last: None,
+			tracks,
+			shuffle: None,
+		}
+	}
+
+	// a seeded order, so a replay hears the same tracks
+	pub fn shuffled(mut self, seed: u64) -> Self {
+		self.shuffle = Some(Rng::new(seed));
+		self
+	}
+
+	pub fn tracks(&self) -> &[PathBuf] {
+		&self.tracks
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.tracks.is_empty()
+	}
+
+	// The track after the current one, wrapping around. A shuffled pass never starts with the track
+	// the previous one ended on.
+	pub fn next_track(&mut self) -> Option<&Path> {
+		if self.tracks.is_empty() {
+			return None;
+		}
+		let mut position = self.position.map_or(self.order.len(), |position| position + 1);
+		if position >= self.order.len() {
+			position = 0;
+			if let Some(rng) = &mut self.shuffle {
+				rng.shuffle(&mut self.order);
+				if self.order.len() > 1 && Some(self.order[0]) == self.last {
+					let end = self.order.len() - 1;
+					self.order.swap(0, end);
+				}
+			}
+		}
+		self.position = Some(position);
+		self.last = Some(self.order[position]);
+		Some(&self.tracks[self.order[position]])
+	}
+}
+
+// A music track fading from one gain to another.
+struct Track<'a> {
+	music: Music<'a>,
+	// set when loaded by the mixer, to tell if a track is already playing
+	path: Option<PathBuf>,
+	from_playlist: bool,
+	gain: f32,
+	from: f32,
+	to: f32,
+	elapsed: f32,
+	duration: f32,
+}
+
+impl Track<'_> {
+	fn fade(&mut self, to: f32, duration: f32) {
+		self.from = self.gain;
+		self.to = to;
+		self.elapsed = 0.0;
+		self.duration = duration;
+	}
+
+	fn update(&mut self, delta_time: f32) {
+		self.elapsed += delta_time;
+		let t = if self.duration > 0.0 {
+			(self.elapsed / self.duration).min(1.0)
+		} else {
+			1.0
+		};
+		// sine in and out add up to constant power, so a crossfade doesn't dip in the middle
+		let ease = if self.to > self.from { Ease::SineOut } else { Ease::SineIn };
+		self.gain = ease.interpolate(self.from, self.to, t);
+		self.music.set_volume(self.gain);
+	}
+
+	fn faded_out(&self) -> bool {
+		self.to == 0.0 && self.elapsed >= self.duration
+	}
+}
+
+// The game's side of the audio device: the standard buses with volumes the player sets, ducking,
+// music that crossfades from track to track and playlists. Call `update` once per frame in place
+// of `AudioDevice::update`.
+pub struct AudioMixer<'a> {
+	device: &'a AudioDevice,
+	buses: [BusId; 5],
+	volumes: [f32; 5],
+	// what the buses were last set to
+	applied: [f32; 5],
+	pub ducking: Ducking,
+	duck: f32,
+	current: Option<Track<'a>>,
+	fading: Vec<Track<'a>>,
+	playlist: Option<Playlist>,
+	// seconds a playlist crossfades into its next track, at most half of the ending one
+	pub playlist_fade: f32,
+	paused: bool,
+	// playlist tracks `update` failed to move on to
+	errors: Vec<io::Error>,
+}
+
+impl<'a> AudioMixer<'a> {
+	// Adds the buses to the device, or takes the ones of the same names it already has.
+	pub fn new(device: &'a AudioDevice) -> Self {
+		let buses = MixBus::ALL.map(|bus| match bus {
+			MixBus::Master => device.master(),
+			_ => device.bus(bus.name()).unwrap_or_else(|| device.add_bus(bus.name(), device.master())),
+		});
+		Self {
+			device,
+			buses,
+			volumes: buses.map(|bus| device.bus_levels(bus).volume),
+			applied: buses.map(|bus| device.bus_levels(bus).volume),
+			ducking: Ducking::default(),
+			duck: 1.0,
+			current: None,
+			fading: Vec::new(),
+			playlist: None,
+			playlist_fade: 2.0,
+			paused: false,
+			errors: Vec::new(),
+		}
+	}
+
+	pub fn device(&self) -> &'a AudioDevice {
+		self.device
+	}
+
+	// to put sounds on, e.g. `sound.set_bus(mixer.bus(MixBus::Sfx))`
+	pub fn bus(&self, bus: MixBus) -> BusId {
+		self.buses[bus.index()]
+	}
+
+	// the player's volume, before ducking
+	pub fn volume(&self, bus: MixBus) -> f32 {
+		self.volumes[bus.index()]
+	}
+
+	pub fn set_volume(&mut self, bus: MixBus, volume: f32) {
+		self.volumes[bus.index()] = volume.clamp(0.0, 1.0);
+		self.apply_volumes();
+	}
+
+	// Writes the bus volumes for a settings file, one per line:
+	//   volume music 0.8
+	pub fn save(&self, writer: &mut impl Write) -> io::Result<()> {
+		write_volumes(&self.volumes, writer)
+	}
+
+	// Reads volumes written by `save`, buses it doesn't mention keep theirs. Empty lines and lines
+	// starting with # are skipped. On error no volume changes.
+	pub fn load(&mut self, reader: impl BufRead) -> io::Result<()> {
+		self.volumes = read_volumes(reader, self.volumes)?;
+		self.apply_volumes();
+		Ok(())
+	}
+
+	// Crossfades from the current track to `music` over `fade` seconds, 0.0 cuts.
+	pub fn play_music(&mut self, music: Music<'a>, fade: f32) {
+		self.playlist = None;
+		self.start(music, None, false, fade);
+	}
+
+	// Loads and crossfades to the track at `path`, unless it is the one playing already, so
+	// screens can each ask for their track.
+	pub fn play_music_file(&mut self, path: impl AsRef<Path>, fade: f32) -> io::Result<()> {
+		let path = path.as_ref();
+		if self.current.as_ref().is_some_and(|track| track.path.as_deref() == Some(path)) {
+			return Ok(());
+		}
+		let music = self.device.load_music(path)?;
+		self.playlist = None;
+		self.start(music, Some(path.to_path_buf()), false, fade);
+		Ok(())
+	}
+
+	pub fn stop_music(&mut self, fade: f32) {
+		self.playlist = None;
+		if let Some(mut track) = self.current.take() {
+			track.fade(0.0, fade);
+			self.fading.push(track);
+		}
+	}
+
+	// Plays the playlist from its next track, crossfading over `fade` seconds, and moves on to the
+	// track after whenever one ends.
+	pub fn play_playlist(&mut self, playlist: Playlist, fade: f32) -> io::Result<()> {
+		self.playlist = Some(playlist);
+		self.next_track(fade)
+	}
+
+	pub fn playlist(&self) -> Option<&Playlist> {
+		self.playlist.as_ref()
+	}
+
+	// Skips to the playlist's next track.
+	pub fn next_track(&mut self, fade: f32) -> io::Result<()> {
+		let Some(playlist) = &mut self.playlist else {
+			return Ok(());
+		};
+		let mut last_error = None;
+		// a track that won't load is skipped, until every one has been tried
+		for _ in 0..playlist.tracks().len() {
+			let Some(path) = playlist.next_track().map(Path::to_path_buf) else {
+				break;
+			};
+			match self.device.load_music(&path) {
+				Ok(music) => {
+					music.set_looping(false);
+					self.start(music, Some(path), true, fade);
+					return Ok(());
+				}
+				Err(error) => last_error = Some(error),
+			}
+		}
+		self.playlist = None;
+		Err(last_error.unwrap_or_else(|| invalid_data("the playlist is empty".to_string())))
+	}
+
+	// Use these rather than pausing `music` itself, so a paused playlist track isn't taken as
+	// finished.
+	pub fn pause_music(&mut self) {
+		self.paused = true;
+		for track in self.current.iter().chain(&self.fading) {
+			track.music.pause();
+		}
+	}
+
+	pub fn resume_music(&mut self) {
+		self.paused = false;
+		for track in self.current.iter().chain(&self.fading) {
+			track.music.resume();
+		}
+	}
+
+	// the track playing or fading in
+	pub fn music(&self) -> Option<&Music<'a>> {
+		self.current.as_ref().map(|track| &track.music)
+	}
+
+	// Errors from playlists that `update` couldn't move on since the last call, the playlist has
+	// stopped by then.
+	pub fn drain_errors(&mut self) -> impl Iterator<Item = io::Error> + '_ {
+		self.errors.drain(..)
+	}
+
+	// Streams the music, runs fades and ducking and moves the playlist on.
+	pub fn update(&mut self, delta_time: f32) {
+		self.device.update();
+
+		let ducking = self.ducking;
+		let (target, time) = if self.device.is_bus_playing(self.bus(ducking.under)) {
+			(ducking.level, ducking.attack)
+		} else {
+			(1.0, ducking.release)
+		};
+		let step = if time > 0.0 {
+			(1.0 - ducking.level).abs() * delta_time / time
+		} else {
+			1.0
+		};
+		self.duck = if self.duck > target {
+			(self.duck - step).max(target)
+		} else {
+			(self.duck + step).min(target)
+		};
+		self.apply_volumes();
+
+		if self.paused {
+			return;
+		}
+		for track in self.current.iter_mut().chain(&mut self.fading) {
+			track.update(delta_time);
+		}
+		self.fading.retain(|track| !track.faded_out());
+
+		let mut fade = self.playlist_fade;
+		let ended = self.current.as_ref().is_some_and(|track| {
+			let length = track.music.length();
+			fade = switch_window(self.playlist_fade, length);
+			track.from_playlist && (!track.music.is_playing() || length - track.music.played() <= fade)
+		});
+		if ended {
+			if let Err(error) = self.next_track(fade) {
+				self.errors.push(error);
+			}
+		}
+	}
+
+	fn start(&mut self, music: Music<'a>, path: Option<PathBuf>, from_playlist: bool, fade: f32) {
+		if let Some(mut old) = self.current.take() {
+			old.fade(0.0, fade);
+			self.fading.push(old);
+		}
+		music.set_bus(self.bus(MixBus::Music));
+		let mut track = Track {
+			music,
+			path,
+			from_playlist,
+			gain: 0.0,
+			from: 0.0,
+			to: 0.0,
+			elapsed: 0.0,
+			duration: 0.0,
+		};
+		track.fade(1.0, fade);
+		track.update(0.0);
+		track.music.play();
+		self.current = Some(track);
+	}
+
+	fn apply_volumes(&mut self) {
+		for bus in MixBus::ALL {
+			let duck = if bus == self.ducking.bus { self.duck } else { 1.0 };
+			let volume = self.volumes[bus.index()] * duck;
+			if volume != self.applied[bus.index()] {
+				self.applied[bus.index()] = volume;
+				self.device.set_bus_volume(self.bus(bus), volume);
+			}
+		}
+	}
+}
+
+// How long before a playlist track ends the next one starts fading in. At most half the track, or
+// a track no longer than the fade would be switched away from as soon as it started.
+fn switch_window(playlist_fade: f32, length: f32) -> f32 {
+	playlist_fade.min(length / 2.0).max(0.0)
+}
+
+fn write_volumes(volumes: &[f32; 5], writer: &mut impl Write) -> io::Result<()> {
+	for bus in MixBus::ALL {
+		writeln!(writer, "volume {} {}", bus.name(), volumes[bus.index()])?;
+	}
+	Ok(())
+}
+
+// `volumes` with the ones `reader` sets replaced
+fn read_volumes(reader: impl BufRead, mut volumes: [f32; 5]) -> io::Result<[f32; 5]> {
+	for (number, line) in reader.lines().enumerate() {
+		let line = line?;
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		let error = |message: &str| invalid_data(format!("line {}: {message}: {line}", number + 1));
+		let mut words = line.split_whitespace();
+		let (Some("volume"), Some(name), Some(value), None) = (words.next(), words.next(), words.next(), words.next()) else {
+			return Err(error("expected volume <bus> <value>"));
+		};
+		let bus = MixBus::from_name(name).ok_or_else(|| error("unknown bus"))?;
+		let volume = value
+			.parse::<f32>()
+			.ok()
+			.filter(|volume| volume.is_finite())
+			.ok_or_else(|| error("bad volume"))?;
+		volumes[bus.index()] = volume.clamp(0.0, 1.0);
+	}
+	Ok(volumes)
+}
+
+// How a sound fades with its distance from the listener, in world units.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Attenuation {
+	// full volume up to here
+	pub min_distance: f32,
+	// silent from here on
+	pub max_distance: f32,
+	// 1.0 fades evenly, higher drops off faster close to the source
+	pub rolloff: f32,
+	// screen pixels from the listener where a sound is all the way on one side
+	pub pan_width: f32,
+}
+
+impl Default for Attenuation {
+	fn default() -> Self {
+		Self {
+			min_distance: 64.0,
+			max_distance: 800.0,
+			rolloff: 1.0,
+			pan_width: 400.0,
+		}
+	}
+}
+
+// Levels of a sound at `position` heard from the camera's target, e.g. for
+// `SoundPool::play_with`. The pan follows where the sound is on screen, so it respects the
+// camera's zoom and rotation.
+pub fn positional(listener: &Camera2D, position: Vector2, attenuation: &Attenuation) -> Levels {
+	let distance = ((position.x - listener.target.x).powi(2) + (position.y - listener.target.y).powi(2)).sqrt();
+	let range = (attenuation.max_distance - attenuation.min_distance).max(f32::EPSILON);
+	let t = ((distance - attenuation.min_distance) / range).clamp(0.0, 1.0);
+	let screen = camera::world_to_screen(position, listener);
+	Levels {
+		volume: (1.0 - t).powf(attenuation.rolloff.max(0.01)),
+		pitch: 1.0,
+		pan: ((screen.x - listener.offset.x) / attenuation.pan_width.max(1.0)).clamp(-1.0, 1.0),
+	}
+}
+
+fn invalid_data(message: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn names(playlist: &mut Playlist, count: usize) -> Vec<String> {
+		(0..count).map(|_| playlist.next_track().unwrap().display().to_string()).collect()
+	}
+
+	#[test]
+	fn playlists_wrap_around() {
+		let mut playlist = Playlist::new(["a", "b", "c"]);
+		assert_eq!(names(&mut playlist, 7), ["a", "b", "c", "a", "b", "c", "a"]);
+		assert_eq!(Playlist::new(Vec::<PathBuf>::new()).next_track(), None);
+	}
+
+	#[test]
+	fn shuffles_replay_and_passes_start_on_another_track() {
+		let tracks = ["a", "b", "c", "d", "e"];
+		for seed in 0..50 {
+			let played = names(&mut Playlist::new(tracks).shuffled(seed), 40);
+			assert_eq!(played, names(&mut Playlist::new(tracks).shuffled(seed), 40));
+			for pass in played.chunks(tracks.len()) {
+				let mut sorted = pass.to_vec();
+				sorted.sort();
+				assert_eq!(sorted, tracks);
+			}
+			for end in (tracks.len()..played.len()).step_by(tracks.len()) {
+				assert_ne!(played[end - 1], played[end], "seed {seed}");
+			}
+		}
+		// a single track has nothing to swap with
+		assert_eq!(names(&mut Playlist::new(["a"]).shuffled(1), 3), ["a", "a", "a"]);
+	}
+
+	#[test]
+	fn short_tracks_switch_halfway() {
+		assert_eq!(switch_window(2.0, 60.0), 2.0);
+		assert_eq!(switch_window(2.0, 3.0), 1.5);
+		assert_eq!(switch_window(2.0, 0.0), 0.0);
+		assert_eq!(switch_window(-1.0, 60.0), 0.0);
+	}
+
+	#[test]
+	fn volumes_round_trip() {
+		let volumes = [1.0, 0.8, 0.25, 0.0, 0.5];
+		let mut saved = Vec::new();
+		write_volumes(&volumes, &mut saved).unwrap();
+		assert_eq!(read_volumes(&saved[..], [1.0; 5]).unwrap(), volumes);
+	}
+
+	#[test]
+	fn volumes_load_only_what_they_name() {
+		let text = "# settings\n\n  volume music 0.5  \nvolume sfx 2\nvolume voice -1\n";
+		assert_eq!(read_volumes(text.as_bytes(), [0.9; 5]).unwrap(), [0.9, 0.5, 1.0, 0.9, 0.0]);
+		for bad in [
+			"volume music",
+			"volume music 0.5 0.5",
+			"level music 0.5",
+			"volume drums 0.5",
+			"volume music loud",
+			"volume music NaN",
+			"volume music inf",
+		] {
+			let error = read_volumes(format!("volume sfx 0.1\n{bad}").as_bytes(), [1.0; 5]).unwrap_err();
+			assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+			assert!(error.to_string().starts_with("line 2: "), "{error}");
+		}
+	}
+
+	fn listener() -> Camera2D {
+		Camera2D {
+			offset: Vector2 { x: 400.0, y: 300.0 },
+			target: Vector2 { x: 1000.0, y: 1000.0 },
+			rotation: 0.0,
+			zoom: 1.0,
+		}
+	}
+
+	fn at(x: f32, y: f32) -> Levels {
+		positional(
+			&listener(),
+			Vector2 {
+				x: 1000.0 + x,
+				y: 1000.0 + y,
+			},
+			&Attenuation::default(),
+		)
+	}
+
+	#[test]
+	fn positional_volume_falls_off_between_min_and_max() {
+		assert_eq!(at(0.0, 0.0).volume, 1.0);
+		assert_eq!(at(0.0, 64.0).volume, 1.0);
+		assert_eq!(at(-40.0, 30.0).volume, 1.0);
+		let (near, far) = (at(200.0, 0.0).volume, at(500.0, 0.0).volume);
+		assert!(1.0 > near && near > far && far > 0.0);
+		assert!((at(432.0, 0.0).volume - 0.5).abs() < 1e-6);
+		assert_eq!(at(800.0, 0.0).volume, 0.0);
+		assert_eq!(at(0.0, -5000.0).volume, 0.0);
+
+		let steep = Attenuation {
+			rolloff: 2.0,
+			..Attenuation::default()
+		};
+		assert!((positional(&listener(), Vector2 { x: 1432.0, y: 1000.0 }, &steep).volume - 0.25).abs() < 1e-6);
+	}
+
+	#[test]
+	fn positional_pan_follows_the_screen() {
+		assert_eq!(at(0.0, 300.0).pan, 0.0);
+		assert_eq!(at(200.0, 0.0).pan, 0.5);
+		assert_eq!(at(-100.0, 0.0).pan, -0.25);
+		assert_eq!(at(900.0, 0.0).pan, 1.0);
+		assert_eq!(at(-900.0, 0.0).pan, -1.0);
+
+		// zoomed in, the same world distance is further across the screen
+		let zoomed = Camera2D { zoom: 2.0, ..listener() };
+		assert_eq!(positional(&zoomed, Vector2 { x: 1100.0, y: 1000.0 }, &Attenuation::default()).pan, 0.5);
+		// turned half around, right in the world is left on screen
+		let turned = Camera2D {
+			rotation: 180.0,
+			..listener()
+		};
+		assert!((positional(&turned, Vector2 { x: 1200.0, y: 1000.0 }, &Attenuation::default()).pan + 0.5).abs() < 1e-4);
+	}
+}
@@ -0,0 +1,575 @@
+use std::f32::consts::TAU;
+
+use crate::{backend::Backend, rlgl::*, rng::Rng, tween::Lerp, *};
+
+// vertices per circle particle, drawn as a triangle fan
+const CIRCLE_SEGMENTS: usize = 12;
+
+// Where new particles appear, relative to the emitter position.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EmitterShape {
+	Point,
+	Circle { radius: f32 },
+	// centered on the emitter
+	Rectangle { width: f32, height: f32 },
+	// from the emitter to `position + end`
+	Line { end: Vector2 },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum ParticleLook {
+	Square,
+	Circle,
+	// `source` in texels, like DrawTexturePro
+	Texture { texture: Texture2D, source: Rectangle },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Space {
+	// particles stay where they were emitted when the emitter moves
+	World,
+	// particles move along with the emitter
+	Local,
+}
+
+// Emits `count` particles at `time` seconds after the emitter started, and every `interval` seconds
+// after that if it is above 0.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Burst {
+	pub time: f32,
+	pub count: u32,
+	pub interval: f32,
+}
+
+// Keys of (time 0..1, value), sampled with straight interpolation between them.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Curve<T: Lerp> {
+	keys: Vec<(f32, T)>,
+}
+
+impl<T: Lerp> Curve<T> {
+	pub fn new(mut keys: Vec<(f32, T)>) -> Self {
+		assert!(!keys.is_empty(), "a curve needs at least one key");
+		keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+		Self { keys }
+	}
+
+	pub fn constant(value: T) -> Self {
+		Self { keys: vec![(0.0, value)] }
+	}
+
+	pub fn linear(from: T, to: T) -> Self {
+		Self {
+			keys: vec![(0.0, from), (1.0, to)],
+		}
+	}
+
+	pub fn sample(&self, t: f32) -> T {
+		let next = self.keys.partition_point(|(time, _)| *time <= t);
+		if next == 0 {
+			return self.keys[0].1;
+		}
+		let (start, from) = self.keys[next - 1];
+		match self.keys.get(next) {
+			Some(&(end, to)) => from.lerp(to, (t - start) / (end - start)),
+			None => from,
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct EmitterConfig {
+	pub shape: EmitterShape,
+	// particles per second while emitting
+	pub rate: f32,
+	pub bursts: Vec<Burst>,
+	// new particles are dropped while this many are alive
+	pub max_particles: usize,
+	// seconds, each particle picks one between min and max
+	pub lifetime: (f32, f32),
+	// degrees, 0 points right and 90 down like raylib rotations
+	pub direction: f32,
+	// degrees, the whole cone around `direction`
+	pub spread: f32,
+	pub speed: (f32, f32),
+	// picked per particle between the two, e.g. gravity plus some wind
+	pub acceleration: (Vector2, Vector2),
+	// degrees
+	pub rotation: (f32, f32),
+	// degrees per second
+	pub spin: (f32, f32),
+	// over each particle's life, 0.0 born to 1.0 dead
+	pub color: Curve<Color>,
+	// width in pixels over each particle's life
+	pub size: Curve<f32>,
+	pub look: ParticleLook,
+	// BLEND_ALPHA or BLEND_ADDITIVE for glowing effects
+	pub blend: BlendMode,
+	pub space: Space,
+}
+
+impl Default for EmitterConfig {
+	fn default() -> Self {
+		Self {
+			shape: EmitterShape::Point,
+			rate: 50.0,
+			bursts: Vec::new(),
+			max_particles: 1000,
+			lifetime: (1.0, 1.0),
+			direction: -90.0,
+			spread: 360.0,
+			speed: (50.0, 100.0),
+			acceleration: (Vector2::default(), Vector2::default()),
+			rotation: (0.0, 0.0),
+			spin: (0.0, 0.0),
+			color: Curve::linear(WHITE, fade(WHITE, 0.0)),
+			size: Curve::constant(4.0),
+			look: ParticleLook::Square,
+			blend: BlendMode_BLEND_ALPHA,
+			space: Space::World,
+		}
+	}
+}
+
+// One live particle, with the curves applied.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Particle {
+	// world position
+	pub position: Vector2,
+	pub velocity: Vector2,
+	pub rotation: f32,
+	pub size: f32,
+	pub color: Color,
+	// 0.0 born to 1.0 dead
+	pub life: f32,
+}
+
+// Particle state as one array per field, so the update runs over tight loops. Dead particles are
+// swapped with the last one, nothing is allocated once the pool reached `max_particles`.
+#[derive(Clone, Default, Debug)]
+struct Pool {
+	x: Vec<f32>,
+	y: Vec<f32>,
+	velocity_x: Vec<f32>,
+	velocity_y: Vec<f32>,
+	acceleration_x: Vec<f32>,
+	acceleration_y: Vec<f32>,
+	rotation: Vec<f32>,
+	spin: Vec<f32>,
+	age: Vec<f32>,
+	lifetime: Vec<f32>,
+}
+
+impl Pool {
+	fn with_capacity(capacity: usize) -> Self {
+		Self {
+			x: Vec::with_capacity(capacity),
+			y: Vec::with_capacity(capacity),
+			velocity_x: Vec::with_capacity(capacity),
+			velocity_y: Vec::with_capacity(capacity),
+			acceleration_x: Vec::with_capacity(capacity),
+			acceleration_y: Vec::with_capacity(capacity),
+			rotation: Vec::with_capacity(capacity),
+			spin: Vec::with_capacity(capacity),
+			age: Vec::with_capacity(capacity),
+			lifetime: Vec::with_capacity(capacity),
+		}
+	}
+
+	fn len(&self) -> usize {
+		self.x.len()
+	}
+
+	fn swap_remove(&mut self, index: usize) {
+		self.x.swap_remove(index);
+		self.y.swap_remove(index);
+		self.velocity_x.swap_remove(index);
+		self.velocity_y.swap_remove(index);
+		self.acceleration_x.swap_remove(index);
+		self.acceleration_y.swap_remove(index);
+		self.rotation.swap_remove(index);
+		self.spin.swap_remove(index);
+		self.age.swap_remove(index);
+		self.lifetime.swap_remove(index);
+	}
+
+	fn clear(&mut self) {
+		self.x.clear();
+		self.y.clear();
+		self.velocity_x.clear();
+		self.velocity_y.clear();
+		self.acceleration_x.clear();
+		self.acceleration_y.clear();
+		self.rotation.clear();
+		self.spin.clear();
+		self.age.clear();
+		self.lifetime.clear();
+	}
+}
+
+// Emits and simulates particles. All randomness comes from the emitter's own seeded `Rng`, so the
+// same seed and the same update steps give the same particles.
+#[derive(Clone, Debug)]
+pub struct Emitter {
+	pub config: EmitterConfig,
+	pub position: Vector2,
+	// false stops the rate and bursts, live particles play out
+	pub emitting: bool,
+	rng: Rng,
+	pool: Pool,
+	// seconds since the emitter started, for bursts
+	elapsed: f32,
+	// fraction of a particle the rate owes
+	owed: f32,
+}
+
+impl Emitter {
+	pub fn new(config: EmitterConfig, position: Vector2, seed: u64) -> Self {
+		Self {
+			pool: Pool::with_capacity(config.max_particles),
+			config,
+			position,
+			emitting: true,
+			rng: Rng::new(seed),
+			elapsed: 0.0,
+			owed: 0.0,
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.pool.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.pool.len() == 0
+	}
+
+	pub fn clear(&mut self) {
+		self.pool.clear();
+	}
+
+	// Emits `count` particles right away, on top of the rate and bursts.
+	pub fn burst(&mut self, count: u32) {
+		for _ in 0..count {
+			self.spawn();
+		}
+	}
+
+	pub fn update(&mut self, delta_time: f32) {
+		self.simulate(delta_time);
+		if !self.emitting {
+			return;
+		}
+
+		let start = self.elapsed;
+		self.elapsed += delta_time;
+		let mut count = 0;
+		for burst in &self.config.bursts {
+			count += burst.count * burst_repeats(burst, start, self.elapsed);
+		}
+		self.owed += self.config.rate.max(0.0) * delta_time;
+		let owed = self.owed.floor();
+		self.owed -= owed;
+		self.burst(count + owed as u32);
+	}
+
+	pub fn particles(&self) -> impl Iterator<Item = Particle> + '_ {
+		let origin = self.origin();
+		(0..self.pool.len()).map(move |i| {
+			let life = self.pool.age[i] / self.pool.lifetime[i];
+			Particle {
+				position: Vector2 {
+					x: origin.x + self.pool.x[i],
+					y: origin.y + self.pool.y[i],
+				},
+				velocity: Vector2 {
+					x: self.pool.velocity_x[i],
+					y: self.pool.velocity_y[i],
+				},
+				rotation: self.pool.rotation[i],
+				size: self.config.size.sample(life),
+				color: self.config.color.sample(life),
+				life,
+			}
+		})
+	}
+
+	// Draws every particle in one rlgl batch with the emitter's blend mode. Inside BeginDrawing, and
+	// BeginMode2D for world positions.
+	pub fn draw(&self) {
+		if self.is_empty() {
+			return;
+		}
+		unsafe {
+			BeginBlendMode(self.config.blend);
+			match self.config.look {
+				ParticleLook::Square => self.draw_quads(rlGetTextureIdDefault(), [0.0, 0.0, 1.0, 1.0]),
+				ParticleLook::Texture { texture, source } => {
+					let (width, height) = (texture.width as f32, texture.height as f32);
+					let uv = [
+						source.x / width,
+						source.y / height,
+						(source.x + source.width) / width,
+						(source.y + source.height) / height,
+					];
+					self.draw_quads(texture.id, uv);
+				}
+				ParticleLook::Circle => self.draw_circles(),
+			}
+			EndBlendMode();
+		}
+	}
+
+	// Draws through a backend one particle at a time, e.g. to record or rasterize them. Ignores the
+	// blend mode, and square particles are not rotated.
+	pub fn draw_backend(&self, backend: &mut dyn Backend) {
+		for particle in self.particles() {
+			let half = particle.size / 2.0;
+			match self.config.look {
+				ParticleLook::Square => backend.draw_rectangle_rec(
+					Rectangle {
+						x: particle.position.x - half,
+						y: particle.position.y - half,
+						width: particle.size,
+						height: particle.size,
+					},
+					particle.color,
+				),
+				ParticleLook::Circle => backend.draw_circle_v(particle.position, half, particle.color),
+				ParticleLook::Texture { texture, source } => {
+					let height = particle.size * (source.height / source.width).abs();
+					backend.draw_texture_pro(
+						texture,
+						source,
+						Rectangle {
+							x: particle.position.x,
+							y: particle.position.y,
+							width: particle.size,
+							height,
+						},
+						Vector2 { x: half, y: height / 2.0 },
+						particle.rotation,
+						particle.color,
+					);
+				}
+			}
+		}
+	}
+
+	// where pool positions are relative to
+	fn origin(&self) -> Vector2 {
+		match self.config.space {
+			Space::World => Vector2::default(),
+			Space::Local => self.position,
+		}
+	}
+
+	fn simulate(&mut self, delta_time: f32) {
+		let pool = &mut self.pool;
+		for (velocity, acceleration) in pool.velocity_x.iter_mut().zip(&pool.acceleration_x) {
+			*velocity += acceleration * delta_time;
+		}
+		for (velocity, acceleration) in pool.velocity_y.iter_mut().zip(&pool.acceleration_y) {
+			*velocity += acceleration * delta_time;
+		}
+		for (x, velocity) in pool.x.iter_mut().zip(&pool.velocity_x) {
+			*x += velocity * delta_time;
+		}
+		for (y, velocity) in pool.y.iter_mut().zip(&pool.velocity_y) {
+			*y += velocity * delta_time;
+		}
+		for (rotation, spin) in pool.rotation.iter_mut().zip(&pool.spin) {
+			*rotation += spin * delta_time;
+		}
+		for age in pool.age.iter_mut() {
+			*age += delta_time;
+		}
+
+		let mut i = 0;
+		while i < pool.len() {
+			if pool.age[i] >= pool.lifetime[i] {
+				pool.swap_remove(i);
+			} else {
+				i += 1;
+			}
+		}
+	}
+
+	fn spawn(&mut self) {
+		if self.pool.len() >= self.config.max_particles {
+			return;
+		}
+		let config = &self.config;
+		let rng = &mut self.rng;
+		let offset = match config.shape {
+			EmitterShape::Point => Vector2::default(),
+			EmitterShape::Circle { radius } => {
+				// sqrt spreads them evenly over the area instead of bunching at the center
+				let distance = radius * rng.next_f32().sqrt();
+				let angle = rng.next_f32() * TAU;
+				Vector2 {
+					x: angle.cos() * distance,
+					y: angle.sin() * distance,
+				}
+			}
+			EmitterShape::Rectangle { width, height } => Vector2 {
+				x: rng.range_f32(-width / 2.0, width / 2.0),
+				y: rng.range_f32(-height / 2.0, height / 2.0),
+			},
+			EmitterShape::Line { end } => {
+				let t = rng.next_f32();
+				Vector2 { x: end.x * t, y: end.y * t }
+			}
+		};
+		let start = match config.space {
+			Space::World => self.position,
+			Space::Local => Vector2::default(),
+		};
+		let angle = (config.direction + rng.range_f32(-config.spread / 2.0, config.spread / 2.0)).to_radians();
+		let speed = rng.range_f32(config.speed.0, config.speed.1);
+		let (min, max) = config.acceleration;
+
+		let pool = &mut self.pool;
+		pool.x.push(start.x + offset.x);
+		pool.y.push(start.y + offset.y);
+		pool.velocity_x.push(angle.cos() * speed);
+		pool.velocity_y.push(angle.sin() * speed);
+		pool.acceleration_x.push(rng.range_f32(min.x, max.x));
+		pool.acceleration_y.push(rng.range_f32(min.y, max.y));
+		pool.rotation.push(rng.range_f32(config.rotation.0, config.rotation.1));
+		pool.spin.push(rng.range_f32(config.spin.0, config.spin.1));
+		pool.age.push(0.0);
+		// a zero lifetime would divide by zero when sampling the curves
+		pool.lifetime.push(rng.range_f32(config.lifetime.0, config.lifetime.1).max(f32::EPSILON));
+	}
+
+	unsafe fn draw_quads(&self, texture_id: u32, [u0, v0, u1, v1]: [f32; 4]) {
+		rlSetTexture(texture_id);
+		rlBegin(RL_QUADS);
+		for particle in self.particles() {
+			rlCheckRenderBatchLimit(4);
+			let Color { r, g, b, a } = particle.color;
+			rlColor4ub(r, g, b, a);
+			let (sin, cos) = particle.rotation.to_radians().sin_cos();
+			let half = particle.size / 2.0;
+			// same corner order as DrawTexturePro: top left, bottom left, bottom right, top right
+			for (u, v, x, y) in [(u0, v0, -half, -half), (u0, v1, -half, half), (u1, v1, half, half), (u1, v0, half, -half)] {
+				rlTexCoord2f(u, v);
+				rlVertex2f(particle.position.x + x * cos - y * sin, particle.position.y + x * sin + y * cos);
+			}
+		}
+		rlEnd();
+		rlSetTexture(0);
+	}
+
+	unsafe fn draw_circles(&self) {
+		let corners: Vec<(f32, f32)> = (0..=CIRCLE_SEGMENTS)
+			.map(|i| (i as f32 / CIRCLE_SEGMENTS as f32 * TAU).sin_cos())
+			.collect();
+		rlSetTexture(rlGetTextureIdDefault());
+		rlBegin(RL_TRIANGLES);
+		for particle in self.particles() {
+			rlCheckRenderBatchLimit(3 * CIRCLE_SEGMENTS as i32);
+			let Color { r, g, b, a } = particle.color;
+			rlColor4ub(r, g, b, a);
+			let radius = particle.size / 2.0;
+			let Vector2 { x, y } = particle.position;
+			// counter clockwise on screen, like DrawCircleSector
+			for pair in corners.windows(2) {
+				let ((sin0, cos0), (sin1, cos1)) = (pair[0], pair[1]);
+				rlTexCoord2f(0.5, 0.5);
+				rlVertex2f(x, y);
+				rlVertex2f(x + cos1 * radius, y + sin1 * radius);
+				rlVertex2f(x + cos0 * radius, y + sin0 * radius);
+			}
+		}
+		rlEnd();
+		rlSetTexture(0);
+	}
+}
+
+// how often `burst` fires in [start, end)
+fn burst_repeats(burst: &Burst, start: f32, end: f32) -> u32 {
+	if burst.interval <= 0.0 {
+		return (start <= burst.time && burst.time < end) as u32;
+	}
+	// firings before `time`
+	let before = |time: f32| {
+		if time <= burst.time {
+			0
+		} else {
+			((time - burst.time) / burst.interval).ceil() as u32
+		}
+	};
+	before(end) - before(start)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// every random range in use
+	fn config() -> EmitterConfig {
+		EmitterConfig {
+			shape: EmitterShape::Circle { radius: 8.0 },
+			rate: 30.0,
+			bursts: vec![Burst {
+				time: 0.25,
+				count: 10,
+				interval: 0.5,
+			}],
+			lifetime: (0.5, 1.5),
+			spread: 90.0,
+			speed: (20.0, 80.0),
+			acceleration: (Vector2 { x: -5.0, y: 50.0 }, Vector2 { x: 5.0, y: 100.0 }),
+			rotation: (0.0, 360.0),
+			spin: (-90.0, 90.0),
+			color: Curve::new(vec![(0.0, YELLOW), (0.5, ORANGE), (1.0, fade(RED, 0.0))]),
+			size: Curve::linear(6.0, 1.0),
+			..Default::default()
+		}
+	}
+
+	fn run(seed: u64) -> Vec<Vec<Particle>> {
+		let mut emitter = Emitter::new(config(), Vector2 { x: 100.0, y: 50.0 }, seed);
+		(0..120)
+			.map(|_| {
+				emitter.update(1.0 / 60.0);
+				emitter.particles().collect()
+			})
+			.collect()
+	}
+
+	#[test]
+	fn same_seed_gives_same_particles() {
+		let first = run(42);
+		assert_eq!(first, run(42));
+		assert_ne!(first, run(43));
+		assert!(first.iter().any(|frame| frame.len() > 20));
+	}
+
+	#[test]
+	fn rate_and_bursts_emit_on_time() {
+		let mut emitter = Emitter::new(
+			EmitterConfig {
+				lifetime: (10.0, 10.0),
+				..config()
+			},
+			Vector2::default(),
+			1,
+		);
+		// 30 per second and the bursts at 0.25 and 0.75
+		for _ in 0..4 {
+			emitter.update(0.25);
+		}
+		assert_eq!(emitter.len(), 30 + 20);
+
+		emitter.emitting = false;
+		emitter.update(1.0);
+		assert_eq!(emitter.len(), 50);
+		emitter.burst(5);
+		assert_eq!(emitter.len(), 55);
+		emitter.clear();
+		assert!(emitter.is_empty());
+	}
+}
@@ -0,0 +1,637 @@
+use std::{cmp::Reverse, collections::BinaryHeap, num::NonZeroU32};
+
+use crate::tilemap::TileMap;
+
+// Step costs are integers so paths are deterministic, a diagonal step costs ~sqrt(2) of an orthogonal one.
+pub const ORTHOGONAL_COST: u32 = 10;
+pub const DIAGONAL_COST: u32 = 14;
+
+// A grid that can be walked. `cost` is the cost of entering a tile (1 is the cheapest), `None` if it is blocked.
+// Free tiles would let A* overestimate, so there are none.
+pub trait PathGrid: TileMap {
+	fn cost(&self, x: i32, y: i32) -> Option<NonZeroU32>;
+
+	fn is_walkable(&self, x: i32, y: i32) -> bool {
+		self.in_bounds(x, y) && self.cost(x, y).is_some()
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Diagonal {
+	// 4-connected, orthogonal steps only
+	Never,
+	// 8-connected, diagonals may pass between two blocked tiles
+	Always,
+	// 8-connected, diagonals may cut one blocked corner
+	AllowCornerCutting,
+	// 8-connected, diagonals need both orthogonal neighbours open
+	NoCornerCutting,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Path {
+	// every tile from start to goal, both included
+	pub tiles: Vec<(i32, i32)>,
+	pub cost: u32,
+}
+
+const DIRECTIONS: [(i32, i32); 8] = [(1, 0), (0, 1), (-1, 0), (0, -1), (1, 1), (-1, 1), (-1, -1), (1, -1)];
+
+// Walkable neighbours of a tile and the cost of stepping onto them.
+pub fn neighbours<G: PathGrid + ?Sized>(grid: &G, x: i32, y: i32, diagonal: Diagonal) -> impl Iterator<Item = ((i32, i32), u32)> + '_ {
+	let count = if diagonal == Diagonal::Never { 4 } else { 8 };
+	DIRECTIONS[..count].iter().filter_map(move |&(dx, dy)| {
+		let (nx, ny) = (x + dx, y + dy);
+		let cost = if grid.in_bounds(nx, ny) { grid.cost(nx, ny)?.get() } else { return None };
+		if dx == 0 || dy == 0 {
+			return Some(((nx, ny), cost * ORTHOGONAL_COST));
+		}
+
+		let open = grid.is_walkable(nx, y) as u8 + grid.is_walkable(x, ny) as u8;
+		let allowed = match diagonal {
+			Diagonal::Never => false,
+			Diagonal::Always => true,
+			Diagonal::AllowCornerCutting => open >= 1,
+			Diagonal::NoCornerCutting => open == 2,
+		};
+		allowed.then_some(((nx, ny), cost * DIAGONAL_COST))
+	})
+}
+
+// Lower bound of the cost between two tiles on a grid where every tile costs 1.
+pub fn heuristic(from: (i32, i32), to: (i32, i32), diagonal: Diagonal) -> u32 {
+	let dx = (from.0 - to.0).unsigned_abs();
+	let dy = (from.1 - to.1).unsigned_abs();
+	if diagonal == Diagonal::Never {
+		(dx + dy) * ORTHOGONAL_COST
+	} else {
+		dx.max(dy) * ORTHOGONAL_COST + dx.min(dy) * (DIAGONAL_COST - ORTHOGONAL_COST)
+	}
+}
+
+pub fn astar<G: PathGrid + ?Sized>(grid: &G, start: (i32, i32), goal: (i32, i32), diagonal: Diagonal) -> Option<Path> {
+	if !grid.is_walkable(start.0, start.1) || !grid.is_walkable(goal.0, goal.1) {
+		return None;
+	}
+
+	let len = (grid.width() * grid.height()) as usize;
+	let mut g_score = vec![u32::MAX; len];
+	let mut came_from = vec![usize::MAX; len];
+	// (f, h) ordering prefers the node closest to the goal when costs tie
+	let mut open = BinaryHeap::new();
+
+	let start_index = grid.index(start.0, start.1);
+	g_score[start_index] = 0;
+	open.push(Reverse((heuristic(start, goal, diagonal), heuristic(start, goal, diagonal), start_index)));
+
+	while let Some(Reverse((f, h, index))) = open.pop() {
+		let g = g_score[index];
+		if f - h > g {
+			// stale entry, a cheaper route to this node was found later
+			continue;
+		}
+
+		let current = position(grid, index);
+		if current == goal {
+			return Some(Path {
+				tiles: reconstruct(grid, &came_from, index),
+				cost: g,
+			});
+		}
+
+		for (next, step) in neighbours(grid, current.0, current.1, diagonal) {
+			let next_index = grid.index(next.0, next.1);
+			let next_g = g + step;
+			if next_g < g_score[next_index] {
+				g_score[next_index] = next_g;
+				came_from[next_index] = index;
+				let h = heuristic(next, goal, diagonal);
+				open.push(Reverse((next_g + h, h, next_index)));
+			}
+		}
+	}
+	None
+}
+
+// Jump point search. Much faster than A* on open maps but it ignores tile costs (every walkable tile costs 1)
+// and always moves like `Diagonal::NoCornerCutting`.
+pub fn jump_point_search<G: PathGrid + ?Sized>(grid: &G, start: (i32, i32), goal: (i32, i32)) -> Option<Path> {
+	if !grid.is_walkable(start.0, start.1) || !grid.is_walkable(goal.0, goal.1) {
+		return None;
+	}
+
+	let diagonal = Diagonal::NoCornerCutting;
+	let len = (grid.width() * grid.height()) as usize;
+	let mut g_score = vec![u32::MAX; len];
+	let mut came_from = vec![usize::MAX; len];
+	let mut open = BinaryHeap::new();
+	let mut successors = Vec::with_capacity(8);
+
+	let start_index = grid.index(start.0, start.1);
+	g_score[start_index] = 0;
+	open.push(Reverse((heuristic(start, goal, diagonal), heuristic(start, goal, diagonal), start_index)));
+
+	while let Some(Reverse((f, h, index))) = open.pop() {
+		let g = g_score[index];
+		if f - h > g {
+			continue;
+		}
+
+		let current = position(grid, index);
+		if current == goal {
+			// jump points are joined by straight or diagonal runs, fill in the tiles between them
+			let mut tiles = vec![start];
+			for jump in reconstruct(grid, &came_from, index).windows(2) {
+				let (mut x, mut y) = jump[0];
+				let (dx, dy) = ((jump[1].0 - x).signum(), (jump[1].1 - y).signum());
+				while (x, y) != jump[1] {
+					x += dx;
+					y += dy;
+					tiles.push((x, y));
+				}
+			}
+			return Some(Path { tiles, cost: g });
+		}
+
+		let parent = (came_from[index] != usize::MAX).then(|| position(grid, came_from[index]));
+		successors.clear();
+		jps_neighbours(grid, current, parent, &mut successors);
+		for &(nx, ny) in &successors {
+			let Some(jump) = jump(grid, (nx, ny), current, goal) else { continue };
+			let jump_index = grid.index(jump.0, jump.1);
+			let next_g = g + heuristic(current, jump, diagonal);
+			if next_g < g_score[jump_index] {
+				g_score[jump_index] = next_g;
+				came_from[jump_index] = index;
+				let h = heuristic(jump, goal, diagonal);
+				open.push(Reverse((next_g + h, h, jump_index)));
+			}
+		}
+	}
+	None
+}
+
+// pruned neighbours for the direction we arrived from
+fn jps_neighbours<G: PathGrid + ?Sized>(grid: &G, (x, y): (i32, i32), parent: Option<(i32, i32)>, out: &mut Vec<(i32, i32)>) {
+	let walkable = |x, y| grid.is_walkable(x, y);
+	let Some((px, py)) = parent else {
+		out.extend(neighbours(grid, x, y, Diagonal::NoCornerCutting).map(|(tile, _)| tile));
+		return;
+	};
+
+	let (dx, dy) = ((x - px).signum(), (y - py).signum());
+	if dx != 0 && dy != 0 {
+		if walkable(x, y + dy) {
+			out.push((x, y + dy));
+		}
+		if walkable(x + dx, y) {
+			out.push((x + dx, y));
+		}
+		if walkable(x, y + dy) && walkable(x + dx, y) {
+			out.push((x + dx, y + dy));
+		}
+	} else if dx != 0 {
+		let up = walkable(x, y - 1);
+		let down = walkable(x, y + 1);
+		if walkable(x + dx, y) {
+			out.push((x + dx, y));
+			if down {
+				out.push((x + dx, y + 1));
+			}
+			if up {
+				out.push((x + dx, y - 1));
+			}
+		}
+		if down {
+			out.push((x, y + 1));
+		}
+		if up {
+			out.push((x, y - 1));
+		}
+	} else {
+		let right = walkable(x + 1, y);
+		let left = walkable(x - 1, y);
+		if walkable(x, y + dy) {
+			out.push((x, y + dy));
+			if right {
+				out.push((x + 1, y + dy));
+			}
+			if left {
+				out.push((x - 1, y + dy));
+			}
+		}
+		if right {
+			out.push((x + 1, y));
+		}
+		if left {
+			out.push((x - 1, y));
+		}
+	}
+}
+
+// Follows a straight or diagonal run until it reaches a jump point. Only the two straight probes of a diagonal
+// step branch off, so the depth stays the same however long the run is.
+fn jump<G: PathGrid + ?Sized>(grid: &G, (mut x, mut y): (i32, i32), (px, py): (i32, i32), goal: (i32, i32)) -> Option<(i32, i32)> {
+	let walkable = |x, y| grid.is_walkable(x, y);
+	let (dx, dy) = (x - px, y - py);
+	loop {
+		if !walkable(x, y) {
+			return None;
+		}
+		if (x, y) == goal {
+			return Some((x, y));
+		}
+
+		if dx != 0 && dy != 0 {
+			// a diagonal run stops where one of its straight runs finds something
+			if jump(grid, (x + dx, y), (x, y), goal).is_some() || jump(grid, (x, y + dy), (x, y), goal).is_some() {
+				return Some((x, y));
+			}
+		} else if dx != 0 {
+			if (walkable(x, y - 1) && !walkable(x - dx, y - 1)) || (walkable(x, y + 1) && !walkable(x - dx, y + 1)) {
+				return Some((x, y));
+			}
+		} else if (walkable(x - 1, y) && !walkable(x - 1, y - dy)) || (walkable(x + 1, y) && !walkable(x + 1, y - dy)) {
+			return Some((x, y));
+		}
+
+		if !walkable(x + dx, y) || !walkable(x, y + dy) {
+			return None;
+		}
+		x += dx;
+		y += dy;
+	}
+}
+
+fn position<G: PathGrid + ?Sized>(grid: &G, index: usize) -> (i32, i32) {
+	(index as i32 % grid.width(), index as i32 / grid.width())
+}
+
+fn reconstruct<G: PathGrid + ?Sized>(grid: &G, came_from: &[usize], mut index: usize) -> Vec<(i32, i32)> {
+	let mut tiles = vec![position(grid, index)];
+	while came_from[index] != usize::MAX {
+		index = came_from[index];
+		tiles.push(position(grid, index));
+	}
+	tiles.reverse();
+	tiles
+}
+
+// Cost of reaching the nearest goal from every tile, computed once and shared by any number of agents.
+#[derive(Clone, Debug)]
+pub struct DijkstraMap {
+	width: i32,
+	height: i32,
+	diagonal: Diagonal,
+	distances: Box<[u32]>,
+}
+
+impl TileMap for DijkstraMap {
+	fn width(&self) -> i32 {
+		self.width
+	}
+
+	fn height(&self) -> i32 {
+		self.height
+	}
+}
+
+impl DijkstraMap {
+	pub fn new<G: PathGrid + ?Sized>(grid: &G, goals: &[(i32, i32)], diagonal: Diagonal) -> Self {
+		let mut map = Self {
+			width: grid.width(),
+			height: grid.height(),
+			diagonal,
+			distances: vec![u32::MAX; (grid.width() * grid.height()) as usize].into_boxed_slice(),
+		};
+		map.rebuild(grid, goals);
+		map
+	}
+
+	// Recomputes the distances, e.g. after the goals moved or tiles changed.
+	pub fn rebuild<G: PathGrid + ?Sized>(&mut self, grid: &G, goals: &[(i32, i32)]) {
+		self.distances.fill(u32::MAX);
+		let mut open = BinaryHeap::new();
+		for &(x, y) in goals {
+			if grid.is_walkable(x, y) {
+				let index = grid.index(x, y);
+				self.distances[index] = 0;
+				open.push(Reverse((0, index)));
+			}
+		}
+
+		// costs are paid when entering a tile and agents walk towards the goal, so a step from a neighbour onto
+		// the current tile costs the current tile
+		while let Some(Reverse((distance, index))) = open.pop() {
+			if distance > self.distances[index] {
+				continue;
+			}
+			let (x, y) = position(grid, index);
+			let enter_cost = grid.cost(x, y).map_or(1, NonZeroU32::get);
+			for ((nx, ny), _) in neighbours(grid, x, y, self.diagonal) {
+				let step = if nx != x && ny != y { DIAGONAL_COST } else { ORTHOGONAL_COST };
+				let next = distance + step * enter_cost;
+				let next_index = grid.index(nx, ny);
+				if next < self.distances[next_index] {
+					self.distances[next_index] = next;
+					open.push(Reverse((next, next_index)));
+				}
+			}
+		}
+	}
+
+	pub fn diagonal(&self) -> Diagonal {
+		self.diagonal
+	}
+
+	// Cost to the nearest goal, `None` if no goal can be reached.
+	pub fn distance(&self, x: i32, y: i32) -> Option<u32> {
+		if !self.in_bounds(x, y) {
+			return None;
+		}
+		let distance = self.distances[self.index(x, y)];
+		(distance != u32::MAX).then_some(distance)
+	}
+
+	// The neighbour to step to for going downhill towards the nearest goal.
+	pub fn next_step<G: PathGrid + ?Sized>(&self, grid: &G, x: i32, y: i32) -> Option<(i32, i32)> {
+		let here = self.distance(x, y)?;
+		neighbours(grid, x, y, self.diagonal)
+			.filter_map(|(tile, _)| Some((self.distance(tile.0, tile.1)?, tile)))
+			.filter(|(distance, _)| *distance < here)
+			.min_by_key(|(distance, _)| *distance)
+			.map(|(_, tile)| tile)
+	}
+}
+
+// Direction to move from every tile towards the nearest goal of a `DijkstraMap`.
+#[derive(Clone, Debug)]
+pub struct FlowField {
+	width: i32,
+	height: i32,
+	directions: Box<[(i8, i8)]>,
+}
+
+impl TileMap for FlowField {
+	fn width(&self) -> i32 {
+		self.width
+	}
+
+	fn height(&self) -> i32 {
+		self.height
+	}
+}
+
+impl FlowField {
+	pub fn new<G: PathGrid + ?Sized>(grid: &G, dijkstra: &DijkstraMap) -> Self {
+		let mut directions = vec![(0, 0); (grid.width() * grid.height()) as usize].into_boxed_slice();
+		for y in 0..grid.height() {
+			for x in 0..grid.width() {
+				if let Some((nx, ny)) = dijkstra.next_step(grid, x, y) {
+					directions[grid.index(x, y)] = ((nx - x) as i8, (ny - y) as i8);
+				}
+			}
+		}
+		Self {
+			width: grid.width(),
+			height: grid.height(),
+			directions,
+		}
+	}
+
+	// Unit step towards the goal, `None` on goals, blocked and unreachable tiles.
+	pub fn direction(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+		if !self.in_bounds(x, y) {
+			return None;
+		}
+		match self.directions[self.index(x, y)] {
+			(0, 0) => None,
+			(dx, dy) => Some((dx as i32, dy as i32)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// '#' is blocked, '.' costs 1 and a digit costs that much
+	struct Fixture {
+		width: i32,
+		height: i32,
+		costs: Vec<Option<NonZeroU32>>,
+	}
+
+	impl Fixture {
+		fn new(rows: &[&str]) -> Self {
+			let costs = rows
+				.iter()
+				.flat_map(|row| row.chars())
+				.map(|c| match c {
+					'#' => None,
+					'.' => NonZeroU32::new(1),
+					digit => NonZeroU32::new(digit.to_digit(10).expect("cost digit")),
+				})
+				.collect();
+			Self {
+				width: rows[0].len() as i32,
+				height: rows.len() as i32,
+				costs,
+			}
+		}
+
+		fn open_tiles(&self) -> Vec<(i32, i32)> {
+			(0..self.height)
+				.flat_map(|y| (0..self.width).map(move |x| (x, y)))
+				.filter(|&(x, y)| self.is_walkable(x, y))
+				.collect()
+		}
+	}
+
+	impl TileMap for Fixture {
+		fn width(&self) -> i32 {
+			self.width
+		}
+
+		fn height(&self) -> i32 {
+			self.height
+		}
+	}
+
+	impl PathGrid for Fixture {
+		fn cost(&self, x: i32, y: i32) -> Option<NonZeroU32> {
+			self.costs[self.index(x, y)]
+		}
+	}
+
+	fn maze() -> Fixture {
+		Fixture::new(&[
+			"..........",
+			".####.###.",
+			".#......#.",
+			".#.####.#.",
+			"...#..#...",
+			"##.#..#.##",
+			"...#....#.",
+			".###.####.",
+			"..........",
+		])
+	}
+
+	fn pillars() -> Fixture {
+		Fixture::new(&[
+			"...........",
+			"..#.....#..",
+			"...........",
+			"....#.#....",
+			"...........",
+			"..#..#..#..",
+			"...........",
+		])
+	}
+
+	// steps are between neighbours, the cost adds up and nothing blocked is walked
+	fn assert_valid(grid: &Fixture, path: &Path, diagonal: Diagonal) {
+		let mut cost = 0;
+		for step in path.tiles.windows(2) {
+			let ((x, y), next) = (step[0], step[1]);
+			let (_, step_cost) = neighbours(grid, x, y, diagonal)
+				.find(|(tile, _)| *tile == next)
+				.unwrap_or_else(|| panic!("{:?} to {next:?} is not a step", (x, y)));
+			cost += step_cost;
+		}
+		assert_eq!(cost, path.cost);
+	}
+
+	#[test]
+	fn astar_and_jps_agree_on_the_cost() {
+		for grid in [maze(), pillars()] {
+			let tiles = grid.open_tiles();
+			for &start in tiles.iter().step_by(3) {
+				for &goal in tiles.iter().step_by(5) {
+					let astar = astar(&grid, start, goal, Diagonal::NoCornerCutting);
+					let jps = jump_point_search(&grid, start, goal);
+					assert_eq!(
+						astar.as_ref().map(|path| path.cost),
+						jps.as_ref().map(|path| path.cost),
+						"{start:?} to {goal:?}"
+					);
+					if let (Some(astar), Some(jps)) = (astar, jps) {
+						assert_valid(&grid, &astar, Diagonal::NoCornerCutting);
+						assert_valid(&grid, &jps, Diagonal::NoCornerCutting);
+						assert_eq!((jps.tiles[0], jps.tiles[jps.tiles.len() - 1]), (start, goal));
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn astar_goes_around_expensive_tiles() {
+		let grid = Fixture::new(&[".....", ".999.", "....."]);
+		let path = astar(&grid, (0, 1), (4, 1), Diagonal::Never).unwrap();
+		// through the middle would cost 9 * 3 + 1 tiles, around costs 6 tiles
+		assert_eq!(path.cost, 6 * ORTHOGONAL_COST);
+		assert_valid(&grid, &path, Diagonal::Never);
+
+		// through a single cost 2 tile is still cheaper than the detour
+		let grid = Fixture::new(&[".....", "..2..", "....."]);
+		let path = astar(&grid, (0, 1), (4, 1), Diagonal::Never).unwrap();
+		assert_eq!(path.cost, 5 * ORTHOGONAL_COST);
+		assert_eq!(path.tiles.len(), 5);
+	}
+
+	#[test]
+	fn diagonal_rules() {
+		let grid = Fixture::new(&[".#", "#."]);
+		assert_eq!(astar(&grid, (0, 0), (1, 1), Diagonal::Never), None);
+		assert_eq!(astar(&grid, (0, 0), (1, 1), Diagonal::NoCornerCutting), None);
+		assert_eq!(astar(&grid, (0, 0), (1, 1), Diagonal::AllowCornerCutting), None);
+		assert_eq!(astar(&grid, (0, 0), (1, 1), Diagonal::Always).unwrap().cost, DIAGONAL_COST);
+
+		let grid = Fixture::new(&["..", "#."]);
+		assert_eq!(astar(&grid, (0, 0), (1, 1), Diagonal::AllowCornerCutting).unwrap().cost, DIAGONAL_COST);
+		assert_eq!(astar(&grid, (0, 0), (1, 1), Diagonal::NoCornerCutting).unwrap().cost, 2 * ORTHOGONAL_COST);
+	}
+
+	#[test]
+	fn no_path_to_or_from_blocked_tiles() {
+		let grid = maze();
+		assert_eq!(astar(&grid, (1, 1), (0, 0), Diagonal::Always), None);
+		assert_eq!(jump_point_search(&grid, (0, 0), (1, 1)), None);
+		assert_eq!(astar(&grid, (0, 0), (20, 0), Diagonal::Always), None);
+
+		let walled = Fixture::new(&["..#..", "..#.."]);
+		assert_eq!(astar(&walled, (0, 0), (4, 1), Diagonal::Always), None);
+		assert_eq!(jump_point_search(&walled, (0, 0), (4, 1)), None);
+		assert_eq!(astar(&walled, (0, 0), (0, 0), Diagonal::Always).unwrap().tiles, [(0, 0)]);
+	}
+
+	#[test]
+	fn jps_follows_long_runs_without_deep_recursion() {
+		// one frame per tile would overflow the test thread's stack long before the end
+		let corridor = Fixture::new(&[&".".repeat(200_000)]);
+		let path = jump_point_search(&corridor, (0, 0), (199_999, 0)).unwrap();
+		assert_eq!(path.cost, 199_999 * ORTHOGONAL_COST);
+		assert_eq!(path.tiles.len(), 200_000);
+
+		let rows = vec![".".repeat(2_000); 2_000];
+		let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+		let open = Fixture::new(&rows);
+		let path = jump_point_search(&open, (0, 0), (1_999, 1_999)).unwrap();
+		assert_eq!(path.cost, 1_999 * DIAGONAL_COST);
+	}
+
+	#[test]
+	fn dijkstra_distances_match_astar() {
+		let grid = Fixture::new(&["..3..#...", ".#3#.#.#.", ".#...5.#.", ".####.##.", "....2...."]);
+		for diagonal in [Diagonal::Never, Diagonal::NoCornerCutting, Diagonal::Always] {
+			let goal = (8, 0);
+			let dijkstra = DijkstraMap::new(&grid, &[goal], diagonal);
+			for y in 0..grid.height() {
+				for x in 0..grid.width() {
+					let expected = astar(&grid, (x, y), goal, diagonal).map(|path| path.cost);
+					assert_eq!(dijkstra.distance(x, y), expected, "{diagonal:?} {:?}", (x, y));
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn dijkstra_uses_the_nearest_goal() {
+		let grid = Fixture::new(&["........."]);
+		let dijkstra = DijkstraMap::new(&grid, &[(0, 0), (8, 0), (20, 0)], Diagonal::Never);
+		let distances: Vec<_> = (0..9).map(|x| dijkstra.distance(x, 0).unwrap() / ORTHOGONAL_COST).collect();
+		assert_eq!(distances, [0, 1, 2, 3, 4, 3, 2, 1, 0]);
+		assert_eq!(dijkstra.distance(-1, 0), None);
+		assert_eq!(dijkstra.next_step(&grid, 3, 0), Some((2, 0)));
+		assert_eq!(dijkstra.next_step(&grid, 0, 0), None);
+	}
+
+	#[test]
+	fn flow_field_points_downhill_to_the_goal() {
+		let grid = maze();
+		let goal = (5, 4);
+		let dijkstra = DijkstraMap::new(&grid, &[goal], Diagonal::NoCornerCutting);
+		let flow = FlowField::new(&grid, &dijkstra);
+
+		assert_eq!(flow.direction(goal.0, goal.1), None);
+		assert_eq!(flow.direction(1, 1), None);
+		// the room only opens to the south
+		assert_eq!(flow.direction(4, 5), Some((1, -1)));
+		assert_eq!(flow.direction(5, 6), Some((0, -1)));
+
+		for start in grid.open_tiles() {
+			let (mut x, mut y) = start;
+			let mut steps = 0;
+			while let Some((dx, dy)) = flow.direction(x, y) {
+				assert!(dx.abs() <= 1 && dy.abs() <= 1);
+				let before = dijkstra.distance(x, y).unwrap();
+				(x, y) = (x + dx, y + dy);
+				assert!(dijkstra.distance(x, y).unwrap() < before);
+				steps += 1;
+			}
+			assert_eq!((x, y), goal, "from {start:?} after {steps} steps");
+		}
+	}
+}
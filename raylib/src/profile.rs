@@ -0,0 +1,464 @@
+use std::{
+	cell::Cell,
+	collections::{HashMap, VecDeque},
+	io::{self, Write},
+	sync::{Mutex, OnceLock},
+	time::{Duration, Instant},
+};
+
+use crate::{backend::Backend, *};
+
+// false without the `profile` feature, `profile_scope!` then compiles to nothing
+pub const ENABLED: bool = cfg!(feature = "profile");
+
+// about five seconds at 60 fps
+const HISTORY_FRAMES: usize = 300;
+const ROW_HEIGHT: f32 = 14.0;
+const FONT_SIZE: i32 = 10;
+
+// Times the rest of the enclosing block under `name` when the `profile` feature is on.
+#[cfg(feature = "profile")]
+#[macro_export]
+macro_rules! profile_scope {
+	($name:expr) => {
+		let _profile_scope = $crate::profile::Scope::new($name);
+	};
+}
+
+#[cfg(not(feature = "profile"))]
+#[macro_export]
+macro_rules! profile_scope {
+	($name:expr) => {};
+}
+
+// One timed scope, times are relative to when the profiler first ran.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ScopeEvent {
+	pub name: &'static str,
+	pub thread: u32,
+	// scopes open around it on the same thread
+	pub depth: u32,
+	pub start: Duration,
+	pub duration: Duration,
+}
+
+// All the scopes of one name in a frame.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ScopeStats {
+	pub name: &'static str,
+	pub calls: u32,
+	pub total: Duration,
+	pub max: Duration,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct FrameProfile {
+	pub index: u64,
+	pub start: Duration,
+	pub duration: Duration,
+	// in the order they ended
+	pub events: Vec<ScopeEvent>,
+	// slowest total first
+	pub scopes: Vec<ScopeStats>,
+}
+
+impl FrameProfile {
+	pub fn scope(&self, name: &str) -> Option<&ScopeStats> {
+		self.scopes.iter().find(|scope| scope.name == name)
+	}
+}
+
+#[derive(Default)]
+struct Profiler {
+	events: Vec<ScopeEvent>,
+	frames: VecDeque<FrameProfile>,
+	frame_start: Duration,
+	next_frame: u64,
+	thread_names: Vec<String>,
+}
+
+static PROFILER: Mutex<Option<Profiler>> = Mutex::new(None);
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+thread_local! {
+	static THREAD: Cell<Option<u32>> = const { Cell::new(None) };
+	static DEPTH: Cell<u32> = const { Cell::new(0) };
+}
+
+fn with_profiler<R>(f: impl FnOnce(&mut Profiler) -> R) -> R {
+	let mut profiler = PROFILER.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+	f(profiler.get_or_insert_with(Profiler::default))
+}
+
+fn since_epoch(instant: Instant) -> Duration {
+	instant.saturating_duration_since(*EPOCH.get_or_init(Instant::now))
+}
+
+// threads are numbered in the order they first record a scope
+fn thread_id() -> u32 {
+	THREAD.with(|thread| {
+		*thread.get().get_or_insert_with(|| {
+			let name = std::thread::current().name().unwrap_or("worker").to_string();
+			let id = with_profiler(|profiler| {
+				profiler.thread_names.push(name);
+				profiler.thread_names.len() as u32 - 1
+			});
+			thread.set(Some(id));
+			id
+		})
+	})
+}
+
+// Records from its creation until it is dropped, see `profile_scope!`.
+pub struct Scope {
+	name: &'static str,
+	start: Instant,
+	depth: u32,
+}
+
+impl Scope {
+	pub fn new(name: &'static str) -> Self {
+		EPOCH.get_or_init(Instant::now);
+		let depth = DEPTH.with(|depth| depth.replace(depth.get() + 1));
+		Self {
+			name,
+			start: Instant::now(),
+			depth,
+		}
+	}
+}
+
+impl Drop for Scope {
+	fn drop(&mut self) {
+		let end = Instant::now();
+		DEPTH.with(|depth| depth.set(self.depth));
+		let event = ScopeEvent {
+			name: self.name,
+			thread: thread_id(),
+			depth: self.depth,
+			start: since_epoch(self.start),
+			duration: end - self.start,
+		};
+		with_profiler(|profiler| profiler.events.push(event));
+	}
+}
+
+// Closes the frame: the scopes that ended since the last call become its profile, kept with the
+// last few seconds of frames. Call once per frame, after drawing.
+pub fn finish_frame() {
+	let now = since_epoch(Instant::now());
+	with_profiler(|profiler| {
+		let events = std::mem::take(&mut profiler.events);
+		let mut scopes: Vec<ScopeStats> = Vec::new();
+		let mut index = HashMap::new();
+		for event in &events {
+			let i = *index.entry(event.name).or_insert_with(|| {
+				scopes.push(ScopeStats {
+					name: event.name,
+					calls: 0,
+					total: Duration::ZERO,
+					max: Duration::ZERO,
+				});
+				scopes.len() - 1
+			});
+			let stats = &mut scopes[i];
+			stats.calls += 1;
+			stats.total += event.duration;
+			stats.max = stats.max.max(event.duration);
+		}
+		scopes.sort_by_key(|scope| std::cmp::Reverse(scope.total));
+
+		if profiler.frames.len() == HISTORY_FRAMES {
+			profiler.frames.pop_front();
+		}
+		profiler.frames.push_back(FrameProfile {
+			index: profiler.next_frame,
+			start: profiler.frame_start,
+			duration: now.saturating_sub(profiler.frame_start),
+			events,
+			scopes,
+		});
+		profiler.next_frame += 1;
+		profiler.frame_start = now;
+	});
+}
+
+pub fn last_frame() -> Option<FrameProfile> {
+	with_profiler(|profiler| profiler.frames.back().cloned())
+}
+
+// the kept frames, oldest first
+pub fn frames() -> Vec<FrameProfile> {
+	with_profiler(|profiler| profiler.frames.iter().cloned().collect())
+}
+
+// Writes `frames` in the Chrome trace event format, for chrome://tracing or Perfetto. Frames go on
+// their own track above the threads.
+pub fn write_chrome_trace(frames: &[FrameProfile], writer: &mut impl Write) -> io::Result<()> {
+	let thread_names = with_profiler(|profiler| profiler.thread_names.clone());
+	writeln!(writer, "{{\"displayTimeUnit\":\"ms\",\"traceEvents\":[")?;
+	let mut first = true;
+	let mut separator = |writer: &mut dyn Write| -> io::Result<()> {
+		if !std::mem::take(&mut first) {
+			writeln!(writer, ",")?;
+		}
+		Ok(())
+	};
+	separator(writer)?;
+	write!(
+		writer,
+		"{{\"ph\":\"M\",\"name\":\"thread_name\",\"pid\":0,\"tid\":0,\"args\":{{\"name\":\"frames\"}}}}"
+	)?;
+	for (thread, name) in thread_names.iter().enumerate() {
+		separator(writer)?;
+		write!(
+			writer,
+			"{{\"ph\":\"M\",\"name\":\"thread_name\",\"pid\":0,\"tid\":{},\"args\":{{\"name\":{}}}}}",
+			thread + 1,
+			json_string(name)
+		)?;
+	}
+	for frame in frames {
+		separator(writer)?;
+		write!(
+			writer,
+			"{{\"ph\":\"X\",\"cat\":\"frame\",\"name\":\"frame {}\",\"pid\":0,\"tid\":0,\"ts\":{:.3},\"dur\":{:.3}}}",
+			frame.index,
+			micros(frame.start),
+			micros(frame.duration)
+		)?;
+		for event in &frame.events {
+			separator(writer)?;
+			write!(
+				writer,
+				"{{\"ph\":\"X\",\"cat\":\"scope\",\"name\":{},\"pid\":0,\"tid\":{},\"ts\":{:.3},\"dur\":{:.3}}}",
+				json_string(event.name),
+				event.thread + 1,
+				micros(event.start),
+				micros(event.duration)
+			)?;
+		}
+	}
+	writeln!(writer, "\n]}}")
+}
+
+fn micros(duration: Duration) -> f64 {
+	duration.as_secs_f64() * 1_000_000.0
+}
+
+fn json_string(text: &str) -> String {
+	let mut json = String::with_capacity(text.len() + 2);
+	json.push('"');
+	for c in text.chars() {
+		match c {
+			'"' => json.push_str("\\\""),
+			'\\' => json.push_str("\\\\"),
+			c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+			c => json.push(c),
+		}
+	}
+	json.push('"');
+	json
+}
+
+// Draws `frame` as a flame graph in `area`: one band per thread, nested scopes below their parents,
+// then the slowest scopes of the frame.
+pub fn draw_flame(frame: &FrameProfile, backend: &mut dyn Backend, area: Rectangle) {
+	backend.draw_rectangle_rec(area, fade(BLACK, 0.7));
+	let title = format!("frame {}  {:.2} ms", frame.index, frame.duration.as_secs_f32() * 1000.0);
+	backend.draw_text(&title, area.x as i32 + 4, area.y as i32 + 2, FONT_SIZE, RAYWHITE);
+	let length = frame.duration.as_secs_f32().max(f32::EPSILON);
+
+	let mut threads: Vec<u32> = frame.events.iter().map(|event| event.thread).collect();
+	threads.sort_unstable();
+	threads.dedup();
+	let mut y = area.y + ROW_HEIGHT + 2.0;
+	for thread in threads {
+		let events = frame.events.iter().filter(|event| event.thread == thread);
+		let rows = events.clone().map(|event| event.depth + 1).max().unwrap_or(0);
+		for event in events {
+			let start = (event.start.saturating_sub(frame.start)).as_secs_f32() / length;
+			let bar = Rectangle {
+				x: area.x + start.min(1.0) * area.width,
+				y: y + event.depth as f32 * ROW_HEIGHT,
+				width: (event.duration.as_secs_f32() / length * area.width).max(1.0),
+				height: ROW_HEIGHT - 1.0,
+			};
+			backend.draw_rectangle_rec(bar, scope_color(event.name));
+			let label = short_name(event.name);
+			if raster::measure_text(label, FONT_SIZE) + 4 < bar.width as i32 {
+				backend.draw_text(label, bar.x as i32 + 2, bar.y as i32 + 2, FONT_SIZE, BLACK);
+			}
+		}
+		y += rows as f32 * ROW_HEIGHT + 4.0;
+	}
+
+	for scope in frame.scopes.iter().take(5) {
+		let line = format!(
+			"{:.2} ms  x{}  {}",
+			scope.total.as_secs_f32() * 1000.0,
+			scope.calls,
+			short_name(scope.name)
+		);
+		backend.draw_text(&line, area.x as i32 + 4, y as i32, FONT_SIZE, LIGHTGRAY);
+		y += ROW_HEIGHT;
+	}
+}
+
+// system names are full paths, the last part is enough on screen
+fn short_name(name: &str) -> &str {
+	name.rsplit("::").next().unwrap_or(name)
+}
+
+// the same name keeps its color from frame to frame
+fn scope_color(name: &str) -> Color {
+	const COLORS: [Color; 8] = [GOLD, ORANGE, PINK, LIME, SKYBLUE, PURPLE, BEIGE, YELLOW];
+	let hash = name.bytes().fold(0u32, |hash, byte| hash.wrapping_mul(31).wrapping_add(byte as u32));
+	COLORS[hash as usize % COLORS.len()]
+}
+
+#[cfg(test)]
+mod tests {
+	use std::thread;
+
+	use super::*;
+
+	// the profiler is global, so the tests mustn't see each other's scopes
+	static SERIAL: Mutex<()> = Mutex::new(());
+
+	fn serial() -> std::sync::MutexGuard<'static, ()> {
+		let guard = SERIAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+		// drop whatever was recorded before
+		finish_frame();
+		guard
+	}
+
+	#[test]
+	fn frames_add_up_their_scopes() {
+		let _serial = serial();
+		for _ in 0..3 {
+			let _fast = Scope::new("fast");
+		}
+		{
+			let _slow = Scope::new("slow");
+			thread::sleep(Duration::from_millis(5));
+		}
+		let _fast = Scope::new("fast");
+		drop(_fast);
+		finish_frame();
+
+		let frame = last_frame().unwrap();
+		assert_eq!(
+			frame.events.iter().map(|event| event.name).collect::<Vec<_>>(),
+			["fast", "fast", "fast", "slow", "fast"]
+		);
+		assert_eq!(
+			frame.scopes.iter().map(|scope| (scope.name, scope.calls)).collect::<Vec<_>>(),
+			[("slow", 1), ("fast", 4)]
+		);
+		let fast = frame.scope("fast").unwrap();
+		let durations = frame.events.iter().filter(|event| event.name == "fast").map(|event| event.duration);
+		assert_eq!(fast.total, durations.clone().sum());
+		assert_eq!(fast.max, durations.max().unwrap());
+		assert!(frame.scope("slow").unwrap().max >= Duration::from_millis(5));
+		assert!(frame.duration >= frame.scope("slow").unwrap().total);
+		assert_eq!(frame.scope("missing"), None);
+
+		// the next frame starts empty where this one ended
+		finish_frame();
+		let next = last_frame().unwrap();
+		assert_eq!(next.index, frame.index + 1);
+		assert_eq!(next.start, frame.start + frame.duration);
+		assert!(next.events.is_empty() && next.scopes.is_empty());
+		assert_eq!(frames().last().unwrap().index, next.index);
+	}
+
+	#[test]
+	fn nested_scopes_go_deeper() {
+		let _serial = serial();
+		{
+			let _outer = Scope::new("outer");
+			{
+				let _middle = Scope::new("middle");
+				let _inner = Scope::new("inner");
+			}
+			let _sibling = Scope::new("sibling");
+		}
+		let _after = Scope::new("after");
+		drop(_after);
+		finish_frame();
+
+		let frame = last_frame().unwrap();
+		let depths: Vec<_> = frame.events.iter().map(|event| (event.name, event.depth)).collect();
+		assert_eq!(depths, [("inner", 2), ("middle", 1), ("sibling", 1), ("outer", 0), ("after", 0)]);
+		let (inner, outer) = (frame.events[0], frame.events[3]);
+		assert!(outer.start <= inner.start && inner.start + inner.duration <= outer.start + outer.duration);
+	}
+
+	#[test]
+	fn other_threads_get_their_own_depth_and_id() {
+		let _serial = serial();
+		let _outer = Scope::new("main");
+		thread::Builder::new()
+			.name("loader".into())
+			.spawn(|| drop(Scope::new("load")))
+			.unwrap()
+			.join()
+			.unwrap();
+		drop(_outer);
+		finish_frame();
+
+		let frame = last_frame().unwrap();
+		let (load, main) = (frame.events[0], frame.events[1]);
+		assert_eq!((load.name, load.depth, main.name, main.depth), ("load", 0, "main", 0));
+		assert_ne!(load.thread, main.thread);
+		let names = with_profiler(|profiler| profiler.thread_names.clone());
+		assert_eq!(names[load.thread as usize], "loader");
+	}
+
+	#[test]
+	fn json_strings_escape_quotes_backslashes_and_control_characters() {
+		assert_eq!(json_string("plain"), r#""plain""#);
+		assert_eq!(json_string(r#"say "hi" \o/"#), r#""say \"hi\" \\o/""#);
+		assert_eq!(json_string("a\nb\t\u{1}"), r#""a\u000ab\u0009\u0001""#);
+		assert_eq!(json_string("émoji ✓"), "\"émoji ✓\"");
+	}
+
+	#[test]
+	fn chrome_traces_list_frames_and_scopes() {
+		let _serial = serial();
+		let event = |name, depth, start| ScopeEvent {
+			name,
+			thread: 0,
+			depth,
+			start: Duration::from_micros(start),
+			duration: Duration::from_micros(250),
+		};
+		let frames = [FrameProfile {
+			index: 7,
+			start: Duration::from_micros(1000),
+			duration: Duration::from_micros(16_500),
+			events: vec![event("app::\"render\"", 1, 1100), event("frame", 0, 1000)],
+			scopes: Vec::new(),
+		}];
+		let mut trace = Vec::new();
+		write_chrome_trace(&frames, &mut trace).unwrap();
+		let trace = String::from_utf8(trace).unwrap();
+		let lines: Vec<&str> = trace.lines().collect();
+		let thread_count = with_profiler(|profiler| profiler.thread_names.len());
+
+		assert_eq!(lines[0], r#"{"displayTimeUnit":"ms","traceEvents":["#);
+		assert_eq!(lines[1], r#"{"ph":"M","name":"thread_name","pid":0,"tid":0,"args":{"name":"frames"}},"#);
+		assert_eq!(lines.len(), 1 + 1 + thread_count + 3 + 1);
+		assert_eq!(
+			lines[2 + thread_count],
+			r#"{"ph":"X","cat":"frame","name":"frame 7","pid":0,"tid":0,"ts":1000.000,"dur":16500.000},"#
+		);
+		assert_eq!(
+			lines[3 + thread_count],
+			r#"{"ph":"X","cat":"scope","name":"app::\"render\"","pid":0,"tid":1,"ts":1100.000,"dur":250.000},"#
+		);
+		assert!(lines[4 + thread_count].ends_with(r#""dur":250.000}"#));
+		assert_eq!(lines.last(), Some(&"]}"));
+	}
+}
@@ -0,0 +1,1191 @@
+use std::{
+	collections::HashMap,
+	io::{self, Write},
+	ptr,
+};
+
+use crate::{
+	camera,
+	recording::{DrawCommand, TextureRef},
+	*,
+};
+
+// 5x7 glyphs for ' ' to '~', one byte per row with bit 4 as the leftmost column. g, p, q and y
+// start at the x-height and are drawn lower.
+const GLYPHS: [[u8; 7]; 95] = [
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+	[0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04], // '!'
+	[0x0a, 0x0a, 0x0a, 0x00, 0x00, 0x00, 0x00], // '"'
+	[0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a], // '#'
+	[0x04, 0x0f, 0x14, 0x0e, 0x05, 0x1e, 0x04], // '$'
+	[0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03], // '%'
+	[0x0c, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0d], // '&'
+	[0x0c, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00], // '''
+	[0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02], // '('
+	[0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08], // ')'
+	[0x00, 0x04, 0x15, 0x0e, 0x15, 0x04, 0x00], // '*'
+	[0x00, 0x04, 0x04, 0x1f, 0x04, 0x04, 0x00], // '+'
+	[0x00, 0x00, 0x00, 0x00, 0x0c, 0x04, 0x08], // ','
+	[0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00], // '-'
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c], // '.'
+	[0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00], // '/'
+	[0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e], // '0'
+	[0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e], // '1'
+	[0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f], // '2'
+	[0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e], // '3'
+	[0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02], // '4'
+	[0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e], // '5'
+	[0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e], // '6'
+	[0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08], // '7'
+	[0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e], // '8'
+	[0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c], // '9'
+	[0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00], // ':'
+	[0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x04, 0x08], // ';'
+	[0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02], // '<'
+	[0x00, 0x00, 0x1f, 0x00, 0x1f, 0x00, 0x00], // '='
+	[0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08], // '>'
+	[0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // '?'
+	[0x0e, 0x11, 0x01, 0x0d, 0x15, 0x15, 0x0e], // '@'
+	[0x0e, 0x11, 0x11, 0x11, 0x1f, 0x11, 0x11], // 'A'
+	[0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e], // 'B'
+	[0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e], // 'C'
+	[0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c], // 'D'
+	[0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f], // 'E'
+	[0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10], // 'F'
+	[0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f], // 'G'
+	[0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11], // 'H'
+	[0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e], // 'I'
+	[0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c], // 'J'
+	[0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11], // 'K'
+	[0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f], // 'L'
+	[0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11], // 'M'
+	[0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11], // 'N'
+	[0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e], // 'O'
+	[0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10], // 'P'
+	[0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d], // 'Q'
+	[0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11], // 'R'
+	[0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e], // 'S'
+	[0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // 'T'
+	[0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e], // 'U'
+	[0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04], // 'V'
+	[0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a], // 'W'
+	[0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11], // 'X'
+	[0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04], // 'Y'
+	[0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f], // 'Z'
+	[0x0e, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0e], // '['
+	[0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00], // '\'
+	[0x0e, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0e], // ']'
+	[0x04, 0x0a, 0x11, 0x00, 0x00, 0x00, 0x00], // '^'
+	[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f], // '_'
+	[0x08, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00], // '`'
+	[0x00, 0x00, 0x0e, 0x01, 0x0f, 0x11, 0x0f], // 'a'
+	[0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1e], // 'b'
+	[0x00, 0x00, 0x0e, 0x10, 0x10, 0x11, 0x0e], // 'c'
+	[0x01, 0x01, 0x0d, 0x13, 0x11, 0x11, 0x0f], // 'd'
+	[0x00, 0x00, 0x0e, 0x11, 0x1f, 0x10, 0x0e], // 'e'
+	[0x06, 0x09, 0x08, 0x1c, 0x08, 0x08, 0x08], // 'f'
+	[0x0f, 0x11, 0x11, 0x11, 0x0f, 0x01, 0x0e], // 'g'
+	[0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11], // 'h'
+	[0x04, 0x00, 0x0c, 0x04, 0x04, 0x04, 0x0e], // 'i'
+	[0x02, 0x00, 0x06, 0x02, 0x02, 0x12, 0x0c], // 'j'
+	[0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12], // 'k'
+	[0x0c, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e], // 'l'
+	[0x00, 0x00, 0x1a, 0x15, 0x15, 0x11, 0x11], // 'm'
+	[0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11], // 'n'
+	[0x00, 0x00, 0x0e, 0x11, 0x11, 0x11, 0x0e], // 'o'
+	[0x16, 0x19, 0x11, 0x11, 0x1e, 0x10, 0x10], // 'p'
+	[0x0d, 0x13, 0x11, 0x11, 0x0f, 0x01, 0x01], // 'q'
+	[0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10], // 'r'
+	[0x00, 0x00, 0x0e, 0x10, 0x0e, 0x01, 0x1e], // 's'
+	[0x08, 0x08, 0x1c, 0x08, 0x08, 0x09, 0x06], // 't'
+	[0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0d], // 'u'
+	[0x00, 0x00, 0x11, 0x11, 0x11, 0x0a, 0x04], // 'v'
+	[0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0a], // 'w'
+	[0x00, 0x00, 0x11, 0x0a, 0x04, 0x0a, 0x11], // 'x'
+	[0x11, 0x11, 0x11, 0x13, 0x0d, 0x01, 0x0e], // 'y'
+	[0x00, 0x00, 0x1f, 0x02, 0x04, 0x08, 0x1f], // 'z'
+	[0x02, 0x04, 0x04, 0x08, 0x04, 0x04, 0x02], // '{'
+	[0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // '|'
+	[0x08, 0x04, 0x04, 0x02, 0x04, 0x04, 0x08], // '}'
+	[0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00], // '~'
+];
+
+// the default font is 10 px high, the glyphs sit one row below the top and descenders drop
+// below the baseline
+const FONT_SIZE: i32 = 10;
+const GLYPH_TOP: i32 = 1;
+const DESCENDER: i32 = 2;
+const SPACE_WIDTH: i32 = 3;
+// DrawText adds this between lines
+const LINE_SPACING: i32 = 2;
+
+// Drawn for textures that were never given to `set_texture`.
+const MISSING_TEXTURE: Color = MAGENTA;
+
+#[derive(Clone, Debug)]
+struct Texture {
+	width: i32,
+	height: i32,
+	pixels: Vec<Color>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Clip {
+	left: i32,
+	top: i32,
+	right: i32,
+	bottom: i32,
+}
+
+// Runs recorded draw commands on the CPU into an RGBA buffer, for comparing screenshots without
+// a GPU. Follows raylib's default state: alpha blending, point filtered textures, the default
+// font. Text uses a built-in 5x7 font with the default font's metrics, so glyph shapes differ
+// a little from what raylib draws.
+#[derive(Clone, Debug)]
+pub struct Canvas {
+	width: i32,
+	height: i32,
+	pixels: Vec<Color>,
+	textures: HashMap<u32, Texture>,
+	// DrawFPS has no fixed output, it shows this
+	pub fps: i32,
+	camera: Option<Camera2D>,
+	scissor: Option<Clip>,
+	// render texture between begin/end_texture_mode
+	target: Option<u32>,
+}
+
+impl Canvas {
+	pub fn new(width: i32, height: i32) -> Self {
+		let width = width.max(0);
+		let height = height.max(0);
+		Self {
+			width,
+			height,
+			pixels: vec![BLANK; (width * height) as usize],
+			textures: HashMap::new(),
+			fps: 60,
+			camera: None,
+			scissor: None,
+			target: None,
+		}
+	}
+
+	pub fn width(&self) -> i32 {
+		self.width
+	}
+
+	pub fn height(&self) -> i32 {
+		self.height
+	}
+
+	// row by row, top to bottom
+	pub fn pixels(&self) -> &[Color] {
+		&self.pixels
+	}
+
+	pub fn pixel(&self, x: i32, y: i32) -> Color {
+		self.pixels[(y * self.width + x) as usize]
+	}
+
+	// The pixels drawn for `texture`, row by row. Render textures are filled in by
+	// begin_texture_mode, upside down like on the GPU.
+	pub fn set_texture(&mut self, texture: TextureRef, pixels: Vec<Color>) {
+		assert_eq!(
+			pixels.len(),
+			(texture.width.max(0) * texture.height.max(0)) as usize,
+			"texture size does not match"
+		);
+		self.textures.insert(
+			texture.id,
+			Texture {
+				width: texture.width,
+				height: texture.height,
+				pixels,
+			},
+		);
+	}
+
+	pub fn run(&mut self, commands: &[DrawCommand]) {
+		for command in commands {
+			self.execute(command);
+		}
+	}
+
+	pub fn execute(&mut self, command: &DrawCommand) {
+		match command {
+			DrawCommand::BeginDrawing | DrawCommand::EndDrawing | DrawCommand::EndMode2D => self.camera = None,
+			DrawCommand::ClearBackground(color) => {
+				let (width, height) = self.target_size();
+				let clip = self.clip(width, height);
+				for y in clip.top..clip.bottom {
+					for x in clip.left..clip.right {
+						self.put(x, y, height, *color);
+					}
+				}
+			}
+			DrawCommand::BeginMode2D {
+				offset,
+				target,
+				rotation,
+				zoom,
+			} => {
+				self.camera = Some(Camera2D {
+					offset: *offset,
+					target: *target,
+					rotation: *rotation,
+					zoom: *zoom,
+				})
+			}
+			DrawCommand::BeginScissorMode { x, y, width, height } => {
+				self.scissor = Some(Clip {
+					left: *x,
+					top: *y,
+					right: x + width,
+					bottom: y + height,
+				})
+			}
+			DrawCommand::EndScissorMode => self.scissor = None,
+			DrawCommand::BeginTextureMode(texture) => {
+				self.textures.entry(texture.id).or_insert_with(|| Texture {
+					width: texture.width,
+					height: texture.height,
+					pixels: vec![BLANK; (texture.width.max(0) * texture.height.max(0)) as usize],
+				});
+				self.target = Some(texture.id);
+				self.camera = None;
+			}
+			DrawCommand::EndTextureMode => {
+				self.target = None;
+				self.camera = None;
+			}
+			DrawCommand::Rectangle { rec, color } => self.fill(*rec, |point| contains(rec, point).then_some(*color)),
+			DrawCommand::RectangleLines { rec, thickness, color } => self.rectangle_lines(*rec, *thickness, *color),
+			DrawCommand::RectanglePro {
+				rec,
+				origin,
+				rotation,
+				color,
+			} => {
+				let (bounds, to_local) = rotated(*rec, *origin, *rotation);
+				self.fill(bounds, |point| {
+					let local = to_local(point);
+					((0.0..rec.width).contains(&local.x) && (0.0..rec.height).contains(&local.y)).then_some(*color)
+				});
+			}
+			DrawCommand::Circle { center, radius, color } => {
+				let bounds = Rectangle {
+					x: center.x - radius,
+					y: center.y - radius,
+					width: radius * 2.0,
+					height: radius * 2.0,
+				};
+				self.fill(bounds, |point| {
+					let (dx, dy) = (point.x - center.x, point.y - center.y);
+					(dx * dx + dy * dy <= radius * radius).then_some(*color)
+				});
+			}
+			DrawCommand::CircleLines { center, radius, color } => {
+				// a one pixel wide ring
+				let bounds = Rectangle {
+					x: center.x - radius - 0.5,
+					y: center.y - radius - 0.5,
+					width: radius * 2.0 + 1.0,
+					height: radius * 2.0 + 1.0,
+				};
+				self.fill(bounds, |point| {
+					let distance = (point.x - center.x).hypot(point.y - center.y);
+					((distance - radius).abs() <= 0.5).then_some(*color)
+				});
+			}
+			DrawCommand::Poly {
+				center,
+				sides,
+				radius,
+				rotation,
+				color,
+			} => self.poly(*center, *sides, *radius, *rotation, *color),
+			DrawCommand::Line {
+				start,
+				end,
+				thickness,
+				color,
+			} => self.line(*start, *end, *thickness, *color),
+			DrawCommand::Text {
+				text,
+				x,
+				y,
+				font_size,
+				color,
+			} => self.text(text, *x, *y, *font_size, *color),
+			DrawCommand::TextPro {
+				text,
+				position,
+				rotation,
+				font_size,
+				spacing,
+				color,
+			} => self.text_pro(text, *position, *rotation, *font_size, *spacing, *color),
+			DrawCommand::TextureRec {
+				texture,
+				source,
+				position,
+				tint,
+			} => {
+				let dest = Rectangle {
+					x: position.x,
+					y: position.y,
+					width: source.width.abs(),
+					height: source.height.abs(),
+				};
+				self.texture(texture, *source, dest, Vector2::default(), 0.0, *tint);
+			}
+			DrawCommand::TexturePro {
+				texture,
+				source,
+				dest,
+				origin,
+				rotation,
+				tint,
+			} => self.texture(texture, *source, *dest, *origin, *rotation, *tint),
+			DrawCommand::Fps { x, y } => {
+				// same colors as DrawFPS
+				let color = match self.fps {
+					fps if fps < 15 => RED,
+					fps if fps < 30 => ORANGE,
+					_ => LIME,
+				};
+				self.text(&format!("{:2} FPS", self.fps), *x, *y, 20, color);
+			}
+		}
+	}
+
+	// Number of pixels where any channel differs by more than `tolerance`. Every pixel counts
+	// when the sizes differ.
+	pub fn count_differences(&self, other: &Canvas, tolerance: u8) -> usize {
+		if self.width != other.width || self.height != other.height {
+			return self.pixels.len().max(other.pixels.len());
+		}
+		let differs = |a: u8, b: u8| a.abs_diff(b) > tolerance;
+		self
+			.pixels
+			.iter()
+			.zip(&other.pixels)
+			.filter(|(a, b)| differs(a.r, b.r) || differs(a.g, b.g) || differs(a.b, b.b) || differs(a.a, b.a))
+			.count()
+	}
+
+	// An uncompressed RGBA PNG, written without the C library.
+	pub fn write_png(&self, writer: &mut impl Write) -> io::Result<()> {
+		let mut raw = Vec::with_capacity(self.pixels.len() * 4 + self.height as usize);
+		for row in self.pixels.chunks(self.width.max(1) as usize) {
+			// filter type none
+			raw.push(0);
+			for color in row {
+				raw.extend_from_slice(&[color.r, color.g, color.b, color.a]);
+			}
+		}
+
+		let mut header = Vec::with_capacity(13);
+		header.extend_from_slice(&(self.width as u32).to_be_bytes());
+		header.extend_from_slice(&(self.height as u32).to_be_bytes());
+		// 8 bits per channel, RGBA, deflate, no filtering scheme, no interlacing
+		header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+		writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+		write_chunk(writer, b"IHDR", &header)?;
+		write_chunk(writer, b"IDAT", &zlib_stored(&raw))?;
+		write_chunk(writer, b"IEND", &[])
+	}
+
+	// An R8G8B8A8 raylib image, free it with UnloadImage.
+	pub fn to_image(&self) -> Image {
+		let size = self.pixels.len() * 4;
+		unsafe {
+			let data = MemAlloc(size as u32) as *mut Color;
+			ptr::copy_nonoverlapping(self.pixels.as_ptr(), data, self.pixels.len());
+			Image {
+				data: data.cast(),
+				width: self.width,
+				height: self.height,
+				mipmaps: 1,
+				format: PixelFormat_PIXELFORMAT_UNCOMPRESSED_R8G8B8A8,
+			}
+		}
+	}
+
+	/// A canvas holding a copy of `image`, e.g. a reference screenshot from LoadImage. None unless
+	/// the image is R8G8B8A8, see ImageFormat.
+	///
+	/// # Safety
+	/// `image` must be a loaded image.
+	pub unsafe fn from_image(image: &Image) -> Option<Self> {
+		if image.format != PixelFormat_PIXELFORMAT_UNCOMPRESSED_R8G8B8A8 || image.data.is_null() {
+			return None;
+		}
+		let mut canvas = Canvas::new(image.width, image.height);
+		let data = std::slice::from_raw_parts(image.data as *const Color, canvas.pixels.len());
+		canvas.pixels.copy_from_slice(data);
+		Some(canvas)
+	}
+
+	fn target_size(&self) -> (i32, i32) {
+		match self.target.and_then(|id| self.textures.get(&id)) {
+			Some(texture) => (texture.width, texture.height),
+			None => (self.width, self.height),
+		}
+	}
+
+	fn clip(&self, width: i32, height: i32) -> Clip {
+		let mut clip = Clip {
+			left: 0,
+			top: 0,
+			right: width,
+			bottom: height,
+		};
+		if let Some(scissor) = self.scissor {
+			clip.left = clip.left.max(scissor.left);
+			clip.top = clip.top.max(scissor.top);
+			clip.right = clip.right.min(scissor.right);
+			clip.bottom = clip.bottom.min(scissor.bottom);
+		}
+		clip
+	}
+
+	// writes to the screen or, flipped vertically, to the render texture
+	fn put(&mut self, x: i32, y: i32, height: i32, color: Color) {
+		let (pixels, width, y) = match self.target.and_then(|id| self.textures.get_mut(&id)) {
+			Some(texture) => (&mut texture.pixels, texture.width, height - 1 - y),
+			None => (&mut self.pixels, self.width, y),
+		};
+		pixels[(y * width + x) as usize] = color;
+	}
+
+	fn get(&self, x: i32, y: i32, height: i32) -> Color {
+		match self.target.and_then(|id| self.textures.get(&id)) {
+			Some(texture) => texture.pixels[((height - 1 - y) * texture.width + x) as usize],
+			None => self.pixels[(y * self.width + x) as usize],
+		}
+	}
+
+	// Blends `shade` into every pixel whose center maps into the shape. `bounds` holds the shape
+	// in world units, `shade` gets the pixel center in world units.
+	fn fill(&mut self, bounds: Rectangle, shade: impl Fn(Vector2) -> Option<Color>) {
+		let (width, height) = self.target_size();
+		let clip = self.clip(width, height);
+		let corners = [
+			Vector2 { x: bounds.x, y: bounds.y },
+			Vector2 {
+				x: bounds.x + bounds.width,
+				y: bounds.y,
+			},
+			Vector2 {
+				x: bounds.x,
+				y: bounds.y + bounds.height,
+			},
+			Vector2 {
+				x: bounds.x + bounds.width,
+				y: bounds.y + bounds.height,
+			},
+		]
+		.map(|corner| self.to_screen(corner));
+		let min_x = corners.iter().map(|corner| corner.x).fold(f32::INFINITY, f32::min).floor() as i32;
+		let max_x = corners.iter().map(|corner| corner.x).fold(f32::NEG_INFINITY, f32::max).ceil() as i32;
+		let min_y = corners.iter().map(|corner| corner.y).fold(f32::INFINITY, f32::min).floor() as i32;
+		let max_y = corners.iter().map(|corner| corner.y).fold(f32::NEG_INFINITY, f32::max).ceil() as i32;
+
+		for y in min_y.max(clip.top)..max_y.min(clip.bottom) {
+			for x in min_x.max(clip.left)..max_x.min(clip.right) {
+				let center = self.to_world(Vector2 {
+					x: x as f32 + 0.5,
+					y: y as f32 + 0.5,
+				});
+				if let Some(color) = shade(center) {
+					let blended = blend(color, self.get(x, y, height));
+					self.put(x, y, height, blended);
+				}
+			}
+		}
+	}
+
+	fn to_screen(&self, point: Vector2) -> Vector2 {
+		match &self.camera {
+			Some(camera) => camera::world_to_screen(point, camera),
+			None => point,
+		}
+	}
+
+	fn to_world(&self, point: Vector2) -> Vector2 {
+		match &self.camera {
+			Some(camera) => camera::screen_to_world(point, camera),
+			None => point,
+		}
+	}
+
+	// like DrawRectangleLinesEx, four rectangles inside `rec`
+	fn rectangle_lines(&mut self, rec: Rectangle, thickness: f32, color: Color) {
+		let thickness = if thickness > rec.width || thickness > rec.height {
+			rec.width.min(rec.height) / 2.0
+		} else {
+			thickness
+		};
+		let sides = [
+			Rectangle { height: thickness, ..rec },
+			Rectangle {
+				y: rec.y + rec.height - thickness,
+				height: thickness,
+				..rec
+			},
+			Rectangle {
+				y: rec.y + thickness,
+				width: thickness,
+				height: rec.height - thickness * 2.0,
+				..rec
+			},
+			Rectangle {
+				x: rec.x + rec.width - thickness,
+				y: rec.y + thickness,
+				width: thickness,
+				height: rec.height - thickness * 2.0,
+			},
+		];
+		self.fill(rec, |point| sides.iter().any(|side| contains(side, point)).then_some(color));
+	}
+
+	// like DrawLineEx, a quad without caps
+	fn line(&mut self, start: Vector2, end: Vector2, thickness: f32, color: Color) {
+		let (dx, dy) = (end.x - start.x, end.y - start.y);
+		let length = (dx * dx + dy * dy).sqrt();
+		if length == 0.0 {
+			return;
+		}
+		let (dx, dy) = (dx / length, dy / length);
+		let half = thickness / 2.0;
+		let bounds = Rectangle {
+			x: start.x.min(end.x) - half,
+			y: start.y.min(end.y) - half,
+			width: (end.x - start.x).abs() + thickness,
+			height: (end.y - start.y).abs() + thickness,
+		};
+		self.fill(bounds, |point| {
+			let (px, py) = (point.x - start.x, point.y - start.y);
+			let along = px * dx + py * dy;
+			let across = px * -dy + py * dx;
+			((0.0..length).contains(&along) && across.abs() <= half).then_some(color)
+		});
+	}
+
+	// like DrawTexturePro: `dest` is rotated around its position, `origin` is relative to `dest`,
+	// a negative source width or height flips the texture
+	fn texture(&mut self, texture: &TextureRef, source: Rectangle, dest: Rectangle, origin: Vector2, rotation: f32, tint: Color) {
+		if dest.width <= 0.0 || dest.height <= 0.0 {
+			return;
+		}
+		let (bounds, to_dest) = rotated(dest, origin, rotation);
+
+		// taken out while drawing, sampling the render texture being drawn into reads a copy
+		let pixels = if self.target == Some(texture.id) {
+			self.textures.get(&texture.id).cloned()
+		} else {
+			self.textures.remove(&texture.id)
+		};
+		self.fill(bounds, |point| {
+			let local = to_dest(point);
+			if !(0.0..dest.width).contains(&local.x) || !(0.0..dest.height).contains(&local.y) {
+				return None;
+			}
+			let Some(pixels) = &pixels else {
+				return Some(modulate(MISSING_TEXTURE, tint));
+			};
+			let mut u = local.x / dest.width;
+			let mut v = local.y / dest.height;
+			if source.width < 0.0 {
+				u = 1.0 - u;
+			}
+			if source.height < 0.0 {
+				v = 1.0 - v;
+			}
+			// point filtering, repeat wrapping
+			let x = ((source.x + u * source.width.abs()).floor() as i32).rem_euclid(pixels.width.max(1));
+			let y = ((source.y + v * source.height.abs()).floor() as i32).rem_euclid(pixels.height.max(1));
+			let texel = pixels.pixels.get((y * pixels.width + x) as usize).copied()?;
+			Some(modulate(texel, tint))
+		});
+		if let Some(pixels) = pixels.filter(|_| self.target != Some(texture.id)) {
+			self.textures.insert(texture.id, pixels);
+		}
+	}
+
+	// like DrawText with the default font
+	fn text(&mut self, text: &str, x: i32, y: i32, font_size: i32, color: Color) {
+		let font_size = font_size.max(FONT_SIZE);
+		let position = Vector2 { x: x as f32, y: y as f32 };
+		self.text_pro(text, position, 0.0, font_size as f32, (font_size / FONT_SIZE) as f32, color);
+	}
+
+	// like DrawTextPro with the default font and no origin: every glyph is rotated around `position`
+	fn text_pro(&mut self, text: &str, position: Vector2, rotation: f32, font_size: f32, spacing: f32, color: Color) {
+		let scale = font_size / FONT_SIZE as f32;
+		let mut offset = Vector2::default();
+		for c in text.chars() {
+			if c == '\n' {
+				offset.x = 0.0;
+				offset.y += font_size + LINE_SPACING as f32;
+				continue;
+			}
+			let (rows, left, width, top) = glyph(c);
+			if width > 0 && rows.iter().any(|row| *row != 0) {
+				let dest = Rectangle {
+					x: position.x,
+					y: position.y,
+					width: width as f32 * scale,
+					height: rows.len() as f32 * scale,
+				};
+				let origin = Vector2 {
+					x: -offset.x,
+					y: -(offset.y + top as f32 * scale),
+				};
+				let (bounds, to_glyph) = rotated(dest, origin, rotation);
+				self.fill(bounds, |point| {
+					let local = to_glyph(point);
+					if local.x < 0.0 || local.y < 0.0 {
+						return None;
+					}
+					let column = (local.x / scale).floor() as i32;
+					let row = (local.y / scale).floor() as usize;
+					let bits = *rows.get(row)?;
+					((0..width).contains(&column) && bits & (0x10 >> (column + left)) != 0).then_some(color)
+				});
+			}
+			offset.x += width as f32 * scale + spacing;
+		}
+	}
+
+	// like DrawPoly: vertices at `rotation` degrees plus multiples of 360 / `sides`, at least three
+	fn poly(&mut self, center: Vector2, sides: i32, radius: f32, rotation: f32, color: Color) {
+		let sides = sides.max(3);
+		let vertices: Vec<Vector2> = (0..sides)
+			.map(|i| {
+				let (sin, cos) = (rotation + i as f32 * 360.0 / sides as f32).to_radians().sin_cos();
+				Vector2 {
+					x: center.x + cos * radius,
+					y: center.y + sin * radius,
+				}
+			})
+			.collect();
+		let bounds = Rectangle {
+			x: center.x - radius,
+			y: center.y - radius,
+			width: radius * 2.0,
+			height: radius * 2.0,
+		};
+		// convex, so inside means on the same side of every edge
+		self.fill(bounds, |point| {
+			let side = |(a, b): (&Vector2, &Vector2)| (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x);
+			let edges = vertices.iter().zip(vertices.iter().cycle().skip(1));
+			edges.map(side).all(|cross| cross >= 0.0).then_some(color)
+		});
+	}
+}
+
+// `dest` rotated by `rotation` degrees around its position like in DrawTexturePro, `origin` is
+// relative to `dest`: the bounds of the rotated rectangle and a map from a point to `dest`'s
+// own coordinates
+fn rotated(dest: Rectangle, origin: Vector2, rotation: f32) -> (Rectangle, impl Fn(Vector2) -> Vector2) {
+	let (sin, cos) = rotation.to_radians().sin_cos();
+	let to_dest = move |point: Vector2| {
+		let (x, y) = (point.x - dest.x, point.y - dest.y);
+		Vector2 {
+			x: x * cos + y * sin + origin.x,
+			y: -x * sin + y * cos + origin.y,
+		}
+	};
+	let from_dest = |point: Vector2| {
+		let (x, y) = (point.x - origin.x, point.y - origin.y);
+		Vector2 {
+			x: x * cos - y * sin + dest.x,
+			y: x * sin + y * cos + dest.y,
+		}
+	};
+	let corners = [(0.0, 0.0), (dest.width, 0.0), (0.0, dest.height), (dest.width, dest.height)].map(|(x, y)| from_dest(Vector2 { x, y }));
+	let min_x = corners.iter().map(|corner| corner.x).fold(f32::INFINITY, f32::min);
+	let min_y = corners.iter().map(|corner| corner.y).fold(f32::INFINITY, f32::min);
+	let bounds = Rectangle {
+		x: min_x,
+		y: min_y,
+		width: corners.iter().map(|corner| corner.x).fold(f32::NEG_INFINITY, f32::max) - min_x,
+		height: corners.iter().map(|corner| corner.y).fold(f32::NEG_INFINITY, f32::max) - min_y,
+	};
+	(bounds, to_dest)
+}
+
+// Like MeasureText, for the glyphs the canvas draws: width of the longest line in pixels.
+pub fn measure_text(text: &str, font_size: i32) -> i32 {
+	let font_size = font_size.max(FONT_SIZE);
+	let scale = font_size as f32 / FONT_SIZE as f32;
+	let spacing = (font_size / FONT_SIZE) as f32;
+	let width = |line: &str| {
+		let glyphs: f32 = line.chars().map(|c| glyph(c).2 as f32 * scale).sum();
+		glyphs + spacing * line.chars().count().saturating_sub(1) as f32
+	};
+	text.split('\n').map(width).fold(0.0, f32::max) as i32
+}
+
+// rows, first used column, width and top row; characters outside ASCII draw as '?' like raylib does
+fn glyph(c: char) -> ([u8; 7], i32, i32, i32) {
+	let index = match c {
+		' '..='~' => c as usize - ' ' as usize,
+		_ => '?' as usize - ' ' as usize,
+	};
+	let rows = GLYPHS[index];
+	let used = rows.iter().fold(0, |used, row| used | row);
+	if used == 0 {
+		return (rows, 0, SPACE_WIDTH, GLYPH_TOP);
+	}
+	let left = used.leading_zeros() as i32 - 3;
+	let right = 4 - used.trailing_zeros() as i32;
+	let top = if matches!(c, 'g' | 'p' | 'q' | 'y') {
+		GLYPH_TOP + DESCENDER
+	} else {
+		GLYPH_TOP
+	};
+	(rows, left, right - left + 1, top)
+}
+
+fn contains(rec: &Rectangle, point: Vector2) -> bool {
+	(rec.x..rec.x + rec.width).contains(&point.x) && (rec.y..rec.y + rec.height).contains(&point.y)
+}
+
+fn modulate(color: Color, tint: Color) -> Color {
+	let channel = |a: u8, b: u8| ((a as u32 * b as u32 + 127) / 255) as u8;
+	Color {
+		r: channel(color.r, tint.r),
+		g: channel(color.g, tint.g),
+		b: channel(color.b, tint.b),
+		a: channel(color.a, tint.a),
+	}
+}
+
+// BLEND_ALPHA: every channel, alpha included, is src*src_alpha + dst*(1 - src_alpha)
+fn blend(src: Color, dst: Color) -> Color {
+	let alpha = src.a as f32 / 255.0;
+	let channel = |s: u8, d: u8| (s as f32 * alpha + d as f32 * (1.0 - alpha)).round() as u8;
+	Color {
+		r: channel(src.r, dst.r),
+		g: channel(src.g, dst.g),
+		b: channel(src.b, dst.b),
+		a: channel(src.a, dst.a),
+	}
+}
+
+fn write_chunk(writer: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+	writer.write_all(&(data.len() as u32).to_be_bytes())?;
+	writer.write_all(kind)?;
+	writer.write_all(data)?;
+	let crc = crc32(kind.iter().chain(data));
+	writer.write_all(&crc.to_be_bytes())
+}
+
+// zlib stream made of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+	let mut out = vec![0x78, 0x01];
+	let mut blocks = data.chunks(u16::MAX as usize).peekable();
+	if blocks.peek().is_none() {
+		out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+	}
+	while let Some(block) = blocks.next() {
+		let last = blocks.peek().is_none();
+		out.push(last as u8);
+		out.extend_from_slice(&(block.len() as u16).to_le_bytes());
+		out.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+		out.extend_from_slice(block);
+	}
+
+	let (mut a, mut b) = (1u32, 0u32);
+	for byte in data {
+		a = (a + *byte as u32) % 65521;
+		b = (b + a) % 65521;
+	}
+	out.extend_from_slice(&((b << 16) | a).to_be_bytes());
+	out
+}
+
+fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+	let mut crc = !0u32;
+	for byte in bytes {
+		crc ^= *byte as u32;
+		for _ in 0..8 {
+			crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+		}
+	}
+	!crc
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn rec(x: f32, y: f32, width: f32, height: f32) -> Rectangle {
+		Rectangle { x, y, width, height }
+	}
+
+	// positions of the pixels that are not `background`
+	fn painted(canvas: &Canvas, background: Color) -> Vec<(i32, i32)> {
+		let mut painted = Vec::new();
+		for y in 0..canvas.height() {
+			for x in 0..canvas.width() {
+				if canvas.pixel(x, y) != background {
+					painted.push((x, y));
+				}
+			}
+		}
+		painted
+	}
+
+	// Reads back what `write_png` writes: checks the chunk CRCs, the stored deflate blocks and the
+	// Adler-32, then undoes the row filter bytes.
+	fn decode_png(png: &[u8]) -> (u32, u32, Vec<Color>) {
+		assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+		let (mut header, mut idat) = (Vec::new(), Vec::new());
+		let mut rest = &png[8..];
+		while !rest.is_empty() {
+			let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+			let (kind, data) = (&rest[4..8], &rest[8..8 + length]);
+			let crc = u32::from_be_bytes(rest[8 + length..12 + length].try_into().unwrap());
+			assert_eq!(crc, crc32(kind.iter().chain(data)));
+			match kind {
+				b"IHDR" => header = data.to_vec(),
+				b"IDAT" => idat.extend_from_slice(data),
+				b"IEND" => assert!(data.is_empty()),
+				_ => panic!("unexpected chunk"),
+			}
+			rest = &rest[12 + length..];
+		}
+		let width = u32::from_be_bytes(header[..4].try_into().unwrap());
+		let height = u32::from_be_bytes(header[4..8].try_into().unwrap());
+		assert_eq!(&header[8..], &[8, 6, 0, 0, 0]);
+
+		let raw = inflate_stored(&idat);
+		let pixels = raw
+			.chunks(width as usize * 4 + 1)
+			.flat_map(|row| {
+				assert_eq!(row[0], 0);
+				row[1..].chunks(4).map(|rgba| Color {
+					r: rgba[0],
+					g: rgba[1],
+					b: rgba[2],
+					a: rgba[3],
+				})
+			})
+			.collect();
+		(width, height, pixels)
+	}
+
+	fn inflate_stored(zlib: &[u8]) -> Vec<u8> {
+		assert_eq!(u16::from_be_bytes([zlib[0], zlib[1]]) % 31, 0);
+		let mut data = Vec::new();
+		let mut at = 2;
+		loop {
+			let last = zlib[at] & 1 != 0;
+			assert_eq!(zlib[at] >> 1, 0, "only stored blocks");
+			let length = u16::from_le_bytes([zlib[at + 1], zlib[at + 2]]);
+			assert_eq!(!length, u16::from_le_bytes([zlib[at + 3], zlib[at + 4]]));
+			data.extend_from_slice(&zlib[at + 5..at + 5 + length as usize]);
+			at += 5 + length as usize;
+			if last {
+				break;
+			}
+		}
+		let (mut a, mut b) = (1u32, 0u32);
+		for byte in &data {
+			a = (a + *byte as u32) % 65521;
+			b = (b + a) % 65521;
+		}
+		assert_eq!(&zlib[at..], &((b << 16) | a).to_be_bytes());
+		data
+	}
+
+	#[test]
+	fn rectangle_covers_pixel_centers() {
+		let mut canvas = Canvas::new(4, 4);
+		canvas.execute(&DrawCommand::Rectangle {
+			rec: rec(1.0, 1.0, 2.0, 2.0),
+			color: RED,
+		});
+		assert_eq!(painted(&canvas, BLANK), [(1, 1), (2, 1), (1, 2), (2, 2)]);
+		assert_eq!(canvas.pixel(1, 1), RED);
+
+		// smaller than a pixel, only the pixel whose center it covers
+		let mut canvas = Canvas::new(4, 4);
+		canvas.run(&[
+			DrawCommand::Rectangle {
+				rec: rec(1.2, 1.2, 0.5, 0.5),
+				color: RED,
+			},
+			DrawCommand::Rectangle {
+				rec: rec(2.6, 2.6, 0.8, 0.8),
+				color: RED,
+			},
+		]);
+		assert_eq!(painted(&canvas, BLANK), [(1, 1)]);
+	}
+
+	#[test]
+	fn alpha_blends_over_the_background() {
+		let mut canvas = Canvas::new(1, 1);
+		canvas.run(&[
+			DrawCommand::ClearBackground(WHITE),
+			DrawCommand::Rectangle {
+				rec: rec(0.0, 0.0, 1.0, 1.0),
+				color: fade(BLACK, 0.5),
+			},
+		]);
+		let pixel = canvas.pixel(0, 0);
+		assert_eq!((pixel.r, pixel.g, pixel.b), (128, 128, 128));
+	}
+
+	#[test]
+	fn scissor_clips_drawing() {
+		let mut canvas = Canvas::new(4, 4);
+		canvas.run(&[
+			DrawCommand::BeginScissorMode {
+				x: 2,
+				y: 0,
+				width: 2,
+				height: 1,
+			},
+			DrawCommand::ClearBackground(RED),
+			DrawCommand::EndScissorMode,
+		]);
+		assert_eq!(painted(&canvas, BLANK), [(2, 0), (3, 0)]);
+	}
+
+	#[test]
+	fn camera_zoom_scales_shapes() {
+		let mut canvas = Canvas::new(4, 4);
+		canvas.run(&[
+			DrawCommand::BeginMode2D {
+				offset: Vector2::default(),
+				target: Vector2::default(),
+				rotation: 0.0,
+				zoom: 2.0,
+			},
+			DrawCommand::Rectangle {
+				rec: rec(1.0, 0.0, 1.0, 1.0),
+				color: RED,
+			},
+			DrawCommand::EndMode2D,
+		]);
+		assert_eq!(painted(&canvas, BLANK), [(2, 0), (3, 0), (2, 1), (3, 1)]);
+	}
+
+	#[test]
+	fn circle_is_round() {
+		let mut canvas = Canvas::new(9, 9);
+		canvas.execute(&DrawCommand::Circle {
+			center: Vector2 { x: 4.5, y: 4.5 },
+			radius: 3.0,
+			color: RED,
+		});
+		assert_eq!(canvas.pixel(4, 4), RED);
+		assert_eq!(canvas.pixel(4, 2), RED);
+		assert_eq!(canvas.pixel(1, 1), BLANK);
+		assert_eq!(canvas.pixel(4, 0), BLANK);
+		// symmetric in both axes
+		let painted = painted(&canvas, BLANK);
+		assert!(painted
+			.iter()
+			.all(|&(x, y)| painted.contains(&(8 - x, y)) && painted.contains(&(x, 8 - y))));
+	}
+
+	#[test]
+	fn rotated_rectangle_turns_around_its_origin() {
+		let mut canvas = Canvas::new(8, 8);
+		canvas.execute(&DrawCommand::RectanglePro {
+			rec: rec(4.0, 4.0, 4.0, 2.0),
+			origin: Vector2::default(),
+			rotation: 90.0,
+			color: RED,
+		});
+		let painted = painted(&canvas, BLANK);
+		assert_eq!(painted.len(), 8);
+		assert!(painted.iter().all(|&(x, y)| (2..4).contains(&x) && (4..8).contains(&y)));
+	}
+
+	#[test]
+	fn circle_lines_and_polygons() {
+		let center = Vector2 { x: 4.5, y: 4.5 };
+		let mut canvas = Canvas::new(9, 9);
+		canvas.execute(&DrawCommand::CircleLines {
+			center,
+			radius: 3.0,
+			color: RED,
+		});
+		assert_eq!(canvas.pixel(4, 1), RED);
+		assert_eq!(canvas.pixel(7, 4), RED);
+		assert_eq!(canvas.pixel(4, 4), BLANK);
+
+		// a diamond
+		let mut canvas = Canvas::new(9, 9);
+		canvas.execute(&DrawCommand::Poly {
+			center,
+			sides: 4,
+			radius: 3.0,
+			rotation: 0.0,
+			color: RED,
+		});
+		assert_eq!(canvas.pixel(4, 4), RED);
+		assert_eq!(canvas.pixel(6, 4), RED);
+		assert_eq!(canvas.pixel(4, 2), RED);
+		assert_eq!(canvas.pixel(2, 2), BLANK);
+		assert_eq!(canvas.pixel(6, 6), BLANK);
+	}
+
+	#[test]
+	fn render_textures_are_stored_upside_down() {
+		let target = TextureRef { id: 1, width: 2, height: 2 };
+		let mut canvas = Canvas::new(2, 2);
+		canvas.run(&[
+			DrawCommand::BeginTextureMode(target),
+			DrawCommand::Rectangle {
+				rec: rec(0.0, 0.0, 2.0, 1.0),
+				color: RED,
+			},
+			DrawCommand::EndTextureMode,
+		]);
+		assert_eq!(painted(&canvas, BLANK), []);
+
+		// drawn with a negative source height like the screen stack does, the top row stays on top
+		canvas.execute(&DrawCommand::TextureRec {
+			texture: target,
+			source: rec(0.0, 0.0, 2.0, -2.0),
+			position: Vector2::default(),
+			tint: WHITE,
+		});
+		assert_eq!(painted(&canvas, BLANK), [(0, 0), (1, 0)]);
+	}
+
+	#[test]
+	fn textures_sample_their_pixels() {
+		let texture = TextureRef { id: 3, width: 2, height: 1 };
+		let mut canvas = Canvas::new(4, 2);
+		canvas.set_texture(texture, vec![RED, BLUE]);
+		canvas.execute(&DrawCommand::TexturePro {
+			texture,
+			source: rec(0.0, 0.0, 2.0, 1.0),
+			dest: rec(0.0, 0.0, 4.0, 2.0),
+			origin: Vector2::default(),
+			rotation: 0.0,
+			tint: WHITE,
+		});
+		assert_eq!(canvas.pixel(1, 1), RED);
+		assert_eq!(canvas.pixel(2, 0), BLUE);
+
+		let missing = TextureRef { id: 4, width: 1, height: 1 };
+		canvas.execute(&DrawCommand::TextureRec {
+			texture: missing,
+			source: rec(0.0, 0.0, 1.0, 1.0),
+			position: Vector2::default(),
+			tint: WHITE,
+		});
+		assert_eq!(canvas.pixel(0, 0), MISSING_TEXTURE);
+	}
+
+	#[test]
+	fn text_stays_inside_its_measured_width() {
+		let mut canvas = Canvas::new(64, 32);
+		canvas.execute(&DrawCommand::Text {
+			text: "Hi!".to_string(),
+			x: 2,
+			y: 2,
+			font_size: 20,
+			color: BLACK,
+		});
+		let painted = painted(&canvas, BLANK);
+		assert!(!painted.is_empty());
+		let right = painted.iter().map(|&(x, _)| x).max().unwrap();
+		assert!(right < 2 + measure_text("Hi!", 20));
+		assert!(painted.iter().all(|&(x, y)| x >= 2 && (2..22).contains(&y)));
+	}
+
+	#[test]
+	fn text_pro_rotates_around_its_position() {
+		let mut text = Canvas::new(32, 32);
+		text.execute(&DrawCommand::Text {
+			text: "Hi!".to_string(),
+			x: 0,
+			y: 0,
+			font_size: 20,
+			color: BLACK,
+		});
+		let mut unrotated = Canvas::new(32, 32);
+		unrotated.execute(&DrawCommand::TextPro {
+			text: "Hi!".to_string(),
+			position: Vector2::default(),
+			rotation: 0.0,
+			font_size: 20.0,
+			spacing: 2.0,
+			color: BLACK,
+		});
+		assert_eq!(text.count_differences(&unrotated, 0), 0);
+
+		// a quarter turn clockwise around (24, 2)
+		let mut rotated = Canvas::new(32, 32);
+		rotated.execute(&DrawCommand::TextPro {
+			text: "Hi!".to_string(),
+			position: Vector2 { x: 24.0, y: 2.0 },
+			rotation: 90.0,
+			font_size: 20.0,
+			spacing: 2.0,
+			color: BLACK,
+		});
+		let mut expected: Vec<(i32, i32)> = painted(&text, BLANK)
+			.iter()
+			.map(|&(x, y)| (23 - y, 2 + x))
+			.filter(|&(_, y)| y < 32)
+			.collect();
+		expected.sort();
+		let mut painted = painted(&rotated, BLANK);
+		painted.sort();
+		assert!(!expected.is_empty());
+		assert_eq!(painted, expected);
+	}
+
+	#[test]
+	fn checksums_match_known_values() {
+		assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+		assert_eq!(&zlib_stored(b"Wikipedia")[16..], &0x11e6_0398u32.to_be_bytes());
+		assert_eq!(zlib_stored(&[]), [0x78, 0x01, 1, 0, 0, 0xff, 0xff, 0, 0, 0, 1]);
+	}
+
+	#[test]
+	fn zlib_round_trips_across_blocks() {
+		for length in [0, 1, 65_535, 65_536, 200_000] {
+			let data: Vec<u8> = (0..length).map(|i| (i * 7 % 251) as u8).collect();
+			assert_eq!(inflate_stored(&zlib_stored(&data)), data);
+		}
+	}
+
+	#[test]
+	fn png_round_trips() {
+		// large enough for several deflate blocks
+		let mut canvas = Canvas::new(160, 120);
+		canvas.run(&[
+			DrawCommand::ClearBackground(SKYBLUE),
+			DrawCommand::Circle {
+				center: Vector2 { x: 80.0, y: 60.0 },
+				radius: 40.0,
+				color: fade(RED, 0.5),
+			},
+		]);
+		let mut png = Vec::new();
+		canvas.write_png(&mut png).unwrap();
+		let (width, height, pixels) = decode_png(&png);
+		assert_eq!((width, height), (160, 120));
+		assert_eq!(pixels, canvas.pixels());
+
+		let mut png = Vec::new();
+		Canvas::new(0, 0).write_png(&mut png).unwrap();
+		assert_eq!(decode_png(&png), (0, 0, Vec::new()));
+	}
+}
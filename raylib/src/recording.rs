@@ -0,0 +1,979 @@
+use std::{
+	env, fmt, fs,
+	io::{self, BufRead, BufReader, BufWriter, Write},
+	path::Path,
+};
+
+use crate::{
+	backend::{Backend, HeadlessBackend},
+	gui::GuiInput,
+	input_map::InputSource,
+	*,
+};
+
+// A texture as it was drawn. Ids depend on load order, compare sizes when that matters.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TextureRef {
+	pub id: u32,
+	pub width: i32,
+	pub height: i32,
+}
+
+impl From<Texture2D> for TextureRef {
+	fn from(texture: Texture2D) -> Self {
+		Self {
+			id: texture.id,
+			width: texture.width,
+			height: texture.height,
+		}
+	}
+}
+
+// One `Backend` call that affects what ends up on screen.
+#[derive(Clone, PartialEq, Debug)]
+pub enum DrawCommand {
+	BeginDrawing,
+	EndDrawing,
+	ClearBackground(Color),
+	BeginMode2D {
+		offset: Vector2,
+		target: Vector2,
+		rotation: f32,
+		zoom: f32,
+	},
+	EndMode2D,
+	BeginScissorMode {
+		x: i32,
+		y: i32,
+		width: i32,
+		height: i32,
+	},
+	EndScissorMode,
+	BeginTextureMode(TextureRef),
+	EndTextureMode,
+	// draw_rectangle and draw_rectangle_rec
+	Rectangle {
+		rec: Rectangle,
+		color: Color,
+	},
+	RectangleLines {
+		rec: Rectangle,
+		thickness: f32,
+		color: Color,
+	},
+	RectanglePro {
+		rec: Rectangle,
+		origin: Vector2,
+		rotation: f32,
+		color: Color,
+	},
+	Circle {
+		center: Vector2,
+		radius: f32,
+		color: Color,
+	},
+	CircleLines {
+		center: Vector2,
+		radius: f32,
+		color: Color,
+	},
+	Poly {
+		center: Vector2,
+		sides: i32,
+		radius: f32,
+		rotation: f32,
+		color: Color,
+	},
+	Line {
+		start: Vector2,
+		end: Vector2,
+		thickness: f32,
+		color: Color,
+	},
+	Text {
+		text: String,
+		x: i32,
+		y: i32,
+		font_size: i32,
+		color: Color,
+	},
+	TextPro {
+		text: String,
+		position: Vector2,
+		rotation: f32,
+		font_size: f32,
+		spacing: f32,
+		color: Color,
+	},
+	TextureRec {
+		texture: TextureRef,
+		source: Rectangle,
+		position: Vector2,
+		tint: Color,
+	},
+	TexturePro {
+		texture: TextureRef,
+		source: Rectangle,
+		dest: Rectangle,
+		origin: Vector2,
+		rotation: f32,
+		tint: Color,
+	},
+	Fps {
+		x: i32,
+		y: i32,
+	},
+}
+
+struct Hex(Color);
+
+impl fmt::Display for Hex {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let Color { r, g, b, a } = self.0;
+		write!(f, "#{r:02x}{g:02x}{b:02x}{a:02x}")
+	}
+}
+
+struct Rect(Rectangle);
+
+impl fmt::Display for Rect {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let Rectangle { x, y, width, height } = self.0;
+		write!(f, "{x} {y} {width} {height}")
+	}
+}
+
+struct Texture(TextureRef);
+
+impl fmt::Display for Texture {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let TextureRef { id, width, height } = self.0;
+		write!(f, "{id} {width} {height}")
+	}
+}
+
+// One line per command, e.g. `rectangle 0 0 800 450 #00e430ff`. Text comes last on its line,
+// with backslashes, newlines and carriage returns escaped.
+impl fmt::Display for DrawCommand {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			DrawCommand::BeginDrawing => write!(f, "begin_drawing"),
+			DrawCommand::EndDrawing => write!(f, "end_drawing"),
+			DrawCommand::ClearBackground(color) => write!(f, "clear_background {}", Hex(*color)),
+			DrawCommand::BeginMode2D {
+				offset,
+				target,
+				rotation,
+				zoom,
+			} => {
+				write!(f, "begin_mode_2d {} {} {} {} {rotation} {zoom}", offset.x, offset.y, target.x, target.y)
+			}
+			DrawCommand::EndMode2D => write!(f, "end_mode_2d"),
+			DrawCommand::BeginScissorMode { x, y, width, height } => write!(f, "begin_scissor_mode {x} {y} {width} {height}"),
+			DrawCommand::EndScissorMode => write!(f, "end_scissor_mode"),
+			DrawCommand::BeginTextureMode(texture) => write!(f, "begin_texture_mode {}", Texture(*texture)),
+			DrawCommand::EndTextureMode => write!(f, "end_texture_mode"),
+			DrawCommand::Rectangle { rec, color } => write!(f, "rectangle {} {}", Rect(*rec), Hex(*color)),
+			DrawCommand::RectangleLines { rec, thickness, color } => {
+				write!(f, "rectangle_lines {} {thickness} {}", Rect(*rec), Hex(*color))
+			}
+			DrawCommand::RectanglePro {
+				rec,
+				origin,
+				rotation,
+				color,
+			} => write!(f, "rectangle_pro {} {} {} {rotation} {}", Rect(*rec), origin.x, origin.y, Hex(*color)),
+			DrawCommand::Circle { center, radius, color } => write!(f, "circle {} {} {radius} {}", center.x, center.y, Hex(*color)),
+			DrawCommand::CircleLines { center, radius, color } => write!(f, "circle_lines {} {} {radius} {}", center.x, center.y, Hex(*color)),
+			DrawCommand::Poly {
+				center,
+				sides,
+				radius,
+				rotation,
+				color,
+			} => write!(f, "poly {} {} {sides} {radius} {rotation} {}", center.x, center.y, Hex(*color)),
+			DrawCommand::Line {
+				start,
+				end,
+				thickness,
+				color,
+			} => {
+				write!(f, "line {} {} {} {} {thickness} {}", start.x, start.y, end.x, end.y, Hex(*color))
+			}
+			DrawCommand::Text {
+				text,
+				x,
+				y,
+				font_size,
+				color,
+			} => {
+				write!(f, "text {x} {y} {font_size} {} {}", Hex(*color), escape(text))
+			}
+			DrawCommand::TextPro {
+				text,
+				position,
+				rotation,
+				font_size,
+				spacing,
+				color,
+			} => write!(
+				f,
+				"text_pro {} {} {rotation} {font_size} {spacing} {} {}",
+				position.x,
+				position.y,
+				Hex(*color),
+				escape(text)
+			),
+			DrawCommand::TextureRec {
+				texture,
+				source,
+				position,
+				tint,
+			} => {
+				write!(
+					f,
+					"texture_rec {} {} {} {} {}",
+					Texture(*texture),
+					Rect(*source),
+					position.x,
+					position.y,
+					Hex(*tint)
+				)
+			}
+			DrawCommand::TexturePro {
+				texture,
+				source,
+				dest,
+				origin,
+				rotation,
+				tint,
+			} => write!(
+				f,
+				"texture_pro {} {} {} {} {} {rotation} {}",
+				Texture(*texture),
+				Rect(*source),
+				Rect(*dest),
+				origin.x,
+				origin.y,
+				Hex(*tint)
+			),
+			DrawCommand::Fps { x, y } => write!(f, "fps {x} {y}"),
+		}
+	}
+}
+
+pub fn save_commands(commands: &[DrawCommand], writer: &mut impl Write) -> io::Result<()> {
+	for command in commands {
+		writeln!(writer, "{command}")?;
+	}
+	Ok(())
+}
+
+// Reads what `save_commands` wrote, empty lines are skipped.
+pub fn load_commands(reader: impl BufRead) -> io::Result<Vec<DrawCommand>> {
+	let mut commands = Vec::new();
+	for (number, line) in reader.lines().enumerate() {
+		let line = line?;
+		if line.trim().is_empty() {
+			continue;
+		}
+		let command = parse_command(&line).ok_or_else(|| invalid_data(format!("line {}: bad command: {line}", number + 1)))?;
+		commands.push(command);
+	}
+	Ok(commands)
+}
+
+// Describes the first difference between two command lists, None when they are the same.
+pub fn diff_commands(expected: &[DrawCommand], actual: &[DrawCommand]) -> Option<String> {
+	let index = expected.iter().zip(actual).position(|(expected, actual)| expected != actual);
+	match index {
+		Some(index) => Some(format!(
+			"command {index}:\n  expected: {}\n  actual:   {}",
+			expected[index], actual[index]
+		)),
+		None if expected.len() < actual.len() => Some(format!(
+			"{} extra commands, the first is: {}",
+			actual.len() - expected.len(),
+			actual[expected.len()]
+		)),
+		None if expected.len() > actual.len() => Some(format!(
+			"{} missing commands, the first is: {}",
+			expected.len() - actual.len(),
+			expected[actual.len()]
+		)),
+		None => None,
+	}
+}
+
+// Compares `actual` with the golden file at `path`, a list written by `save_commands` and committed
+// next to the tests. With UPDATE_GOLDEN set in the environment the file is rewritten instead. The
+// error describes the first difference or why the file could not be read.
+pub fn check_golden(path: impl AsRef<Path>, actual: &[DrawCommand]) -> Result<(), String> {
+	let path = path.as_ref();
+	if env::var_os("UPDATE_GOLDEN").is_some_and(|value| !value.is_empty()) {
+		let saved = fs::File::create(path).and_then(|file| {
+			let mut writer = BufWriter::new(file);
+			save_commands(actual, &mut writer)?;
+			writer.flush()
+		});
+		return saved.map_err(|err| format!("could not write {}: {err}", path.display()));
+	}
+	let expected = fs::File::open(path)
+		.and_then(|file| load_commands(BufReader::new(file)))
+		.map_err(|err| format!("{}: {err}, run with UPDATE_GOLDEN=1 to write it", path.display()))?;
+	match diff_commands(&expected, actual) {
+		Some(diff) => Err(format!("{}: {diff}", path.display())),
+		None => Ok(()),
+	}
+}
+
+fn parse_command(line: &str) -> Option<DrawCommand> {
+	let (name, rest) = line.split_once(' ').unwrap_or((line, ""));
+	if name == "text" {
+		let mut parts = rest.splitn(5, ' ');
+		let x = parts.next()?.parse().ok()?;
+		let y = parts.next()?.parse().ok()?;
+		let font_size = parts.next()?.parse().ok()?;
+		let color = parse_color(parts.next()?)?;
+		let text = unescape(parts.next().unwrap_or_default())?;
+		return Some(DrawCommand::Text {
+			text,
+			x,
+			y,
+			font_size,
+			color,
+		});
+	}
+
+	if name == "text_pro" {
+		let mut parts = rest.splitn(7, ' ');
+		let mut float = || parts.next()?.parse::<f32>().ok();
+		let position = Vector2 { x: float()?, y: float()? };
+		let rotation = float()?;
+		let font_size = float()?;
+		let spacing = float()?;
+		let color = parse_color(parts.next()?)?;
+		let text = unescape(parts.next().unwrap_or_default())?;
+		return Some(DrawCommand::TextPro {
+			text,
+			position,
+			rotation,
+			font_size,
+			spacing,
+			color,
+		});
+	}
+
+	let mut words = rest.split_whitespace();
+	let mut float = || words.next()?.parse::<f32>().ok();
+	let command = match name {
+		"begin_drawing" => DrawCommand::BeginDrawing,
+		"end_drawing" => DrawCommand::EndDrawing,
+		"end_mode_2d" => DrawCommand::EndMode2D,
+		"end_scissor_mode" => DrawCommand::EndScissorMode,
+		"end_texture_mode" => DrawCommand::EndTextureMode,
+		"begin_mode_2d" => DrawCommand::BeginMode2D {
+			offset: Vector2 { x: float()?, y: float()? },
+			target: Vector2 { x: float()?, y: float()? },
+			rotation: float()?,
+			zoom: float()?,
+		},
+		_ => return parse_with_colors(name, rest),
+	};
+	Some(command)
+}
+
+// commands whose arguments include integers, colors or textures
+fn parse_with_colors(name: &str, rest: &str) -> Option<DrawCommand> {
+	let mut words = rest.split_whitespace();
+	let mut next = || words.next();
+	fn float(word: Option<&str>) -> Option<f32> {
+		word?.parse().ok()
+	}
+	fn int(word: Option<&str>) -> Option<i32> {
+		word?.parse().ok()
+	}
+	macro_rules! rect {
+		() => {
+			Rectangle {
+				x: float(next())?,
+				y: float(next())?,
+				width: float(next())?,
+				height: float(next())?,
+			}
+		};
+	}
+	macro_rules! texture {
+		() => {
+			TextureRef {
+				id: next()?.parse().ok()?,
+				width: int(next())?,
+				height: int(next())?,
+			}
+		};
+	}
+	macro_rules! vector {
+		() => {
+			Vector2 {
+				x: float(next())?,
+				y: float(next())?,
+			}
+		};
+	}
+
+	let command = match name {
+		"clear_background" => DrawCommand::ClearBackground(parse_color(next()?)?),
+		"begin_scissor_mode" => DrawCommand::BeginScissorMode {
+			x: int(next())?,
+			y: int(next())?,
+			width: int(next())?,
+			height: int(next())?,
+		},
+		"begin_texture_mode" => DrawCommand::BeginTextureMode(texture!()),
+		"rectangle" => DrawCommand::Rectangle {
+			rec: rect!(),
+			color: parse_color(next()?)?,
+		},
+		"rectangle_lines" => DrawCommand::RectangleLines {
+			rec: rect!(),
+			thickness: float(next())?,
+			color: parse_color(next()?)?,
+		},
+		"rectangle_pro" => DrawCommand::RectanglePro {
+			rec: rect!(),
+			origin: vector!(),
+			rotation: float(next())?,
+			color: parse_color(next()?)?,
+		},
+		"circle" => DrawCommand::Circle {
+			center: vector!(),
+			radius: float(next())?,
+			color: parse_color(next()?)?,
+		},
+		"circle_lines" => DrawCommand::CircleLines {
+			center: vector!(),
+			radius: float(next())?,
+			color: parse_color(next()?)?,
+		},
+		"poly" => DrawCommand::Poly {
+			center: vector!(),
+			sides: int(next())?,
+			radius: float(next())?,
+			rotation: float(next())?,
+			color: parse_color(next()?)?,
+		},
+		"line" => DrawCommand::Line {
+			start: vector!(),
+			end: vector!(),
+			thickness: float(next())?,
+			color: parse_color(next()?)?,
+		},
+		"texture_rec" => DrawCommand::TextureRec {
+			texture: texture!(),
+			source: rect!(),
+			position: vector!(),
+			tint: parse_color(next()?)?,
+		},
+		"texture_pro" => DrawCommand::TexturePro {
+			texture: texture!(),
+			source: rect!(),
+			dest: rect!(),
+			origin: vector!(),
+			rotation: float(next())?,
+			tint: parse_color(next()?)?,
+		},
+		"fps" => DrawCommand::Fps {
+			x: int(next())?,
+			y: int(next())?,
+		},
+		_ => return None,
+	};
+	next().is_none().then_some(command)
+}
+
+fn parse_color(word: &str) -> Option<Color> {
+	let hex = word.strip_prefix('#').filter(|hex| hex.len() == 8)?;
+	let value = u32::from_str_radix(hex, 16).ok()?;
+	let [r, g, b, a] = value.to_be_bytes();
+	Some(Color { r, g, b, a })
+}
+
+fn escape(text: &str) -> String {
+	text.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+fn unescape(text: &str) -> Option<String> {
+	let mut result = String::with_capacity(text.len());
+	let mut chars = text.chars();
+	while let Some(c) = chars.next() {
+		if c == '\\' {
+			match chars.next()? {
+				'n' => result.push('\n'),
+				'r' => result.push('\r'),
+				'\\' => result.push('\\'),
+				_ => return None,
+			}
+		} else {
+			result.push(c);
+		}
+	}
+	Some(result)
+}
+
+fn invalid_data(message: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// Forwards everything to `inner` and keeps a list of the draw calls, for asserting on what a
+// frame draws. Input and window state come from `inner`.
+#[derive(Clone, Debug)]
+pub struct RecordingBackend<B: Backend = HeadlessBackend> {
+	pub inner: B,
+	commands: Vec<DrawCommand>,
+}
+
+impl Default for RecordingBackend {
+	fn default() -> Self {
+		Self::new(HeadlessBackend::default())
+	}
+}
+
+impl<B: Backend> RecordingBackend<B> {
+	pub fn new(inner: B) -> Self {
+		Self { inner, commands: Vec::new() }
+	}
+
+	pub fn commands(&self) -> &[DrawCommand] {
+		&self.commands
+	}
+
+	pub fn take_commands(&mut self) -> Vec<DrawCommand> {
+		std::mem::take(&mut self.commands)
+	}
+
+	pub fn clear(&mut self) {
+		self.commands.clear();
+	}
+
+	// Commands between the last complete begin_drawing/end_drawing pair.
+	pub fn last_frame(&self) -> &[DrawCommand] {
+		let Some(end) = self.commands.iter().rposition(|command| *command == DrawCommand::EndDrawing) else {
+			return &[];
+		};
+		let start = self.commands[..end]
+			.iter()
+			.rposition(|command| *command == DrawCommand::BeginDrawing)
+			.map_or(0, |begin| begin + 1);
+		&self.commands[start..end]
+	}
+
+	fn record(&mut self, command: DrawCommand) {
+		self.commands.push(command);
+	}
+}
+
+impl<B: Backend> InputSource for RecordingBackend<B> {
+	fn key_down(&self, key: KeyboardKey) -> bool {
+		self.inner.key_down(key)
+	}
+
+	fn mouse_button_down(&self, button: MouseButton) -> bool {
+		self.inner.mouse_button_down(button)
+	}
+
+	fn mouse_wheel(&self) -> f32 {
+		self.inner.mouse_wheel()
+	}
+
+	fn gamepad_button_down(&self, gamepad: i32, button: GamepadButton) -> bool {
+		self.inner.gamepad_button_down(gamepad, button)
+	}
+
+	fn gamepad_axis(&self, gamepad: i32, axis: GamepadAxis) -> f32 {
+		self.inner.gamepad_axis(gamepad, axis)
+	}
+
+	fn gesture_detected(&self, gesture: Gesture) -> bool {
+		self.inner.gesture_detected(gesture)
+	}
+}
+
+impl<B: Backend> Backend for RecordingBackend<B> {
+	fn set_config_flags(&mut self, flags: u32) {
+		self.inner.set_config_flags(flags);
+	}
+
+	fn init_window(&mut self, width: i32, height: i32, title: &str) {
+		self.inner.init_window(width, height, title);
+	}
+
+	fn close_window(&mut self) {
+		self.inner.close_window();
+	}
+
+	fn window_should_close(&self) -> bool {
+		self.inner.window_should_close()
+	}
+
+	fn set_window_title(&mut self, title: &str) {
+		self.inner.set_window_title(title);
+	}
+
+	fn toggle_fullscreen(&mut self) {
+		self.inner.toggle_fullscreen();
+	}
+
+	fn set_target_fps(&mut self, fps: i32) {
+		self.inner.set_target_fps(fps);
+	}
+
+	fn fps(&self) -> i32 {
+		self.inner.fps()
+	}
+
+	fn frame_time(&self) -> f32 {
+		self.inner.frame_time()
+	}
+
+	fn screen_width(&self) -> i32 {
+		self.inner.screen_width()
+	}
+
+	fn screen_height(&self) -> i32 {
+		self.inner.screen_height()
+	}
+
+	fn gui_input(&mut self, gamepad: i32) -> GuiInput {
+		self.inner.gui_input(gamepad)
+	}
+
+	fn begin_drawing(&mut self) {
+		self.record(DrawCommand::BeginDrawing);
+		self.inner.begin_drawing();
+	}
+
+	fn end_drawing(&mut self) {
+		self.record(DrawCommand::EndDrawing);
+		self.inner.end_drawing();
+	}
+
+	fn clear_background(&mut self, color: Color) {
+		self.record(DrawCommand::ClearBackground(color));
+		self.inner.clear_background(color);
+	}
+
+	fn begin_mode_2d(&mut self, camera: Camera2D) {
+		self.record(DrawCommand::BeginMode2D {
+			offset: camera.offset,
+			target: camera.target,
+			rotation: camera.rotation,
+			zoom: camera.zoom,
+		});
+		self.inner.begin_mode_2d(camera);
+	}
+
+	fn end_mode_2d(&mut self) {
+		self.record(DrawCommand::EndMode2D);
+		self.inner.end_mode_2d();
+	}
+
+	fn begin_scissor_mode(&mut self, x: i32, y: i32, width: i32, height: i32) {
+		self.record(DrawCommand::BeginScissorMode { x, y, width, height });
+		self.inner.begin_scissor_mode(x, y, width, height);
+	}
+
+	fn end_scissor_mode(&mut self) {
+		self.record(DrawCommand::EndScissorMode);
+		self.inner.end_scissor_mode();
+	}
+
+	fn load_render_texture(&mut self, width: i32, height: i32) -> RenderTexture2D {
+		self.inner.load_render_texture(width, height)
+	}
+
+	fn unload_render_texture(&mut self, target: RenderTexture2D) {
+		self.inner.unload_render_texture(target);
+	}
+
+	fn begin_texture_mode(&mut self, target: RenderTexture2D) {
+		self.record(DrawCommand::BeginTextureMode(target.texture.into()));
+		self.inner.begin_texture_mode(target);
+	}
+
+	fn end_texture_mode(&mut self) {
+		self.record(DrawCommand::EndTextureMode);
+		self.inner.end_texture_mode();
+	}
+
+	fn draw_rectangle(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color) {
+		let rec = Rectangle {
+			x: x as f32,
+			y: y as f32,
+			width: width as f32,
+			height: height as f32,
+		};
+		self.record(DrawCommand::Rectangle { rec, color });
+		self.inner.draw_rectangle(x, y, width, height, color);
+	}
+
+	fn draw_rectangle_rec(&mut self, rec: Rectangle, color: Color) {
+		self.record(DrawCommand::Rectangle { rec, color });
+		self.inner.draw_rectangle_rec(rec, color);
+	}
+
+	fn draw_rectangle_lines_ex(&mut self, rec: Rectangle, thickness: f32, color: Color) {
+		self.record(DrawCommand::RectangleLines { rec, thickness, color });
+		self.inner.draw_rectangle_lines_ex(rec, thickness, color);
+	}
+
+	fn draw_rectangle_pro(&mut self, rec: Rectangle, origin: Vector2, rotation: f32, color: Color) {
+		self.record(DrawCommand::RectanglePro {
+			rec,
+			origin,
+			rotation,
+			color,
+		});
+		self.inner.draw_rectangle_pro(rec, origin, rotation, color);
+	}
+
+	fn draw_circle_v(&mut self, center: Vector2, radius: f32, color: Color) {
+		self.record(DrawCommand::Circle { center, radius, color });
+		self.inner.draw_circle_v(center, radius, color);
+	}
+
+	fn draw_circle_lines_v(&mut self, center: Vector2, radius: f32, color: Color) {
+		self.record(DrawCommand::CircleLines { center, radius, color });
+		self.inner.draw_circle_lines_v(center, radius, color);
+	}
+
+	fn draw_poly(&mut self, center: Vector2, sides: i32, radius: f32, rotation: f32, color: Color) {
+		self.record(DrawCommand::Poly {
+			center,
+			sides,
+			radius,
+			rotation,
+			color,
+		});
+		self.inner.draw_poly(center, sides, radius, rotation, color);
+	}
+
+	fn draw_line_ex(&mut self, start: Vector2, end: Vector2, thickness: f32, color: Color) {
+		self.record(DrawCommand::Line {
+			start,
+			end,
+			thickness,
+			color,
+		});
+		self.inner.draw_line_ex(start, end, thickness, color);
+	}
+
+	fn draw_text(&mut self, text: &str, x: i32, y: i32, font_size: i32, color: Color) {
+		self.record(DrawCommand::Text {
+			text: text.to_string(),
+			x,
+			y,
+			font_size,
+			color,
+		});
+		self.inner.draw_text(text, x, y, font_size, color);
+	}
+
+	fn draw_text_pro(&mut self, text: &str, position: Vector2, rotation: f32, font_size: f32, spacing: f32, color: Color) {
+		self.record(DrawCommand::TextPro {
+			text: text.to_string(),
+			position,
+			rotation,
+			font_size,
+			spacing,
+			color,
+		});
+		self.inner.draw_text_pro(text, position, rotation, font_size, spacing, color);
+	}
+
+	fn draw_texture_rec(&mut self, texture: Texture2D, source: Rectangle, position: Vector2, tint: Color) {
+		self.record(DrawCommand::TextureRec {
+			texture: texture.into(),
+			source,
+			position,
+			tint,
+		});
+		self.inner.draw_texture_rec(texture, source, position, tint);
+	}
+
+	fn draw_texture_pro(&mut self, texture: Texture2D, source: Rectangle, dest: Rectangle, origin: Vector2, rotation: f32, tint: Color) {
+		self.record(DrawCommand::TexturePro {
+			texture: texture.into(),
+			source,
+			dest,
+			origin,
+			rotation,
+			tint,
+		});
+		self.inner.draw_texture_pro(texture, source, dest, origin, rotation, tint);
+	}
+
+	fn draw_fps(&mut self, x: i32, y: i32) {
+		self.record(DrawCommand::Fps { x, y });
+		self.inner.draw_fps(x, y);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn every_command() -> Vec<DrawCommand> {
+		let texture = TextureRef { id: 2, width: 16, height: 8 };
+		let rec = Rectangle {
+			x: 1.5,
+			y: -2.0,
+			width: 30.0,
+			height: 0.1,
+		};
+		let point = Vector2 { x: 0.25, y: 100.0 };
+		vec![
+			DrawCommand::BeginDrawing,
+			DrawCommand::ClearBackground(RAYWHITE),
+			DrawCommand::BeginMode2D {
+				offset: point,
+				target: Vector2 { x: -3.0, y: 1e-3 },
+				rotation: 45.0,
+				zoom: 1.0 / 3.0,
+			},
+			DrawCommand::Rectangle { rec, color: fade(RED, 0.5) },
+			DrawCommand::RectangleLines {
+				rec,
+				thickness: 2.0,
+				color: BLUE,
+			},
+			DrawCommand::Circle {
+				center: point,
+				radius: 4.5,
+				color: GREEN,
+			},
+			DrawCommand::Line {
+				start: point,
+				end: Vector2::default(),
+				thickness: 1.0,
+				color: BLACK,
+			},
+			DrawCommand::RectanglePro {
+				rec,
+				origin: point,
+				rotation: 30.0,
+				color: ORANGE,
+			},
+			DrawCommand::CircleLines {
+				center: point,
+				radius: 8.0,
+				color: PURPLE,
+			},
+			DrawCommand::Poly {
+				center: point,
+				sides: 6,
+				radius: 12.5,
+				rotation: -15.0,
+				color: MAROON,
+			},
+			DrawCommand::EndMode2D,
+			DrawCommand::BeginScissorMode {
+				x: 0,
+				y: 10,
+				width: 20,
+				height: 30,
+			},
+			DrawCommand::EndScissorMode,
+			DrawCommand::BeginTextureMode(texture),
+			DrawCommand::EndTextureMode,
+			DrawCommand::TextureRec {
+				texture,
+				source: rec,
+				position: point,
+				tint: WHITE,
+			},
+			DrawCommand::TexturePro {
+				texture,
+				source: rec,
+				dest: rec,
+				origin: point,
+				rotation: -90.0,
+				tint: GRAY,
+			},
+			DrawCommand::Text {
+				text: "two  spaces\nnew line\r\nwindows \\n not a newline\\".to_string(),
+				x: 10,
+				y: 20,
+				font_size: 20,
+				color: DARKGRAY,
+			},
+			DrawCommand::Text {
+				text: String::new(),
+				x: 0,
+				y: 0,
+				font_size: 10,
+				color: BLACK,
+			},
+			DrawCommand::TextPro {
+				text: " leading space and\ttab\n".to_string(),
+				position: point,
+				rotation: 90.0,
+				font_size: 12.5,
+				spacing: 1.25,
+				color: DARKGREEN,
+			},
+			DrawCommand::Fps { x: 10, y: 10 },
+			DrawCommand::EndDrawing,
+		]
+	}
+
+	#[test]
+	fn commands_round_trip() {
+		let commands = every_command();
+		let mut saved = Vec::new();
+		save_commands(&commands, &mut saved).unwrap();
+		let text = String::from_utf8(saved.clone()).unwrap();
+		assert_eq!(text.lines().count(), commands.len());
+		assert!(!text.contains('\r'));
+		assert_eq!(load_commands(saved.as_slice()).unwrap(), commands);
+	}
+
+	#[test]
+	fn load_skips_blank_lines_and_rejects_garbage() {
+		let commands = load_commands("begin_drawing\n\n  \nend_drawing\n".as_bytes()).unwrap();
+		assert_eq!(commands, [DrawCommand::BeginDrawing, DrawCommand::EndDrawing]);
+
+		let err = load_commands("begin_drawing\nrectangle 1 2\n".as_bytes()).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+		assert!(err.to_string().starts_with("line 2:"));
+		assert!(load_commands("text 0 0 10 #000000ff bad \\x escape".as_bytes()).is_err());
+	}
+
+	#[test]
+	fn diff_reports_the_first_difference() {
+		let commands = every_command();
+		assert_eq!(diff_commands(&commands, &commands), None);
+
+		let mut changed = commands.clone();
+		changed[1] = DrawCommand::ClearBackground(BLACK);
+		let diff = diff_commands(&commands, &changed).unwrap();
+		assert!(diff.starts_with("command 1:"));
+		assert!(diff.contains("clear_background #000000ff"));
+
+		let diff = diff_commands(&commands[..2], &commands[..4]).unwrap();
+		assert!(diff.starts_with("2 extra commands"));
+		let diff = diff_commands(&commands[..4], &commands[..3]).unwrap();
+		assert!(diff.starts_with("1 missing commands"));
+	}
+
+	#[test]
+	fn recording_keeps_the_last_frame() {
+		let mut backend = RecordingBackend::default();
+		for color in [RED, GREEN] {
+			backend.begin_drawing();
+			backend.clear_background(color);
+			backend.end_drawing();
+		}
+		assert_eq!(backend.commands().len(), 6);
+		assert_eq!(backend.last_frame(), [DrawCommand::ClearBackground(GREEN)]);
+	}
+}
@@ -0,0 +1,169 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{game_loop::Frame, input_map::SyntheticInput};
+
+// One recorded frame: the timing the game loop produced and the input seen during it.
+#[derive(Clone, Debug)]
+pub struct ReplayFrame {
+	pub frame: Frame,
+	pub input: SyntheticInput,
+}
+
+// Input and frame timing of a session. Playing the frames back instead of calling
+// `GameLoop::advance` runs the same fixed updates with the same input, so a deterministic game
+// ends up in exactly the same state. Raylib's automation events are not used because they replay
+// raw events against the wall clock, not against our fixed steps.
+#[derive(Clone, Default, Debug)]
+pub struct Replay {
+	// whatever the game seeds its random numbers with
+	pub seed: u64,
+	frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+	pub fn new(seed: u64) -> Self {
+		Self { seed, frames: Vec::new() }
+	}
+
+	pub fn record(&mut self, frame: Frame, input: SyntheticInput) {
+		self.frames.push(ReplayFrame { frame, input });
+	}
+
+	pub fn frames(&self) -> &[ReplayFrame] {
+		&self.frames
+	}
+
+	pub fn len(&self) -> usize {
+		self.frames.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.frames.is_empty()
+	}
+
+	// Text, one line per frame:
+	//   frame <fixed steps> <fixed delta> <delta> <real delta> <alpha> [keys=32,263] [mouse=0] [wheel=1]
+	//     [gamepad=0:7,0:8] [axes=0:0:0.5] [gestures=1]
+	// Floats are written so they read back bit for bit.
+	pub fn save(&self, writer: &mut impl Write) -> io::Result<()> {
+		writeln!(writer, "replay 1")?;
+		writeln!(writer, "seed {}", self.seed)?;
+		for ReplayFrame { frame, input } in &self.frames {
+			write!(
+				writer,
+				"frame {} {} {} {} {}",
+				frame.fixed_steps, frame.fixed_delta, frame.delta, frame.real_delta, frame.alpha
+			)?;
+			write_list(writer, "keys", input.keys.iter().map(|key| key.to_string()))?;
+			write_list(writer, "mouse", input.mouse_buttons.iter().map(|button| button.to_string()))?;
+			if input.mouse_wheel != 0.0 {
+				write!(writer, " wheel={}", input.mouse_wheel)?;
+			}
+			write_list(
+				writer,
+				"gamepad",
+				input.gamepad_buttons.iter().map(|(gamepad, button)| format!("{gamepad}:{button}")),
+			)?;
+			write_list(
+				writer,
+				"axes",
+				input
+					.gamepad_axes
+					.iter()
+					.map(|((gamepad, axis), value)| format!("{gamepad}:{axis}:{value}")),
+			)?;
+			write_list(writer, "gestures", input.gestures.iter().map(|gesture| gesture.to_string()))?;
+			writeln!(writer)?;
+		}
+		Ok(())
+	}
+
+	pub fn load(reader: impl BufRead) -> io::Result<Self> {
+		let mut lines = reader.lines();
+		if lines.next().transpose()?.as_deref() != Some("replay 1") {
+			return Err(invalid_data("not a replay".to_string()));
+		}
+		let seed = lines
+			.next()
+			.transpose()?
+			.and_then(|line| line.strip_prefix("seed ")?.parse().ok())
+			.ok_or_else(|| invalid_data("bad seed".to_string()))?;
+
+		let mut replay = Replay::new(seed);
+		for (number, line) in lines.enumerate() {
+			let line = line?;
+			let frame = parse_frame(&line).ok_or_else(|| invalid_data(format!("line {}: bad frame: {line}", number + 3)))?;
+			replay.frames.push(frame);
+		}
+		Ok(replay)
+	}
+}
+
+impl IntoIterator for Replay {
+	type Item = ReplayFrame;
+	type IntoIter = std::vec::IntoIter<ReplayFrame>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.frames.into_iter()
+	}
+}
+
+// sorted so the same input always gives the same line
+fn write_list(writer: &mut impl Write, name: &str, items: impl Iterator<Item = String>) -> io::Result<()> {
+	let mut items: Vec<String> = items.collect();
+	if items.is_empty() {
+		return Ok(());
+	}
+	items.sort();
+	write!(writer, " {name}={}", items.join(","))
+}
+
+fn parse_frame(line: &str) -> Option<ReplayFrame> {
+	let mut words = line.split_whitespace();
+	if words.next()? != "frame" {
+		return None;
+	}
+	let frame = Frame {
+		fixed_steps: words.next()?.parse().ok()?,
+		fixed_delta: words.next()?.parse().ok()?,
+		delta: words.next()?.parse().ok()?,
+		real_delta: words.next()?.parse().ok()?,
+		alpha: words.next()?.parse().ok()?,
+	};
+
+	let mut input = SyntheticInput::default();
+	for word in words {
+		let (name, value) = word.split_once('=')?;
+		let items = value.split(',');
+		match name {
+			"keys" => input.keys = items.map(|key| key.parse().ok()).collect::<Option<_>>()?,
+			"mouse" => input.mouse_buttons = items.map(|button| button.parse().ok()).collect::<Option<_>>()?,
+			"wheel" => input.mouse_wheel = value.parse().ok()?,
+			"gamepad" => {
+				input.gamepad_buttons = items
+					.map(|item| {
+						let (gamepad, button) = item.split_once(':')?;
+						Some((gamepad.parse().ok()?, button.parse().ok()?))
+					})
+					.collect::<Option<_>>()?
+			}
+			"axes" => {
+				input.gamepad_axes = items
+					.map(|item| {
+						let mut parts = item.split(':');
+						let gamepad = parts.next()?.parse().ok()?;
+						let axis = parts.next()?.parse().ok()?;
+						Some(((gamepad, axis), parts.next()?.parse().ok()?))
+					})
+					.collect::<Option<_>>()?
+			}
+			"gestures" => input.gestures = items.map(|gesture| gesture.parse().ok()).collect::<Option<_>>()?,
+			_ => return None,
+		}
+	}
+	Some(ReplayFrame { frame, input })
+}
+
+fn invalid_data(message: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
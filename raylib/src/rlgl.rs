@@ -0,0 +1,17 @@
+// The few rlgl.h functions the crate batches with, raylib.dll exports them next to the raylib.h ones.
+// Same rules as the rest of the bindings: only between BeginDrawing and EndDrawing on the window thread.
+
+pub const RL_TRIANGLES: i32 = 0x0004;
+pub const RL_QUADS: i32 = 0x0007;
+
+extern "C" {
+	pub fn rlBegin(mode: i32);
+	pub fn rlEnd();
+	pub fn rlVertex2f(x: f32, y: f32);
+	pub fn rlTexCoord2f(x: f32, y: f32);
+	pub fn rlColor4ub(r: u8, g: u8, b: u8, a: u8);
+	pub fn rlSetTexture(id: u32);
+	pub fn rlGetTextureIdDefault() -> u32;
+	// flushes the batch if `vertex_count` more vertices would not fit, keeps the mode and texture
+	pub fn rlCheckRenderBatchLimit(vertex_count: i32) -> bool;
+}
@@ -0,0 +1,129 @@
+// Small seeded PCG32 generator. Unlike `GetRandomValue` it is owned, so every system can keep its own
+// stream and reproduce it from the seed.
+#[derive(Clone, Debug)]
+pub struct Rng {
+	state: u64,
+	inc: u64,
+}
+
+impl Rng {
+	pub fn new(seed: u64) -> Self {
+		let mut rng = Self {
+			state: 0,
+			inc: (0xda3e_39cb_94b9_5bdb << 1) | 1,
+		};
+		rng.next_u32();
+		rng.state = rng.state.wrapping_add(seed);
+		rng.next_u32();
+		rng
+	}
+
+	pub fn next_u32(&mut self) -> u32 {
+		let old = self.state;
+		self.state = old.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(self.inc);
+		let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+		let rot = (old >> 59) as u32;
+		xorshifted.rotate_right(rot)
+	}
+
+	pub fn next_u64(&mut self) -> u64 {
+		((self.next_u32() as u64) << 32) | self.next_u32() as u64
+	}
+
+	// uniform in [0, 1)
+	pub fn next_f32(&mut self) -> f32 {
+		(self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+	}
+
+	// uniform in [min, max], both included like `GetRandomValue`
+	pub fn range_i32(&mut self, min: i32, max: i32) -> i32 {
+		if max <= min {
+			return min;
+		}
+		let span = (max as i64 - min as i64 + 1) as u64;
+		(min as i64 + (self.next_u64() % span) as i64) as i32
+	}
+
+	// uniform in [min, max)
+	pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+		min + (max - min) * self.next_f32()
+	}
+
+	pub fn chance(&mut self, probability: f32) -> bool {
+		self.next_f32() < probability
+	}
+
+	pub fn shuffle<T>(&mut self, items: &mut [T]) {
+		for i in (1..items.len()).rev() {
+			let j = self.range_i32(0, i as i32) as usize;
+			items.swap(i, j);
+		}
+	}
+
+	pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+		if items.is_empty() {
+			None
+		} else {
+			Some(&items[self.range_i32(0, items.len() as i32 - 1) as usize])
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn seeds_repeat() {
+		let run = |seed| {
+			let mut rng = Rng::new(seed);
+			(0..16).map(|_| rng.next_u32()).collect::<Vec<_>>()
+		};
+		assert_eq!(run(1), run(1));
+		assert_ne!(run(1), run(2));
+		// the clone carries on from the same point
+		let mut rng = Rng::new(9);
+		rng.next_u64();
+		let mut copy = rng.clone();
+		assert_eq!(rng.next_u64(), copy.next_u64());
+	}
+
+	#[test]
+	fn ranges() {
+		let mut rng = Rng::new(3);
+		let mut seen = [false; 5];
+		for _ in 0..1000 {
+			let value = rng.range_i32(-2, 2);
+			assert!((-2..=2).contains(&value));
+			seen[(value + 2) as usize] = true;
+
+			let float = rng.next_f32();
+			assert!((0.0..1.0).contains(&float));
+			assert!((4.0..6.0).contains(&rng.range_f32(4.0, 6.0)));
+		}
+		// both ends included
+		assert_eq!(seen, [true; 5]);
+		assert_eq!(rng.range_i32(7, 7), 7);
+		assert_eq!(rng.range_i32(7, 3), 7);
+		assert_eq!(rng.range_i32(i32::MIN, i32::MIN), i32::MIN);
+		let _ = rng.range_i32(i32::MIN, i32::MAX);
+
+		assert!(!rng.chance(0.0));
+		assert!(rng.chance(1.0));
+	}
+
+	#[test]
+	fn shuffle_and_choose() {
+		let mut rng = Rng::new(11);
+		let mut items: Vec<u32> = (0..20).collect();
+		rng.shuffle(&mut items);
+		assert_ne!(items, (0..20).collect::<Vec<_>>());
+		items.sort_unstable();
+		assert_eq!(items, (0..20).collect::<Vec<_>>());
+
+		let mut empty: [u32; 0] = [];
+		rng.shuffle(&mut empty);
+		assert_eq!(rng.choose(&empty), None);
+		assert_eq!(rng.choose(&[4]), Some(&4));
+	}
+}
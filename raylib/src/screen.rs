@@ -0,0 +1,516 @@
+use crate::{backend::Backend, *};
+
+// What the stack should do after a screen update.
+pub enum Transition<C = ()> {
+	None,
+	// put a screen on top, the current one stays below it
+	Push(Box<dyn Screen<C>>),
+	// leave the current screen and go back to the one below
+	Pop,
+	// swap the current screen for another one
+	Replace(Box<dyn Screen<C>>),
+	Quit,
+}
+
+// One screen of a game (logo, title, gameplay, pause menu, ...). `C` is whatever state the screens share.
+// A change calls `exit` on the screens leaving before `enter` on the one coming in, both as the change
+// starts. Leaving screens are still drawn until the effect finishes.
+pub trait Screen<C = ()> {
+	fn enter(&mut self, _ctx: &mut C) {}
+
+	fn update(&mut self, ctx: &mut C, delta_time: f32) -> Transition<C>;
+
+	// called inside BeginDrawing/EndDrawing
+	fn draw(&self, backend: &mut dyn Backend, ctx: &C);
+
+	fn exit(&mut self, _ctx: &mut C) {}
+
+	// overlays are drawn on top of the screen below them, e.g. a pause menu over the gameplay
+	fn is_overlay(&self) -> bool {
+		false
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Direction {
+	Left,
+	Right,
+	Up,
+	Down,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TransitionEffect {
+	// switch on the next frame
+	Cut,
+	// fade out to the color and back in to the new screen
+	Fade { color: Color, duration: f32 },
+	// the new screen pushes the old one out, moving in the given direction
+	Slide { direction: Direction, duration: f32 },
+	// the new screen is revealed over the old one, the edge moves in the given direction
+	Wipe { direction: Direction, duration: f32 },
+}
+
+impl TransitionEffect {
+	pub fn duration(&self) -> f32 {
+		match *self {
+			TransitionEffect::Cut => 0.0,
+			TransitionEffect::Fade { duration, .. } | TransitionEffect::Slide { duration, .. } | TransitionEffect::Wipe { duration, .. } => duration,
+		}
+	}
+}
+
+enum Pending<C> {
+	Push(Box<dyn Screen<C>>),
+	Pop,
+	Replace(Box<dyn Screen<C>>),
+	Quit,
+}
+
+struct ActiveTransition<C> {
+	effect: TransitionEffect,
+	elapsed: f32,
+	pending: Pending<C>,
+}
+
+impl<C> ActiveTransition<C> {
+	// eased 0..1
+	fn progress(&self) -> f32 {
+		let duration = self.effect.duration();
+		let t = if duration > 0.0 {
+			(self.elapsed / duration).clamp(0.0, 1.0)
+		} else {
+			1.0
+		};
+		t * t * (3.0 - 2.0 * t)
+	}
+}
+
+// Stack of screens, only the top one is updated. Screen changes play `effect` and the screens being
+// left or entered are frozen until it finishes.
+pub struct ScreenStack<C = ()> {
+	pub effect: TransitionEffect,
+	width: i32,
+	height: i32,
+	screens: Vec<Box<dyn Screen<C>>>,
+	transition: Option<ActiveTransition<C>>,
+	running: bool,
+	// only loaded for slides
+	targets: Option<[RenderTexture2D; 2]>,
+}
+
+// Slide targets are unloaded through the backend once the slide ends, this only catches a stack
+// dropped mid-slide. Headless targets have id 0 and nothing to free.
+#[cfg(not(feature = "headless"))]
+impl<C> Drop for ScreenStack<C> {
+	fn drop(&mut self) {
+		if let Some(targets) = self.targets.take() {
+			unsafe {
+				for target in targets.into_iter().filter(|target| target.id != 0) {
+					UnloadRenderTexture(target);
+				}
+			}
+		}
+	}
+}
+
+impl<C> ScreenStack<C> {
+	pub fn new(width: i32, height: i32, effect: TransitionEffect) -> Self {
+		Self {
+			effect,
+			width,
+			height,
+			screens: Vec::new(),
+			transition: None,
+			running: true,
+			targets: None,
+		}
+	}
+
+	// Puts a screen on top right away, without an effect. Used for the first screen.
+	pub fn push(&mut self, mut screen: Box<dyn Screen<C>>, ctx: &mut C) {
+		screen.enter(ctx);
+		self.screens.push(screen);
+	}
+
+	// False once a screen asked to quit or the last screen was popped.
+	pub fn is_running(&self) -> bool {
+		self.running
+	}
+
+	pub fn is_transitioning(&self) -> bool {
+		self.transition.is_some()
+	}
+
+	pub fn len(&self) -> usize {
+		self.screens.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.screens.is_empty()
+	}
+
+	pub fn update(&mut self, ctx: &mut C, delta_time: f32) {
+		if !self.running {
+			return;
+		}
+
+		if let Some(transition) = &mut self.transition {
+			transition.elapsed += delta_time;
+			if transition.elapsed >= transition.effect.duration() {
+				let transition = self.transition.take().expect("transition");
+				self.apply(transition.pending);
+			}
+			return;
+		}
+
+		let Some(top) = self.screens.last_mut() else {
+			self.running = false;
+			return;
+		};
+		let pending = match top.update(ctx, delta_time) {
+			Transition::None => return,
+			Transition::Push(mut screen) => {
+				screen.enter(ctx);
+				Pending::Push(screen)
+			}
+			Transition::Pop => {
+				top.exit(ctx);
+				Pending::Pop
+			}
+			Transition::Replace(mut screen) => {
+				top.exit(ctx);
+				screen.enter(ctx);
+				Pending::Replace(screen)
+			}
+			Transition::Quit => {
+				for screen in self.screens.iter_mut().rev() {
+					screen.exit(ctx);
+				}
+				Pending::Quit
+			}
+		};
+
+		if self.effect == TransitionEffect::Cut {
+			self.apply(pending);
+		} else {
+			self.transition = Some(ActiveTransition {
+				effect: self.effect,
+				elapsed: 0.0,
+				pending,
+			});
+		}
+	}
+
+	pub fn draw(&mut self, backend: &mut dyn Backend, ctx: &C) {
+		let Some(transition) = &self.transition else {
+			if let Some(targets) = self.targets.take() {
+				for target in targets {
+					backend.unload_render_texture(target);
+				}
+			}
+			draw_view(backend, &self.screens, None, ctx);
+			return;
+		};
+
+		// slides need both pictures as textures
+		if matches!(transition.effect, TransitionEffect::Slide { .. }) && self.targets.is_none() {
+			self.targets = Some([
+				backend.load_render_texture(self.width, self.height),
+				backend.load_render_texture(self.width, self.height),
+			]);
+		}
+
+		let transition = self.transition.as_ref().expect("transition");
+		let (old_view, new_view) = self.views();
+		let t = transition.progress();
+		match transition.effect {
+			TransitionEffect::Cut => draw_view(backend, &self.screens, None, ctx),
+			TransitionEffect::Fade { color, .. } => {
+				// out over the first half, in over the second
+				if t < 0.5 {
+					draw_view(backend, old_view.0, old_view.1, ctx);
+					backend.draw_rectangle(0, 0, self.width, self.height, fade(color, t * 2.0));
+				} else {
+					draw_view(backend, new_view.0, new_view.1, ctx);
+					backend.draw_rectangle(0, 0, self.width, self.height, fade(color, (1.0 - t) * 2.0));
+				}
+			}
+			TransitionEffect::Wipe { direction, .. } => {
+				draw_view(backend, old_view.0, old_view.1, ctx);
+				let (w, h) = (self.width as f32, self.height as f32);
+				let (x, y, width, height) = match direction {
+					Direction::Right => (0.0, 0.0, w * t, h),
+					Direction::Left => (w * (1.0 - t), 0.0, w * t, h),
+					Direction::Down => (0.0, 0.0, w, h * t),
+					Direction::Up => (0.0, h * (1.0 - t), w, h * t),
+				};
+				backend.begin_scissor_mode(x as i32, y as i32, width.ceil() as i32, height.ceil() as i32);
+				draw_view(backend, new_view.0, new_view.1, ctx);
+				backend.end_scissor_mode();
+			}
+			TransitionEffect::Slide { direction, .. } => {
+				let targets = self.targets.expect("slide targets");
+				for (target, (screens, incoming)) in targets.iter().zip([old_view, new_view]) {
+					backend.begin_texture_mode(*target);
+					backend.clear_background(BLANK);
+					draw_view(backend, screens, incoming, ctx);
+					backend.end_texture_mode();
+				}
+
+				let (dx, dy) = match direction {
+					Direction::Left => (-1.0, 0.0),
+					Direction::Right => (1.0, 0.0),
+					Direction::Up => (0.0, -1.0),
+					Direction::Down => (0.0, 1.0),
+				};
+				let (w, h) = (self.width as f32, self.height as f32);
+				// render textures are stored upside down
+				let source = Rectangle {
+					x: 0.0,
+					y: 0.0,
+					width: w,
+					height: -h,
+				};
+				backend.draw_texture_rec(
+					targets[0].texture,
+					source,
+					Vector2 {
+						x: dx * w * t,
+						y: dy * h * t,
+					},
+					WHITE,
+				);
+				backend.draw_texture_rec(
+					targets[1].texture,
+					source,
+					Vector2 {
+						x: dx * w * (t - 1.0),
+						y: dy * h * (t - 1.0),
+					},
+					WHITE,
+				);
+			}
+		}
+	}
+
+	// screens making up the picture before and after the pending change
+	fn views(&self) -> (View<'_, C>, View<'_, C>) {
+		let all = &self.screens[..];
+		let below = &self.screens[..self.screens.len().saturating_sub(1)];
+		let old = (all, None);
+		let new = match &self.transition.as_ref().expect("transition").pending {
+			Pending::Push(screen) => (all, Some(&**screen)),
+			Pending::Pop => (below, None),
+			Pending::Replace(screen) => (below, Some(&**screen)),
+			Pending::Quit => (&all[..0], None),
+		};
+		(old, new)
+	}
+
+	// `enter` and `exit` already ran when the change started
+	fn apply(&mut self, pending: Pending<C>) {
+		match pending {
+			Pending::Push(screen) => self.screens.push(screen),
+			Pending::Pop => {
+				self.screens.pop();
+				self.running = !self.screens.is_empty();
+			}
+			Pending::Replace(screen) => {
+				self.screens.pop();
+				self.screens.push(screen);
+			}
+			Pending::Quit => {
+				self.screens.clear();
+				self.running = false;
+			}
+		}
+	}
+}
+
+// screens of the stack plus the screen being entered
+type View<'a, C> = (&'a [Box<dyn Screen<C>>], Option<&'a dyn Screen<C>>);
+
+// draws from the topmost screen that is not an overlay upwards
+fn draw_view<C>(backend: &mut dyn Backend, screens: &[Box<dyn Screen<C>>], incoming: Option<&dyn Screen<C>>, ctx: &C) {
+	let view: Vec<&dyn Screen<C>> = screens.iter().map(|screen| &**screen).chain(incoming).collect();
+	let start = view.iter().rposition(|screen| !screen.is_overlay()).unwrap_or(0);
+	for screen in &view[start..] {
+		screen.draw(backend, ctx);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		backend::HeadlessBackend,
+		recording::{DrawCommand, RecordingBackend},
+	};
+
+	// what the screens share: the lifecycle calls so far and the next transition to ask for
+	#[derive(Default)]
+	struct Script {
+		log: Vec<String>,
+		next: Option<Transition<Script>>,
+	}
+
+	struct Named {
+		name: &'static str,
+		overlay: bool,
+	}
+
+	fn screen(name: &'static str) -> Box<Named> {
+		Box::new(Named { name, overlay: false })
+	}
+
+	fn overlay(name: &'static str) -> Box<Named> {
+		Box::new(Named { name, overlay: true })
+	}
+
+	impl Screen<Script> for Named {
+		fn enter(&mut self, script: &mut Script) {
+			script.log.push(format!("enter {}", self.name));
+		}
+
+		fn update(&mut self, script: &mut Script, _delta_time: f32) -> Transition<Script> {
+			script.log.push(format!("update {}", self.name));
+			script.next.take().unwrap_or(Transition::None)
+		}
+
+		fn draw(&self, backend: &mut dyn Backend, _script: &Script) {
+			backend.draw_text(self.name, 0, 0, 10, BLACK);
+		}
+
+		fn exit(&mut self, script: &mut Script) {
+			script.log.push(format!("exit {}", self.name));
+		}
+
+		fn is_overlay(&self) -> bool {
+			self.overlay
+		}
+	}
+
+	// requests `transition` from the top screen, then runs a frame
+	fn frame(
+		screens: &mut ScreenStack<Script>,
+		backend: &mut RecordingBackend<HeadlessBackend>,
+		script: &mut Script,
+		transition: Option<Transition<Script>>,
+	) -> Vec<String> {
+		script.next = transition;
+		screens.update(script, backend.frame_time());
+		backend.begin_drawing();
+		screens.draw(backend, script);
+		backend.end_drawing();
+		backend
+			.last_frame()
+			.iter()
+			.filter_map(|command| match command {
+				DrawCommand::Text { text, .. } => Some(text.clone()),
+				_ => None,
+			})
+			.collect()
+	}
+
+	fn take_log(script: &mut Script) -> Vec<String> {
+		std::mem::take(&mut script.log)
+	}
+
+	#[test]
+	fn cut_changes_right_away() {
+		let mut backend = RecordingBackend::new(HeadlessBackend::new(100));
+		let mut script = Script::default();
+		let mut screens = ScreenStack::new(320, 240, TransitionEffect::Cut);
+		screens.push(screen("title"), &mut script);
+
+		assert_eq!(frame(&mut screens, &mut backend, &mut script, None), ["title"]);
+		let drawn = frame(&mut screens, &mut backend, &mut script, Some(Transition::Push(overlay("pause"))));
+		assert_eq!(drawn, ["title", "pause"]);
+		assert_eq!(screens.len(), 2);
+
+		let drawn = frame(&mut screens, &mut backend, &mut script, Some(Transition::Pop));
+		assert_eq!(drawn, ["title"]);
+		let drawn = frame(&mut screens, &mut backend, &mut script, Some(Transition::Replace(screen("game"))));
+		assert_eq!(drawn, ["game"]);
+		assert!(!screens.is_transitioning());
+		assert_eq!(
+			take_log(&mut script),
+			[
+				"enter title",
+				"update title",
+				"update title",
+				"enter pause",
+				"update pause",
+				"exit pause",
+				"update title",
+				"exit title",
+				"enter game",
+			]
+		);
+	}
+
+	#[test]
+	fn quit_exits_every_screen_from_the_top() {
+		let mut backend = RecordingBackend::new(HeadlessBackend::new(100));
+		let mut script = Script::default();
+		let mut screens = ScreenStack::new(320, 240, TransitionEffect::Cut);
+		screens.push(screen("game"), &mut script);
+		frame(&mut screens, &mut backend, &mut script, Some(Transition::Push(overlay("pause"))));
+		take_log(&mut script);
+
+		let drawn = frame(&mut screens, &mut backend, &mut script, Some(Transition::Quit));
+		assert!(drawn.is_empty());
+		assert_eq!(take_log(&mut script), ["update pause", "exit pause", "exit game"]);
+		assert!(!screens.is_running());
+		assert!(screens.is_empty());
+
+		// a stopped stack does nothing
+		frame(&mut screens, &mut backend, &mut script, None);
+		assert!(script.log.is_empty());
+	}
+
+	#[test]
+	fn popping_the_last_screen_stops_the_stack() {
+		let mut backend = RecordingBackend::new(HeadlessBackend::new(100));
+		let mut script = Script::default();
+		let mut screens = ScreenStack::new(320, 240, TransitionEffect::Cut);
+		screens.push(screen("only"), &mut script);
+		frame(&mut screens, &mut backend, &mut script, Some(Transition::Pop));
+		assert_eq!(take_log(&mut script), ["enter only", "update only", "exit only"]);
+		assert!(!screens.is_running());
+	}
+
+	#[test]
+	fn fade_freezes_both_screens_until_it_ends() {
+		// 1/64 s frames, 8 of them
+		let mut backend = RecordingBackend::new(HeadlessBackend::new(100));
+		backend.inner.frame_time = 1.0 / 64.0;
+		let mut script = Script::default();
+		let mut screens = ScreenStack::new(
+			320,
+			240,
+			TransitionEffect::Fade {
+				color: BLACK,
+				duration: 0.125,
+			},
+		);
+		screens.push(screen("title"), &mut script);
+
+		let mut drawn = vec![frame(&mut screens, &mut backend, &mut script, Some(Transition::Replace(screen("game"))))];
+		// the old screen is exited, the new one entered as the fade starts
+		assert_eq!(take_log(&mut script), ["enter title", "update title", "exit title", "enter game"]);
+		while screens.is_transitioning() {
+			drawn.push(frame(&mut screens, &mut backend, &mut script, None));
+		}
+		// neither screen updates while fading
+		assert!(script.log.is_empty());
+		assert_eq!(drawn.len(), 9);
+		assert_eq!(drawn[..4], [["title"]; 4]);
+		assert_eq!(drawn[4..], [["game"]; 5]);
+		assert_eq!(screens.len(), 1);
+
+		frame(&mut screens, &mut backend, &mut script, None);
+		assert_eq!(take_log(&mut script), ["update game"]);
+	}
+}
@@ -0,0 +1,15 @@
+// Shared view over rectangular tile grids, so grid algorithms (fov, pathfinding,
+// generation) can run on any map type.
+pub trait TileMap {
+	fn width(&self) -> i32;
+	fn height(&self) -> i32;
+
+	fn in_bounds(&self, x: i32, y: i32) -> bool {
+		x >= 0 && y >= 0 && x < self.width() && y < self.height()
+	}
+
+	// row-major index of an in-bounds tile
+	fn index(&self, x: i32, y: i32) -> usize {
+		(y * self.width() + x) as usize
+	}
+}
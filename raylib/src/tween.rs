@@ -0,0 +1,940 @@
+use std::{
+	any::Any,
+	f32::consts::{PI, TAU},
+};
+
+use crate::*;
+
+// Robert Penner's easing functions, the same set as raylib's reasings.h plus quart and quint.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Ease {
+	#[default]
+	Linear,
+	SineIn,
+	SineOut,
+	SineInOut,
+	QuadIn,
+	QuadOut,
+	QuadInOut,
+	CubicIn,
+	CubicOut,
+	CubicInOut,
+	QuartIn,
+	QuartOut,
+	QuartInOut,
+	QuintIn,
+	QuintOut,
+	QuintInOut,
+	ExpoIn,
+	ExpoOut,
+	ExpoInOut,
+	CircIn,
+	CircOut,
+	CircInOut,
+	// overshoots below 0 / above 1 before settling
+	BackIn,
+	BackOut,
+	BackInOut,
+	ElasticIn,
+	ElasticOut,
+	ElasticInOut,
+	BounceIn,
+	BounceOut,
+	BounceInOut,
+}
+
+impl Ease {
+	pub const ALL: [Ease; 31] = [
+		Ease::Linear,
+		Ease::SineIn,
+		Ease::SineOut,
+		Ease::SineInOut,
+		Ease::QuadIn,
+		Ease::QuadOut,
+		Ease::QuadInOut,
+		Ease::CubicIn,
+		Ease::CubicOut,
+		Ease::CubicInOut,
+		Ease::QuartIn,
+		Ease::QuartOut,
+		Ease::QuartInOut,
+		Ease::QuintIn,
+		Ease::QuintOut,
+		Ease::QuintInOut,
+		Ease::ExpoIn,
+		Ease::ExpoOut,
+		Ease::ExpoInOut,
+		Ease::CircIn,
+		Ease::CircOut,
+		Ease::CircInOut,
+		Ease::BackIn,
+		Ease::BackOut,
+		Ease::BackInOut,
+		Ease::ElasticIn,
+		Ease::ElasticOut,
+		Ease::ElasticInOut,
+		Ease::BounceIn,
+		Ease::BounceOut,
+		Ease::BounceInOut,
+	];
+
+	// Maps progress `t` (clamped to 0..1) to eased progress, 0 at the start and 1 at the end.
+	pub fn apply(self, t: f32) -> f32 {
+		const BACK: f32 = 1.70158;
+		const BACK_IN_OUT: f32 = BACK * 1.525;
+		const ELASTIC: f32 = TAU / 3.0;
+		const ELASTIC_IN_OUT: f32 = TAU / 4.5;
+
+		let t = t.clamp(0.0, 1.0);
+		match self {
+			Ease::Linear => t,
+			Ease::SineIn => 1.0 - (t * PI / 2.0).cos(),
+			Ease::SineOut => (t * PI / 2.0).sin(),
+			Ease::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+			Ease::QuadIn => power_in(t, 2),
+			Ease::QuadOut => power_out(t, 2),
+			Ease::QuadInOut => power_in_out(t, 2),
+			Ease::CubicIn => power_in(t, 3),
+			Ease::CubicOut => power_out(t, 3),
+			Ease::CubicInOut => power_in_out(t, 3),
+			Ease::QuartIn => power_in(t, 4),
+			Ease::QuartOut => power_out(t, 4),
+			Ease::QuartInOut => power_in_out(t, 4),
+			Ease::QuintIn => power_in(t, 5),
+			Ease::QuintOut => power_out(t, 5),
+			Ease::QuintInOut => power_in_out(t, 5),
+			// the exponential curves never reach 0 and 1 on their own
+			Ease::ExpoIn if t == 0.0 => 0.0,
+			Ease::ExpoIn => 2f32.powf(10.0 * t - 10.0),
+			Ease::ExpoOut if t == 1.0 => 1.0,
+			Ease::ExpoOut => 1.0 - 2f32.powf(-10.0 * t),
+			Ease::ExpoInOut if t == 0.0 || t == 1.0 => t,
+			Ease::ExpoInOut if t < 0.5 => 2f32.powf(20.0 * t - 10.0) / 2.0,
+			Ease::ExpoInOut => (2.0 - 2f32.powf(-20.0 * t + 10.0)) / 2.0,
+			Ease::CircIn => 1.0 - (1.0 - t * t).sqrt(),
+			Ease::CircOut => (1.0 - (t - 1.0).powi(2)).sqrt(),
+			Ease::CircInOut if t < 0.5 => (1.0 - (1.0 - (2.0 * t).powi(2)).sqrt()) / 2.0,
+			Ease::CircInOut => ((1.0 - (-2.0 * t + 2.0).powi(2)).sqrt() + 1.0) / 2.0,
+			Ease::BackIn => (BACK + 1.0) * t * t * t - BACK * t * t,
+			Ease::BackOut => 1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2),
+			Ease::BackInOut if t < 0.5 => (2.0 * t).powi(2) * ((BACK_IN_OUT + 1.0) * 2.0 * t - BACK_IN_OUT) / 2.0,
+			Ease::BackInOut => ((2.0 * t - 2.0).powi(2) * ((BACK_IN_OUT + 1.0) * (t * 2.0 - 2.0) + BACK_IN_OUT) + 2.0) / 2.0,
+			Ease::ElasticIn | Ease::ElasticOut | Ease::ElasticInOut if t == 0.0 || t == 1.0 => t,
+			Ease::ElasticIn => -(2f32.powf(10.0 * t - 10.0)) * ((10.0 * t - 10.75) * ELASTIC).sin(),
+			Ease::ElasticOut => 2f32.powf(-10.0 * t) * ((10.0 * t - 0.75) * ELASTIC).sin() + 1.0,
+			Ease::ElasticInOut if t < 0.5 => -(2f32.powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * ELASTIC_IN_OUT).sin()) / 2.0,
+			Ease::ElasticInOut => 2f32.powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * ELASTIC_IN_OUT).sin() / 2.0 + 1.0,
+			Ease::BounceIn => 1.0 - bounce_out(1.0 - t),
+			Ease::BounceOut => bounce_out(t),
+			Ease::BounceInOut if t < 0.5 => (1.0 - bounce_out(1.0 - 2.0 * t)) / 2.0,
+			Ease::BounceInOut => (1.0 + bounce_out(2.0 * t - 1.0)) / 2.0,
+		}
+	}
+
+	// `from` to `to` at eased progress `t`
+	pub fn interpolate<T: Lerp>(self, from: T, to: T, t: f32) -> T {
+		from.lerp(to, self.apply(t))
+	}
+}
+
+fn power_in(t: f32, power: i32) -> f32 {
+	t.powi(power)
+}
+
+fn power_out(t: f32, power: i32) -> f32 {
+	1.0 - (1.0 - t).powi(power)
+}
+
+fn power_in_out(t: f32, power: i32) -> f32 {
+	if t < 0.5 {
+		2f32.powi(power - 1) * t.powi(power)
+	} else {
+		1.0 - (-2.0 * t + 2.0).powi(power) / 2.0
+	}
+}
+
+fn bounce_out(t: f32) -> f32 {
+	const N: f32 = 7.5625;
+	const D: f32 = 2.75;
+	if t < 1.0 / D {
+		N * t * t
+	} else if t < 2.0 / D {
+		let t = t - 1.5 / D;
+		N * t * t + 0.75
+	} else if t < 2.5 / D {
+		let t = t - 2.25 / D;
+		N * t * t + 0.9375
+	} else {
+		let t = t - 2.625 / D;
+		N * t * t + 0.984375
+	}
+}
+
+// Values a tween can move between. `t` may leave 0..1 for the overshooting eases.
+pub trait Lerp: Copy {
+	fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+	fn lerp(self, to: Self, t: f32) -> Self {
+		self + (to - self) * t
+	}
+}
+
+impl Lerp for Vector2 {
+	fn lerp(self, to: Self, t: f32) -> Self {
+		Vector2Lerp(self, to, t)
+	}
+}
+
+impl Lerp for Vector3 {
+	fn lerp(self, to: Self, t: f32) -> Self {
+		Vector3 {
+			x: self.x.lerp(to.x, t),
+			y: self.y.lerp(to.y, t),
+			z: self.z.lerp(to.z, t),
+		}
+	}
+}
+
+impl Lerp for Color {
+	fn lerp(self, to: Self, t: f32) -> Self {
+		let channel = |from: u8, to: u8| (from as f32).lerp(to as f32, t).round().clamp(0.0, 255.0) as u8;
+		Color {
+			r: channel(self.r, to.r),
+			g: channel(self.g, to.g),
+			b: channel(self.b, to.b),
+			a: channel(self.a, to.a),
+		}
+	}
+}
+
+impl Lerp for Rectangle {
+	fn lerp(self, to: Self, t: f32) -> Self {
+		Rectangle {
+			x: self.x.lerp(to.x, t),
+			y: self.y.lerp(to.y, t),
+			width: self.width.lerp(to.width, t),
+			height: self.height.lerp(to.height, t),
+		}
+	}
+}
+
+impl Lerp for Camera2D {
+	fn lerp(self, to: Self, t: f32) -> Self {
+		Camera2D {
+			offset: self.offset.lerp(to.offset, t),
+			target: self.target.lerp(to.target, t),
+			rotation: self.rotation.lerp(to.rotation, t),
+			zoom: self.zoom.lerp(to.zoom, t),
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Repeat {
+	// extra plays after the first one
+	Times(u32),
+	Forever,
+}
+
+// Anything that plays over time: tweens, delays and groups of them. Everything is driven by the
+// `delta_time` passed in, so the same steps always give the same values.
+pub trait Timeline: Any {
+	// Advances by `delta_time` seconds and returns the part of it left over after finishing,
+	// 0.0 while still playing. Sequences hand the leftover to their next entry.
+	fn advance(&mut self, delta_time: f32) -> f32;
+
+	fn is_finished(&self) -> bool;
+
+	// back to the start, completion callbacks fire again
+	fn reset(&mut self);
+
+	// None when repeating forever
+	fn duration(&self) -> Option<f32>;
+
+	fn as_any(&self) -> &dyn Any;
+}
+
+// Moves a value from `from` to `to` over `duration` seconds.
+pub struct Tween<T: Lerp> {
+	pub from: T,
+	pub to: T,
+	pub duration: f32,
+	pub ease: Ease,
+	// seconds before it starts moving, only once and not again on repeats
+	pub delay: f32,
+	pub repeat: Repeat,
+	// every other play runs backwards
+	pub yoyo: bool,
+	elapsed: f32,
+	completed: bool,
+	on_update: Option<Box<dyn FnMut(T)>>,
+	on_complete: Option<Box<dyn FnMut()>>,
+}
+
+impl<T: Lerp> Tween<T> {
+	pub fn new(from: T, to: T, duration: f32) -> Self {
+		Self {
+			from,
+			to,
+			duration,
+			ease: Ease::Linear,
+			delay: 0.0,
+			repeat: Repeat::Times(0),
+			yoyo: false,
+			elapsed: 0.0,
+			completed: false,
+			on_update: None,
+			on_complete: None,
+		}
+	}
+
+	pub fn ease(mut self, ease: Ease) -> Self {
+		self.ease = ease;
+		self
+	}
+
+	pub fn delay(mut self, delay: f32) -> Self {
+		self.delay = delay;
+		self
+	}
+
+	pub fn repeat(mut self, repeat: Repeat) -> Self {
+		self.repeat = repeat;
+		self
+	}
+
+	pub fn yoyo(mut self) -> Self {
+		self.yoyo = true;
+		self
+	}
+
+	// gets the new value after every advance
+	pub fn on_update(mut self, on_update: impl FnMut(T) + 'static) -> Self {
+		self.on_update = Some(Box::new(on_update));
+		self
+	}
+
+	pub fn on_complete(mut self, on_complete: impl FnMut() + 'static) -> Self {
+		self.on_complete = Some(Box::new(on_complete));
+		self
+	}
+
+	pub fn elapsed(&self) -> f32 {
+		self.elapsed
+	}
+
+	// the value at the current time, `from` during the delay
+	pub fn value(&self) -> T {
+		self.value_at(self.elapsed)
+	}
+
+	// the value `time` seconds after the start, delay included
+	pub fn value_at(&self, time: f32) -> T {
+		let time = time - self.delay;
+		if time < 0.0 {
+			return self.from;
+		}
+		let (play, progress) = match self.total() {
+			_ if self.duration <= 0.0 => (0, 1.0),
+			Some(total) if time >= total - self.delay => (self.plays() - 1, 1.0),
+			_ => {
+				let play = (time / self.duration).floor();
+				(play as u32, (time - play * self.duration) / self.duration)
+			}
+		};
+		let progress = if self.yoyo && play % 2 == 1 { 1.0 - progress } else { progress };
+		self.ease.interpolate(self.from, self.to, progress)
+	}
+
+	// delay and every play, None when repeating forever
+	fn total(&self) -> Option<f32> {
+		// a zero length tween jumps to `to` however often it repeats
+		if self.duration <= 0.0 {
+			return Some(self.delay.max(0.0));
+		}
+		match self.repeat {
+			Repeat::Times(times) => Some(self.delay.max(0.0) + self.duration * (times as f32 + 1.0)),
+			Repeat::Forever => None,
+		}
+	}
+
+	fn plays(&self) -> u32 {
+		match self.repeat {
+			Repeat::Times(times) => times.saturating_add(1),
+			Repeat::Forever => u32::MAX,
+		}
+	}
+}
+
+impl<T: Lerp + 'static> Timeline for Tween<T> {
+	fn advance(&mut self, delta_time: f32) -> f32 {
+		if self.completed {
+			return delta_time;
+		}
+		self.elapsed += delta_time;
+		let mut leftover = 0.0;
+		if let Some(total) = self.total() {
+			if self.elapsed >= total {
+				leftover = self.elapsed - total;
+				self.elapsed = total;
+				self.completed = true;
+			}
+		}
+		let value = self.value();
+		if let Some(on_update) = &mut self.on_update {
+			on_update(value);
+		}
+		if self.completed {
+			if let Some(on_complete) = &mut self.on_complete {
+				on_complete();
+			}
+		}
+		leftover
+	}
+
+	fn is_finished(&self) -> bool {
+		self.completed
+	}
+
+	fn reset(&mut self) {
+		self.elapsed = 0.0;
+		self.completed = false;
+	}
+
+	fn duration(&self) -> Option<f32> {
+		self.total()
+	}
+
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+}
+
+// Waits, e.g. between the entries of a sequence.
+pub struct Delay {
+	pub duration: f32,
+	elapsed: f32,
+}
+
+impl Delay {
+	pub fn new(duration: f32) -> Self {
+		Self { duration, elapsed: 0.0 }
+	}
+}
+
+impl Timeline for Delay {
+	fn advance(&mut self, delta_time: f32) -> f32 {
+		self.elapsed += delta_time;
+		let leftover = (self.elapsed - self.duration).max(0.0);
+		self.elapsed = self.elapsed.min(self.duration);
+		leftover
+	}
+
+	fn is_finished(&self) -> bool {
+		self.elapsed >= self.duration
+	}
+
+	fn reset(&mut self) {
+		self.elapsed = 0.0;
+	}
+
+	fn duration(&self) -> Option<f32> {
+		Some(self.duration.max(0.0))
+	}
+
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+}
+
+// Advances a group's entries through one play, returns the leftover time once it finished.
+type PlayOnce = fn(&mut [Box<dyn Timeline>], f32) -> Option<f32>;
+
+// Shared by sequences and parallel groups: the entries, repeats and the completion callback.
+struct Group {
+	entries: Vec<Box<dyn Timeline>>,
+	repeat: Repeat,
+	plays: u32,
+	completed: bool,
+	on_complete: Option<Box<dyn FnMut()>>,
+}
+
+impl Group {
+	fn new() -> Self {
+		Self {
+			entries: Vec::new(),
+			repeat: Repeat::Times(0),
+			plays: 0,
+			completed: false,
+			on_complete: None,
+		}
+	}
+
+	fn get<T: Timeline>(&self, index: usize) -> Option<&T> {
+		self.entries.get(index)?.as_any().downcast_ref()
+	}
+
+	// Runs `play_once` until it stops finishing a play, restarting the entries for every repeat.
+	fn advance(&mut self, mut delta_time: f32, play_once: PlayOnce, one_play: Option<f32>) -> f32 {
+		if self.completed {
+			return delta_time;
+		}
+		loop {
+			let Some(leftover) = play_once(&mut self.entries, delta_time) else {
+				return 0.0;
+			};
+			self.plays += 1;
+			let more = match self.repeat {
+				Repeat::Times(times) => self.plays <= times,
+				// a group taking no time would repeat forever within one step
+				Repeat::Forever => one_play != Some(0.0),
+			};
+			if !more {
+				self.completed = true;
+				if let Some(on_complete) = &mut self.on_complete {
+					on_complete();
+				}
+				return leftover;
+			}
+			self.entries.iter_mut().for_each(|entry| entry.reset());
+			delta_time = leftover;
+		}
+	}
+
+	fn reset(&mut self) {
+		self.entries.iter_mut().for_each(|entry| entry.reset());
+		self.plays = 0;
+		self.completed = false;
+	}
+
+	fn duration(&self, one_play: Option<f32>) -> Option<f32> {
+		match self.repeat {
+			Repeat::Times(times) => Some(one_play? * (times as f32 + 1.0)),
+			Repeat::Forever => None,
+		}
+	}
+}
+
+// Plays its entries one after another.
+pub struct Sequence {
+	group: Group,
+}
+
+impl Default for Sequence {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Sequence {
+	pub fn new() -> Self {
+		Self { group: Group::new() }
+	}
+
+	pub fn then(mut self, entry: impl Timeline) -> Self {
+		self.group.entries.push(Box::new(entry));
+		self
+	}
+
+	pub fn wait(self, duration: f32) -> Self {
+		self.then(Delay::new(duration))
+	}
+
+	pub fn repeat(mut self, repeat: Repeat) -> Self {
+		self.group.repeat = repeat;
+		self
+	}
+
+	pub fn on_complete(mut self, on_complete: impl FnMut() + 'static) -> Self {
+		self.group.on_complete = Some(Box::new(on_complete));
+		self
+	}
+
+	// the entry at `index` if it is a `T`, e.g. `sequence.get::<Tween<Color>>(0)`
+	pub fn get<T: Timeline>(&self, index: usize) -> Option<&T> {
+		self.group.get(index)
+	}
+
+	// index of the entry playing now
+	pub fn current(&self) -> usize {
+		self
+			.group
+			.entries
+			.iter()
+			.position(|entry| !entry.is_finished())
+			.unwrap_or(self.group.entries.len())
+	}
+
+	fn play_once(entries: &mut [Box<dyn Timeline>], mut delta_time: f32) -> Option<f32> {
+		for entry in entries.iter_mut().filter(|entry| !entry.is_finished()) {
+			delta_time = entry.advance(delta_time);
+			if !entry.is_finished() {
+				return None;
+			}
+		}
+		Some(delta_time)
+	}
+
+	fn one_play(&self) -> Option<f32> {
+		self.group.entries.iter().map(|entry| entry.duration()).sum()
+	}
+}
+
+impl Timeline for Sequence {
+	fn advance(&mut self, delta_time: f32) -> f32 {
+		let one_play = self.one_play();
+		self.group.advance(delta_time, Self::play_once, one_play)
+	}
+
+	fn is_finished(&self) -> bool {
+		self.group.completed
+	}
+
+	fn reset(&mut self) {
+		self.group.reset();
+	}
+
+	fn duration(&self) -> Option<f32> {
+		self.group.duration(self.one_play())
+	}
+
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+}
+
+// Plays its entries together, finishes with the longest one.
+pub struct Parallel {
+	group: Group,
+}
+
+impl Default for Parallel {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Parallel {
+	pub fn new() -> Self {
+		Self { group: Group::new() }
+	}
+
+	pub fn with(mut self, entry: impl Timeline) -> Self {
+		self.group.entries.push(Box::new(entry));
+		self
+	}
+
+	pub fn repeat(mut self, repeat: Repeat) -> Self {
+		self.group.repeat = repeat;
+		self
+	}
+
+	pub fn on_complete(mut self, on_complete: impl FnMut() + 'static) -> Self {
+		self.group.on_complete = Some(Box::new(on_complete));
+		self
+	}
+
+	pub fn get<T: Timeline>(&self, index: usize) -> Option<&T> {
+		self.group.get(index)
+	}
+
+	fn play_once(entries: &mut [Box<dyn Timeline>], delta_time: f32) -> Option<f32> {
+		let mut leftover = delta_time;
+		for entry in entries.iter_mut() {
+			let entry_leftover = match entry.is_finished() {
+				true => delta_time,
+				false => entry.advance(delta_time),
+			};
+			leftover = leftover.min(entry_leftover);
+		}
+		entries.iter().all(|entry| entry.is_finished()).then_some(leftover)
+	}
+
+	fn one_play(&self) -> Option<f32> {
+		self
+			.group
+			.entries
+			.iter()
+			.try_fold(0.0f32, |longest, entry| Some(longest.max(entry.duration()?)))
+	}
+}
+
+impl Timeline for Parallel {
+	fn advance(&mut self, delta_time: f32) -> f32 {
+		let one_play = self.one_play();
+		self.group.advance(delta_time, Self::play_once, one_play)
+	}
+
+	fn is_finished(&self) -> bool {
+		self.group.completed
+	}
+
+	fn reset(&mut self) {
+		self.group.reset();
+	}
+
+	fn duration(&self) -> Option<f32> {
+		self.group.duration(self.one_play())
+	}
+
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{cell::Cell, rc::Rc};
+
+	use super::*;
+
+	fn close(a: f32, b: f32) -> bool {
+		(a - b).abs() < 1e-5
+	}
+
+	// the in, out and in-out variants of each curve
+	const FAMILIES: [[Ease; 3]; 10] = [
+		[Ease::SineIn, Ease::SineOut, Ease::SineInOut],
+		[Ease::QuadIn, Ease::QuadOut, Ease::QuadInOut],
+		[Ease::CubicIn, Ease::CubicOut, Ease::CubicInOut],
+		[Ease::QuartIn, Ease::QuartOut, Ease::QuartInOut],
+		[Ease::QuintIn, Ease::QuintOut, Ease::QuintInOut],
+		[Ease::ExpoIn, Ease::ExpoOut, Ease::ExpoInOut],
+		[Ease::CircIn, Ease::CircOut, Ease::CircInOut],
+		[Ease::BackIn, Ease::BackOut, Ease::BackInOut],
+		[Ease::ElasticIn, Ease::ElasticOut, Ease::ElasticInOut],
+		[Ease::BounceIn, Ease::BounceOut, Ease::BounceInOut],
+	];
+
+	// counts calls of a callback
+	fn counter() -> (Rc<Cell<u32>>, impl FnMut() + 'static) {
+		let count = Rc::new(Cell::new(0));
+		let counted = count.clone();
+		(count, move || counted.set(counted.get() + 1))
+	}
+
+	#[test]
+	fn eases_start_at_0_and_end_at_1() {
+		for ease in Ease::ALL {
+			assert!(close(ease.apply(0.0), 0.0), "{ease:?}");
+			assert!(close(ease.apply(1.0), 1.0), "{ease:?}");
+			// progress outside 0..1 is clamped
+			assert_eq!(ease.apply(-1.0), ease.apply(0.0), "{ease:?}");
+			assert_eq!(ease.apply(2.0), ease.apply(1.0), "{ease:?}");
+		}
+	}
+
+	#[test]
+	fn out_and_in_out_mirror_in() {
+		for [ease_in, ease_out, ease_in_out] in FAMILIES {
+			for step in 0..=20 {
+				let t = step as f32 / 20.0;
+				let mirrored = 1.0 - ease_in.apply(1.0 - t);
+				assert!(close(ease_out.apply(t), mirrored), "{ease_out:?} at {t}");
+				let turned = 1.0 - ease_in_out.apply(1.0 - t);
+				assert!(close(ease_in_out.apply(t), turned), "{ease_in_out:?} at {t}");
+				// back and elastic in-out have their own constants
+				if ![Ease::BackInOut, Ease::ElasticInOut].contains(&ease_in_out) {
+					let half = if t < 0.5 {
+						ease_in.apply(2.0 * t) / 2.0
+					} else {
+						(1.0 + ease_out.apply(2.0 * t - 1.0)) / 2.0
+					};
+					assert!(close(ease_in_out.apply(t), half), "{ease_in_out:?} at {t}");
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn eases_match_known_values() {
+		assert_eq!(Ease::Linear.apply(0.25), 0.25);
+		assert_eq!(Ease::QuadIn.apply(0.5), 0.25);
+		assert_eq!(Ease::CubicOut.apply(0.5), 0.875);
+		assert_eq!(Ease::QuintIn.apply(0.5), 0.03125);
+		assert!(close(Ease::SineOut.apply(0.5), 0.70710677));
+		assert!(close(Ease::ExpoIn.apply(0.5), 0.03125));
+		assert!(close(Ease::CircOut.apply(0.5), 0.8660254));
+		// the first bounce lands exactly
+		assert!(close(Ease::BounceOut.apply(1.0 / 2.75), 1.0));
+		assert!(close(Ease::BounceOut.apply(0.5), 0.765625));
+		// back and elastic overshoot
+		assert!(Ease::BackIn.apply(0.25) < 0.0);
+		assert!(Ease::BackOut.apply(0.75) > 1.0);
+		assert!(Ease::ElasticOut.apply(0.1) > 1.0);
+	}
+
+	#[test]
+	fn lerp_covers_every_value_type() {
+		assert_eq!(2.0f32.lerp(4.0, 0.25), 2.5);
+		assert_eq!(
+			Vector2 { x: 0.0, y: 10.0 }.lerp(Vector2 { x: 4.0, y: 20.0 }, 0.5),
+			Vector2 { x: 2.0, y: 15.0 }
+		);
+		assert_eq!(
+			BLACK.lerp(WHITE, 0.5),
+			Color {
+				r: 128,
+				g: 128,
+				b: 128,
+				a: 255
+			}
+		);
+		// overshooting channels stay in range
+		assert_eq!(BLACK.lerp(WHITE, 1.5), WHITE);
+		assert_eq!(WHITE.lerp(BLACK, 1.5), BLACK);
+		let rectangle = Rectangle {
+			x: 0.0,
+			y: 0.0,
+			width: 10.0,
+			height: 20.0,
+		};
+		assert_eq!(rectangle.lerp(Rectangle { x: 4.0, ..rectangle }, 0.25).x, 1.0);
+	}
+
+	#[test]
+	fn tween_waits_its_delay_then_moves() {
+		let mut tween = Tween::new(0.0, 10.0, 1.0).delay(0.5);
+		assert_eq!(tween.duration(), Some(1.5));
+		assert_eq!(tween.advance(0.25), 0.0);
+		assert_eq!(tween.value(), 0.0);
+		tween.advance(0.5);
+		assert_eq!(tween.value(), 2.5);
+		assert_eq!(tween.value_at(1.0), 5.0);
+		// the time past the end is handed back
+		assert_eq!(tween.advance(1.0), 0.25);
+		assert!(tween.is_finished());
+		assert_eq!((tween.value(), tween.elapsed()), (10.0, 1.5));
+		assert_eq!(tween.advance(0.5), 0.5);
+
+		tween.reset();
+		assert!(!tween.is_finished());
+		assert_eq!(tween.value(), 0.0);
+	}
+
+	#[test]
+	fn tween_repeats_and_yoyos() {
+		let mut tween = Tween::new(0.0, 8.0, 1.0).repeat(Repeat::Times(2)).yoyo();
+		assert_eq!(tween.duration(), Some(3.0));
+		let values: Vec<f32> = (0..6)
+			.map(|_| {
+				tween.advance(0.5);
+				tween.value()
+			})
+			.collect();
+		assert_eq!(values, [4.0, 8.0, 4.0, 0.0, 4.0, 8.0]);
+		assert!(tween.is_finished());
+
+		let mut forever = Tween::new(0.0, 8.0, 1.0).repeat(Repeat::Forever);
+		assert_eq!(forever.duration(), None);
+		assert_eq!(forever.advance(100.25), 0.0);
+		assert_eq!(forever.value(), 2.0);
+		assert!(!forever.is_finished());
+	}
+
+	#[test]
+	fn tween_calls_back_on_update_and_once_on_complete() {
+		let seen = Rc::new(Cell::new(0.0));
+		let updated = seen.clone();
+		let (completions, on_complete) = counter();
+		let mut tween = Tween::new(0.0, 1.0, 1.0)
+			.ease(Ease::QuadIn)
+			.on_update(move |value| updated.set(value))
+			.on_complete(on_complete);
+		tween.advance(0.5);
+		assert_eq!((seen.get(), completions.get()), (0.25, 0));
+		tween.advance(1.0);
+		tween.advance(1.0);
+		assert_eq!((seen.get(), completions.get()), (1.0, 1));
+
+		// a zero length tween jumps to the end
+		let mut jump = Tween::new(0.0, 1.0, 0.0).repeat(Repeat::Forever);
+		assert_eq!(jump.advance(0.25), 0.25);
+		assert_eq!(jump.value(), 1.0);
+	}
+
+	#[test]
+	fn sequence_hands_leftover_time_to_the_next_entry() {
+		let (completions, on_complete) = counter();
+		let mut sequence = Sequence::new()
+			.then(Tween::new(0.0, 1.0, 0.5))
+			.wait(0.25)
+			.then(Tween::new(BLACK, WHITE, 1.0))
+			.on_complete(on_complete);
+		assert_eq!(sequence.duration(), Some(1.75));
+		assert_eq!(sequence.current(), 0);
+
+		sequence.advance(1.0);
+		assert_eq!(sequence.current(), 2);
+		assert_eq!(sequence.get::<Tween<f32>>(0).map(Tween::value), Some(1.0));
+		assert_eq!(sequence.get::<Delay>(1).map(Timeline::is_finished), Some(true));
+		assert_eq!(sequence.get::<Tween<Color>>(2).map(Tween::elapsed), Some(0.25));
+		// wrong type
+		assert!(sequence.get::<Tween<f32>>(2).is_none());
+
+		assert_eq!(sequence.advance(1.0), 0.25);
+		assert!(sequence.is_finished());
+		assert_eq!((sequence.current(), completions.get()), (3, 1));
+
+		sequence.reset();
+		assert_eq!(sequence.current(), 0);
+		assert_eq!(sequence.get::<Tween<f32>>(0).map(Tween::value), Some(0.0));
+	}
+
+	#[test]
+	fn sequence_repeats_from_the_start() {
+		let mut sequence = Sequence::new()
+			.then(Tween::new(0.0, 1.0, 0.5))
+			.then(Tween::new(1.0, 0.0, 0.5))
+			.repeat(Repeat::Times(1));
+		assert_eq!(sequence.duration(), Some(2.0));
+		sequence.advance(1.25);
+		assert_eq!(sequence.current(), 0);
+		assert_eq!(sequence.get::<Tween<f32>>(0).map(Tween::value), Some(0.5));
+		assert_eq!(sequence.advance(1.0), 0.25);
+
+		// an empty group repeating forever finishes instead of looping within one step
+		let mut empty = Sequence::new().repeat(Repeat::Forever);
+		assert_eq!(empty.advance(0.5), 0.5);
+		assert!(empty.is_finished());
+	}
+
+	#[test]
+	fn parallel_finishes_with_its_longest_entry() {
+		let (completions, on_complete) = counter();
+		let mut parallel = Parallel::new()
+			.with(Tween::new(0.0, 1.0, 0.5))
+			.with(Tween::new(0.0, 1.0, 1.0))
+			.on_complete(on_complete);
+		assert_eq!(parallel.duration(), Some(1.0));
+		assert_eq!(parallel.advance(0.75), 0.0);
+		assert_eq!(parallel.get::<Tween<f32>>(0).map(Tween::value), Some(1.0));
+		assert_eq!(parallel.get::<Tween<f32>>(1).map(Tween::value), Some(0.75));
+		assert!(!parallel.is_finished());
+
+		assert_eq!(parallel.advance(0.5), 0.25);
+		assert!(parallel.is_finished());
+		assert_eq!(completions.get(), 1);
+
+		let forever = Parallel::new().with(Tween::new(0.0, 1.0, 1.0).repeat(Repeat::Forever));
+		assert_eq!(forever.duration(), None);
+	}
+
+	#[test]
+	fn same_steps_give_same_values() {
+		let build = || {
+			Sequence::new()
+				.then(Tween::new(0.0, 3.0, 0.3).ease(Ease::ElasticOut))
+				.then(Tween::new(3.0, -1.0, 0.7).ease(Ease::BounceInOut))
+		};
+		let (mut first, mut second) = (build(), build());
+		for step in 0..40 {
+			let delta_time = 0.01 + (step % 3) as f32 * 0.007;
+			assert_eq!(first.advance(delta_time), second.advance(delta_time));
+			let value = |sequence: &Sequence| sequence.get::<Tween<f32>>(1).map(Tween::value);
+			assert_eq!(value(&first), value(&second));
+		}
+	}
+}
@@ -0,0 +1,274 @@
+// Runs the game loop, the screen stack and replays through `HeadlessBackend` for a fixed number of
+// frames, the same way the examples do with `--features raylib/headless`.
+
+use std::time::Duration;
+
+use raylib::{
+	backend::{Backend, HeadlessBackend},
+	game_loop::{Game, GameLoop, ManualClock},
+	input_map::{Binding, InputMap},
+	raster::Canvas,
+	recording::{DrawCommand, RecordingBackend},
+	replay::Replay,
+	screen::{Direction, Screen, ScreenStack, Transition, TransitionEffect},
+	*,
+};
+
+// powers of two, so the clock adds up without rounding
+const FRAME_TIME: f32 = 1.0 / 64.0;
+const TIMESTEP: Duration = Duration::from_nanos(7_812_500);
+
+fn headless(max_frames: u64) -> HeadlessBackend {
+	let mut backend = HeadlessBackend::new(max_frames);
+	backend.frame_time = FRAME_TIME;
+	backend.init_window(320, 240, "test");
+	backend
+}
+
+fn frame_clock(game_loop: &mut GameLoop<ManualClock>, backend: &HeadlessBackend) {
+	game_loop.clock.advance(Duration::from_secs_f32(backend.frame_time()));
+}
+
+#[derive(Default)]
+struct Counter {
+	fixed_updates: u32,
+	updates: u32,
+	renders: u32,
+	time: f32,
+}
+
+impl Game for Counter {
+	fn fixed_update(&mut self, fixed_delta: f32) {
+		self.fixed_updates += 1;
+		self.time += fixed_delta;
+	}
+
+	fn update(&mut self, _delta: f32) {
+		self.updates += 1;
+	}
+
+	fn render(&mut self, _alpha: f32) {
+		self.renders += 1;
+	}
+}
+
+#[test]
+fn game_loop_runs_two_fixed_steps_per_frame() {
+	let mut backend = headless(60);
+	let mut game_loop = GameLoop::with_clock(TIMESTEP, ManualClock::default());
+	let mut game = Counter::default();
+	while !backend.window_should_close() {
+		frame_clock(&mut game_loop, &backend);
+		game_loop.run_frame(&mut game);
+		backend.begin_drawing();
+		backend.end_drawing();
+	}
+
+	assert_eq!(backend.frames(), 60);
+	assert_eq!((game.updates, game.renders), (60, 60));
+	// the first frame has no previous time to measure from
+	assert_eq!(game.fixed_updates, 2 * 59);
+	assert_eq!(game.time, 59.0 * FRAME_TIME);
+}
+
+struct Shared {
+	input: InputMap,
+	log: Vec<String>,
+}
+
+// goes to the next screen on confirm, quits after the last one
+struct Numbered(u32);
+
+impl Screen<Shared> for Numbered {
+	fn enter(&mut self, shared: &mut Shared) {
+		shared.log.push(format!("enter {}", self.0));
+	}
+
+	fn update(&mut self, shared: &mut Shared, _delta_time: f32) -> Transition<Shared> {
+		match (shared.input.pressed("confirm"), self.0) {
+			(false, _) => Transition::None,
+			(true, 3) => Transition::Quit,
+			(true, number) => Transition::Replace(Box::new(Numbered(number + 1))),
+		}
+	}
+
+	fn draw(&self, backend: &mut dyn Backend, _shared: &Shared) {
+		backend.draw_text(&self.0.to_string(), 10, 10, 20, BLACK);
+	}
+
+	fn exit(&mut self, shared: &mut Shared) {
+		shared.log.push(format!("exit {}", self.0));
+	}
+}
+
+#[test]
+fn screen_stack_follows_input_until_quit() {
+	let mut backend = headless(100);
+	let mut shared = Shared {
+		input: InputMap::new(),
+		log: Vec::new(),
+	};
+	shared.input.bind("confirm", Binding::Key(KeyboardKey_KEY_ENTER));
+	let mut screens = ScreenStack::new(
+		320,
+		240,
+		TransitionEffect::Fade {
+			color: BLACK,
+			duration: 0.25,
+		},
+	);
+	screens.push(Box::new(Numbered(1)), &mut shared);
+
+	while !backend.window_should_close() && screens.is_running() {
+		// tap enter every 20 frames
+		if backend.frames() % 20 == 10 {
+			backend.input.keys.insert(KeyboardKey_KEY_ENTER);
+		} else {
+			backend.input.keys.clear();
+		}
+		shared.input.update(&backend, backend.frame_time());
+		screens.update(&mut shared, backend.frame_time());
+		backend.begin_drawing();
+		screens.draw(&mut backend, &shared);
+		backend.end_drawing();
+	}
+
+	assert!(!screens.is_running());
+	assert!(screens.is_empty());
+	// third tap at frame 50 quits after the 16 frame fade
+	assert_eq!(backend.frames(), 67);
+	// each screen exits before the next one enters
+	assert_eq!(shared.log, ["enter 1", "exit 1", "enter 2", "exit 2", "enter 3", "exit 3"]);
+}
+
+// fills the screen, pushes `next` on its first update
+struct Solid {
+	color: Color,
+	next: Option<Color>,
+}
+
+impl Screen for Solid {
+	fn update(&mut self, _ctx: &mut (), _delta_time: f32) -> Transition {
+		match self.next.take() {
+			Some(color) => Transition::Push(Box::new(Solid { color, next: None })),
+			None => Transition::None,
+		}
+	}
+
+	fn draw(&self, backend: &mut dyn Backend, _ctx: &()) {
+		backend.draw_rectangle(0, 0, 320, 240, self.color);
+	}
+}
+
+#[test]
+fn slide_draws_both_screens_from_separate_render_textures() {
+	let mut backend = RecordingBackend::new(headless(9));
+	let mut screens = ScreenStack::new(
+		320,
+		240,
+		TransitionEffect::Slide {
+			direction: Direction::Right,
+			duration: 0.25,
+		},
+	);
+	screens.push(
+		Box::new(Solid {
+			color: RED,
+			next: Some(BLUE),
+		}),
+		&mut (),
+	);
+	while !backend.window_should_close() {
+		screens.update(&mut (), backend.frame_time());
+		backend.begin_drawing();
+		screens.draw(&mut backend, &());
+		backend.end_drawing();
+	}
+
+	let targets: Vec<u32> = backend
+		.last_frame()
+		.iter()
+		.filter_map(|command| match command {
+			DrawCommand::BeginTextureMode(texture) => Some(texture.id),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(targets.len(), 2);
+	assert_ne!(targets[0], targets[1]);
+
+	// halfway through, the new screen covers the left half and pushes the old one out right
+	let mut canvas = Canvas::new(320, 240);
+	canvas.run(backend.last_frame());
+	assert_eq!(canvas.pixel(80, 120), BLUE);
+	assert_eq!(canvas.pixel(240, 120), RED);
+}
+
+// moves right while the key is down
+#[derive(Default, PartialEq, Debug)]
+struct Walker {
+	x: f32,
+	steps: u32,
+}
+
+impl Walker {
+	fn fixed_update(&mut self, input: &InputMap, fixed_delta: f32) {
+		if input.down("right") {
+			self.x += 100.0 * fixed_delta;
+		}
+		self.steps += 1;
+	}
+}
+
+fn walker_input() -> InputMap {
+	let mut input = InputMap::new();
+	input.bind("right", Binding::Key(KeyboardKey_KEY_RIGHT));
+	input
+}
+
+#[test]
+fn replay_reproduces_the_recorded_session() {
+	let mut backend = headless(90);
+	let mut game_loop = GameLoop::with_clock(TIMESTEP, ManualClock::default());
+	let mut input = walker_input();
+	let mut walker = Walker::default();
+	let mut replay = Replay::new(7);
+	while !backend.window_should_close() {
+		if backend.frames() % 30 < 20 {
+			backend.input.keys.insert(KeyboardKey_KEY_RIGHT);
+		} else {
+			backend.input.keys.clear();
+		}
+		frame_clock(&mut game_loop, &backend);
+		let frame = game_loop.advance();
+		input.update(&backend, frame.delta);
+		replay.record(frame, backend.input.clone());
+		for _ in 0..frame.fixed_steps {
+			walker.fixed_update(&input, frame.fixed_delta);
+		}
+		backend.begin_drawing();
+		backend.end_drawing();
+	}
+
+	let mut saved = Vec::new();
+	replay.save(&mut saved).unwrap();
+	let loaded = Replay::load(saved.as_slice()).unwrap();
+	assert_eq!(loaded.seed, 7);
+	assert_eq!(loaded.len(), 90);
+
+	let mut backend = headless(u64::MAX);
+	let mut input = walker_input();
+	let mut replayed = Walker::default();
+	for recorded in loaded {
+		backend.input = recorded.input;
+		input.update(&backend, recorded.frame.delta);
+		for _ in 0..recorded.frame.fixed_steps {
+			replayed.fixed_update(&input, recorded.frame.fixed_delta);
+		}
+		backend.begin_drawing();
+		backend.end_drawing();
+	}
+
+	assert_eq!(replayed, walker);
+	assert!(walker.x > 0.0);
+	assert_eq!(backend.frames(), 90);
+}
@@ -0,0 +1,349 @@
+use std::time::Duration;
+
+use bevy_ecs::{
+	prelude::*,
+	schedule::{ExecutorKind, IntoSystemConfigs, MainThreadExecutor, ScheduleLabel, Schedules},
+};
+use raylib::{
+	backend::{Backend, WindowBackend},
+	game_loop::{Clock, GameLoop},
+	profile, profile_scope, *,
+};
+
+use crate::{
+	draw::draw_2d,
+	input::{Actions, Input},
+	time::{FixedTime, Time},
+	transform::propagate_transforms,
+	window::{ScreenSize, Window, WindowConfig},
+};
+
+// Runs once after the window is opened, single threaded. Load textures, shaders, ... here.
+#[derive(ScheduleLabel, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct Startup;
+
+// Runs zero or more times per frame at the fixed timestep, before `Update`.
+#[derive(ScheduleLabel, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct FixedUpdate;
+
+// Runs once per frame.
+#[derive(ScheduleLabel, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct Update;
+
+// Runs once per frame after `Update`, world transforms are worked out here.
+#[derive(ScheduleLabel, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct PostUpdate;
+
+// Runs once per frame between BeginDrawing and EndDrawing, single threaded on the main thread.
+// Drawing through `Window::backend` belongs here and nowhere else.
+#[derive(ScheduleLabel, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct Render;
+
+// Send it to close the app at the end of the frame.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct AppExit;
+
+// Background color the frame is cleared to before `Render`.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct ClearColor(pub Color);
+
+pub struct App {
+	pub world: World,
+	window: WindowConfig,
+}
+
+impl App {
+	pub fn new(window: WindowConfig) -> Self {
+		#[cfg(feature = "profile")]
+		crate::profile::install();
+		let mut world = World::new();
+		let mut schedules = Schedules::new();
+		schedules.insert(single_threaded(Startup));
+		schedules.insert(Schedule::new(FixedUpdate));
+		schedules.insert(Schedule::new(Update));
+		schedules.insert(Schedule::new(PostUpdate));
+		schedules.insert(single_threaded(Render));
+		schedules.add_systems(PostUpdate, propagate_transforms);
+		schedules.add_systems(Render, draw_2d);
+		world.insert_resource(schedules);
+
+		world.init_resource::<Time>();
+		world.insert_resource(FixedTime::new(Duration::from_micros(15625)));
+		world.init_resource::<Input>();
+		world.init_resource::<Actions>();
+		world.init_resource::<ScreenSize>();
+		world.init_resource::<Events<AppExit>>();
+		world.insert_resource(ClearColor(RAYWHITE));
+		Self { world, window }
+	}
+
+	pub fn timestep(mut self, timestep: Duration) -> Self {
+		self.world.resource_mut::<FixedTime>().timestep = timestep;
+		self
+	}
+
+	pub fn clear_color(mut self, color: Color) -> Self {
+		self.world.resource_mut::<ClearColor>().0 = color;
+		self
+	}
+
+	pub fn insert_resource(mut self, resource: impl Resource) -> Self {
+		self.world.insert_resource(resource);
+		self
+	}
+
+	pub fn add_event<E: Event>(mut self) -> Self {
+		self.world.init_resource::<Events<E>>();
+		self
+	}
+
+	pub fn add_systems<M>(mut self, schedule: impl ScheduleLabel, systems: impl IntoSystemConfigs<M>) -> Self {
+		self.world.resource_mut::<Schedules>().add_systems(schedule, systems);
+		self
+	}
+
+	// Opens the window and runs frames until it is closed or an `AppExit` is sent.
+	pub fn run(self) {
+		self.run_with(WindowBackend::default());
+	}
+
+	// `run` against another backend, e.g. `HeadlessBackend` or a `RecordingBackend`.
+	pub fn run_with(mut self, backend: impl Backend + 'static) {
+		self.start(backend);
+		let mut game_loop = GameLoop::new(self.world.resource::<FixedTime>().timestep);
+		while !self.should_exit() {
+			self.frame(&mut game_loop);
+		}
+		self.finish();
+	}
+
+	// Opens the window on `backend` and runs `Startup`. `run_with` is `start`, then `frame` until
+	// `should_exit`, then `finish`; tests call them one by one with a `ManualClock`.
+	pub fn start(&mut self, backend: impl Backend + 'static) {
+		let world = &mut self.world;
+		let mut window = Window::open(&self.window, backend);
+		world.insert_resource(ScreenSize::read(window.backend(), None));
+		world.insert_non_send_resource(window);
+		// lets the multi threaded executor hand non-send systems back to this thread
+		world.insert_resource(MainThreadExecutor::new());
+		for label in [Startup.intern(), Render.intern()] {
+			// in case the schedule was replaced through the world
+			world.schedule_scope(label, |_, schedule| {
+				schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+			});
+		}
+
+		world.run_schedule(Startup);
+	}
+
+	pub fn should_exit(&self) -> bool {
+		self.world.non_send_resource::<Window>().should_close() || !self.world.resource::<Events<AppExit>>().is_empty()
+	}
+
+	// Runs one frame: input, the fixed steps `game_loop` asks for, `Update`, `PostUpdate` and `Render`.
+	pub fn frame<K: Clock>(&mut self, game_loop: &mut GameLoop<K>) {
+		let world = &mut self.world;
+		let time = *world.resource::<Time>();
+		game_loop.set_time_scale(time.time_scale);
+		if time.paused != game_loop.is_paused() {
+			game_loop.set_paused(time.paused);
+		}
+		game_loop.timestep = world.resource::<FixedTime>().timestep;
+
+		let frame = game_loop.advance();
+		world.resource_mut::<Time>().advance(&frame);
+		world.resource_scope(|world, mut screen_size: Mut<ScreenSize>| {
+			*screen_size = ScreenSize::read(world.non_send_resource_mut::<Window>().backend(), Some(*screen_size));
+		});
+		world.resource_scope(|world, mut input: Mut<Input>| {
+			input.poll(world.non_send_resource_mut::<Window>().backend());
+		});
+		world.resource_scope(|world, mut actions: Mut<Actions>| {
+			actions.update(world.non_send_resource_mut::<Window>().backend(), frame.real_delta);
+		});
+
+		for _ in 0..frame.fixed_steps {
+			profile_scope!("FixedUpdate");
+			world.run_schedule(FixedUpdate);
+			world.resource_mut::<FixedTime>().step();
+		}
+		world.resource_mut::<FixedTime>().set_alpha(frame.alpha);
+
+		{
+			profile_scope!("Update");
+			world.run_schedule(Update);
+		}
+		{
+			profile_scope!("PostUpdate");
+			world.run_schedule(PostUpdate);
+		}
+
+		let clear_color = world.resource::<ClearColor>().0;
+		{
+			let mut window = world.non_send_resource_mut::<Window>();
+			window.backend().begin_drawing();
+			window.backend().clear_background(clear_color);
+		}
+		{
+			profile_scope!("Render");
+			world.run_schedule(Render);
+		}
+		{
+			// includes waiting for the frame rate limit
+			profile_scope!("EndDrawing");
+			world.non_send_resource_mut::<Window>().backend().end_drawing();
+		}
+		if profile::ENABLED {
+			profile::finish_frame();
+		}
+
+		world.resource_mut::<Events<AppExit>>().update();
+		world.clear_trackers();
+	}
+
+	// Closes the window.
+	pub fn finish(self) {
+		let mut world = self.world;
+		// everything holding GPU resources has to go before the window closes
+		let window = world.remove_non_send_resource::<Window>();
+		world.clear_all();
+		drop(world);
+		drop(window);
+	}
+}
+
+fn single_threaded(label: impl ScheduleLabel) -> Schedule {
+	let mut schedule = Schedule::new(label);
+	schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+	schedule
+}
+
+#[cfg(test)]
+mod tests {
+	use raylib::{backend::HeadlessBackend, game_loop::ManualClock};
+
+	use super::*;
+
+	const TIMESTEP: Duration = Duration::from_millis(10);
+
+	#[derive(Resource, Default)]
+	struct Log(Vec<&'static str>);
+
+	fn log(name: &'static str) -> impl FnMut(ResMut<Log>) {
+		move |mut log: ResMut<Log>| log.0.push(name)
+	}
+
+	fn app() -> App {
+		App::new(WindowConfig::default()).timestep(TIMESTEP).insert_resource(Log::default())
+	}
+
+	fn manual_loop() -> GameLoop<ManualClock> {
+		GameLoop::with_clock(TIMESTEP, ManualClock::default())
+	}
+
+	#[test]
+	fn startup_once_then_every_schedule_each_frame() {
+		let mut app = app()
+			.add_systems(Startup, log("startup"))
+			.add_systems(FixedUpdate, log("fixed"))
+			.add_systems(Update, log("update"))
+			.add_systems(PostUpdate, log("post"))
+			.add_systems(Render, log("render"));
+		app.start(HeadlessBackend::default());
+		assert_eq!(app.world.resource::<Log>().0, ["startup"]);
+
+		let mut game_loop = manual_loop();
+		// the first frame only starts the clock
+		app.frame(&mut game_loop);
+		game_loop.clock.advance(Duration::from_millis(25));
+		app.frame(&mut game_loop);
+		game_loop.clock.advance(Duration::from_millis(5));
+		app.frame(&mut game_loop);
+		assert_eq!(
+			app.world.resource::<Log>().0,
+			["startup", "update", "post", "render", "fixed", "fixed", "update", "post", "render", "fixed", "update", "post", "render"]
+		);
+		assert_eq!(
+			app.world.non_send_resource::<Window>().backend_as::<HeadlessBackend>().unwrap().frames(),
+			3
+		);
+		app.finish();
+	}
+
+	#[test]
+	fn app_exit_and_closing_the_window_stop_the_app() {
+		let mut exiting = app().add_systems(Update, |time: Res<Time>, mut exit: EventWriter<AppExit>| {
+			if time.frame() == 2 {
+				exit.send(AppExit);
+			}
+		});
+		exiting.start(HeadlessBackend::default());
+		let mut game_loop = manual_loop();
+		exiting.frame(&mut game_loop);
+		assert!(!exiting.should_exit());
+		exiting.frame(&mut game_loop);
+		assert!(exiting.should_exit());
+		exiting.finish();
+
+		let mut closing = app();
+		closing.start(HeadlessBackend::new(2));
+		let mut frames = 0;
+		while !closing.should_exit() {
+			closing.frame(&mut manual_loop());
+			frames += 1;
+		}
+		assert_eq!(frames, 2);
+		closing.finish();
+	}
+
+	#[test]
+	fn time_matches_a_bare_world_on_the_same_clock() {
+		let mut app = app();
+		app.start(HeadlessBackend::default());
+		let mut app_loop = manual_loop();
+
+		let mut world = World::new();
+		world.init_resource::<Time>();
+		let mut bare_loop = manual_loop();
+
+		let steps = [
+			(0, 1.0, false),
+			(16, 1.0, false),
+			(16, 2.0, false),
+			(40, 0.5, false),
+			(16, 1.0, true),
+			(16, 1.0, false),
+		];
+		for (millis, time_scale, paused) in steps {
+			for time in [&mut *app.world.resource_mut::<Time>(), &mut *world.resource_mut::<Time>()] {
+				time.time_scale = time_scale;
+				time.paused = paused;
+			}
+			app_loop.clock.advance(Duration::from_millis(millis));
+			app.frame(&mut app_loop);
+
+			bare_loop.clock.advance(Duration::from_millis(millis));
+			bare_loop.set_time_scale(time_scale);
+			if paused != bare_loop.is_paused() {
+				bare_loop.set_paused(paused);
+			}
+			let frame = bare_loop.advance();
+			world.resource_mut::<Time>().advance(&frame);
+
+			let (app_time, bare_time) = (app.world.resource::<Time>(), world.resource::<Time>());
+			assert_eq!(app_time.delta(), bare_time.delta());
+			assert_eq!(app_time.real_delta(), bare_time.real_delta());
+			assert_eq!(app_time.elapsed(), bare_time.elapsed());
+			assert_eq!(app_time.frame(), bare_time.frame());
+			assert_eq!(app_time.real_delta(), millis as f32 / 1000.0);
+			if paused {
+				assert_eq!(app_time.delta(), 0.0);
+			} else {
+				assert!((app_time.delta() - app_time.real_delta() * time_scale).abs() < 1e-6);
+			}
+		}
+		assert_eq!(app.world.resource::<Time>().frame(), steps.len() as u64);
+		assert_eq!(app.world.resource::<FixedTime>().steps(), bare_loop.fixed_steps());
+		app.finish();
+	}
+}
@@ -0,0 +1,437 @@
+use bevy_ecs::prelude::*;
+use raylib::{backend::Backend, *};
+
+use crate::{
+	transform::{GlobalTransform2D, Parent},
+	window::Window,
+};
+
+// Textured quad drawn at the entity's `GlobalTransform2D`.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Sprite {
+	pub texture: Texture2D,
+	// part of the texture to draw, the whole texture when None
+	pub source: Option<Rectangle>,
+	pub tint: Color,
+	// point of the sprite placed at the translation, 0..1, (0, 0) is the top left corner
+	pub anchor: Vector2,
+	pub flip_x: bool,
+	pub flip_y: bool,
+}
+
+impl Sprite {
+	pub fn new(texture: Texture2D) -> Self {
+		Self {
+			texture,
+			source: None,
+			tint: WHITE,
+			anchor: Vector2::default(),
+			flip_x: false,
+			flip_y: false,
+		}
+	}
+
+	fn source_rect(&self) -> Rectangle {
+		let source = self.source.unwrap_or(Rectangle {
+			x: 0.0,
+			y: 0.0,
+			width: self.texture.width as f32,
+			height: self.texture.height as f32,
+		});
+		// a negative source size flips the texture
+		Rectangle {
+			width: if self.flip_x { -source.width } else { source.width },
+			height: if self.flip_y { -source.height } else { source.height },
+			..source
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ShapeKind {
+	// `anchor` works like `Sprite::anchor`
+	Rectangle { size: Vector2, anchor: Vector2 },
+	RectangleLines { size: Vector2, anchor: Vector2, thickness: f32 },
+	// centered on the translation, scaled by the x scale
+	Circle { radius: f32 },
+	CircleLines { radius: f32 },
+	// regular polygon, centered on the translation
+	Polygon { sides: i32, radius: f32 },
+	// from the translation to `end`, in local space
+	Line { end: Vector2, thickness: f32 },
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub struct Shape {
+	pub kind: ShapeKind,
+	pub color: Color,
+}
+
+impl Shape {
+	pub fn rectangle(width: f32, height: f32, color: Color) -> Self {
+		Self {
+			kind: ShapeKind::Rectangle {
+				size: Vector2 { x: width, y: height },
+				anchor: Vector2::default(),
+			},
+			color,
+		}
+	}
+
+	pub fn circle(radius: f32, color: Color) -> Self {
+		Self {
+			kind: ShapeKind::Circle { radius },
+			color,
+		}
+	}
+}
+
+// Text in the default font, the translation is the top left corner.
+#[derive(Component, Clone, Debug)]
+pub struct Text {
+	pub font_size: f32,
+	pub spacing: f32,
+	pub color: Color,
+	text: String,
+}
+
+impl Text {
+	pub fn new(text: &str, font_size: f32, color: Color) -> Self {
+		Self {
+			font_size,
+			spacing: font_size / 10.0,
+			color,
+			text: text.to_string(),
+		}
+	}
+
+	pub fn text(&self) -> &str {
+		&self.text
+	}
+
+	pub fn set_text(&mut self, text: &str) {
+		if self.text != text {
+			self.text = text.to_string();
+		}
+	}
+}
+
+// Draw layer, higher is drawn later. Entities without one are on layer 0. Entities on the same
+// layer are grouped by texture and have no defined order among each other.
+#[derive(Component, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+pub struct ZIndex(pub i32);
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Visibility {
+	// same as the `Parent`, visible without a parent
+	#[default]
+	Inherited,
+	Visible,
+	// hides the children that inherit their visibility as well
+	Hidden,
+}
+
+fn is_visible(entity: Entity, visibility: &Query<(Option<&Visibility>, Option<&Parent>)>) -> bool {
+	let mut current = entity;
+	// bounded in case of a cycle
+	for _ in 0..64 {
+		let Ok((visibility, parent)) = visibility.get(current) else {
+			return true;
+		};
+		match (visibility.copied().unwrap_or_default(), parent) {
+			(Visibility::Hidden, _) => return false,
+			(Visibility::Visible, _) | (Visibility::Inherited, None) => return true,
+			(Visibility::Inherited, Some(parent)) => current = parent.0,
+		}
+	}
+	true
+}
+
+#[derive(Clone, Copy)]
+enum Drawable {
+	Sprite,
+	Shape,
+	Text,
+}
+
+// one draw of `draw_2d`
+struct DrawItem {
+	z: i32,
+	texture: u32,
+	entity: Entity,
+	drawable: Drawable,
+}
+
+// `draw_2d`'s list of draws, kept between frames to reuse its allocation
+#[derive(Default)]
+pub struct DrawList(Vec<DrawItem>);
+
+type DrawQuery<'w, 's> = Query<
+	'w,
+	's,
+	(
+		Entity,
+		&'static GlobalTransform2D,
+		Option<&'static ZIndex>,
+		Option<&'static Sprite>,
+		Option<&'static Shape>,
+		Option<&'static Text>,
+	),
+	Or<(With<Sprite>, With<Shape>, With<Text>)>,
+>;
+
+// Draws every visible `Sprite`, `Shape` and `Text` in `ZIndex` order, grouping draws that share a
+// texture so raylib can batch them. Added to `Render` by the app, order custom render systems
+// with `.before(draw_2d)` and `.after(draw_2d)`.
+pub fn draw_2d(
+	mut window: NonSendMut<Window>,
+	drawables: DrawQuery,
+	visibility: Query<(Option<&Visibility>, Option<&Parent>)>,
+	mut items: Local<DrawList>,
+) {
+	let items = &mut items.0;
+	items.clear();
+	for (entity, _, z, sprite, shape, text) in &drawables {
+		if !is_visible(entity, &visibility) {
+			continue;
+		}
+		let z = z.copied().unwrap_or_default().0;
+		if let Some(sprite) = sprite {
+			items.push(DrawItem {
+				z,
+				texture: sprite.texture.id,
+				entity,
+				drawable: Drawable::Sprite,
+			});
+		}
+		// raylib draws shapes with the default font's texture, so they batch with text
+		if shape.is_some() {
+			items.push(DrawItem {
+				z,
+				texture: 0,
+				entity,
+				drawable: Drawable::Shape,
+			});
+		}
+		if text.is_some() {
+			items.push(DrawItem {
+				z,
+				texture: 0,
+				entity,
+				drawable: Drawable::Text,
+			});
+		}
+	}
+	items.sort_by_key(|item| (item.z, item.texture));
+
+	let backend = window.backend();
+	for item in items.iter() {
+		let Ok((_, global, _, sprite, shape, text)) = drawables.get(item.entity) else {
+			continue;
+		};
+		match item.drawable {
+			Drawable::Sprite => draw_sprite(backend, global, sprite.expect("sprite")),
+			Drawable::Shape => draw_shape(backend, global, shape.expect("shape")),
+			Drawable::Text => draw_text(backend, global, text.expect("text")),
+		}
+	}
+}
+
+// destination rectangle and origin for draw_texture_pro/draw_rectangle_pro
+fn placement(global: &GlobalTransform2D, size: Vector2, anchor: Vector2) -> (Rectangle, Vector2) {
+	let position = global.translation();
+	let scale = global.scale();
+	let width = size.x.abs() * scale.x.abs();
+	let height = size.y.abs() * scale.y.abs();
+	(
+		Rectangle {
+			x: position.x,
+			y: position.y,
+			width,
+			height,
+		},
+		Vector2 {
+			x: anchor.x * width,
+			y: anchor.y * height,
+		},
+	)
+}
+
+fn draw_sprite(backend: &mut dyn Backend, global: &GlobalTransform2D, sprite: &Sprite) {
+	let mut source = sprite.source_rect();
+	let scale = global.scale();
+	// mirrored by the transform
+	if scale.x < 0.0 {
+		source.width = -source.width;
+	}
+	if scale.y < 0.0 {
+		source.height = -source.height;
+	}
+	let size = Vector2 {
+		x: source.width,
+		y: source.height,
+	};
+	let (dest, origin) = placement(global, size, sprite.anchor);
+	backend.draw_texture_pro(sprite.texture, source, dest, origin, global.rotation(), sprite.tint);
+}
+
+fn draw_shape(backend: &mut dyn Backend, global: &GlobalTransform2D, shape: &Shape) {
+	let center = global.translation();
+	let radius_scale = global.scale().x.abs();
+	match shape.kind {
+		ShapeKind::Rectangle { size, anchor } => {
+			let (dest, origin) = placement(global, size, anchor);
+			backend.draw_rectangle_pro(dest, origin, global.rotation(), shape.color);
+		}
+		ShapeKind::RectangleLines { size, anchor, thickness } => {
+			let corner = |x: f32, y: f32| {
+				global.transform_point(Vector2 {
+					x: (x - anchor.x) * size.x,
+					y: (y - anchor.y) * size.y,
+				})
+			};
+			let corners = [corner(0.0, 0.0), corner(1.0, 0.0), corner(1.0, 1.0), corner(0.0, 1.0)];
+			for i in 0..4 {
+				backend.draw_line_ex(corners[i], corners[(i + 1) % 4], thickness, shape.color);
+			}
+		}
+		ShapeKind::Circle { radius } => backend.draw_circle_v(center, radius * radius_scale, shape.color),
+		ShapeKind::CircleLines { radius } => backend.draw_circle_lines_v(center, radius * radius_scale, shape.color),
+		ShapeKind::Polygon { sides, radius } => backend.draw_poly(center, sides, radius * radius_scale, global.rotation(), shape.color),
+		ShapeKind::Line { end, thickness } => backend.draw_line_ex(center, global.transform_point(end), thickness, shape.color),
+	}
+}
+
+fn draw_text(backend: &mut dyn Backend, global: &GlobalTransform2D, text: &Text) {
+	let scale = global.scale().y.abs();
+	backend.draw_text_pro(
+		&text.text,
+		global.translation(),
+		global.rotation(),
+		text.font_size * scale,
+		text.spacing * scale,
+		text.color,
+	);
+}
+
+#[cfg(test)]
+mod tests {
+	use raylib::recording::{DrawCommand, RecordingBackend};
+
+	use super::*;
+	use crate::{
+		transform::{propagate_transforms, Transform2D},
+		window::WindowConfig,
+	};
+
+	fn world() -> World {
+		let mut world = World::new();
+		world.insert_non_send_resource(Window::open(&WindowConfig::default(), RecordingBackend::default()));
+		world
+	}
+
+	fn draw(world: &mut World) -> Vec<DrawCommand> {
+		let mut propagate = Schedule::default();
+		propagate.add_systems(propagate_transforms);
+		propagate.run(world);
+		let mut render = Schedule::default();
+		render.add_systems(draw_2d);
+		render.run(world);
+		let mut window = world.non_send_resource_mut::<Window>();
+		window.backend_as_mut::<RecordingBackend>().unwrap().take_commands()
+	}
+
+	fn texture(id: u32) -> Texture2D {
+		Texture2D {
+			id,
+			width: 8,
+			height: 4,
+			mipmaps: 1,
+			format: PixelFormat_PIXELFORMAT_UNCOMPRESSED_R8G8B8A8,
+		}
+	}
+
+	fn colors(commands: &[DrawCommand]) -> Vec<Color> {
+		commands
+			.iter()
+			.map(|command| match command {
+				DrawCommand::RectanglePro { color, .. } => *color,
+				other => panic!("not a rectangle: {other}"),
+			})
+			.collect()
+	}
+
+	#[test]
+	fn hidden_parents_hide_children_that_inherit() {
+		let mut world = world();
+		let shape = |color| (Transform2D::default(), Shape::rectangle(1.0, 1.0, color));
+		let hidden = world.spawn((shape(RED), Visibility::Hidden)).id();
+		let inherits = world.spawn((shape(GREEN), Parent(hidden))).id();
+		world.spawn((shape(BLUE), Parent(inherits)));
+		let visible = world.spawn((shape(YELLOW), Visibility::Visible, Parent(hidden))).id();
+		world.spawn((shape(PURPLE), Parent(visible)));
+		world.spawn(shape(ORANGE));
+		world.spawn((shape(PINK), Parent(Entity::from_raw(999))));
+
+		// same layer and texture, in no particular order
+		let key = |color: &Color| u32::from_be_bytes([color.r, color.g, color.b, color.a]);
+		let mut drawn = colors(&draw(&mut world));
+		drawn.sort_by_key(key);
+		let mut expected = vec![YELLOW, PURPLE, ORANGE, PINK];
+		expected.sort_by_key(key);
+		assert_eq!(drawn, expected);
+
+		// showing the parent again brings back the ones that inherit
+		*world.get_mut::<Visibility>(hidden).unwrap() = Visibility::Inherited;
+		assert_eq!(draw(&mut world).len(), 7);
+	}
+
+	#[test]
+	fn draws_by_layer_then_texture() {
+		let mut world = world();
+		world.spawn((Transform2D::default(), ZIndex(1), Shape::rectangle(1.0, 1.0, RED)));
+		world.spawn((Transform2D::default(), Sprite::new(texture(7))));
+		world.spawn((Transform2D::default(), Text::new("hi", 10.0, BLACK)));
+		world.spawn((Transform2D::default(), ZIndex(1), Sprite::new(texture(3))));
+		world.spawn((Transform2D::default(), ZIndex(-1), Shape::rectangle(1.0, 1.0, BLUE)));
+		world.spawn((Transform2D::default(), Sprite::new(texture(3))));
+
+		let order: Vec<String> = draw(&mut world)
+			.iter()
+			.map(|command| match command {
+				DrawCommand::RectanglePro { color, .. } if *color == RED => "red".to_string(),
+				DrawCommand::RectanglePro { color, .. } if *color == BLUE => "blue".to_string(),
+				DrawCommand::TextPro { text, .. } => text.clone(),
+				DrawCommand::TexturePro { texture, .. } => format!("texture {}", texture.id),
+				other => panic!("unexpected {other}"),
+			})
+			.collect();
+		assert_eq!(order, ["blue", "hi", "texture 3", "texture 7", "red", "texture 3"]);
+	}
+
+	#[test]
+	fn sprites_follow_their_transform() {
+		let mut world = world();
+		let mut sprite = Sprite::new(texture(1));
+		sprite.anchor = Vector2 { x: 0.5, y: 1.0 };
+		sprite.flip_x = true;
+		world.spawn((Transform2D::from_xy(20.0, 30.0).with_rotation(45.0).with_scale(2.0, -1.0), sprite));
+
+		let commands = draw(&mut world);
+		let [DrawCommand::TexturePro {
+			source,
+			dest,
+			origin,
+			rotation,
+			..
+		}] = commands.as_slice()
+		else {
+			panic!("one sprite: {commands:?}");
+		};
+		assert_eq!((dest.x, dest.y, dest.width, dest.height), (20.0, 30.0, 16.0, 4.0));
+		assert_eq!((origin.x, origin.y), (8.0, 4.0));
+		assert!((rotation - 45.0).abs() < 1e-3);
+		// flipped by the sprite, and upside down by the negative y scale
+		assert_eq!((source.width, source.height), (-8.0, -4.0));
+	}
+}
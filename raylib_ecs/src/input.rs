@@ -0,0 +1,223 @@
+use std::ops::{Deref, DerefMut};
+
+use bevy_ecs::prelude::*;
+use raylib::{
+	backend::Backend,
+	gui::{GuiInput, GuiKey},
+	input_map::InputMap,
+	*,
+};
+
+const KEY_COUNT: usize = KeyboardKey_KEY_KB_MENU as usize + 1;
+const MOUSE_BUTTON_COUNT: usize = MouseButton_MOUSE_BUTTON_BACK as usize + 1;
+
+#[derive(Clone)]
+struct Buttons<const N: usize> {
+	down: [bool; N],
+	pressed: [bool; N],
+	released: [bool; N],
+}
+
+impl<const N: usize> Default for Buttons<N> {
+	fn default() -> Self {
+		Self {
+			down: [false; N],
+			pressed: [false; N],
+			released: [false; N],
+		}
+	}
+}
+
+impl<const N: usize> Buttons<N> {
+	fn get(states: &[bool; N], button: i32) -> bool {
+		usize::try_from(button).ok().and_then(|index| states.get(index)).copied().unwrap_or(false)
+	}
+
+	fn set(&mut self, index: usize, down: bool) {
+		self.pressed[index] = down && !self.down[index];
+		self.released[index] = !down && self.down[index];
+		self.down[index] = down;
+	}
+}
+
+// Keyboard and mouse state of the current frame, polled once at the start of the frame so every
+// system sees the same input. Pressed and released are per frame: a `FixedUpdate` system sees
+// them on every fixed step of that frame, and not at all on frames without a fixed step.
+#[derive(Resource, Clone, Default)]
+pub struct Input {
+	keys: Buttons<KEY_COUNT>,
+	mouse: Buttons<MOUSE_BUTTON_COUNT>,
+	mouse_position: Vector2,
+	mouse_delta: Vector2,
+	mouse_wheel: f32,
+	chars: Vec<char>,
+}
+
+impl Input {
+	pub fn key_down(&self, key: KeyboardKey) -> bool {
+		Buttons::get(&self.keys.down, key)
+	}
+
+	pub fn key_pressed(&self, key: KeyboardKey) -> bool {
+		Buttons::get(&self.keys.pressed, key)
+	}
+
+	pub fn key_released(&self, key: KeyboardKey) -> bool {
+		Buttons::get(&self.keys.released, key)
+	}
+
+	pub fn mouse_down(&self, button: MouseButton) -> bool {
+		Buttons::get(&self.mouse.down, button)
+	}
+
+	pub fn mouse_pressed(&self, button: MouseButton) -> bool {
+		Buttons::get(&self.mouse.pressed, button)
+	}
+
+	pub fn mouse_released(&self, button: MouseButton) -> bool {
+		Buttons::get(&self.mouse.released, button)
+	}
+
+	pub fn mouse_position(&self) -> Vector2 {
+		self.mouse_position
+	}
+
+	pub fn mouse_delta(&self) -> Vector2 {
+		self.mouse_delta
+	}
+
+	pub fn mouse_wheel(&self) -> f32 {
+		self.mouse_wheel
+	}
+
+	// text typed this frame, in order
+	pub fn chars(&self) -> &[char] {
+		&self.chars
+	}
+
+	// This frame's input for `gui` widgets and the console. raylib's typed characters were taken by
+	// `poll`, so `GuiInput::capture` would miss them. Keyboard only, held keys don't repeat.
+	pub fn gui_input(&self) -> GuiInput {
+		let shift = self.key_down(KeyboardKey_KEY_LEFT_SHIFT) || self.key_down(KeyboardKey_KEY_RIGHT_SHIFT);
+		let bindings = [
+			(KeyboardKey_KEY_TAB, if shift { GuiKey::Previous } else { GuiKey::Next }),
+			(KeyboardKey_KEY_UP, GuiKey::Up),
+			(KeyboardKey_KEY_DOWN, GuiKey::Down),
+			(KeyboardKey_KEY_LEFT, GuiKey::Left),
+			(KeyboardKey_KEY_RIGHT, GuiKey::Right),
+			(KeyboardKey_KEY_ENTER, GuiKey::Activate),
+			(KeyboardKey_KEY_KP_ENTER, GuiKey::Activate),
+			(KeyboardKey_KEY_ESCAPE, GuiKey::Back),
+			(KeyboardKey_KEY_BACKSPACE, GuiKey::Backspace),
+		];
+		GuiInput {
+			mouse: self.mouse_position,
+			mouse_down: self.mouse_down(MouseButton_MOUSE_BUTTON_LEFT),
+			wheel: self.mouse_wheel,
+			text: self.chars.iter().collect(),
+			keys: bindings
+				.iter()
+				.filter(|(key, _)| self.key_pressed(*key))
+				.map(|(_, gui_key)| *gui_key)
+				.collect(),
+		}
+	}
+
+	// Pressed and released compare with the previous poll, the backend's `gui_input` supplies the
+	// mouse position and the typed text.
+	pub(crate) fn poll(&mut self, backend: &mut dyn Backend) {
+		for key in 0..KEY_COUNT {
+			self.keys.set(key, backend.key_down(key as KeyboardKey));
+		}
+		for button in 0..MOUSE_BUTTON_COUNT {
+			self.mouse.set(button, backend.mouse_button_down(button as MouseButton));
+		}
+		self.mouse_wheel = backend.mouse_wheel();
+
+		let gui = backend.gui_input(0);
+		self.mouse_delta = Vector2 {
+			x: gui.mouse.x - self.mouse_position.x,
+			y: gui.mouse.y - self.mouse_position.y,
+		};
+		self.mouse_position = gui.mouse;
+		self.chars.clear();
+		self.chars.extend(gui.text.chars());
+	}
+}
+
+// The app's `InputMap`, updated from the window once per frame right after `Input`.
+#[derive(Resource, Clone, Default, Debug)]
+pub struct Actions(pub InputMap);
+
+impl Actions {
+	pub(crate) fn update(&mut self, backend: &dyn Backend, delta_time: f32) {
+		self.0.update(backend, delta_time);
+	}
+}
+
+impl Deref for Actions {
+	type Target = InputMap;
+
+	fn deref(&self) -> &InputMap {
+		&self.0
+	}
+}
+
+impl DerefMut for Actions {
+	fn deref_mut(&mut self) -> &mut InputMap {
+		&mut self.0
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use raylib::backend::HeadlessBackend;
+
+	use super::*;
+
+	#[test]
+	fn pressed_and_released_last_one_poll() {
+		let mut backend = HeadlessBackend::default();
+		let mut input = Input::default();
+		let key = KeyboardKey_KEY_SPACE;
+		let button = MouseButton_MOUSE_BUTTON_LEFT;
+
+		backend.input.keys.insert(key);
+		input.poll(&mut backend);
+		assert!(input.key_down(key) && input.key_pressed(key) && !input.key_released(key));
+		assert!(input.gui_input().keys.is_empty());
+
+		backend.input.mouse_buttons.insert(button);
+		input.poll(&mut backend);
+		assert!(input.key_down(key) && !input.key_pressed(key));
+		assert!(input.mouse_pressed(button) && input.gui_input().mouse_down);
+
+		backend.input.keys.clear();
+		backend.input.mouse_buttons.clear();
+		input.poll(&mut backend);
+		assert!(!input.key_down(key) && input.key_released(key));
+		assert!(input.mouse_released(button) && !input.mouse_down(button));
+
+		input.poll(&mut backend);
+		assert!(!input.key_released(key) && !input.mouse_released(button));
+	}
+
+	#[test]
+	fn mouse_delta_and_text_come_from_the_gui_input() {
+		let mut backend = HeadlessBackend::default();
+		let mut input = Input::default();
+		backend.gui_input.mouse = Vector2 { x: 10.0, y: 20.0 };
+		backend.gui_input.text = "ab".into();
+		backend.input.mouse_wheel = -1.0;
+		input.poll(&mut backend);
+		assert_eq!(input.mouse_delta(), Vector2 { x: 10.0, y: 20.0 });
+		assert_eq!(input.chars(), ['a', 'b']);
+		assert_eq!(input.mouse_wheel(), -1.0);
+
+		backend.gui_input.mouse = Vector2 { x: 7.0, y: 25.0 };
+		input.poll(&mut backend);
+		assert_eq!(input.mouse_position(), Vector2 { x: 7.0, y: 25.0 });
+		assert_eq!(input.mouse_delta(), Vector2 { x: -3.0, y: 5.0 });
+		assert!(input.chars().is_empty());
+	}
+}
@@ -0,0 +1,20 @@
+// bevy_ecs on top of raylib: an `App` that owns the window and runs the schedules of every frame.
+pub mod app;
+pub mod draw;
+pub mod input;
+#[cfg(feature = "profile")]
+mod profile;
+pub mod time;
+pub mod transform;
+pub mod window;
+
+pub mod prelude {
+	pub use crate::{
+		app::{App, AppExit, ClearColor, FixedUpdate, PostUpdate, Render, Startup, Update},
+		draw::{draw_2d, Shape, ShapeKind, Sprite, Text, Visibility, ZIndex},
+		input::{Actions, Input},
+		time::{FixedTime, Time},
+		transform::{GlobalTransform2D, Parent, Transform2D},
+		window::{ScreenSize, Window, WindowConfig},
+	};
+}
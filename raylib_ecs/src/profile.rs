@@ -0,0 +1,94 @@
+use std::{
+	cell::RefCell,
+	collections::HashMap,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Mutex,
+	},
+};
+
+use raylib::profile::Scope;
+use tracing_core::{
+	field::{Field, Visit},
+	span, Dispatch, Event, Metadata, Subscriber,
+};
+
+thread_local! {
+	static OPEN: RefCell<Vec<Scope>> = const { RefCell::new(Vec::new()) };
+}
+
+// Times every ECS system as a profiler scope named after it. bevy_ecs makes a "system" trace span
+// per system when it's built with `trace`, this receives them in place of a tracing subscriber.
+#[derive(Default)]
+struct SystemSpans {
+	next_id: AtomicU64,
+	// span id -> system name
+	names: Mutex<HashMap<u64, &'static str>>,
+	// one copy of each name for the life of the program, systems are built once
+	interned: Mutex<HashMap<String, &'static str>>,
+}
+
+struct NameField(Option<String>);
+
+impl Visit for NameField {
+	fn record_str(&mut self, field: &Field, value: &str) {
+		if field.name() == "name" {
+			self.0 = Some(value.to_string());
+		}
+	}
+
+	fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+		if field.name() == "name" {
+			self.0 = Some(format!("{value:?}"));
+		}
+	}
+}
+
+impl SystemSpans {
+	fn name(&self, id: &span::Id) -> Option<&'static str> {
+		self.names.lock().ok()?.get(&id.into_u64()).copied()
+	}
+}
+
+impl Subscriber for SystemSpans {
+	fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+		metadata.is_span() && metadata.name() == "system"
+	}
+
+	fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+		// ids start at 1, 0 is not a valid span id
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+		let mut name = NameField(None);
+		span.record(&mut name);
+		let name = name.0.unwrap_or_else(|| "system".to_string());
+		if let (Ok(mut interned), Ok(mut names)) = (self.interned.lock(), self.names.lock()) {
+			let name = *interned.entry(name.clone()).or_insert_with(|| Box::leak(name.into_boxed_str()));
+			names.insert(id, name);
+		}
+		span::Id::from_u64(id)
+	}
+
+	fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+	fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+	fn event(&self, _event: &Event<'_>) {}
+
+	fn enter(&self, span: &span::Id) {
+		if let Some(name) = self.name(span) {
+			OPEN.with(|open| open.borrow_mut().push(Scope::new(name)));
+		}
+	}
+
+	fn exit(&self, span: &span::Id) {
+		if self.name(span).is_some() {
+			OPEN.with(|open| open.borrow_mut().pop());
+		}
+	}
+}
+
+// Has to run before any system is built, their spans are made with them.
+pub(crate) fn install() {
+	// fails if the program set its own subscriber, which then gets the spans instead
+	let _ = tracing_core::dispatcher::set_global_default(Dispatch::new(SystemSpans::default()));
+}
@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use bevy_ecs::prelude::*;
+use raylib::game_loop::Frame;
+
+// Frame time, updated at the start of every frame. Use it in `Update` and `Render` systems.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct Time {
+	// read back by the app before the next frame
+	pub time_scale: f32,
+	pub paused: bool,
+	delta: f32,
+	real_delta: f32,
+	elapsed: f64,
+	frame: u64,
+}
+
+impl Default for Time {
+	fn default() -> Self {
+		Self {
+			time_scale: 1.0,
+			paused: false,
+			delta: 0.0,
+			real_delta: 0.0,
+			elapsed: 0.0,
+			frame: 0,
+		}
+	}
+}
+
+impl Time {
+	// seconds since the previous frame, after pause and time scale
+	pub fn delta(&self) -> f32 {
+		self.delta
+	}
+
+	// wall clock seconds since the previous frame
+	pub fn real_delta(&self) -> f32 {
+		self.real_delta
+	}
+
+	// scaled seconds since the app started
+	pub fn elapsed(&self) -> f64 {
+		self.elapsed
+	}
+
+	pub fn frame(&self) -> u64 {
+		self.frame
+	}
+
+	pub(crate) fn advance(&mut self, frame: &Frame) {
+		self.delta = frame.delta;
+		self.real_delta = frame.real_delta;
+		self.elapsed += frame.delta as f64;
+		self.frame += 1;
+	}
+}
+
+// Fixed timestep state. Use it in `FixedUpdate` systems, and `alpha` in `Render` to interpolate.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct FixedTime {
+	// read back by the app before the next frame
+	pub timestep: Duration,
+	alpha: f32,
+	steps: u64,
+}
+
+impl FixedTime {
+	pub fn new(timestep: Duration) -> Self {
+		Self {
+			timestep,
+			alpha: 0.0,
+			steps: 0,
+		}
+	}
+
+	pub fn delta(&self) -> f32 {
+		self.timestep.as_secs_f32()
+	}
+
+	// how far the frame is between the last two fixed updates, 0..1
+	pub fn alpha(&self) -> f32 {
+		self.alpha
+	}
+
+	// fixed updates run since the app started
+	pub fn steps(&self) -> u64 {
+		self.steps
+	}
+
+	pub(crate) fn step(&mut self) {
+		self.steps += 1;
+	}
+
+	pub(crate) fn set_alpha(&mut self, alpha: f32) {
+		self.alpha = alpha;
+	}
+}
@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+
+use bevy_ecs::prelude::*;
+use raylib::*;
+
+// Position, rotation and scale relative to the `Parent`, or to the world without one.
+// Rotation is in degrees, clockwise like the rest of raylib.
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub struct Transform2D {
+	pub translation: Vector2,
+	pub rotation: f32,
+	pub scale: Vector2,
+}
+
+impl Default for Transform2D {
+	fn default() -> Self {
+		Self {
+			translation: Vector2::default(),
+			rotation: 0.0,
+			scale: Vector2 { x: 1.0, y: 1.0 },
+		}
+	}
+}
+
+impl Transform2D {
+	pub fn from_xy(x: f32, y: f32) -> Self {
+		Self {
+			translation: Vector2 { x, y },
+			..Default::default()
+		}
+	}
+
+	pub fn with_rotation(mut self, rotation: f32) -> Self {
+		self.rotation = rotation;
+		self
+	}
+
+	pub fn with_scale(mut self, x: f32, y: f32) -> Self {
+		self.scale = Vector2 { x, y };
+		self
+	}
+}
+
+// Makes the entity's `Transform2D` and `Visibility` relative to another entity.
+// A missing parent, e.g. after it was despawned, makes the entity a root again.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Parent(pub Entity);
+
+// World space transform, written by `propagate_transforms` in `PostUpdate`. Added automatically
+// to every entity with a `Transform2D`.
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub struct GlobalTransform2D {
+	x_axis: Vector2,
+	y_axis: Vector2,
+	translation: Vector2,
+}
+
+impl Default for GlobalTransform2D {
+	fn default() -> Self {
+		Self::from(Transform2D::default())
+	}
+}
+
+impl From<Transform2D> for GlobalTransform2D {
+	fn from(transform: Transform2D) -> Self {
+		let (sin, cos) = transform.rotation.to_radians().sin_cos();
+		Self {
+			x_axis: Vector2 {
+				x: cos * transform.scale.x,
+				y: sin * transform.scale.x,
+			},
+			y_axis: Vector2 {
+				x: -sin * transform.scale.y,
+				y: cos * transform.scale.y,
+			},
+			translation: transform.translation,
+		}
+	}
+}
+
+impl GlobalTransform2D {
+	pub fn translation(&self) -> Vector2 {
+		self.translation
+	}
+
+	// degrees
+	pub fn rotation(&self) -> f32 {
+		self.x_axis.y.atan2(self.x_axis.x).to_degrees()
+	}
+
+	// a mirrored transform shows up as a negative y scale
+	pub fn scale(&self) -> Vector2 {
+		let det = self.x_axis.x * self.y_axis.y - self.x_axis.y * self.y_axis.x;
+		Vector2 {
+			x: self.x_axis.x.hypot(self.x_axis.y),
+			y: self.y_axis.x.hypot(self.y_axis.y).copysign(det),
+		}
+	}
+
+	pub fn transform_vector(&self, v: Vector2) -> Vector2 {
+		Vector2 {
+			x: self.x_axis.x * v.x + self.y_axis.x * v.y,
+			y: self.x_axis.y * v.x + self.y_axis.y * v.y,
+		}
+	}
+
+	pub fn transform_point(&self, point: Vector2) -> Vector2 {
+		let v = self.transform_vector(point);
+		Vector2 {
+			x: v.x + self.translation.x,
+			y: v.y + self.translation.y,
+		}
+	}
+
+	// `child` in the space of this transform
+	pub fn mul_transform(&self, child: &GlobalTransform2D) -> GlobalTransform2D {
+		GlobalTransform2D {
+			x_axis: self.transform_vector(child.x_axis),
+			y_axis: self.transform_vector(child.y_axis),
+			translation: self.transform_point(child.translation),
+		}
+	}
+}
+
+// deeper chains are treated as cycles
+const MAX_DEPTH: usize = 64;
+
+fn resolve(
+	entity: Entity,
+	transforms: &Query<(Entity, &Transform2D, Option<&Parent>)>,
+	resolved: &mut HashMap<Entity, GlobalTransform2D>,
+	depth: usize,
+) -> Option<GlobalTransform2D> {
+	if let Some(global) = resolved.get(&entity) {
+		return Some(*global);
+	}
+	let (_, transform, parent) = transforms.get(entity).ok()?;
+	let local = GlobalTransform2D::from(*transform);
+	let global = match parent {
+		Some(parent) if depth < MAX_DEPTH => match resolve(parent.0, transforms, resolved, depth + 1) {
+			Some(parent) => parent.mul_transform(&local),
+			None => local,
+		},
+		_ => local,
+	};
+	resolved.insert(entity, global);
+	Some(global)
+}
+
+pub fn propagate_transforms(
+	mut commands: Commands,
+	transforms: Query<(Entity, &Transform2D, Option<&Parent>)>,
+	mut globals: Query<&mut GlobalTransform2D>,
+	mut resolved: Local<HashMap<Entity, GlobalTransform2D>>,
+) {
+	resolved.clear();
+	for (entity, ..) in &transforms {
+		let Some(global) = resolve(entity, &transforms, &mut resolved, 0) else {
+			continue;
+		};
+		match globals.get_mut(entity) {
+			Ok(mut current) => {
+				// keeps change detection quiet for entities that did not move
+				if *current != global {
+					*current = global;
+				}
+			}
+			Err(_) => {
+				commands.entity(entity).insert(global);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_near(actual: Vector2, expected: Vector2) {
+		let close = (actual.x - expected.x).abs() < 1e-3 && (actual.y - expected.y).abs() < 1e-3;
+		assert!(close, "{actual:?} != {expected:?}");
+	}
+
+	fn v(x: f32, y: f32) -> Vector2 {
+		Vector2 { x, y }
+	}
+
+	fn propagate(world: &mut World) {
+		let mut schedule = Schedule::default();
+		schedule.add_systems(propagate_transforms);
+		schedule.run(world);
+	}
+
+	#[test]
+	fn scale_then_rotate_then_translate() {
+		// clockwise on screen: +x turns into +y
+		let global = GlobalTransform2D::from(Transform2D::from_xy(100.0, 50.0).with_rotation(90.0).with_scale(2.0, 3.0));
+		assert_near(global.transform_point(v(10.0, 0.0)), v(100.0, 70.0));
+		assert_near(global.transform_point(v(0.0, 10.0)), v(70.0, 50.0));
+		assert_near(global.transform_vector(v(1.0, 1.0)), v(-3.0, 2.0));
+		assert_near(global.translation(), v(100.0, 50.0));
+	}
+
+	#[test]
+	fn decomposes_into_rotation_and_scale() {
+		let global = GlobalTransform2D::from(Transform2D::default().with_rotation(30.0).with_scale(2.0, 0.5));
+		assert!((global.rotation() - 30.0).abs() < 1e-3);
+		assert_near(global.scale(), v(2.0, 0.5));
+
+		// a mirror shows up as a negative y scale and rebuilds the same transform
+		let mirrored = GlobalTransform2D::from(Transform2D::from_xy(5.0, -5.0).with_rotation(30.0).with_scale(-2.0, 0.5));
+		assert!(mirrored.scale().y < 0.0);
+		let rebuilt = GlobalTransform2D::from(Transform2D {
+			translation: mirrored.translation(),
+			rotation: mirrored.rotation(),
+			scale: mirrored.scale(),
+		});
+		for point in [v(1.0, 0.0), v(0.0, 1.0), v(-3.0, 7.0)] {
+			assert_near(rebuilt.transform_point(point), mirrored.transform_point(point));
+		}
+	}
+
+	#[test]
+	fn children_inherit_parent_transforms() {
+		let mut world = World::new();
+		let parent = world
+			.spawn(Transform2D::from_xy(100.0, 0.0).with_rotation(90.0).with_scale(2.0, 1.0))
+			.id();
+		let child = world.spawn((Transform2D::from_xy(10.0, 0.0).with_rotation(-90.0), Parent(parent))).id();
+		let grandchild = world.spawn((Transform2D::from_xy(0.0, 5.0).with_scale(3.0, 3.0), Parent(child))).id();
+		let orphan = world.spawn((Transform2D::from_xy(1.0, 2.0), Parent(Entity::from_raw(999)))).id();
+		propagate(&mut world);
+
+		let global = |entity| *world.get::<GlobalTransform2D>(entity).unwrap();
+		assert_near(global(parent).translation(), v(100.0, 0.0));
+		// (10, 0) scaled to (20, 0) by the parent, then turned to (0, 20)
+		assert_near(global(child).translation(), v(100.0, 20.0));
+		assert!(global(child).rotation().abs() < 1e-3);
+		// (0, 5) turned back to (5, 0) by the child, (15, 0) in the parent, scaled to (30, 0) and
+		// turned to (0, 30)
+		assert_near(global(grandchild).translation(), v(100.0, 30.0));
+		// the rotations cancel out, so the parent's 2x stretch ends up on the grandchild's y axis
+		assert_near(global(grandchild).transform_vector(v(1.0, 0.0)), v(3.0, 0.0));
+		assert_near(global(grandchild).transform_vector(v(0.0, 1.0)), v(0.0, 6.0));
+		assert_near(global(grandchild).scale(), v(3.0, 6.0));
+		// a missing parent makes it a root
+		assert_near(global(orphan).translation(), v(1.0, 2.0));
+
+		// moving the root moves the whole chain
+		world.get_mut::<Transform2D>(parent).unwrap().translation = v(0.0, 0.0);
+		propagate(&mut world);
+		let global = |entity| *world.get::<GlobalTransform2D>(entity).unwrap();
+		assert_near(global(grandchild).translation(), v(0.0, 30.0));
+	}
+
+	#[test]
+	fn parent_cycles_stop_at_the_depth_limit() {
+		let mut world = World::new();
+		let a = world.spawn(Transform2D::from_xy(1.0, 0.0)).id();
+		let b = world.spawn((Transform2D::from_xy(1.0, 0.0), Parent(a))).id();
+		world.entity_mut(a).insert(Parent(b));
+		propagate(&mut world);
+		assert!(world.get::<GlobalTransform2D>(a).is_some());
+		assert!(world.get::<GlobalTransform2D>(b).is_some());
+	}
+}
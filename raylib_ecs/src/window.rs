@@ -0,0 +1,127 @@
+use std::any::Any;
+
+use bevy_ecs::prelude::*;
+use raylib::backend::Backend;
+
+#[derive(Clone, Debug)]
+pub struct WindowConfig {
+	pub width: i32,
+	pub height: i32,
+	pub title: String,
+	pub target_fps: i32,
+	// raylib `ConfigFlags`, e.g. FLAG_WINDOW_RESIZABLE | FLAG_MSAA_4X_HINT
+	pub flags: u32,
+}
+
+impl Default for WindowConfig {
+	fn default() -> Self {
+		Self {
+			width: 800,
+			height: 450,
+			title: "raylib".to_string(),
+			target_fps: 60,
+			flags: 0,
+		}
+	}
+}
+
+// The raylib window, or whatever backend the app was run with. It is a non-send resource, so
+// every system that takes `NonSend<Window>` or `NonSendMut<Window>` runs on the main thread.
+// Closes the window when dropped.
+pub struct Window {
+	// raylib keeps its state in thread locals of the thread that opened the window, a boxed
+	// backend isn't `Send` either
+	backend: Box<dyn AnyBackend>,
+}
+
+impl Window {
+	pub(crate) fn open(config: &WindowConfig, backend: impl Backend + 'static) -> Self {
+		let mut backend = Box::new(backend);
+		backend.set_config_flags(config.flags);
+		backend.init_window(config.width, config.height, &config.title);
+		backend.set_target_fps(config.target_fps);
+		Self { backend }
+	}
+
+	// Draw through this from `Render` systems.
+	pub fn backend(&mut self) -> &mut dyn Backend {
+		self.backend.as_backend()
+	}
+
+	// The backend the app was run with, e.g. the `RecordingBackend` of a test.
+	pub fn backend_as<B: Backend + 'static>(&self) -> Option<&B> {
+		self.backend.as_any().downcast_ref()
+	}
+
+	pub fn backend_as_mut<B: Backend + 'static>(&mut self) -> Option<&mut B> {
+		self.backend.as_any_mut().downcast_mut()
+	}
+
+	pub fn should_close(&self) -> bool {
+		self.backend.window_should_close()
+	}
+
+	pub fn set_title(&mut self, title: &str) {
+		self.backend.set_window_title(title);
+	}
+
+	pub fn set_target_fps(&mut self, fps: i32) {
+		self.backend.set_target_fps(fps);
+	}
+
+	pub fn toggle_fullscreen(&mut self) {
+		self.backend.toggle_fullscreen();
+	}
+
+	pub fn fps(&self) -> i32 {
+		self.backend.fps()
+	}
+}
+
+impl Drop for Window {
+	fn drop(&mut self) {
+		self.backend.close_window();
+	}
+}
+
+// `Backend` that can be handed back as the type it was opened with.
+trait AnyBackend: Backend {
+	fn as_backend(&mut self) -> &mut dyn Backend;
+	fn as_any(&self) -> &dyn Any;
+	fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<B: Backend + 'static> AnyBackend for B {
+	fn as_backend(&mut self) -> &mut dyn Backend {
+		self
+	}
+
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+
+	fn as_any_mut(&mut self) -> &mut dyn Any {
+		self
+	}
+}
+
+// Size of the drawable area in pixels, refreshed at the start of every frame.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct ScreenSize {
+	pub width: i32,
+	pub height: i32,
+	// the window was resized since the previous frame
+	pub resized: bool,
+}
+
+impl ScreenSize {
+	// `previous` is what the last frame saw, none right after the window opened
+	pub(crate) fn read(backend: &dyn Backend, previous: Option<ScreenSize>) -> Self {
+		let (width, height) = (backend.screen_width(), backend.screen_height());
+		Self {
+			width,
+			height,
+			resized: previous.is_some_and(|previous| (previous.width, previous.height) != (width, height)),
+		}
+	}
+}